@@ -240,6 +240,10 @@ RUST_OPENSSL_101
 #else
 RUST_OPENSSL_OLD
 #endif
+
+#if !defined(LIBRESSL_VERSION_NUMBER) && OPENSSL_VERSION_NUMBER >= 0x10101000
+RUST_OPENSSL_111
+#endif
 ").unwrap();
 
     for define in DEFINES {
@@ -300,6 +304,12 @@ See rust-openssl README for more information:
     } else if expanded.contains("RUST_OPENSSL_110") {
         println!("cargo:rustc-cfg=ossl110");
         println!("cargo:version=110");
+        // 1.1.1 is API compatible with 1.1.0, so it is exposed as an additional cfg on top of
+        // ossl110 rather than as a separate version.
+        if expanded.contains("RUST_OPENSSL_111") {
+            println!("cargo:rustc-cfg=ossl111");
+            println!("cargo:ossl111=true");
+        }
         Version::Openssl110
     } else if expanded.contains("RUST_OPENSSL_102") {
         println!("cargo:rustc-cfg=ossl102");
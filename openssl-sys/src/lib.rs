@@ -4,7 +4,7 @@
 
 extern crate libc;
 
-use libc::{c_void, c_int, c_char, c_ulong, c_long, c_uint, c_uchar, size_t, FILE};
+use libc::{c_void, c_int, c_char, c_ulong, c_long, c_uint, c_uchar, c_double, size_t, time_t, FILE};
 use std::ptr;
 use std::mem;
 
@@ -23,11 +23,12 @@ mod libressl;
 #[cfg(libressl)]
 pub use libressl::*;
 
+pub enum ASN1_ENUMERATED {}
 pub enum ASN1_INTEGER {}
+pub enum ASN1_OBJECT {}
 pub enum ASN1_GENERALIZEDTIME {}
 pub enum ASN1_STRING {}
 pub enum ASN1_TIME {}
-pub enum ASN1_TYPE {}
 pub enum BN_CTX {}
 pub enum BN_GENCB {}
 pub enum CONF {}
@@ -45,13 +46,15 @@ pub enum OCSP_CERTID {}
 pub enum OCSP_RESPONSE {}
 pub enum OCSP_REQUEST {}
 pub enum OCSP_ONEREQ {}
+pub enum PKCS8_PRIV_KEY_INFO {}
 pub enum SSL_CIPHER {}
 pub enum SSL_METHOD {}
+pub enum UI_METHOD {}
 pub enum X509_CRL {}
 pub enum X509_EXTENSION {}
 pub enum X509_NAME {}
 pub enum X509_NAME_ENTRY {}
-pub enum X509_REQ {}
+pub enum X509_PUBKEY {}
 pub enum X509_STORE {}
 pub enum X509_STORE_CTX {}
 pub enum bio_st {}
@@ -66,6 +69,7 @@ pub type bio_info_cb = Option<unsafe extern fn(*mut BIO, c_int, *const c_char, c
 pub type GEN_SESSION_CB = Option<unsafe extern fn(*const SSL, *mut c_uchar, *mut c_uint) -> c_int>;
 pub type tls_session_ticket_ext_cb_fn = Option<unsafe extern fn(*mut SSL, *const c_uchar, c_int, *mut c_void) -> c_int>;
 pub type tls_session_secret_cb_fn = Option<unsafe extern fn(*mut SSL, *mut c_void, *mut c_int, *mut stack_st_SSL_CIPHER, *mut *mut SSL_CIPHER, *mut c_void) -> c_int>;
+pub type EVP_PKEY_gen_cb = Option<unsafe extern fn(*mut EVP_PKEY_CTX) -> c_int>;
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -82,12 +86,55 @@ pub struct AES_KEY {
     rounds: c_int,
 }
 
+#[repr(C)]
+pub struct ASN1_TYPE {
+    pub type_: c_int,
+    pub value: *mut c_void,
+}
+
 #[repr(C)]
 pub struct GENERAL_NAME {
     pub type_: c_int,
     pub d: *mut c_void,
 }
 
+#[repr(C)]
+pub struct X509_ALGOR {
+    pub algorithm: *mut ASN1_OBJECT,
+    pub parameter: *mut ASN1_TYPE,
+}
+
+#[repr(C)]
+pub struct DIST_POINT_NAME {
+    pub type_: c_int,
+    pub name: *mut c_void,
+    pub dpname: *mut X509_NAME,
+}
+
+#[repr(C)]
+pub struct DIST_POINT {
+    pub distpoint: *mut DIST_POINT_NAME,
+    pub reasons: *mut ASN1_STRING,
+    pub CRLissuer: *mut stack_st_GENERAL_NAME,
+    pub dp_reasons: c_int,
+}
+
+#[repr(C)]
+pub struct PKCS7 {
+    pub asn1: *mut c_uchar,
+    pub length: c_long,
+    pub state: c_int,
+    pub detached: c_int,
+    pub type_: *mut ASN1_OBJECT,
+    pub d: *mut c_void,
+}
+
+#[repr(C)]
+pub struct SRTP_PROTECTION_PROFILE {
+    pub name: *const c_char,
+    pub id: c_ulong,
+}
+
 #[repr(C)]
 pub struct X509V3_CTX {
     flags: c_int,
@@ -139,27 +186,89 @@ pub const BIO_FLAGS_IO_SPECIAL: c_int = 0x04;
 pub const BIO_FLAGS_RWS: c_int = BIO_FLAGS_READ | BIO_FLAGS_WRITE | BIO_FLAGS_IO_SPECIAL;
 pub const BIO_FLAGS_SHOULD_RETRY: c_int = 0x08;
 
+pub const BN_FLG_CONSTTIME: c_int = 0x04;
+
 pub const CRYPTO_LOCK: c_int = 1;
 
+pub const DTLS1_COOKIE_LENGTH: c_uint = 256;
+
+pub const ERR_LIB_EVP: c_int = 6;
 pub const ERR_LIB_PEM: c_int = 9;
+pub const ERR_LIB_X509: c_int = 11;
+pub const ERR_LIB_SSL: c_int = 20;
+pub const ERR_LIB_USER: c_int = 128;
+
+pub const ERR_TXT_MALLOCED: c_int = 0x01;
+pub const ERR_TXT_STRING: c_int = 0x02;
+
+pub const EVP_R_BAD_DECRYPT: c_int = 100;
+
 pub const PEM_R_NO_START_LINE: c_int = 108;
 
+pub const SSL_R_CERTIFICATE_VERIFY_FAILED: c_int = 134;
+
+pub const X509_R_KEY_VALUES_MISMATCH: c_int = 116;
+
 pub const EVP_MAX_MD_SIZE: c_uint = 64;
 pub const EVP_PKEY_RSA: c_int = NID_rsaEncryption;
 pub const EVP_PKEY_HMAC: c_int = NID_hmac;
 pub const EVP_PKEY_DSA: c_int = NID_dsa;
 pub const EVP_PKEY_DH: c_int = NID_dhKeyAgreement;
 pub const EVP_PKEY_EC: c_int = NID_X9_62_id_ecPublicKey;
+pub const EVP_PKEY_X25519: c_int = NID_X25519;
+pub const EVP_PKEY_ED25519: c_int = NID_ED25519;
+#[cfg(ossl110)]
+pub const EVP_PKEY_HKDF: c_int = NID_hkdf;
 
 pub const EVP_PKEY_ALG_CTRL: c_int = 0x1000;
 
 pub const EVP_PKEY_CTRL_RSA_PADDING: c_int = EVP_PKEY_ALG_CTRL + 1;
+pub const EVP_PKEY_CTRL_RSA_PSS_SALTLEN: c_int = EVP_PKEY_ALG_CTRL + 2;
 
+pub const EVP_PKEY_CTRL_RSA_MGF1_MD: c_int = EVP_PKEY_ALG_CTRL + 5;
 pub const EVP_PKEY_CTRL_GET_RSA_PADDING: c_int = EVP_PKEY_ALG_CTRL + 6;
+pub const EVP_PKEY_CTRL_RSA_OAEP_MD: c_int = EVP_PKEY_ALG_CTRL + 9;
+pub const EVP_PKEY_CTRL_RSA_OAEP_LABEL: c_int = EVP_PKEY_ALG_CTRL + 10;
+
+pub const EVP_PKEY_CTRL_DSA_PARAMGEN_BITS: c_int = EVP_PKEY_ALG_CTRL + 1;
+
+pub const EVP_PKEY_CTRL_DH_PARAMGEN_PRIME_LEN: c_int = EVP_PKEY_ALG_CTRL + 1;
+pub const EVP_PKEY_CTRL_DH_PARAMGEN_GENERATOR: c_int = EVP_PKEY_ALG_CTRL + 2;
+
+pub const EVP_PKEY_CTRL_EC_PARAMGEN_CURVE_NID: c_int = EVP_PKEY_ALG_CTRL + 1;
+
+#[cfg(ossl110)]
+pub const EVP_PKEY_CTRL_HKDF_MD: c_int = EVP_PKEY_ALG_CTRL + 3;
+#[cfg(ossl110)]
+pub const EVP_PKEY_CTRL_HKDF_SALT: c_int = EVP_PKEY_ALG_CTRL + 4;
+#[cfg(ossl110)]
+pub const EVP_PKEY_CTRL_HKDF_KEY: c_int = EVP_PKEY_ALG_CTRL + 5;
+#[cfg(ossl110)]
+pub const EVP_PKEY_CTRL_HKDF_INFO: c_int = EVP_PKEY_ALG_CTRL + 6;
+
+pub const EVP_PKEY_OP_PARAMGEN: c_int = 1 << 1;
+pub const EVP_PKEY_OP_KEYGEN: c_int = 1 << 2;
+pub const EVP_PKEY_OP_SIGN: c_int = 1 << 3;
+pub const EVP_PKEY_OP_VERIFY: c_int = 1 << 4;
+pub const EVP_PKEY_OP_VERIFYRECOVER: c_int = 1 << 5;
+pub const EVP_PKEY_OP_SIGNCTX: c_int = 1 << 6;
+pub const EVP_PKEY_OP_VERIFYCTX: c_int = 1 << 7;
+pub const EVP_PKEY_OP_ENCRYPT: c_int = 1 << 8;
+pub const EVP_PKEY_OP_DECRYPT: c_int = 1 << 9;
+
+pub const EVP_PKEY_OP_TYPE_SIG: c_int = EVP_PKEY_OP_SIGN | EVP_PKEY_OP_VERIFY |
+                                        EVP_PKEY_OP_VERIFYRECOVER | EVP_PKEY_OP_SIGNCTX |
+                                        EVP_PKEY_OP_VERIFYCTX;
+pub const EVP_PKEY_OP_TYPE_CRYPT: c_int = EVP_PKEY_OP_ENCRYPT | EVP_PKEY_OP_DECRYPT;
+
+pub const EVP_CIPH_FLAG_AEAD_CIPHER: c_ulong = 0x200000;
 
 pub const EVP_CTRL_GCM_SET_IVLEN: c_int = 0x9;
 pub const EVP_CTRL_GCM_GET_TAG: c_int = 0x10;
 pub const EVP_CTRL_GCM_SET_TAG: c_int = 0x11;
+pub const EVP_CTRL_CCM_SET_IVLEN: c_int = EVP_CTRL_GCM_SET_IVLEN;
+pub const EVP_CTRL_CCM_GET_TAG: c_int = EVP_CTRL_GCM_GET_TAG;
+pub const EVP_CTRL_CCM_SET_TAG: c_int = EVP_CTRL_GCM_SET_TAG;
 
 pub const MBSTRING_ASC:  c_int = MBSTRING_FLAG | 1;
 pub const MBSTRING_BMP:  c_int = MBSTRING_FLAG | 2;
@@ -1079,6 +1188,20 @@ pub const NID_rc4_hmac_md5: c_int = 915;
 pub const NID_aes_128_cbc_hmac_sha1: c_int = 916;
 pub const NID_aes_192_cbc_hmac_sha1: c_int = 917;
 pub const NID_aes_256_cbc_hmac_sha1: c_int = 918;
+pub const NID_X25519: c_int = 1034;
+#[cfg(ossl110)]
+pub const NID_hkdf: c_int = 1036;
+pub const NID_ED25519: c_int = 1087;
+pub const NID_chacha20_poly1305: c_int = 1018;
+pub const NID_chacha20: c_int = 1019;
+pub const NID_blake2b512: c_int = 1056;
+pub const NID_blake2s256: c_int = 1057;
+pub const NID_sha3_224: c_int = 1096;
+pub const NID_sha3_256: c_int = 1097;
+pub const NID_sha3_384: c_int = 1098;
+pub const NID_sha3_512: c_int = 1099;
+pub const NID_shake128: c_int = 1100;
+pub const NID_shake256: c_int = 1101;
 
 pub const OCSP_NOCERTS: c_ulong = 0x1;
 pub const OCSP_NOINTERN: c_ulong = 0x2;
@@ -1126,12 +1249,50 @@ pub const RSA_SSLV23_PADDING: c_int = 2;
 pub const RSA_NO_PADDING: c_int = 3;
 pub const RSA_PKCS1_OAEP_PADDING: c_int = 4;
 pub const RSA_X931_PADDING: c_int = 5;
+pub const RSA_PKCS1_PSS_PADDING: c_int = 6;
+
+pub const RSA_PSS_SALTLEN_DIGEST: c_int = -1;
+pub const RSA_PSS_SALTLEN_MAX: c_int = -2;
+
+pub const SRTP_AES128_CM_SHA1_80: c_ulong = 0x0001;
+pub const SRTP_AES128_CM_SHA1_32: c_ulong = 0x0002;
+
+pub const SSL_CB_READ: c_int = 0x04;
+pub const SSL_CB_ALERT: c_int = 0x4000;
+pub const SSL_CB_READ_ALERT: c_int = SSL_CB_ALERT | SSL_CB_READ;
+pub const SSL_CB_HANDSHAKE_START: c_int = 0x10;
+pub const SSL_CB_HANDSHAKE_DONE: c_int = 0x20;
+
+pub const SSL3_VERSION: c_int = 0x300;
+pub const TLS1_VERSION: c_int = 0x301;
+pub const TLS1_1_VERSION: c_int = 0x302;
+pub const TLS1_2_VERSION: c_int = 0x303;
+pub const TLS1_3_VERSION: c_int = 0x304;
+pub const DTLS1_VERSION: c_int = 0xFEFF;
+pub const DTLS1_2_VERSION: c_int = 0xFEFD;
+pub const DTLS1_BAD_VER: c_int = 0x0100;
+pub const DTLS_ANY_VERSION: c_int = 0x1FFFF;
 
 pub const SSL_CTRL_SET_TMP_DH: c_int = 3;
 pub const SSL_CTRL_SET_TMP_ECDH: c_int = 4;
 pub const SSL_CTRL_EXTRA_CHAIN_CERT: c_int = 14;
+pub const SSL_CTRL_SET_MTU: c_int = 17;
+pub const SSL_CTRL_SESS_NUMBER: c_int = 20;
+pub const SSL_CTRL_SESS_CONNECT: c_int = 21;
+pub const SSL_CTRL_SESS_CONNECT_GOOD: c_int = 22;
+pub const SSL_CTRL_SESS_CONNECT_RENEGOTIATE: c_int = 23;
+pub const SSL_CTRL_SESS_ACCEPT: c_int = 24;
+pub const SSL_CTRL_SESS_ACCEPT_GOOD: c_int = 25;
+pub const SSL_CTRL_SESS_ACCEPT_RENEGOTIATE: c_int = 26;
+pub const SSL_CTRL_SESS_HIT: c_int = 27;
+pub const SSL_CTRL_SESS_CB_HIT: c_int = 28;
+pub const SSL_CTRL_SESS_MISSES: c_int = 29;
+pub const SSL_CTRL_SESS_TIMEOUTS: c_int = 30;
+pub const SSL_CTRL_SESS_CACHE_FULL: c_int = 31;
 pub const SSL_CTRL_MODE: c_int = 33;
 pub const SSL_CTRL_SET_READ_AHEAD: c_int = 41;
+pub const SSL_CTRL_SET_MAX_CERT_LIST: c_int = 51;
+pub const SSL_CTRL_SET_MAX_SEND_FRAGMENT: c_int = 52;
 pub const SSL_CTRL_SET_TLSEXT_SERVERNAME_CB:  c_int = 53;
 pub const SSL_CTRL_SET_TLSEXT_SERVERNAME_ARG: c_int = 54;
 pub const SSL_CTRL_SET_TLSEXT_HOSTNAME: c_int = 55;
@@ -1141,6 +1302,15 @@ pub const SSL_CTRL_SET_TLSEXT_STATUS_REQ_TYPE: c_int = 65;
 pub const SSL_CTRL_GET_TLSEXT_STATUS_REQ_OCSP_RESP: c_int = 70;
 pub const SSL_CTRL_SET_TLSEXT_STATUS_REQ_OCSP_RESP: c_int = 71;
 pub const SSL_CTRL_GET_EXTRA_CHAIN_CERTS: c_int = 82;
+#[cfg(not(any(ossl101, libressl)))]
+pub const SSL_CTRL_BUILD_CERT_CHAIN: c_int = 105;
+pub const SSL_CTRL_GET_PEER_SIGNATURE_NID: c_int = 108;
+#[cfg(ossl110)]
+pub const SSL_CTRL_SET_MIN_PROTO_VERSION: c_int = 123;
+#[cfg(ossl110)]
+pub const SSL_CTRL_SET_MAX_PROTO_VERSION: c_int = 124;
+#[cfg(ossl110)]
+pub const SSL_CTRL_SET_SPLIT_SEND_FRAGMENT: c_int = 125;
 
 pub const SSL_MODE_ENABLE_PARTIAL_WRITE: c_long = 0x1;
 pub const SSL_MODE_ACCEPT_MOVING_WRITE_BUFFER: c_long = 0x2;
@@ -1166,6 +1336,8 @@ pub const SSL_ERROR_ZERO_RETURN: c_int = 6;
 pub const SSL_VERIFY_NONE: c_int = 0;
 pub const SSL_VERIFY_PEER: c_int = 1;
 pub const SSL_VERIFY_FAIL_IF_NO_PEER_CERT: c_int = 2;
+#[cfg(ossl111)]
+pub const SSL_VERIFY_POST_HANDSHAKE: c_int = 8;
 
 #[cfg(not(ossl101))]
 pub const SSL_OP_TLSEXT_PADDING: c_ulong =                          0x00000010;
@@ -1189,6 +1361,10 @@ pub const SSL_OP_NO_SSLv3: c_ulong =                                0x02000000;
 pub const SSL_OP_NO_TLSv1: c_ulong =                                0x04000000;
 pub const SSL_OP_NO_TLSv1_2: c_ulong =                              0x08000000;
 pub const SSL_OP_NO_TLSv1_1: c_ulong =                              0x10000000;
+#[cfg(ossl111)]
+pub const SSL_OP_NO_TLSv1_3: c_ulong =                              0x20000000;
+#[cfg(ossl111)]
+pub const SSL_OP_NO_RENEGOTIATION: c_ulong =                        0x40000000;
 
 #[cfg(not(any(ossl101, libressl)))]
 pub const SSL_OP_NO_DTLSv1: c_ulong =                               0x04000000;
@@ -1202,21 +1378,93 @@ pub const TLSEXT_NAMETYPE_host_name: c_int = 0;
 
 pub const TLSEXT_STATUSTYPE_ocsp: c_int = 1;
 
+#[cfg(ossl111)]
+pub const TLSEXT_max_fragment_length_DISABLED: u8 = 0;
+#[cfg(ossl111)]
+pub const TLSEXT_max_fragment_length_512: u8 = 1;
+#[cfg(ossl111)]
+pub const TLSEXT_max_fragment_length_1024: u8 = 2;
+#[cfg(ossl111)]
+pub const TLSEXT_max_fragment_length_2048: u8 = 3;
+#[cfg(ossl111)]
+pub const TLSEXT_max_fragment_length_4096: u8 = 4;
+
 pub const SSL_TLSEXT_ERR_OK: c_int = 0;
 pub const SSL_TLSEXT_ERR_ALERT_WARNING: c_int = 1;
 pub const SSL_TLSEXT_ERR_ALERT_FATAL: c_int = 2;
 pub const SSL_TLSEXT_ERR_NOACK: c_int = 3;
 
+pub const SSL3_AL_WARNING: c_int = 1;
+pub const SSL3_AL_FATAL: c_int = 2;
+
+pub const SSL_AD_CLOSE_NOTIFY: c_int = 0;
+pub const SSL_AD_UNEXPECTED_MESSAGE: c_int = 10;
+pub const SSL_AD_BAD_RECORD_MAC: c_int = 20;
+pub const SSL_AD_RECORD_OVERFLOW: c_int = 22;
+pub const SSL_AD_DECOMPRESSION_FAILURE: c_int = 30;
+pub const SSL_AD_HANDSHAKE_FAILURE: c_int = 40;
+pub const SSL_AD_BAD_CERTIFICATE: c_int = 42;
+pub const SSL_AD_UNSUPPORTED_CERTIFICATE: c_int = 43;
+pub const SSL_AD_CERTIFICATE_REVOKED: c_int = 44;
+pub const SSL_AD_CERTIFICATE_EXPIRED: c_int = 45;
+pub const SSL_AD_CERTIFICATE_UNKNOWN: c_int = 46;
+pub const SSL_AD_ILLEGAL_PARAMETER: c_int = 47;
+pub const SSL_AD_UNKNOWN_CA: c_int = 48;
+pub const SSL_AD_ACCESS_DENIED: c_int = 49;
+pub const SSL_AD_DECODE_ERROR: c_int = 50;
+pub const SSL_AD_DECRYPT_ERROR: c_int = 51;
+pub const SSL_AD_PROTOCOL_VERSION: c_int = 70;
+pub const SSL_AD_INSUFFICIENT_SECURITY: c_int = 71;
+pub const SSL_AD_INTERNAL_ERROR: c_int = 80;
+pub const SSL_AD_USER_CANCELLED: c_int = 90;
+pub const SSL_AD_NO_RENEGOTIATION: c_int = 100;
+pub const SSL_AD_UNSUPPORTED_EXTENSION: c_int = 110;
+pub const SSL_AD_UNRECOGNIZED_NAME: c_int = 112;
+pub const SSL_AD_NO_APPLICATION_PROTOCOL: c_int = 120;
+
+#[cfg(ossl111)]
+pub const SSL_CLIENT_HELLO_SUCCESS: c_int = 1;
+#[cfg(ossl111)]
+pub const SSL_CLIENT_HELLO_ERROR: c_int = 0;
+
+#[cfg(ossl111)]
+pub const SSL_SERVERINFOV1: c_uint = 1;
+#[cfg(ossl111)]
+pub const SSL_SERVERINFOV2: c_uint = 2;
+
+#[cfg(not(any(ossl101, libressl)))]
+pub const SSL_BUILD_CHAIN_FLAG_UNTRUSTED: c_long = 0x1;
+#[cfg(not(any(ossl101, libressl)))]
+pub const SSL_BUILD_CHAIN_FLAG_NO_ROOT: c_long = 0x2;
+#[cfg(not(any(ossl101, libressl)))]
+pub const SSL_BUILD_CHAIN_FLAG_CHECK: c_long = 0x4;
+#[cfg(not(any(ossl101, libressl)))]
+pub const SSL_BUILD_CHAIN_FLAG_IGNORE_ERROR: c_long = 0x8;
+#[cfg(not(any(ossl101, libressl)))]
+pub const SSL_BUILD_CHAIN_FLAG_CLEAR_ERROR: c_long = 0x10;
+
 pub const OPENSSL_NPN_UNSUPPORTED: c_int = 0;
 pub const OPENSSL_NPN_NEGOTIATED: c_int = 1;
 pub const OPENSSL_NPN_NO_OVERLAP: c_int = 2;
 
 pub const V_ASN1_GENERALIZEDTIME: c_int = 24;
 pub const V_ASN1_UTCTIME:         c_int = 23;
+pub const V_ASN1_BOOLEAN:         c_int = 1;
+pub const V_ASN1_OCTET_STRING:    c_int = 4;
+pub const V_ASN1_NULL:            c_int = 5;
+pub const V_ASN1_SEQUENCE:        c_int = 16;
+pub const V_ASN1_UTF8STRING:      c_int = 12;
+pub const V_ASN1_NUMERICSTRING:   c_int = 18;
+pub const V_ASN1_PRINTABLESTRING: c_int = 19;
+pub const V_ASN1_IA5STRING:       c_int = 22;
+pub const V_ASN1_VISIBLESTRING:   c_int = 26;
+pub const V_ASN1_BMPSTRING:       c_int = 30;
 
 pub const X509_FILETYPE_ASN1: c_int = 2;
 pub const X509_FILETYPE_DEFAULT: c_int = 3;
 pub const X509_FILETYPE_PEM: c_int = 1;
+pub const XN_FLAG_RFC2253: c_ulong = 0x1110317;
+
 pub const X509_V_ERR_AKID_ISSUER_SERIAL_MISMATCH: c_int = 31;
 pub const X509_V_ERR_AKID_SKID_MISMATCH: c_int = 30;
 pub const X509_V_ERR_APPLICATION_VERIFICATION: c_int = 50;
@@ -1239,6 +1487,12 @@ pub const X509_V_ERR_ERROR_IN_CRL_LAST_UPDATE_FIELD: c_int = 15;
 pub const X509_V_ERR_ERROR_IN_CRL_NEXT_UPDATE_FIELD: c_int = 16;
 pub const X509_V_ERR_EXCLUDED_VIOLATION: c_int = 48;
 pub const X509_V_ERR_INVALID_CA: c_int = 24;
+#[cfg(not(any(ossl101, libressl)))]
+pub const X509_V_ERR_HOSTNAME_MISMATCH: c_int = 62;
+#[cfg(not(any(ossl101, libressl)))]
+pub const X509_V_ERR_EMAIL_MISMATCH: c_int = 63;
+#[cfg(not(any(ossl101, libressl)))]
+pub const X509_V_ERR_IP_ADDRESS_MISMATCH: c_int = 64;
 pub const X509_V_ERR_INVALID_EXTENSION: c_int = 41;
 pub const X509_V_ERR_INVALID_NON_CA: c_int = 37;
 pub const X509_V_ERR_INVALID_POLICY_EXTENSION: c_int = 42;
@@ -1283,6 +1537,21 @@ pub const X509_CHECK_FLAG_MULTI_LABEL_WILDCARDS: c_uint = 0x8;
 #[cfg(not(any(ossl101, libressl)))]
 pub const X509_CHECK_FLAG_SINGLE_LABEL_SUBDOMAINS: c_uint = 0x10;
 
+pub const X509_V_FLAG_USE_CHECK_TIME: c_ulong = 0x2;
+pub const X509_V_FLAG_CRL_CHECK: c_ulong = 0x4;
+pub const X509_V_FLAG_CRL_CHECK_ALL: c_ulong = 0x8;
+pub const X509_V_FLAG_IGNORE_CRITICAL: c_ulong = 0x10;
+pub const X509_V_FLAG_X509_STRICT: c_ulong = 0x20;
+pub const X509_V_FLAG_ALLOW_PROXY_CERTS: c_ulong = 0x40;
+pub const X509_V_FLAG_POLICY_CHECK: c_ulong = 0x80;
+pub const X509_V_FLAG_EXPLICIT_POLICY: c_ulong = 0x100;
+pub const X509_V_FLAG_INHIBIT_ANY: c_ulong = 0x200;
+pub const X509_V_FLAG_INHIBIT_MAP: c_ulong = 0x400;
+pub const X509_V_FLAG_NOTIFY_POLICY: c_ulong = 0x800;
+pub const X509_V_FLAG_EXTENDED_CRL_SUPPORT: c_ulong = 0x1000;
+pub const X509_V_FLAG_USE_DELTAS: c_ulong = 0x2000;
+pub const X509_V_FLAG_CHECK_SS_SIGNATURE: c_ulong = 0x4000;
+
 pub const GEN_OTHERNAME: c_int = 0;
 pub const GEN_EMAIL: c_int = 1;
 pub const GEN_DNS: c_int = 2;
@@ -1293,6 +1562,20 @@ pub const GEN_URI: c_int = 6;
 pub const GEN_IPADD: c_int = 7;
 pub const GEN_RID: c_int = 8;
 
+pub const CRL_REASON_UNSPECIFIED: c_int = 0;
+pub const CRL_REASON_KEY_COMPROMISE: c_int = 1;
+pub const CRL_REASON_CA_COMPROMISE: c_int = 2;
+pub const CRL_REASON_AFFILIATION_CHANGED: c_int = 3;
+pub const CRL_REASON_SUPERSEDED: c_int = 4;
+pub const CRL_REASON_CESSATION_OF_OPERATION: c_int = 5;
+pub const CRL_REASON_CERTIFICATE_HOLD: c_int = 6;
+pub const CRL_REASON_REMOVE_FROM_CRL: c_int = 8;
+pub const CRL_REASON_PRIVILEGE_WITHDRAWN: c_int = 9;
+pub const CRL_REASON_AA_COMPROMISE: c_int = 10;
+
+pub const X509V3_ADD_DEFAULT: c_ulong = 0;
+pub const X509V3_ADD_REPLACE: c_ulong = 2;
+
 // macros
 pub unsafe fn BIO_get_mem_data(b: *mut BIO, pp: *mut *mut c_char) -> c_long {
     BIO_ctrl(b, BIO_CTRL_INFO, 0, pp as *mut c_void)
@@ -1319,6 +1602,59 @@ pub unsafe fn EVP_PKEY_CTX_get_rsa_padding(ctx: *mut EVP_PKEY_CTX, ppad: *mut c_
     EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_RSA, -1, EVP_PKEY_CTRL_GET_RSA_PADDING, 0, ppad as *mut c_void)
 }
 
+pub unsafe fn EVP_PKEY_CTX_set_rsa_pss_saltlen(ctx: *mut EVP_PKEY_CTX, len: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_RSA, -1, EVP_PKEY_CTRL_RSA_PSS_SALTLEN, len, ptr::null_mut())
+}
+
+pub unsafe fn EVP_PKEY_CTX_set_rsa_mgf1_md(ctx: *mut EVP_PKEY_CTX, md: *const EVP_MD) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_RSA, EVP_PKEY_OP_TYPE_SIG | EVP_PKEY_OP_TYPE_CRYPT,
+                      EVP_PKEY_CTRL_RSA_MGF1_MD, 0, md as *mut c_void)
+}
+
+pub unsafe fn EVP_PKEY_CTX_set_rsa_oaep_md(ctx: *mut EVP_PKEY_CTX, md: *const EVP_MD) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_RSA, EVP_PKEY_OP_TYPE_CRYPT, EVP_PKEY_CTRL_RSA_OAEP_MD, 0, md as *mut c_void)
+}
+
+pub unsafe fn EVP_PKEY_CTX_set0_rsa_oaep_label(ctx: *mut EVP_PKEY_CTX, label: *mut c_void, len: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_RSA, EVP_PKEY_OP_TYPE_CRYPT, EVP_PKEY_CTRL_RSA_OAEP_LABEL, len, label)
+}
+
+pub unsafe fn EVP_PKEY_CTX_set_dsa_paramgen_bits(ctx: *mut EVP_PKEY_CTX, nbits: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_DSA, EVP_PKEY_OP_PARAMGEN, EVP_PKEY_CTRL_DSA_PARAMGEN_BITS, nbits, ptr::null_mut())
+}
+
+pub unsafe fn EVP_PKEY_CTX_set_dh_paramgen_prime_len(ctx: *mut EVP_PKEY_CTX, len: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_DH, EVP_PKEY_OP_PARAMGEN, EVP_PKEY_CTRL_DH_PARAMGEN_PRIME_LEN, len, ptr::null_mut())
+}
+
+pub unsafe fn EVP_PKEY_CTX_set_dh_paramgen_generator(ctx: *mut EVP_PKEY_CTX, gen: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_DH, EVP_PKEY_OP_PARAMGEN, EVP_PKEY_CTRL_DH_PARAMGEN_GENERATOR, gen, ptr::null_mut())
+}
+
+pub unsafe fn EVP_PKEY_CTX_set_ec_paramgen_curve_nid(ctx: *mut EVP_PKEY_CTX, nid: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, EVP_PKEY_EC, EVP_PKEY_OP_PARAMGEN | EVP_PKEY_OP_KEYGEN, EVP_PKEY_CTRL_EC_PARAMGEN_CURVE_NID, nid, ptr::null_mut())
+}
+
+#[cfg(ossl110)]
+pub unsafe fn EVP_PKEY_CTX_set_hkdf_md(ctx: *mut EVP_PKEY_CTX, md: *const EVP_MD) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, -1, -1, EVP_PKEY_CTRL_HKDF_MD, 0, md as *mut c_void)
+}
+
+#[cfg(ossl110)]
+pub unsafe fn EVP_PKEY_CTX_set1_hkdf_salt(ctx: *mut EVP_PKEY_CTX, salt: *const c_uchar, len: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, -1, -1, EVP_PKEY_CTRL_HKDF_SALT, len, salt as *mut c_void)
+}
+
+#[cfg(ossl110)]
+pub unsafe fn EVP_PKEY_CTX_set1_hkdf_key(ctx: *mut EVP_PKEY_CTX, key: *const c_uchar, len: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, -1, -1, EVP_PKEY_CTRL_HKDF_KEY, len, key as *mut c_void)
+}
+
+#[cfg(ossl110)]
+pub unsafe fn EVP_PKEY_CTX_add1_hkdf_info(ctx: *mut EVP_PKEY_CTX, info: *const c_uchar, len: c_int) -> c_int {
+    EVP_PKEY_CTX_ctrl(ctx, -1, -1, EVP_PKEY_CTRL_HKDF_INFO, len, info as *mut c_void)
+}
+
 pub unsafe fn SSL_CTX_set_mode(ctx: *mut SSL_CTX, op: c_long) -> c_long {
     SSL_CTX_ctrl(ctx, SSL_CTRL_MODE, op, ptr::null_mut())
 }
@@ -1327,6 +1663,87 @@ pub unsafe fn SSL_CTX_set_read_ahead(ctx: *mut SSL_CTX, m: c_long) -> c_long {
     SSL_CTX_ctrl(ctx, SSL_CTRL_SET_READ_AHEAD, m, ptr::null_mut())
 }
 
+pub unsafe fn SSL_CTX_set_max_cert_list(ctx: *mut SSL_CTX, m: c_long) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SET_MAX_CERT_LIST, m, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_set_max_send_fragment(ctx: *mut SSL_CTX, m: c_long) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SET_MAX_SEND_FRAGMENT, m, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_number(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_NUMBER, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_connect(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_CONNECT, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_connect_good(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_CONNECT_GOOD, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_connect_renegotiate(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_CONNECT_RENEGOTIATE, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_accept(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_ACCEPT, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_accept_good(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_ACCEPT_GOOD, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_accept_renegotiate(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_ACCEPT_RENEGOTIATE, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_hits(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_HIT, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_cb_hits(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_CB_HIT, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_misses(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_MISSES, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_timeouts(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_TIMEOUTS, 0, ptr::null_mut())
+}
+
+pub unsafe fn SSL_CTX_sess_cache_full(ctx: *mut SSL_CTX) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SESS_CACHE_FULL, 0, ptr::null_mut())
+}
+
+#[cfg(ossl110)]
+pub unsafe fn SSL_CTX_set_min_proto_version(ctx: *mut SSL_CTX, version: c_int) -> c_int {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SET_MIN_PROTO_VERSION, version as c_long, ptr::null_mut()) as c_int
+}
+
+#[cfg(ossl110)]
+pub unsafe fn SSL_CTX_set_max_proto_version(ctx: *mut SSL_CTX, version: c_int) -> c_int {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SET_MAX_PROTO_VERSION, version as c_long, ptr::null_mut()) as c_int
+}
+
+#[cfg(ossl110)]
+pub unsafe fn SSL_set_min_proto_version(s: *mut SSL, version: c_int) -> c_int {
+    SSL_ctrl(s, SSL_CTRL_SET_MIN_PROTO_VERSION, version as c_long, ptr::null_mut()) as c_int
+}
+
+#[cfg(ossl110)]
+pub unsafe fn SSL_set_max_proto_version(s: *mut SSL, version: c_int) -> c_int {
+    SSL_ctrl(s, SSL_CTRL_SET_MAX_PROTO_VERSION, version as c_long, ptr::null_mut()) as c_int
+}
+
+#[cfg(ossl110)]
+pub unsafe fn SSL_CTX_set_split_send_fragment(ctx: *mut SSL_CTX, m: c_long) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_SET_SPLIT_SEND_FRAGMENT, m, ptr::null_mut())
+}
+
 pub unsafe fn SSL_CTX_set_tmp_dh(ctx: *mut SSL_CTX, dh: *mut DH) -> c_long {
     SSL_CTX_ctrl(ctx, SSL_CTRL_SET_TMP_DH, 0, dh as *mut c_void)
 }
@@ -1343,6 +1760,10 @@ pub unsafe fn SSL_set_tmp_ecdh(ssl: *mut SSL, key: *mut EC_KEY) -> c_long {
     SSL_ctrl(ssl, SSL_CTRL_SET_TMP_ECDH, 0, key as *mut c_void)
 }
 
+pub unsafe fn SSL_set_mtu(ssl: *mut SSL, mtu: c_long) -> c_long {
+    SSL_ctrl(ssl, SSL_CTRL_SET_MTU, mtu, ptr::null_mut())
+}
+
 pub unsafe fn SSL_CTX_add_extra_chain_cert(ctx: *mut SSL_CTX, x509: *mut X509) -> c_long {
     SSL_CTX_ctrl(ctx, SSL_CTRL_EXTRA_CHAIN_CERT, 0, x509 as *mut c_void)
 }
@@ -1377,6 +1798,15 @@ pub unsafe fn SSL_CTX_get_extra_chain_certs(ctx: *mut SSL_CTX, chain: *mut *mut
     SSL_CTX_ctrl(ctx, SSL_CTRL_GET_EXTRA_CHAIN_CERTS, 0, chain as *mut c_void)
 }
 
+#[cfg(not(any(ossl101, libressl)))]
+pub unsafe fn SSL_CTX_build_cert_chain(ctx: *mut SSL_CTX, flags: c_long) -> c_long {
+    SSL_CTX_ctrl(ctx, SSL_CTRL_BUILD_CERT_CHAIN, flags, ptr::null_mut())
+}
+
+pub unsafe fn SSL_get_peer_signature_nid(ssl: *mut SSL, pnid: *mut c_int) -> c_long {
+    SSL_ctrl(ssl, SSL_CTRL_GET_PEER_SIGNATURE_NID, 0, pnid as *mut c_void)
+}
+
 pub unsafe fn SSL_get_tlsext_status_ocsp_resp(ssl: *mut SSL, resp: *mut *mut c_uchar) -> c_long {
     SSL_ctrl(ssl, SSL_CTRL_GET_TLSEXT_STATUS_REQ_OCSP_RESP, 0, resp as *mut c_void)
 }
@@ -1402,14 +1832,25 @@ extern {
     pub fn AES_set_decrypt_key(userKey: *const c_uchar, bits: c_int, key: *mut AES_KEY) -> c_int;
     pub fn AES_ige_encrypt(in_: *const c_uchar, out: *mut c_uchar, length: size_t, key: *const AES_KEY, ivec: *mut c_uchar, enc: c_int);
 
+    pub fn ASN1_INTEGER_new() -> *mut ASN1_INTEGER;
+    pub fn ASN1_INTEGER_free(x: *mut ASN1_INTEGER);
     pub fn ASN1_INTEGER_set(dest: *mut ASN1_INTEGER, value: c_long) -> c_int;
+    pub fn ASN1_INTEGER_to_BN(ai: *const ASN1_INTEGER, bn: *mut BIGNUM) -> *mut BIGNUM;
+    pub fn ASN1_OBJECT_free(x: *mut ASN1_OBJECT);
     pub fn ASN1_GENERALIZEDTIME_free(tm: *mut ASN1_GENERALIZEDTIME);
     pub fn ASN1_GENERALIZEDTIME_print(b: *mut BIO, tm: *const ASN1_GENERALIZEDTIME) -> c_int;
     pub fn ASN1_STRING_type_new(ty: c_int) -> *mut ASN1_STRING;
+    pub fn ASN1_TIME_new() -> *mut ASN1_TIME;
     pub fn ASN1_TIME_free(tm: *mut ASN1_TIME);
+    pub fn ASN1_TIME_set_string(s: *mut ASN1_TIME, str: *const c_char) -> c_int;
     pub fn ASN1_TIME_print(b: *mut BIO, tm: *const ASN1_TIME) -> c_int;
+    #[cfg(ossl111)]
+    pub fn ASN1_TIME_normalize(s: *mut ASN1_TIME) -> c_int;
+    #[cfg(ossl111)]
+    pub fn ASN1_TIME_cmp_time_t(s: *const ASN1_TIME, t: time_t) -> c_int;
 
     pub fn BIO_ctrl(b: *mut BIO, cmd: c_int, larg: c_long, parg: *mut c_void) -> c_long;
+    pub fn BIO_ctrl_pending(b: *mut BIO) -> size_t;
     pub fn BIO_free_all(b: *mut BIO);
     pub fn BIO_new_fp(stream: *mut FILE, close_flag: c_int) -> *mut BIO;
     pub fn BIO_new_socket(sock: c_int, close_flag: c_int) -> *mut BIO;
@@ -1492,12 +1933,18 @@ extern {
     /* Conversion from/to hexidecimal string representation */
     pub fn BN_hex2bn(a: *mut *mut BIGNUM, s: *const c_char) -> c_int;
     pub fn BN_bn2hex(a: *const BIGNUM) -> *mut c_char;
+    pub fn BN_to_ASN1_INTEGER(bn: *const BIGNUM, ai: *mut ASN1_INTEGER) -> *mut ASN1_INTEGER;
 
     pub fn CRYPTO_memcmp(a: *const c_void, b: *const c_void,
                          len: size_t) -> c_int;
 
     pub fn DH_new() -> *mut DH;
     pub fn DH_free(dh: *mut DH);
+    pub fn DH_size(dh: *const DH) -> c_int;
+    pub fn DH_generate_parameters_ex(dh: *mut DH, prime_len: c_int, generator: c_int,
+                                     cb: *mut BN_GENCB) -> c_int;
+    pub fn DH_generate_key(dh: *mut DH) -> c_int;
+    pub fn DH_compute_key(key: *mut c_uchar, pub_key: *const BIGNUM, dh: *mut DH) -> c_int;
     #[cfg(not(any(ossl101, libressl)))]
     pub fn DH_get_1024_160() -> *mut DH;
     #[cfg(not(any(ossl101, libressl)))]
@@ -1538,14 +1985,66 @@ extern {
     pub fn EC_POINT_point2oct(group: *const EC_GROUP, p: *const EC_POINT, form: point_conversion_form_t, buf: *mut c_uchar, len: size_t, ctx: *mut BN_CTX) -> size_t;
     pub fn EC_POINT_oct2point(group: *const EC_GROUP, p: *mut EC_POINT, buf: *const c_uchar, len: size_t, ctx: *mut BN_CTX) -> c_int;
     pub fn EC_POINT_cmp(group: *const EC_GROUP, a: *const EC_POINT, b: *const EC_POINT, ctx: *mut BN_CTX) -> c_int;
+    pub fn EC_POINT_get_affine_coordinates_GFp(group: *const EC_GROUP, p: *const EC_POINT, x: *mut BIGNUM, y: *mut BIGNUM, ctx: *mut BN_CTX) -> c_int;
+    pub fn EC_POINT_set_affine_coordinates_GFp(group: *const EC_GROUP, p: *mut EC_POINT, x: *const BIGNUM, y: *const BIGNUM, ctx: *mut BN_CTX) -> c_int;
+    pub fn EC_POINT_get_affine_coordinates_GF2m(group: *const EC_GROUP, p: *const EC_POINT, x: *mut BIGNUM, y: *mut BIGNUM, ctx: *mut BN_CTX) -> c_int;
+    pub fn EC_POINT_set_affine_coordinates_GF2m(group: *const EC_GROUP, p: *mut EC_POINT, x: *const BIGNUM, y: *const BIGNUM, ctx: *mut BN_CTX) -> c_int;
     pub fn EC_POINT_free(point: *mut EC_POINT);
 
+    pub fn ECDSA_SIG_new() -> *mut ECDSA_SIG;
+    pub fn ECDSA_SIG_free(sig: *mut ECDSA_SIG);
+    pub fn ECDSA_do_sign(dgst: *const c_uchar, dgst_len: c_int, eckey: *mut EC_KEY) -> *mut ECDSA_SIG;
+    pub fn ECDSA_do_verify(dgst: *const c_uchar, dgst_len: c_int, sig: *const ECDSA_SIG, eckey: *mut EC_KEY) -> c_int;
+
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_load_builtin_engines();
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_by_id(id: *const c_char) -> *mut ENGINE;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_free(e: *mut ENGINE) -> c_int;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_init(e: *mut ENGINE) -> c_int;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_finish(e: *mut ENGINE) -> c_int;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_get_id(e: *const ENGINE) -> *const c_char;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_get_name(e: *const ENGINE) -> *const c_char;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_ctrl_cmd_string(e: *mut ENGINE,
+                                  cmd_name: *const c_char,
+                                  arg: *const c_char,
+                                  cmd_optional: c_int)
+                                  -> c_int;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_load_private_key(e: *mut ENGINE,
+                                   key_id: *const c_char,
+                                   ui_method: *mut UI_METHOD,
+                                   callback_data: *mut c_void)
+                                   -> *mut EVP_PKEY;
+    #[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+    pub fn ENGINE_load_public_key(e: *mut ENGINE,
+                                  key_id: *const c_char,
+                                  ui_method: *mut UI_METHOD,
+                                  callback_data: *mut c_void)
+                                  -> *mut EVP_PKEY;
+
     pub fn ERR_peek_last_error() -> c_ulong;
     pub fn ERR_get_error() -> c_ulong;
+    pub fn ERR_get_error_line_data(file: *mut *const c_char,
+                                   line: *mut c_int,
+                                   data: *mut *const c_char,
+                                   flags: *mut c_int)
+                                   -> c_ulong;
     pub fn ERR_lib_error_string(err: c_ulong) -> *const c_char;
     pub fn ERR_func_error_string(err: c_ulong) -> *const c_char;
     pub fn ERR_reason_error_string(err: c_ulong) -> *const c_char;
     pub fn ERR_clear_error();
+    pub fn ERR_put_error(lib: c_int, func: c_int, reason: c_int, file: *const c_char, line: c_int);
+    pub fn ERR_add_error_data(num: c_int, ...);
+
+    pub fn EVP_get_digestbyname(name: *const c_char) -> *const EVP_MD;
+    pub fn EVP_get_cipherbyname(name: *const c_char) -> *const EVP_CIPHER;
 
     pub fn EVP_md5() -> *const EVP_MD;
     pub fn EVP_ripemd160() -> *const EVP_MD;
@@ -1554,12 +2053,28 @@ extern {
     pub fn EVP_sha256() -> *const EVP_MD;
     pub fn EVP_sha384() -> *const EVP_MD;
     pub fn EVP_sha512() -> *const EVP_MD;
+    #[cfg(ossl111)]
+    pub fn EVP_sha3_224() -> *const EVP_MD;
+    #[cfg(ossl111)]
+    pub fn EVP_sha3_256() -> *const EVP_MD;
+    #[cfg(ossl111)]
+    pub fn EVP_sha3_384() -> *const EVP_MD;
+    #[cfg(ossl111)]
+    pub fn EVP_sha3_512() -> *const EVP_MD;
+    #[cfg(ossl111)]
+    pub fn EVP_shake128() -> *const EVP_MD;
+    #[cfg(ossl111)]
+    pub fn EVP_shake256() -> *const EVP_MD;
+    pub fn EVP_MD_size(md: *const EVP_MD) -> c_int;
+    pub fn EVP_MD_type(md: *const EVP_MD) -> c_int;
+    pub fn EVP_CIPHER_nid(cipher: *const EVP_CIPHER) -> c_int;
 
     pub fn EVP_aes_128_cbc() -> *const EVP_CIPHER;
     pub fn EVP_aes_128_ecb() -> *const EVP_CIPHER;
     pub fn EVP_aes_128_xts() -> *const EVP_CIPHER;
     pub fn EVP_aes_128_ctr() -> *const EVP_CIPHER;
     pub fn EVP_aes_128_gcm() -> *const EVP_CIPHER;
+    pub fn EVP_aes_128_ccm() -> *const EVP_CIPHER;
     pub fn EVP_aes_128_cfb1() -> *const EVP_CIPHER;
     pub fn EVP_aes_128_cfb128() -> *const EVP_CIPHER;
     pub fn EVP_aes_128_cfb8() -> *const EVP_CIPHER;
@@ -1568,6 +2083,7 @@ extern {
     pub fn EVP_aes_256_xts() -> *const EVP_CIPHER;
     pub fn EVP_aes_256_ctr() -> *const EVP_CIPHER;
     pub fn EVP_aes_256_gcm() -> *const EVP_CIPHER;
+    pub fn EVP_aes_256_ccm() -> *const EVP_CIPHER;
     pub fn EVP_aes_256_cfb1() -> *const EVP_CIPHER;
     pub fn EVP_aes_256_cfb128() -> *const EVP_CIPHER;
     pub fn EVP_aes_256_cfb8() -> *const EVP_CIPHER;
@@ -1589,6 +2105,8 @@ extern {
     pub fn EVP_CIPHER_CTX_set_key_length(ctx: *mut EVP_CIPHER_CTX, keylen: c_int) -> c_int;
     pub fn EVP_CIPHER_CTX_ctrl(ctx: *mut EVP_CIPHER_CTX, type_: c_int, arg: c_int, ptr: *mut c_void) -> c_int;
     pub fn EVP_CIPHER_CTX_free(ctx: *mut EVP_CIPHER_CTX);
+    pub fn EVP_CIPHER_CTX_key_length(ctx: *const EVP_CIPHER_CTX) -> c_int;
+    pub fn EVP_CIPHER_asn1_to_param(ctx: *mut EVP_CIPHER_CTX, type_: *mut ASN1_TYPE) -> c_int;
 
     pub fn EVP_CipherInit(ctx: *mut EVP_CIPHER_CTX, evp: *const EVP_CIPHER,
                           key: *const u8, iv: *const u8, mode: c_int) -> c_int;
@@ -1602,11 +2120,45 @@ extern {
                             outlen: *mut c_int, inbuf: *const u8, inlen: c_int) -> c_int;
     pub fn EVP_CipherFinal(ctx: *mut EVP_CIPHER_CTX, res: *mut u8, len: *mut c_int) -> c_int;
 
+    pub fn EVP_EncodeBlock(t: *mut c_uchar, f: *const c_uchar, n: c_int) -> c_int;
+    pub fn EVP_DecodeBlock(t: *mut c_uchar, f: *const c_uchar, n: c_int) -> c_int;
+    pub fn EVP_EncodeInit(ctx: *mut EVP_ENCODE_CTX);
+    pub fn EVP_EncodeFinal(ctx: *mut EVP_ENCODE_CTX, out: *mut c_uchar, outl: *mut c_int);
+    pub fn EVP_DecodeInit(ctx: *mut EVP_ENCODE_CTX);
+    pub fn EVP_DecodeUpdate(ctx: *mut EVP_ENCODE_CTX,
+                            out: *mut c_uchar,
+                            outl: *mut c_int,
+                            in_: *const c_uchar,
+                            inl: c_int) -> c_int;
+    pub fn EVP_DecodeFinal(ctx: *mut EVP_ENCODE_CTX, out: *mut c_uchar, outl: *mut c_int) -> c_int;
+    pub fn EVP_EncryptUpdate(ctx: *mut EVP_CIPHER_CTX, out: *mut c_uchar, outl: *mut c_int,
+                             in_: *const c_uchar, inl: c_int) -> c_int;
+    pub fn EVP_DecryptUpdate(ctx: *mut EVP_CIPHER_CTX, out: *mut c_uchar, outl: *mut c_int,
+                             in_: *const c_uchar, inl: c_int) -> c_int;
+
+    pub fn EVP_SealInit(ctx: *mut EVP_CIPHER_CTX,
+                        type_: *const EVP_CIPHER,
+                        ek: *mut *mut c_uchar,
+                        ekl: *mut c_int,
+                        iv: *mut c_uchar,
+                        pubk: *mut *mut EVP_PKEY,
+                        npubk: c_int) -> c_int;
+    pub fn EVP_SealFinal(ctx: *mut EVP_CIPHER_CTX, out: *mut c_uchar, outl: *mut c_int) -> c_int;
+    pub fn EVP_OpenInit(ctx: *mut EVP_CIPHER_CTX,
+                        type_: *const EVP_CIPHER,
+                        ek: *const c_uchar,
+                        ekl: c_int,
+                        iv: *const c_uchar,
+                        priv_: *mut EVP_PKEY) -> c_int;
+    pub fn EVP_OpenFinal(ctx: *mut EVP_CIPHER_CTX, out: *mut c_uchar, outl: *mut c_int) -> c_int;
+
     pub fn EVP_DigestInit(ctx: *mut EVP_MD_CTX, typ: *const EVP_MD) -> c_int;
     pub fn EVP_DigestInit_ex(ctx: *mut EVP_MD_CTX, typ: *const EVP_MD, imple: *mut ENGINE) -> c_int;
     pub fn EVP_DigestUpdate(ctx: *mut EVP_MD_CTX, data: *const c_void, n: size_t) -> c_int;
     pub fn EVP_DigestFinal(ctx: *mut EVP_MD_CTX, res: *mut u8, n: *mut u32) -> c_int;
     pub fn EVP_DigestFinal_ex(ctx: *mut EVP_MD_CTX, res: *mut u8, n: *mut u32) -> c_int;
+    #[cfg(ossl111)]
+    pub fn EVP_DigestFinalXOF(ctx: *mut EVP_MD_CTX, res: *mut u8, len: size_t) -> c_int;
 
     pub fn EVP_DigestSignInit(ctx: *mut EVP_MD_CTX,
                               pctx: *mut *mut EVP_PKEY_CTX,
@@ -1629,6 +2181,18 @@ extern {
     pub fn EVP_DigestVerifyFinal(ctx: *mut EVP_MD_CTX,
                                  sigret: *const c_uchar,
                                  siglen: size_t) -> c_int;
+    #[cfg(ossl111)]
+    pub fn EVP_DigestSign(ctx: *mut EVP_MD_CTX,
+                          sigret: *mut c_uchar,
+                          siglen: *mut size_t,
+                          tbs: *const c_uchar,
+                          tbslen: size_t) -> c_int;
+    #[cfg(ossl111)]
+    pub fn EVP_DigestVerify(ctx: *mut EVP_MD_CTX,
+                            sigret: *const c_uchar,
+                            siglen: size_t,
+                            tbs: *const c_uchar,
+                            tbslen: size_t) -> c_int;
 
     pub fn EVP_MD_CTX_copy_ex(dst: *mut EVP_MD_CTX, src: *const EVP_MD_CTX) -> c_int;
 
@@ -1642,16 +2206,70 @@ extern {
     pub fn EVP_PKEY_get1_DH(k: *mut EVP_PKEY) -> *mut DH;
     pub fn EVP_PKEY_get1_EC_KEY(k: *mut EVP_PKEY) -> *mut EC_KEY;
     pub fn EVP_PKEY_cmp(a: *const EVP_PKEY, b: *const EVP_PKEY) -> c_int;
+    pub fn EVP_PKEY_id(pkey: *const EVP_PKEY) -> c_int;
+    pub fn EVP_PKEY_size(pkey: *mut EVP_PKEY) -> c_int;
     pub fn EVP_PKEY_new_mac_key(type_: c_int,
                                 e: *mut ENGINE,
                                 key: *const c_uchar,
                                 keylen: c_int) -> *mut EVP_PKEY;
-
-
+    #[cfg(ossl111)]
+    pub fn EVP_PKEY_new_raw_private_key(type_: c_int,
+                                        e: *mut ENGINE,
+                                        key: *const c_uchar,
+                                        keylen: size_t) -> *mut EVP_PKEY;
+    #[cfg(ossl111)]
+    pub fn EVP_PKEY_new_raw_public_key(type_: c_int,
+                                       e: *mut ENGINE,
+                                       key: *const c_uchar,
+                                       keylen: size_t) -> *mut EVP_PKEY;
+    #[cfg(ossl111)]
+    pub fn EVP_PKEY_get_raw_private_key(pkey: *const EVP_PKEY,
+                                        priv_: *mut c_uchar,
+                                        len: *mut size_t) -> c_int;
+    #[cfg(ossl111)]
+    pub fn EVP_PKEY_get_raw_public_key(pkey: *const EVP_PKEY,
+                                       pub_: *mut c_uchar,
+                                       len: *mut size_t) -> c_int;
+
+    pub fn EVP_PKEY_CTX_new(pkey: *mut EVP_PKEY, e: *mut ENGINE) -> *mut EVP_PKEY_CTX;
+    pub fn EVP_PKEY_CTX_new_id(id: c_int, e: *mut ENGINE) -> *mut EVP_PKEY_CTX;
+    pub fn EVP_PKEY_CTX_free(ctx: *mut EVP_PKEY_CTX);
+    pub fn EVP_PKEY_CTX_set_cb(ctx: *mut EVP_PKEY_CTX, cb: EVP_PKEY_gen_cb);
+    pub fn EVP_PKEY_CTX_get_keygen_info(ctx: *mut EVP_PKEY_CTX, idx: c_int) -> c_int;
+    pub fn EVP_PKEY_CTX_set_app_data(ctx: *mut EVP_PKEY_CTX, data: *mut c_void);
+    pub fn EVP_PKEY_CTX_get_app_data(ctx: *mut EVP_PKEY_CTX) -> *mut c_void;
+    pub fn EVP_PKEY_paramgen_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
+    pub fn EVP_PKEY_paramgen(ctx: *mut EVP_PKEY_CTX, ppkey: *mut *mut EVP_PKEY) -> c_int;
+    pub fn EVP_PKEY_keygen_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
+    pub fn EVP_PKEY_keygen(ctx: *mut EVP_PKEY_CTX, ppkey: *mut *mut EVP_PKEY) -> c_int;
     pub fn EVP_PKEY_CTX_ctrl(ctx: *mut EVP_PKEY_CTX, keytype: c_int, optype: c_int, cmd: c_int, p1: c_int, p2: *mut c_void) -> c_int;
+    pub fn EVP_PKEY_derive_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
+    pub fn EVP_PKEY_derive_set_peer(ctx: *mut EVP_PKEY_CTX, peer: *mut EVP_PKEY) -> c_int;
+    pub fn EVP_PKEY_derive(ctx: *mut EVP_PKEY_CTX, key: *mut c_uchar, size: *mut size_t) -> c_int;
+    pub fn EVP_PKEY_encrypt_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
+    pub fn EVP_PKEY_encrypt(ctx: *mut EVP_PKEY_CTX, out: *mut c_uchar, outlen: *mut size_t, in_: *const c_uchar, inlen: size_t) -> c_int;
+    #[cfg(not(libressl))]
+    pub fn FIPS_mode() -> c_int;
+    #[cfg(not(libressl))]
+    pub fn FIPS_mode_set(onoff: c_int) -> c_int;
+
+    pub fn EVP_PKEY_decrypt_init(ctx: *mut EVP_PKEY_CTX) -> c_int;
+    pub fn EVP_PKEY_decrypt(ctx: *mut EVP_PKEY_CTX, out: *mut c_uchar, outlen: *mut size_t, in_: *const c_uchar, inlen: size_t) -> c_int;
 
     pub fn HMAC_CTX_copy(dst: *mut HMAC_CTX, src: *mut HMAC_CTX) -> c_int;
 
+    pub fn OBJ_cmp(a: *const ASN1_OBJECT, b: *const ASN1_OBJECT) -> c_int;
+    pub fn OBJ_obj2nid(o: *const ASN1_OBJECT) -> c_int;
+    pub fn OBJ_txt2nid(s: *const c_char) -> c_int;
+    pub fn OBJ_obj2txt(buf: *mut c_char, buf_len: c_int, a: *const ASN1_OBJECT, no_name: c_int) -> c_int;
+    pub fn OBJ_txt2obj(s: *const c_char, no_name: c_int) -> *mut ASN1_OBJECT;
+    pub fn OBJ_nid2sn(n: c_int) -> *const c_char;
+    pub fn OBJ_nid2ln(n: c_int) -> *const c_char;
+    pub fn OBJ_sn2nid(s: *const c_char) -> c_int;
+    pub fn OBJ_ln2nid(s: *const c_char) -> c_int;
+    pub fn OBJ_find_sigid_algs(signid: c_int, pdig_nid: *mut c_int, ppkey_nid: *mut c_int) -> c_int;
+    pub fn OBJ_create(oid: *const c_char, sn: *const c_char, ln: *const c_char) -> c_int;
+
     pub fn OCSP_BASICRESP_new() -> *mut OCSP_BASICRESP;
     pub fn OCSP_BASICRESP_free(r: *mut OCSP_BASICRESP);
     pub fn OCSP_basic_verify(bs: *mut OCSP_BASICRESP, certs: *mut stack_st_X509, st: *mut X509_STORE, flags: c_ulong) -> c_int;
@@ -1682,6 +2300,8 @@ extern {
                              user_data: *mut c_void) -> *mut X509;
     pub fn PEM_read_bio_X509_REQ(bio: *mut BIO, out: *mut *mut X509_REQ, callback: Option<PasswordCallback>,
                              user_data: *mut c_void) -> *mut X509_REQ;
+    pub fn PEM_read_bio_X509_CRL(bio: *mut BIO, out: *mut *mut X509_CRL, callback: Option<PasswordCallback>,
+                             user_data: *mut c_void) -> *mut X509_CRL;
     pub fn PEM_read_bio_PrivateKey(bio: *mut BIO, out: *mut *mut EVP_PKEY, callback: Option<PasswordCallback>,
                              user_data: *mut c_void) -> *mut EVP_PKEY;
     pub fn PEM_read_bio_PUBKEY(bio: *mut BIO, out: *mut *mut EVP_PKEY, callback: Option<PasswordCallback>,
@@ -1715,9 +2335,13 @@ extern {
                                        kstr: *mut c_uchar, klen: c_int, callback: Option<PasswordCallback>,
                                        user_data: *mut c_void) -> c_int;
     pub fn PEM_write_bio_DSA_PUBKEY(bp: *mut BIO, dsa: *mut DSA) -> c_int;
+    pub fn PEM_read_bio_DSAparams(bp: *mut BIO, dsa: *mut *mut DSA, callback: Option<PasswordCallback>,
+                                  user_data: *mut c_void) -> *mut DSA;
+    pub fn PEM_write_bio_DSAparams(bp: *mut BIO, dsa: *mut DSA) -> c_int;
 
     pub fn PEM_write_bio_X509(bio: *mut BIO, x509: *mut X509) -> c_int;
     pub fn PEM_write_bio_X509_REQ(bio: *mut BIO, x509: *mut X509_REQ) -> c_int;
+    pub fn PEM_write_bio_X509_CRL(bio: *mut BIO, x509: *mut X509_CRL) -> c_int;
 
     pub fn PEM_write_bio_ECPrivateKey(bio: *mut BIO,
                                       key: *mut EC_KEY,
@@ -1739,7 +2363,13 @@ extern {
                              out: *mut u8) -> c_int;
 
     pub fn RAND_bytes(buf: *mut u8, num: c_int) -> c_int;
+    pub fn RAND_seed(buf: *const c_void, num: c_int);
+    pub fn RAND_add(buf: *const c_void, num: c_int, randomness: c_double);
     pub fn RAND_status() -> c_int;
+    #[cfg(not(libressl))]
+    pub fn RAND_set_rand_method(meth: *const RAND_METHOD) -> c_int;
+    #[cfg(ossl111)]
+    pub fn RAND_keep_random_devices_open(keep: c_int);
 
     pub fn RSA_new() -> *mut RSA;
     pub fn RSA_free(rsa: *mut RSA);
@@ -1769,6 +2399,10 @@ extern {
                     siglen: *mut c_uint, dsa: *mut DSA) -> c_int;
     pub fn DSA_verify(dummy: c_int, dgst: *const c_uchar, len: c_int, sigbuf: *const c_uchar,
                       siglen: c_int, dsa: *mut DSA) -> c_int;
+    pub fn DSA_do_sign(dgst: *const c_uchar, dlen: c_int, dsa: *mut DSA) -> *mut DSA_SIG;
+    pub fn DSA_do_verify(dgst: *const c_uchar, dgst_len: c_int, sig: *mut DSA_SIG, dsa: *mut DSA) -> c_int;
+    pub fn DSA_SIG_new() -> *mut DSA_SIG;
+    pub fn DSA_SIG_free(sig: *mut DSA_SIG);
 
     pub fn SSL_new(ctx: *mut SSL_CTX) -> *mut SSL;
     pub fn SSL_pending(ssl: *const SSL) -> c_int;
@@ -1779,10 +2413,13 @@ extern {
     pub fn SSL_accept(ssl: *mut SSL) -> c_int;
     pub fn SSL_connect(ssl: *mut SSL) -> c_int;
     pub fn SSL_do_handshake(ssl: *mut SSL) -> c_int;
+    pub fn SSL_set_connect_state(ssl: *mut SSL);
+    pub fn SSL_set_accept_state(ssl: *mut SSL);
     pub fn SSL_ctrl(ssl: *mut SSL, cmd: c_int, larg: c_long,
                     parg: *mut c_void) -> c_long;
     pub fn SSL_get_error(ssl: *const SSL, ret: c_int) -> c_int;
     pub fn SSL_read(ssl: *mut SSL, buf: *mut c_void, num: c_int) -> c_int;
+    pub fn SSL_peek(ssl: *mut SSL, buf: *mut c_void, num: c_int) -> c_int;
     pub fn SSL_write(ssl: *mut SSL, buf: *const c_void, num: c_int) -> c_int;
     pub fn SSL_get_ex_data_X509_STORE_CTX_idx() -> c_int;
     pub fn SSL_get_SSL_CTX(ssl: *const SSL) -> *mut SSL_CTX;
@@ -1792,10 +2429,21 @@ extern {
     #[cfg(libressl)]
     pub fn SSL_get_current_compression(ssl: *mut SSL) -> *const libc::c_void;
     pub fn SSL_get_peer_certificate(ssl: *const SSL) -> *mut X509;
+    pub fn SSL_get_peer_cert_chain(ssl: *const SSL) -> *mut stack_st_X509;
     pub fn SSL_get_ssl_method(ssl: *mut SSL) -> *const SSL_METHOD;
     pub fn SSL_get_version(ssl: *const SSL) -> *const c_char;
+    pub fn SSL_version(ssl: *const SSL) -> c_int;
+    pub fn SSL_renegotiate(ssl: *mut SSL) -> c_int;
     pub fn SSL_state_string(ssl: *const SSL) -> *const c_char;
     pub fn SSL_state_string_long(ssl: *const SSL) -> *const c_char;
+    pub fn SSL_alert_type_string_long(value: c_int) -> *const c_char;
+    pub fn SSL_alert_desc_string_long(value: c_int) -> *const c_char;
+    #[cfg(ossl111)]
+    pub fn SSL_set_post_handshake_auth(ssl: *mut SSL, val: c_int);
+    #[cfg(ossl111)]
+    pub fn SSL_verify_client_post_handshake(ssl: *mut SSL) -> c_int;
+    #[cfg(ossl111)]
+    pub fn SSL_set_tlsext_max_fragment_length(ssl: *mut SSL, mode: u8) -> c_int;
     pub fn SSL_set_verify(ssl: *mut SSL,
                           mode: c_int,
                           verify_callback: Option<extern fn(c_int, *mut X509_STORE_CTX) -> c_int>);
@@ -1803,6 +2451,23 @@ extern {
     pub fn SSL_get_ex_data(ssl: *const SSL, idx: c_int) -> *mut c_void;
     pub fn SSL_get_servername(ssl: *const SSL, name_type: c_int) -> *const c_char;
     pub fn SSL_get_current_cipher(ssl: *const SSL) -> *const SSL_CIPHER;
+    pub fn SSL_get_ciphers(ssl: *const SSL) -> *mut stack_st_SSL_CIPHER;
+    #[cfg(not(any(ossl101, libressl)))]
+    pub fn SSL_get_shared_sigalgs(ssl: *mut SSL, idx: c_int, psign: *mut c_int, phash: *mut c_int,
+                                  psignhash: *mut c_int, rsig: *mut c_uchar, rhash: *mut c_uchar) -> c_int;
+    #[cfg(ossl111)]
+    pub fn SSL_get_peer_signature_type_nid(ssl: *const SSL, pnid: *mut c_int) -> c_int;
+    pub fn SSL_set_tlsext_use_srtp(ssl: *mut SSL, profiles: *const c_char) -> c_int;
+    pub fn SSL_get_selected_srtp_profile(s: *mut SSL) -> *mut SRTP_PROTECTION_PROFILE;
+    pub fn SSL_get_client_CA_list(ssl: *const SSL) -> *mut stack_st_X509_NAME;
+    pub fn SSL_export_keying_material(s: *mut SSL,
+                                      out: *mut c_uchar,
+                                      olen: size_t,
+                                      label: *const c_char,
+                                      llen: size_t,
+                                      context: *const c_uchar,
+                                      contextlen: size_t,
+                                      use_context: c_int) -> c_int;
     #[cfg(not(any(ossl101, libressl)))]
     pub fn SSL_get0_param(ssl: *mut SSL) -> *mut X509_VERIFY_PARAM;
     pub fn SSL_get_verify_result(ssl: *const SSL) -> c_long;
@@ -1835,6 +2500,10 @@ extern {
     pub fn SSL_CTX_set_verify(ctx: *mut SSL_CTX, mode: c_int,
                               verify_callback: Option<extern fn(c_int, *mut X509_STORE_CTX) -> c_int>);
     pub fn SSL_CTX_set_verify_depth(ctx: *mut SSL_CTX, depth: c_int);
+    #[cfg(ossl111)]
+    pub fn SSL_CTX_set_post_handshake_auth(ctx: *mut SSL_CTX, val: c_int);
+    #[cfg(ossl111)]
+    pub fn SSL_CTX_set_tlsext_max_fragment_length(ctx: *mut SSL_CTX, mode: u8) -> c_int;
     pub fn SSL_CTX_load_verify_locations(ctx: *mut SSL_CTX, CAfile: *const c_char,
                                          CApath: *const c_char) -> c_int;
     pub fn SSL_CTX_set_default_verify_paths(ctx: *mut SSL_CTX) -> c_int;
@@ -1846,17 +2515,39 @@ extern {
     pub fn SSL_CTX_use_certificate_file(ctx: *mut SSL_CTX, cert_file: *const c_char, file_type: c_int) -> c_int;
     pub fn SSL_CTX_use_certificate_chain_file(ctx: *mut SSL_CTX, cert_chain_file: *const c_char) -> c_int;
     pub fn SSL_CTX_use_certificate(ctx: *mut SSL_CTX, cert: *mut X509) -> c_int;
+    #[cfg(not(any(ossl101, libressl)))]
+    pub fn SSL_CTX_use_serverinfo(ctx: *mut SSL_CTX, serverinfo: *const c_uchar, serverinfo_length: size_t) -> c_int;
+    #[cfg(ossl111)]
+    pub fn SSL_CTX_use_serverinfo_ex(ctx: *mut SSL_CTX, version: c_uint, serverinfo: *const c_uchar, serverinfo_length: size_t) -> c_int;
+    #[cfg(not(any(ossl101, libressl)))]
+    pub fn SSL_CTX_use_serverinfo_file(ctx: *mut SSL_CTX, file: *const c_char) -> c_int;
 
     pub fn SSL_CTX_use_PrivateKey_file(ctx: *mut SSL_CTX, key_file: *const c_char, file_type: c_int) -> c_int;
     pub fn SSL_CTX_use_PrivateKey(ctx: *mut SSL_CTX, key: *mut EVP_PKEY) -> c_int;
     pub fn SSL_CTX_check_private_key(ctx: *const SSL_CTX) -> c_int;
     pub fn SSL_CTX_set_client_CA_list(ctx: *mut SSL_CTX, list: *mut stack_st_X509_NAME);
+    pub fn SSL_CTX_set_tlsext_use_srtp(ctx: *mut SSL_CTX, profiles: *const c_char) -> c_int;
+    pub fn SSL_CTX_set_info_callback(ctx: *mut SSL_CTX,
+                                     cb: Option<unsafe extern fn(*const SSL, c_int, c_int)>);
+    pub fn SSL_CTX_get_client_CA_list(ctx: *const SSL_CTX) -> *mut stack_st_X509_NAME;
+    pub fn SSL_CTX_add_client_CA(ctx: *mut SSL_CTX, cacert: *mut X509) -> c_int;
     pub fn SSL_CTX_get_cert_store(ctx: *const SSL_CTX) -> *mut X509_STORE;
     pub fn SSL_CTX_set_tmp_dh_callback(ctx: *mut SSL_CTX,
                                        dh: unsafe extern fn(ssl: *mut SSL,
                                                             is_export: c_int,
                                                             keylength: c_int)
                                                             -> *mut DH);
+    #[cfg(ossl111)]
+    pub fn SSL_CTX_set_keylog_callback(ctx: *mut SSL_CTX,
+                                       cb: Option<unsafe extern fn(ssl: *const SSL,
+                                                                   line: *const c_char)>);
+    #[cfg(ossl111)]
+    pub fn SSL_CTX_set_client_hello_cb(ctx: *mut SSL_CTX,
+                                       cb: Option<unsafe extern fn(ssl: *mut SSL,
+                                                                   al: *mut c_int,
+                                                                   arg: *mut c_void)
+                                                                   -> c_int>,
+                                       arg: *mut c_void);
 
     #[cfg(not(any(ossl101, libressl)))]
     pub fn SSL_CTX_get0_certificate(ctx: *const SSL_CTX) -> *mut X509;
@@ -1865,6 +2556,28 @@ extern {
 
     pub fn SSL_CTX_set_cipher_list(ssl: *mut SSL_CTX, s: *const c_char) -> c_int;
 
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn SSL_CTX_set_psk_client_callback(ssl: *mut SSL_CTX,
+                                           psk_client_cb: Option<extern fn(*mut SSL,
+                                                                           *const c_char,
+                                                                           *mut c_char,
+                                                                           c_uint,
+                                                                           *mut c_uchar,
+                                                                           c_uint) -> c_uint>);
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn SSL_CTX_set_psk_server_callback(ssl: *mut SSL_CTX,
+                                           psk_server_cb: Option<extern fn(*mut SSL,
+                                                                           *const c_char,
+                                                                           *mut c_uchar,
+                                                                           c_uint) -> c_uint>);
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn SSL_CTX_use_psk_identity_hint(ctx: *mut SSL_CTX, hint: *const c_char) -> c_int;
+
+    pub fn SSL_CTX_set_cookie_generate_cb(s: *mut SSL_CTX,
+                                          cb: Option<extern fn(ssl: *mut SSL,
+                                                               cookie: *mut c_uchar,
+                                                               cookie_len: *mut c_uint) -> c_int>);
+
     pub fn SSL_CTX_set_next_protos_advertised_cb(ssl: *mut SSL_CTX,
                                                  cb: extern "C" fn(ssl: *mut SSL,
                                                                    out: *mut *const c_uchar,
@@ -1889,6 +2602,8 @@ extern {
 
     pub fn SSL_SESSION_free(s: *mut SSL_SESSION);
     pub fn SSL_SESSION_get_id(s: *const SSL_SESSION, len: *mut c_uint) -> *const c_uchar;
+    pub fn i2d_SSL_SESSION(s: *mut SSL_SESSION, pp: *mut *mut c_uchar) -> c_int;
+    pub fn d2i_SSL_SESSION(a: *mut *mut SSL_SESSION, pp: *mut *const c_uchar, len: c_long) -> *mut SSL_SESSION;
 
     #[cfg(not(ossl101))]
     pub fn SSL_CTX_set_alpn_protos(s: *mut SSL_CTX, data: *const c_uchar, len: c_uint) -> c_int;
@@ -1920,30 +2635,82 @@ extern {
     pub fn X509_set_pubkey(x: *mut X509, pkey: *mut EVP_PKEY) -> c_int;
     pub fn X509_sign(x: *mut X509, pkey: *mut EVP_PKEY, md: *const EVP_MD) -> c_int;
     pub fn X509_get_pubkey(x: *mut X509) -> *mut EVP_PKEY;
+
+    pub fn X509_PUBKEY_free(key: *mut X509_PUBKEY);
+    pub fn X509_PUBKEY_set(x: *mut *mut X509_PUBKEY, pkey: *mut EVP_PKEY) -> c_int;
+    pub fn X509_PUBKEY_get(key: *mut X509_PUBKEY) -> *mut EVP_PKEY;
+    pub fn X509_PUBKEY_get0_param(ppkalg: *mut *mut ASN1_OBJECT,
+                                  pk: *mut *const c_uchar,
+                                  ppklen: *mut c_int,
+                                  pa: *mut *mut X509_ALGOR,
+                                  key: *mut X509_PUBKEY)
+                                  -> c_int;
+    pub fn X509_verify(x: *mut X509, pkey: *mut EVP_PKEY) -> c_int;
     pub fn X509_to_X509_REQ(x: *mut X509, pkey: *mut EVP_PKEY, md: *const EVP_MD) -> *mut X509_REQ;
     pub fn X509_verify_cert_error_string(n: c_long) -> *const c_char;
+    pub fn X509_verify_cert(ctx: *mut X509_STORE_CTX) -> c_int;
     pub fn X509_get1_ocsp(x: *mut X509) -> *mut stack_st_OPENSSL_STRING;
     pub fn X509_check_issued(issuer: *mut X509, subject: *mut X509) -> c_int;
 
+    pub fn X509_CRL_new() -> *mut X509_CRL;
+    pub fn X509_CRL_free(x: *mut X509_CRL);
+    pub fn X509_CRL_set_version(x: *mut X509_CRL, version: c_long) -> c_int;
+    pub fn X509_CRL_set_issuer_name(x: *mut X509_CRL, name: *mut X509_NAME) -> c_int;
+    pub fn X509_CRL_add0_revoked(crl: *mut X509_CRL, rev: *mut X509_REVOKED) -> c_int;
+    pub fn X509_CRL_sort(crl: *mut X509_CRL) -> c_int;
+    pub fn X509_CRL_sign(x: *mut X509_CRL, pkey: *mut EVP_PKEY, md: *const EVP_MD) -> c_int;
+
+    pub fn X509_REVOKED_new() -> *mut X509_REVOKED;
+    pub fn X509_REVOKED_free(x: *mut X509_REVOKED);
+    pub fn X509_REVOKED_set_serialNumber(x: *mut X509_REVOKED, serial: *mut ASN1_INTEGER) -> c_int;
+    pub fn X509_REVOKED_set_revocationDate(r: *mut X509_REVOKED, tm: *mut ASN1_TIME) -> c_int;
+    pub fn X509_REVOKED_add1_ext_i2d(x: *mut X509_REVOKED, nid: c_int, value: *mut c_void, crit: c_int, flags: c_ulong) -> c_int;
+
+    pub fn ASN1_ENUMERATED_new() -> *mut ASN1_ENUMERATED;
+    pub fn ASN1_ENUMERATED_free(a: *mut ASN1_ENUMERATED);
+    pub fn ASN1_ENUMERATED_set(a: *mut ASN1_ENUMERATED, v: c_long) -> c_int;
+
+    pub fn GENERAL_NAME_new() -> *mut GENERAL_NAME;
+    pub fn GENERAL_NAME_set0_value(a: *mut GENERAL_NAME, type_: c_int, value: *mut c_void);
+    pub fn X509_NAME_dup(xn: *mut X509_NAME) -> *mut X509_NAME;
+
     pub fn X509_EXTENSION_free(ext: *mut X509_EXTENSION);
+    pub fn X509_EXTENSION_create_by_OBJ(ex: *mut *mut X509_EXTENSION, obj: *mut ASN1_OBJECT, crit: c_int, data: *mut ASN1_STRING) -> *mut X509_EXTENSION;
+    pub fn X509_EXTENSION_get_object(ext: *mut X509_EXTENSION) -> *mut ASN1_OBJECT;
+    pub fn X509_EXTENSION_get_data(ext: *mut X509_EXTENSION) -> *mut ASN1_STRING;
+    pub fn X509_EXTENSION_get_critical(ext: *mut X509_EXTENSION) -> c_int;
 
     pub fn X509_NAME_free(x: *mut X509_NAME);
     pub fn X509_NAME_add_entry_by_txt(x: *mut X509_NAME, field: *const c_char, ty: c_int, bytes: *const c_uchar, len: c_int, loc: c_int, set: c_int) -> c_int;
+    pub fn X509_NAME_print_ex(out: *mut BIO, nm: *mut X509_NAME, indent: c_int, flags: c_ulong) -> c_int;
     pub fn X509_NAME_get_index_by_NID(n: *mut X509_NAME, nid: c_int, last_pos: c_int) -> c_int;
 
     pub fn X509_NAME_ENTRY_free(x: *mut X509_NAME_ENTRY);
 
     pub fn ASN1_STRING_free(x: *mut ASN1_STRING);
     pub fn ASN1_STRING_length(x: *const ASN1_STRING) -> c_int;
+    pub fn ASN1_STRING_type(x: *const ASN1_STRING) -> c_int;
+    pub fn ASN1_STRING_set(x: *mut ASN1_STRING, data: *const c_void, len: c_int) -> c_int;
 
     pub fn X509_STORE_new() -> *mut X509_STORE;
     pub fn X509_STORE_free(store: *mut X509_STORE);
     pub fn X509_STORE_add_cert(store: *mut X509_STORE, x: *mut X509) -> c_int;
     pub fn X509_STORE_set_default_paths(store: *mut X509_STORE) -> c_int;
+    pub fn X509_STORE_add_crl(store: *mut X509_STORE, x: *mut X509_CRL) -> c_int;
+    pub fn X509_STORE_set_flags(store: *mut X509_STORE, flags: c_ulong) -> c_int;
 
+    pub fn X509_STORE_CTX_new() -> *mut X509_STORE_CTX;
     pub fn X509_STORE_CTX_free(ctx: *mut X509_STORE_CTX);
+    pub fn X509_STORE_CTX_init(ctx: *mut X509_STORE_CTX,
+                               store: *mut X509_STORE,
+                               x509: *mut X509,
+                               chain: *mut stack_st_X509)
+                               -> c_int;
+    #[cfg(not(ossl101))]
+    pub fn X509_STORE_CTX_get0_param(ctx: *mut X509_STORE_CTX) -> *mut X509_VERIFY_PARAM;
     pub fn X509_STORE_CTX_get_current_cert(ctx: *mut X509_STORE_CTX) -> *mut X509;
     pub fn X509_STORE_CTX_get_error(ctx: *mut X509_STORE_CTX) -> c_int;
+    pub fn X509_STORE_CTX_set_error(ctx: *mut X509_STORE_CTX, error: c_int);
     pub fn X509_STORE_CTX_get_ex_data(ctx: *mut X509_STORE_CTX, idx: c_int) -> *mut c_void;
     pub fn X509_STORE_CTX_get_error_depth(ctx: *mut X509_STORE_CTX) -> c_int;
 
@@ -1951,6 +2718,8 @@ extern {
 
     pub fn X509_REQ_add_extensions(req: *mut X509_REQ, exts: *mut stack_st_X509_EXTENSION) -> c_int;
     pub fn X509_REQ_sign(x: *mut X509_REQ, pkey: *mut EVP_PKEY, md: *const EVP_MD) -> c_int;
+    pub fn X509_REQ_get_pubkey(req: *mut X509_REQ) -> *mut EVP_PKEY;
+    pub fn X509_REQ_verify(req: *mut X509_REQ, pkey: *mut EVP_PKEY) -> c_int;
 
     #[cfg(not(ossl101))]
     pub fn X509_VERIFY_PARAM_free(param: *mut X509_VERIFY_PARAM);
@@ -1960,6 +2729,8 @@ extern {
     pub fn X509_VERIFY_PARAM_set1_host(param: *mut X509_VERIFY_PARAM,
                                        name: *const c_char,
                                        namelen: size_t) -> c_int;
+    #[cfg(not(ossl101))]
+    pub fn X509_VERIFY_PARAM_set1(to: *mut X509_VERIFY_PARAM, from: *const X509_VERIFY_PARAM) -> c_int;
 
     pub fn d2i_DHparams(k: *mut *mut DH, pp: *mut *const c_uchar, length: c_long) -> *mut DH;
     pub fn i2d_DHparams(dh: *const DH, pp: *mut *mut c_uchar) -> c_int;
@@ -1968,30 +2739,65 @@ extern {
     pub fn i2d_DSAPublicKey(a: *const DSA, pp: *mut *mut c_uchar) -> c_int;
     pub fn d2i_DSAPrivateKey(a: *mut *mut DSA, pp: *mut *const c_uchar, length: c_long) -> *mut DSA;
     pub fn i2d_DSAPrivateKey(a: *const DSA, pp: *mut *mut c_uchar) -> c_int;
+    pub fn d2i_DSAparams(a: *mut *mut DSA, pp: *mut *const c_uchar, length: c_long) -> *mut DSA;
+    pub fn i2d_DSAparams(a: *const DSA, pp: *mut *mut c_uchar) -> c_int;
+    pub fn d2i_DSA_SIG(sig: *mut *mut DSA_SIG, pp: *mut *const c_uchar, length: c_long) -> *mut DSA_SIG;
+    pub fn i2d_DSA_SIG(sig: *const DSA_SIG, pp: *mut *mut c_uchar) -> c_int;
 
     pub fn d2i_ECPrivateKey(k: *mut *mut EC_KEY, pp: *mut *const c_uchar, length: c_long) -> *mut EC_KEY;
     pub fn i2d_ECPrivateKey(ec_key: *mut EC_KEY, pp: *mut *mut c_uchar) -> c_int;
+    pub fn d2i_ECDSA_SIG(sig: *mut *mut ECDSA_SIG, pp: *mut *const c_uchar, length: c_long) -> *mut ECDSA_SIG;
+    pub fn i2d_ECDSA_SIG(sig: *const ECDSA_SIG, pp: *mut *mut c_uchar) -> c_int;
 
     pub fn d2i_X509(a: *mut *mut X509, pp: *mut *const c_uchar, length: c_long) -> *mut X509;
     pub fn d2i_X509_REQ(a: *mut *mut X509_REQ, pp: *mut *const c_uchar, length: c_long) -> *mut X509_REQ;
     pub fn i2d_X509_bio(b: *mut BIO, x: *mut X509) -> c_int;
     pub fn i2d_X509(x: *mut X509, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_X509_EXTENSION(a: *mut *mut X509_EXTENSION,
+                              pp: *mut *const c_uchar,
+                              length: c_long)
+                              -> *mut X509_EXTENSION;
+    pub fn i2d_X509_EXTENSION(ext: *mut X509_EXTENSION, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_GENERAL_NAMES(a: *mut *mut stack_st_GENERAL_NAME,
+                             pp: *mut *const c_uchar,
+                             length: c_long)
+                             -> *mut stack_st_GENERAL_NAME;
     pub fn i2d_X509_REQ_bio(b: *mut BIO, x: *mut X509_REQ) -> c_int;
     pub fn i2d_X509_REQ(x: *mut X509_REQ, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_X509_CRL(a: *mut *mut X509_CRL, pp: *mut *const c_uchar, length: c_long) -> *mut X509_CRL;
+    pub fn i2d_X509_CRL(x: *mut X509_CRL, buf: *mut *mut u8) -> c_int;
+
+    pub fn i2d_ASN1_OBJECT(a: *mut ASN1_OBJECT, pp: *mut *mut c_uchar) -> c_int;
 
     pub fn i2d_PUBKEY_bio(b: *mut BIO, x: *mut EVP_PKEY) -> c_int;
     pub fn i2d_PrivateKey_bio(b: *mut BIO, x: *mut EVP_PKEY) -> c_int;
     pub fn i2d_PUBKEY(k: *mut EVP_PKEY, buf: *mut *mut u8) -> c_int;
     pub fn i2d_PrivateKey(k: *mut EVP_PKEY, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_PUBKEY(k: *mut *mut EVP_PKEY, buf: *mut *const u8, len: c_long) -> *mut EVP_PKEY;
+    pub fn d2i_AutoPrivateKey(k: *mut *mut EVP_PKEY, buf: *mut *const u8, len: c_long) -> *mut EVP_PKEY;
 
     pub fn i2d_RSA_PUBKEY(k: *mut RSA, buf: *mut *mut u8) -> c_int;
     pub fn d2i_RSA_PUBKEY(k: *mut *mut RSA, buf: *mut *const u8, len: c_long) -> *mut RSA;
+    pub fn i2d_RSAPublicKey(k: *const RSA, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_RSAPublicKey(k: *mut *mut RSA, buf: *mut *const u8, len: c_long) -> *mut RSA;
     pub fn i2d_RSAPrivateKey(k: *const RSA, buf: *mut *mut u8) -> c_int;
     pub fn d2i_RSAPrivateKey(k: *mut *mut RSA, buf: *mut *const u8, len: c_long) -> *mut RSA;
 
     pub fn i2d_PKCS12_bio(b: *mut BIO, a: *mut PKCS12) -> c_int;
     pub fn i2d_PKCS12(a: *mut PKCS12, buf: *mut *mut u8) -> c_int;
     pub fn d2i_PKCS12(a: *mut *mut PKCS12, pp: *mut *const u8, length: c_long) -> *mut PKCS12;
+
+    pub fn X509_SIG_new() -> *mut X509_SIG;
+    pub fn X509_SIG_free(sig: *mut X509_SIG);
+    pub fn i2d_X509_SIG(sig: *mut X509_SIG, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_X509_SIG(sig: *mut *mut X509_SIG, buf: *mut *const u8, len: c_long) -> *mut X509_SIG;
+    pub fn X509_ALGOR_free(alg: *mut X509_ALGOR);
+    pub fn i2d_X509_ALGOR(alg: *mut X509_ALGOR, buf: *mut *mut u8) -> c_int;
+    pub fn i2d_X509_PUBKEY(key: *mut X509_PUBKEY, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_X509_PUBKEY(key: *mut *mut X509_PUBKEY, buf: *mut *const u8, len: c_long) -> *mut X509_PUBKEY;
+    pub fn d2i_X509_ALGOR(alg: *mut *mut X509_ALGOR, buf: *mut *const u8, len: c_long) -> *mut X509_ALGOR;
+    pub fn i2d_ASN1_TYPE(a: *mut ASN1_TYPE, buf: *mut *mut u8) -> c_int;
+    pub fn d2i_ASN1_TYPE(a: *mut *mut ASN1_TYPE, buf: *mut *const u8, len: c_long) -> *mut ASN1_TYPE;
     pub fn PKCS12_parse(p12: *mut PKCS12,
                         pass: *const c_char,
                         pkey: *mut *mut EVP_PKEY,
@@ -1999,9 +2805,77 @@ extern {
                         ca: *mut *mut stack_st_X509)
                         -> c_int;
     pub fn PKCS12_free(p12: *mut PKCS12);
+    pub fn PKCS12_verify_mac(p12: *mut PKCS12, pass: *const c_char, passlen: c_int) -> c_int;
+    pub fn PKCS12_set_mac(p12: *mut PKCS12,
+                          pass: *const c_char,
+                          passlen: c_int,
+                          salt: *mut c_uchar,
+                          saltlen: c_int,
+                          iter: c_int,
+                          md_type: *const EVP_MD)
+                          -> c_int;
+    pub fn PKCS12_unpack_authsafes(p12: *const PKCS12) -> *mut stack_st_PKCS7;
+    pub fn PKCS12_unpack_p7data(p7: *mut PKCS7) -> *mut stack_st_PKCS12_SAFEBAG;
+    pub fn PKCS12_unpack_p7encdata(p7: *mut PKCS7,
+                                   pass: *const c_char,
+                                   passlen: c_int)
+                                   -> *mut stack_st_PKCS12_SAFEBAG;
+    pub fn PKCS12_add_cert(pbags: *mut *mut stack_st_PKCS12_SAFEBAG,
+                           cert: *mut X509)
+                           -> *mut PKCS12_SAFEBAG;
+    pub fn PKCS12_add_key(pbags: *mut *mut stack_st_PKCS12_SAFEBAG,
+                          key: *mut EVP_PKEY,
+                          key_usage: c_int,
+                          iter: c_int,
+                          key_nid: c_int,
+                          pass: *const c_char)
+                          -> *mut PKCS12_SAFEBAG;
+    pub fn PKCS12_add_safe(psafes: *mut *mut stack_st_PKCS7,
+                           bags: *mut stack_st_PKCS12_SAFEBAG,
+                           safe_nid: c_int,
+                           iter: c_int,
+                           pass: *const c_char)
+                           -> c_int;
+    pub fn PKCS12_add_safes(safes: *mut stack_st_PKCS7, p7_nid: c_int) -> *mut PKCS12;
+    pub fn PKCS12_add_friendlyname_asc(bag: *mut PKCS12_SAFEBAG,
+                                       name: *const c_char,
+                                       namelen: c_int)
+                                       -> c_int;
+    pub fn PKCS12_add_localkeyid(bag: *mut PKCS12_SAFEBAG,
+                                 name: *mut c_uchar,
+                                 namelen: c_int)
+                                 -> c_int;
+    pub fn PKCS12_get_friendlyname(bag: *mut PKCS12_SAFEBAG) -> *mut c_char;
+    pub fn PKCS12_decrypt_skey(bag: *const PKCS12_SAFEBAG,
+                               pass: *const c_char,
+                               passlen: c_int)
+                               -> *mut PKCS8_PRIV_KEY_INFO;
+    pub fn PKCS12_SAFEBAG_free(bag: *mut PKCS12_SAFEBAG);
+    pub fn PKCS7_free(p7: *mut PKCS7);
+    pub fn PKCS8_PRIV_KEY_INFO_free(p8: *mut PKCS8_PRIV_KEY_INFO);
+    pub fn EVP_PKCS82PKEY(p8: *const PKCS8_PRIV_KEY_INFO) -> *mut EVP_PKEY;
+    pub fn ASN1_TYPE_get_octetstring(a: *const ASN1_TYPE, data: *mut c_uchar, max_len: c_int) -> c_int;
+    pub fn ASN1_TYPE_get_int_octetstring(a: *const ASN1_TYPE,
+                                         num: *mut c_long,
+                                         data: *mut c_uchar,
+                                         max_len: c_int)
+                                         -> c_int;
+    pub fn ASN1_TYPE_get(a: *const ASN1_TYPE) -> c_int;
+    pub fn ASN1_TYPE_new() -> *mut ASN1_TYPE;
+    pub fn ASN1_TYPE_free(a: *mut ASN1_TYPE);
+    pub fn ASN1_TYPE_set(a: *mut ASN1_TYPE, type_: c_int, value: *mut c_void);
 
     pub fn GENERAL_NAME_free(name: *mut GENERAL_NAME);
-
+    pub fn DIST_POINT_free(dp: *mut DIST_POINT);
+
+    pub fn HMAC(evp_md: *const EVP_MD,
+                key: *const c_void,
+                key_len: c_int,
+                d: *const c_uchar,
+                n: size_t,
+                md: *mut c_uchar,
+                md_len: *mut c_uint)
+                -> *mut c_uchar;
     pub fn HMAC_Init_ex(ctx: *mut HMAC_CTX,
                         key: *const c_void,
                         len: c_int,
@@ -2,7 +2,7 @@ use std::sync::{Mutex, MutexGuard};
 use std::sync::{Once, ONCE_INIT};
 use std::mem;
 
-use libc::{c_int, c_char, c_void, c_long, c_uchar, size_t, c_uint, c_ulong};
+use libc::{c_int, c_char, c_void, c_long, c_uchar, size_t, c_uint, c_ulong, c_double};
 #[cfg(not(ossl101))]
 use libc::time_t;
 
@@ -36,6 +36,28 @@ pub struct stack_st_GENERAL_NAME {
     pub stack: _STACK,
 }
 
+#[repr(C)]
+pub struct stack_st_DIST_POINT {
+    pub stack: _STACK,
+}
+
+#[repr(C)]
+pub struct stack_st_PKCS7 {
+    pub stack: _STACK,
+}
+
+#[repr(C)]
+pub struct stack_st_PKCS12_SAFEBAG {
+    pub stack: _STACK,
+}
+
+#[repr(C)]
+pub struct PKCS12_SAFEBAG {
+    pub type_: *mut ::ASN1_OBJECT,
+    pub value: *mut c_void,
+    pub attrib: *mut stack_st_X509_ATTRIBUTE,
+}
+
 #[repr(C)]
 pub struct stack_st_void {
     pub stack: _STACK,
@@ -125,6 +147,18 @@ pub struct DSA {
     pub engine: *mut ::ENGINE,
 }
 
+#[repr(C)]
+pub struct DSA_SIG {
+    pub r: *mut ::BIGNUM,
+    pub s: *mut ::BIGNUM,
+}
+
+#[repr(C)]
+pub struct ECDSA_SIG {
+    pub r: *mut ::BIGNUM,
+    pub s: *mut ::BIGNUM,
+}
+
 #[repr(C)]
 pub struct EVP_PKEY {
     pub type_: c_int,
@@ -206,6 +240,15 @@ pub struct EVP_CIPHER {
     pub app_data: *mut c_void,
 }
 
+#[repr(C)]
+pub struct EVP_ENCODE_CTX {
+    pub num: c_int,
+    pub length: c_int,
+    pub enc_data: [c_uchar; 80],
+    pub line_num: c_int,
+    pub expect_nl: c_int,
+}
+
 #[repr(C)]
 pub struct HMAC_CTX {
     md: *mut ::EVP_MD,
@@ -247,10 +290,43 @@ pub struct DH {
     pub engine: *mut ::ENGINE,
 }
 
+#[repr(C)]
+pub struct stack_st_X509_REVOKED {
+    pub stack: _STACK,
+}
+
+#[repr(C)]
+pub struct X509_CRL_INFO {
+    version: *mut c_void,
+    sig_alg: *mut c_void,
+    issuer: *mut c_void,
+    lastUpdate: *mut c_void,
+    nextUpdate: *mut c_void,
+    pub revoked: *mut stack_st_X509_REVOKED,
+    extensions: *mut c_void,
+    enc: ASN1_ENCODING,
+}
+
+#[repr(C)]
+pub struct X509_SIG {
+    pub algor: *mut ::X509_ALGOR,
+    pub digest: *mut ::ASN1_STRING,
+}
+
+#[repr(C)]
+pub struct X509_REVOKED {
+    pub serialNumber: *mut ::ASN1_INTEGER,
+    pub revocationDate: *mut ::ASN1_TIME,
+    pub extensions: *mut stack_st_X509_EXTENSION,
+    issuer: *mut stack_st_GENERAL_NAME,
+    reason: c_int,
+    sequence: c_int,
+}
+
 #[repr(C)]
 pub struct X509 {
     pub cert_info: *mut X509_CINF,
-    sig_alg: *mut c_void,
+    pub sig_alg: *mut ::X509_ALGOR,
     signature: *mut c_void,
     pub valid: c_int,
     pub references: c_int,
@@ -285,7 +361,7 @@ pub struct X509_CINF {
     issuer: *mut c_void,
     pub validity: *mut X509_VAL,
     subject: *mut c_void,
-    key: *mut c_void,
+    pub key: *mut ::X509_PUBKEY,
     issuerUID: *mut c_void,
     subjectUID: *mut c_void,
     pub extensions: *mut stack_st_X509_EXTENSION,
@@ -299,6 +375,23 @@ pub struct ASN1_ENCODING {
     pub modified: c_int,
 }
 
+#[repr(C)]
+pub struct X509_REQ_INFO {
+    enc: ASN1_ENCODING,
+    version: *mut c_void,
+    subject: *mut c_void,
+    pub pubkey: *mut ::X509_PUBKEY,
+    attributes: *mut c_void,
+}
+
+#[repr(C)]
+pub struct X509_REQ {
+    pub req_info: *mut X509_REQ_INFO,
+    sig_alg: *mut c_void,
+    signature: *mut c_void,
+    references: c_int,
+}
+
 #[repr(C)]
 pub struct X509_VAL {
     pub notBefore: *mut ::ASN1_TIME,
@@ -445,7 +538,7 @@ pub struct SSL {
 #[repr(C)]
 pub struct SSL_CTX {
     method: *mut c_void,
-    cipher_list: *mut c_void,
+    pub cipher_list: *mut stack_st_SSL_CIPHER,
     cipher_list_by_id: *mut c_void,
     cert_store: *mut c_void,
     sessions: *mut c_void,
@@ -653,6 +746,16 @@ pub struct X509_VERIFY_PARAM {
 
 #[cfg(not(ossl101))]
 pub enum X509_VERIFY_PARAM_ID {}
+
+#[repr(C)]
+pub struct RAND_METHOD {
+    pub seed: Option<unsafe extern "C" fn(buf: *const c_void, num: c_int)>,
+    pub bytes: Option<unsafe extern "C" fn(buf: *mut c_uchar, num: c_int) -> c_int>,
+    pub cleanup: Option<unsafe extern "C" fn()>,
+    pub add: Option<unsafe extern "C" fn(buf: *const c_void, num: c_int, randomness: c_double)>,
+    pub pseudorand: Option<unsafe extern "C" fn(buf: *mut c_uchar, num: c_int) -> c_int>,
+    pub status: Option<unsafe extern "C" fn() -> c_int>,
+}
 pub enum PKCS12 {}
 
 pub const SSL_CTRL_OPTIONS: c_int = 32;
@@ -756,6 +859,12 @@ extern {
     pub fn BIO_s_file() -> *mut BIO_METHOD;
     pub fn BIO_s_mem() -> *mut BIO_METHOD;
 
+    pub fn EVP_EncodeUpdate(ctx: *mut EVP_ENCODE_CTX,
+                            out: *mut c_uchar,
+                            outl: *mut c_int,
+                            in_: *const c_uchar,
+                            inl: c_int);
+
     pub fn get_rfc2409_prime_768(bn: *mut BIGNUM) -> *mut BIGNUM;
     pub fn get_rfc2409_prime_1024(bn: *mut BIGNUM) -> *mut BIGNUM;
     pub fn get_rfc3526_prime_1536(bn: *mut BIGNUM) -> *mut BIGNUM;
@@ -767,6 +876,22 @@ extern {
 
     pub fn CRYPTO_malloc(num: c_int, file: *const c_char, line: c_int) -> *mut c_void;
     pub fn CRYPTO_free(buf: *mut c_void);
+    pub fn PKCS5_pbe2_set_iv(cipher: *const ::EVP_CIPHER,
+                             iter: c_int,
+                             salt: *mut c_uchar,
+                             saltlen: c_int,
+                             aiv: *mut c_uchar,
+                             prf_nid: c_int)
+                             -> *mut ::X509_ALGOR;
+    pub fn PKCS12_pbe_crypt(algor: *mut ::X509_ALGOR,
+                            pass: *const c_char,
+                            passlen: c_int,
+                            in_: *mut c_uchar,
+                            inlen: c_int,
+                            data: *mut *mut c_uchar,
+                            datalen: *mut c_int,
+                            en_de: c_int)
+                            -> *mut c_uchar;
     pub fn CRYPTO_num_locks() -> c_int;
     pub fn CRYPTO_set_locking_callback(func: unsafe extern "C" fn(mode: c_int,
                                                                   n: c_int,
@@ -783,6 +908,8 @@ extern {
 
     pub fn OCSP_cert_to_id(dgst: *const ::EVP_MD, subject: *mut ::X509, issuer: *mut ::X509) -> *mut ::OCSP_CERTID;
 
+    pub fn PKCS12_certbag2x509(bag: *mut PKCS12_SAFEBAG) -> *mut ::X509;
+    pub fn PKCS12_get_attr(bag: *mut PKCS12_SAFEBAG, attr_nid: c_int) -> *mut ::ASN1_TYPE;
     pub fn PKCS12_create(pass: *mut c_char,
                          friendly_name: *mut c_char,
                          pkey: *mut EVP_PKEY,
@@ -806,6 +933,8 @@ extern {
     pub fn TLSv1_1_method() -> *const ::SSL_METHOD;
     pub fn TLSv1_2_method() -> *const ::SSL_METHOD;
     pub fn DTLSv1_method() -> *const ::SSL_METHOD;
+    pub fn DTLSv1_client_method() -> *const ::SSL_METHOD;
+    pub fn DTLSv1_server_method() -> *const ::SSL_METHOD;
     #[cfg(ossl102)]
     pub fn DTLSv1_2_method() -> *const ::SSL_METHOD;
     pub fn SSL_get_ex_new_index(argl: c_long, argp: *mut c_void,
@@ -819,6 +948,10 @@ extern {
                                                             keylength: c_int)
                                                             -> *mut ::EC_KEY);
     pub fn SSL_CIPHER_get_version(cipher: *const ::SSL_CIPHER) -> *mut c_char;
+    pub fn SSL_CTX_set_cookie_verify_cb(s: *mut ::SSL_CTX,
+                                        cb: Option<extern fn(ssl: *mut ::SSL,
+                                                             cookie: *mut c_uchar,
+                                                             cookie_len: c_uint) -> c_int>);
     pub fn SSL_CTX_get_ex_new_index(argl: c_long, argp: *mut c_void,
                                     new_func: Option<::CRYPTO_EX_new>,
                                     dup_func: Option<::CRYPTO_EX_dup>,
@@ -832,7 +965,15 @@ extern {
     pub fn X509_get_subject_name(x: *mut ::X509) -> *mut ::X509_NAME;
     pub fn X509_set_notAfter(x: *mut ::X509, tm: *const ::ASN1_TIME) -> c_int;
     pub fn X509_set_notBefore(x: *mut ::X509, tm: *const ::ASN1_TIME) -> c_int;
+    pub fn X509_CRL_set_lastUpdate(x: *mut ::X509_CRL, tm: *const ::ASN1_TIME) -> c_int;
+    pub fn X509_CRL_set_nextUpdate(x: *mut ::X509_CRL, tm: *const ::ASN1_TIME) -> c_int;
     pub fn X509_get_ext_d2i(x: *mut ::X509, nid: c_int, crit: *mut c_int, idx: *mut c_int) -> *mut c_void;
+    pub fn X509_get_ext_count(x: *mut ::X509) -> c_int;
+    pub fn X509_get_ext(x: *mut ::X509, loc: c_int) -> *mut ::X509_EXTENSION;
+    pub fn X509_REVOKED_get_ext_d2i(x: *mut ::X509_REVOKED, nid: c_int, crit: *mut c_int, idx: *mut c_int) -> *mut c_void;
+    pub fn ASN1_ENUMERATED_get(a: *mut ::ASN1_ENUMERATED) -> c_long;
+    pub fn ASN1_TIME_to_generalizedtime(t: *mut ::ASN1_TIME, out: *mut *mut ::ASN1_GENERALIZEDTIME) -> *mut ::ASN1_GENERALIZEDTIME;
+    pub fn GENERAL_NAMES_free(gens: *mut stack_st_GENERAL_NAME);
     pub fn X509_NAME_get_entry(n: *mut ::X509_NAME, loc: c_int) -> *mut ::X509_NAME_ENTRY;
     pub fn X509_NAME_ENTRY_get_data(ne: *mut ::X509_NAME_ENTRY) -> *mut ::ASN1_STRING;
     pub fn X509_STORE_CTX_get_chain(ctx: *mut ::X509_STORE_CTX) -> *mut stack_st_X509;
@@ -851,9 +992,17 @@ extern {
 
     pub fn sk_num(st: *const _STACK) -> c_int;
     pub fn sk_value(st: *const _STACK, n: c_int) -> *mut c_void;
+    pub fn sk_new_null() -> *mut _STACK;
+    pub fn sk_push(st: *mut _STACK, data: *mut c_void) -> c_int;
     pub fn sk_free(st: *mut _STACK);
     pub fn sk_pop_free(st: *mut _STACK, free: Option<unsafe extern "C" fn (*mut c_void)>);
     pub fn sk_pop(st: *mut _STACK) -> *mut c_void;
+    pub fn sk_delete(st: *mut _STACK, loc: c_int) -> *mut c_void;
+    pub fn sk_set(st: *mut _STACK, i: c_int, data: *mut c_void) -> *mut c_void;
+    pub fn sk_sort(st: *mut _STACK);
+    pub fn sk_set_cmp_func(st: *mut _STACK,
+                           cmp: Option<unsafe extern fn(*const c_void, *const c_void) -> c_int>)
+                           -> Option<unsafe extern fn(*const c_void, *const c_void) -> c_int>;
 
     pub fn SSLeay() -> c_ulong;
     pub fn SSLeay_version(key: c_int) -> *const c_char;
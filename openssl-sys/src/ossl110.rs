@@ -1,4 +1,4 @@
-use libc::{c_int, c_void, c_char, c_uchar, c_ulong, c_long, c_uint, size_t};
+use libc::{c_int, c_void, c_char, c_uchar, c_ulong, c_long, c_uint, size_t, c_double};
 
 pub enum BIGNUM {}
 pub enum BIO {}
@@ -6,28 +6,53 @@ pub enum BIO_METHOD {}
 pub enum CRYPTO_EX_DATA {}
 pub enum DH {}
 pub enum DSA {}
+pub enum DSA_SIG {}
+pub enum ECDSA_SIG {}
 pub enum EVP_CIPHER {}
+pub enum EVP_ENCODE_CTX {}
 pub enum EVP_MD_CTX {}
 pub enum EVP_PKEY {}
 pub enum HMAC_CTX {}
 pub enum OPENSSL_STACK {}
 pub enum PKCS12 {}
+pub enum PKCS12_SAFEBAG {}
 pub enum RSA {}
 pub enum SSL {}
 pub enum SSL_CTX {}
 pub enum SSL_SESSION {}
+pub enum X509_REVOKED {}
+pub enum X509_SIG {}
 pub enum stack_st_ASN1_OBJECT {}
+pub enum stack_st_DIST_POINT {}
+pub enum stack_st_PKCS7 {}
+pub enum stack_st_PKCS12_SAFEBAG {}
 pub enum stack_st_GENERAL_NAME {}
 pub enum stack_st_OPENSSL_STRING {}
 pub enum stack_st_void {}
 pub enum stack_st_X509 {}
+pub enum stack_st_X509_REVOKED {}
+pub enum stack_st_X509_CRL {}
 pub enum stack_st_X509_NAME {}
 pub enum stack_st_X509_ATTRIBUTE {}
 pub enum stack_st_X509_EXTENSION {}
 pub enum stack_st_SSL_CIPHER {}
 pub enum X509 {}
+pub enum X509_REQ {}
 pub enum X509_VERIFY_PARAM {}
 
+#[repr(C)]
+pub struct RAND_METHOD {
+    pub seed: Option<unsafe extern "C" fn(buf: *const c_void, num: c_int) -> c_int>,
+    pub bytes: Option<unsafe extern "C" fn(buf: *mut c_uchar, num: c_int) -> c_int>,
+    pub cleanup: Option<unsafe extern "C" fn()>,
+    pub add: Option<unsafe extern "C" fn(buf: *const c_void, num: c_int, randomness: c_double)
+                                         -> c_int>,
+    pub pseudorand: Option<unsafe extern "C" fn(buf: *mut c_uchar, num: c_int) -> c_int>,
+    pub status: Option<unsafe extern "C" fn() -> c_int>,
+}
+
+pub const BN_FLG_SECURE: c_int = 0x08;
+
 pub const SSL_OP_MICROSOFT_SESS_ID_BUG: c_ulong =                   0x00000000;
 pub const SSL_OP_NETSCAPE_CHALLENGE_BUG: c_ulong =                  0x00000000;
 pub const SSL_OP_NETSCAPE_REUSE_CIPHER_CHANGE_BUG: c_ulong =        0x00000000;
@@ -47,6 +72,7 @@ pub const OPENSSL_DIR: c_int = 4;
 
 pub const CRYPTO_EX_INDEX_SSL: c_int = 0;
 pub const CRYPTO_EX_INDEX_SSL_CTX: c_int = 1;
+pub const CRYPTO_EX_INDEX_X509_STORE: c_int = 4;
 
 pub const X509_CHECK_FLAG_NEVER_CHECK_SUBJECT: c_uint = 0x20;
 
@@ -57,6 +83,14 @@ extern {
     pub fn BIO_s_file() -> *const BIO_METHOD;
     pub fn BIO_s_mem() -> *const BIO_METHOD;
 
+    pub fn EVP_ENCODE_CTX_new() -> *mut EVP_ENCODE_CTX;
+    pub fn EVP_ENCODE_CTX_free(ctx: *mut EVP_ENCODE_CTX);
+    pub fn EVP_EncodeUpdate(ctx: *mut EVP_ENCODE_CTX,
+                            out: *mut c_uchar,
+                            outl: *mut c_int,
+                            in_: *const c_uchar,
+                            inl: c_int) -> c_int;
+
     pub fn BN_get_rfc2409_prime_768(bn: *mut BIGNUM) -> *mut BIGNUM;
     pub fn BN_get_rfc2409_prime_1024(bn: *mut BIGNUM) -> *mut BIGNUM;
     pub fn BN_get_rfc3526_prime_1536(bn: *mut BIGNUM) -> *mut BIGNUM;
@@ -68,9 +102,33 @@ extern {
 
     pub fn CRYPTO_malloc(num: size_t, file: *const c_char, line: c_int) -> *mut c_void;
     pub fn CRYPTO_free(buf: *mut c_void, file: *const c_char, line: c_int);
+    pub fn PKCS5_pbe2_set_iv(cipher: *const ::EVP_CIPHER,
+                             iter: c_int,
+                             salt: *const c_uchar,
+                             saltlen: c_int,
+                             aiv: *mut c_uchar,
+                             prf_nid: c_int)
+                             -> *mut ::X509_ALGOR;
+    pub fn PKCS12_pbe_crypt(algor: *const ::X509_ALGOR,
+                            pass: *const c_char,
+                            passlen: c_int,
+                            in_: *const c_uchar,
+                            inlen: c_int,
+                            data: *mut *mut c_uchar,
+                            datalen: *mut c_int,
+                            en_de: c_int)
+                            -> *mut c_uchar;
+    pub fn X509_SIG_get0(sig: *const ::X509_SIG,
+                         palg: *mut *const ::X509_ALGOR,
+                         pdigest: *mut *const ::ASN1_STRING);
+    pub fn X509_SIG_getm(sig: *mut ::X509_SIG,
+                         palg: *mut *mut ::X509_ALGOR,
+                         pdigest: *mut *mut ::ASN1_STRING);
 
     pub fn EVP_chacha20() -> *const ::EVP_CIPHER;
     pub fn EVP_chacha20_poly1305() -> *const ::EVP_CIPHER;
+    pub fn EVP_blake2b512() -> *const ::EVP_MD;
+    pub fn EVP_blake2s256() -> *const ::EVP_MD;
 
     pub fn HMAC_CTX_new() -> *mut HMAC_CTX;
     pub fn HMAC_CTX_free(ctx: *mut HMAC_CTX);
@@ -79,20 +137,55 @@ extern {
 
     pub fn TLS_method() -> *const ::SSL_METHOD;
     pub fn DTLS_method() -> *const ::SSL_METHOD;
+    pub fn DTLS_client_method() -> *const ::SSL_METHOD;
+    pub fn DTLS_server_method() -> *const ::SSL_METHOD;
+    pub fn SSL_CTX_set_cookie_verify_cb(s: *mut ::SSL_CTX,
+                                        cb: Option<extern fn(ssl: *mut ::SSL,
+                                                             cookie: *const c_uchar,
+                                                             cookie_len: c_uint) -> c_int>);
     pub fn SSL_CIPHER_get_version(cipher: *const ::SSL_CIPHER) -> *const c_char;
+    pub fn SSL_CTX_get_ciphers(ctx: *const ::SSL_CTX) -> *mut stack_st_SSL_CIPHER;
     pub fn X509_get_subject_name(x: *const ::X509) -> *mut ::X509_NAME;
     pub fn X509_set1_notAfter(x: *mut ::X509, tm: *const ::ASN1_TIME) -> c_int;
     pub fn X509_set1_notBefore(x: *mut ::X509, tm: *const ::ASN1_TIME) -> c_int;
+    pub fn X509_CRL_set1_lastUpdate(x: *mut ::X509_CRL, tm: *const ::ASN1_TIME) -> c_int;
+    pub fn X509_CRL_set1_nextUpdate(x: *mut ::X509_CRL, tm: *const ::ASN1_TIME) -> c_int;
     pub fn X509_get_ext_d2i(x: *const ::X509, nid: c_int, crit: *mut c_int, idx: *mut c_int) -> *mut c_void;
+    pub fn X509_get_ext_count(x: *const ::X509) -> c_int;
+    pub fn X509_get_ext(x: *const ::X509, loc: c_int) -> *mut ::X509_EXTENSION;
+    pub fn X509_CRL_get_REVOKED(crl: *mut ::X509_CRL) -> *mut stack_st_X509_REVOKED;
+    pub fn X509_REVOKED_get0_serialNumber(x: *const X509_REVOKED) -> *const ::ASN1_INTEGER;
+    pub fn X509_REVOKED_get0_revocationDate(x: *const X509_REVOKED) -> *const ::ASN1_TIME;
+    pub fn X509_REVOKED_get0_extensions(r: *const X509_REVOKED) -> *const stack_st_X509_EXTENSION;
+    pub fn X509_REVOKED_get_ext_d2i(x: *const X509_REVOKED, nid: c_int, crit: *mut c_int, idx: *mut c_int) -> *mut c_void;
+    pub fn ASN1_ENUMERATED_get(a: *const ::ASN1_ENUMERATED) -> c_long;
+    pub fn ASN1_TIME_to_generalizedtime(t: *const ::ASN1_TIME, out: *mut *mut ::ASN1_GENERALIZEDTIME) -> *mut ::ASN1_GENERALIZEDTIME;
+    pub fn GENERAL_NAMES_free(gens: *mut stack_st_GENERAL_NAME);
     pub fn X509_NAME_get_entry(n: *const ::X509_NAME, loc: c_int) -> *mut ::X509_NAME_ENTRY;
     pub fn X509_NAME_ENTRY_get_data(ne: *const ::X509_NAME_ENTRY) -> *mut ::ASN1_STRING;
     pub fn X509V3_EXT_nconf_nid(conf: *mut ::CONF, ctx: *mut ::X509V3_CTX, ext_nid: c_int, value: *const c_char) -> *mut ::X509_EXTENSION;
     pub fn X509V3_EXT_nconf(conf: *mut ::CONF, ctx: *mut ::X509V3_CTX, name: *const c_char, value: *const c_char) -> *mut ::X509_EXTENSION;
     pub fn ASN1_STRING_to_UTF8(out: *mut *mut c_uchar, s: *const ::ASN1_STRING) -> c_int;
     pub fn BN_is_negative(b: *const ::BIGNUM) -> c_int;
+    pub fn BN_set_flags(b: *mut ::BIGNUM, n: c_int);
+    pub fn BN_get_flags(b: *const ::BIGNUM, n: c_int) -> c_int;
+    pub fn BN_secure_new() -> *mut ::BIGNUM;
+    pub fn BN_bn2binpad(a: *const ::BIGNUM, to: *mut u8, tolen: c_int) -> c_int;
     pub fn EVP_CIPHER_key_length(cipher: *const EVP_CIPHER) -> c_int;
     pub fn EVP_CIPHER_block_size(cipher: *const EVP_CIPHER) -> c_int;
     pub fn EVP_CIPHER_iv_length(cipher: *const EVP_CIPHER) -> c_int;
+    pub fn EVP_CIPHER_flags(cipher: *const EVP_CIPHER) -> c_ulong;
+    pub fn EVP_PBE_scrypt(pass: *const c_char,
+                          passlen: size_t,
+                          salt: *const c_uchar,
+                          saltlen: size_t,
+                          N: u64,
+                          r: u64,
+                          p: u64,
+                          maxmem: u64,
+                          key: *mut c_uchar,
+                          keylen: size_t)
+                          -> c_int;
     pub fn DSA_get0_pqg(d: *const ::DSA,
                         p: *mut *const ::BIGNUM,
                         q: *mut *const ::BIGNUM,
@@ -100,6 +193,18 @@ extern {
     pub fn DSA_get0_key(d: *const ::DSA,
                         pub_key: *mut *const ::BIGNUM,
                         priv_key: *mut *const ::BIGNUM);
+    pub fn DSA_SIG_get0(sig: *const ::DSA_SIG,
+                        pr: *mut *const ::BIGNUM,
+                        ps: *mut *const ::BIGNUM);
+    pub fn DSA_SIG_set0(sig: *mut ::DSA_SIG,
+                        r: *mut ::BIGNUM,
+                        s: *mut ::BIGNUM) -> c_int;
+    pub fn ECDSA_SIG_get0(sig: *const ::ECDSA_SIG,
+                          pr: *mut *const ::BIGNUM,
+                          ps: *mut *const ::BIGNUM);
+    pub fn ECDSA_SIG_set0(sig: *mut ::ECDSA_SIG,
+                          r: *mut ::BIGNUM,
+                          s: *mut ::BIGNUM) -> c_int;
     pub fn RSA_get0_key(r: *const ::RSA,
                         n: *mut *const ::BIGNUM,
                         e: *mut *const ::BIGNUM,
@@ -107,6 +212,10 @@ extern {
     pub fn RSA_get0_factors(r: *const ::RSA,
                             p: *mut *const ::BIGNUM,
                             q: *mut *const ::BIGNUM);
+    pub fn RSA_get0_crt_params(r: *const ::RSA,
+                               dmp1: *mut *const ::BIGNUM,
+                               dmq1: *mut *const ::BIGNUM,
+                               iqmp: *mut *const ::BIGNUM);
     pub fn RSA_set0_key(r: *mut ::RSA,
                         n: *mut ::BIGNUM,
                         e: *mut ::BIGNUM,
@@ -123,14 +232,27 @@ extern {
     pub fn OPENSSL_sk_value(stack: *const ::OPENSSL_STACK,
                             idx: c_int) -> *mut c_void;
     pub fn SSL_CTX_get_options(ctx: *const ::SSL_CTX) -> c_ulong;
+    pub fn SSL_is_dtls(s: *const ::SSL) -> c_int;
     pub fn SSL_CTX_set_options(ctx: *mut ::SSL_CTX, op: c_ulong) -> c_ulong;
     pub fn SSL_CTX_clear_options(ctx: *mut ::SSL_CTX, op: c_ulong) -> c_ulong;
+    pub fn SSL_CTX_set_default_read_buffer_len(ctx: *mut ::SSL_CTX, len: size_t);
     pub fn X509_getm_notAfter(x: *const ::X509) -> *mut ::ASN1_TIME;
+    pub fn X509_get_X509_PUBKEY(x: *const ::X509) -> *mut ::X509_PUBKEY;
+    pub fn X509_REQ_get_X509_PUBKEY(req: *mut ::X509_REQ) -> *mut ::X509_PUBKEY;
+    pub fn X509_get0_signature(psig: *mut *const ::ASN1_STRING,
+                               palg: *mut *const ::X509_ALGOR,
+                               x: *const ::X509);
     pub fn X509_getm_notBefore(x: *const ::X509) -> *mut ::ASN1_TIME;
     pub fn DH_set0_pqg(dh: *mut ::DH,
                        p: *mut ::BIGNUM,
                        q: *mut ::BIGNUM,
                        g: *mut ::BIGNUM) -> c_int;
+    pub fn DH_get0_key(dh: *const ::DH,
+                       pub_key: *mut *const ::BIGNUM,
+                       priv_key: *mut *const ::BIGNUM);
+    pub fn DH_set0_key(dh: *mut ::DH,
+                       pub_key: *mut ::BIGNUM,
+                       priv_key: *mut ::BIGNUM) -> c_int;
     pub fn BIO_set_init(a: *mut ::BIO, init: c_int);
     pub fn BIO_set_data(a: *mut ::BIO, data: *mut c_void);
     pub fn BIO_get_data(a: *mut ::BIO) -> *mut c_void;
@@ -165,22 +287,46 @@ extern {
                                    -> c_int;
     pub fn X509_up_ref(x: *mut X509) -> c_int;
     pub fn SSL_CTX_up_ref(x: *mut SSL_CTX) -> c_int;
+    pub fn SSL_get_client_random(ssl: *const SSL, out: *mut c_uchar, outlen: size_t) -> size_t;
     pub fn SSL_SESSION_get_master_key(session: *const SSL_SESSION,
                                       out: *mut c_uchar,
                                       outlen: size_t)
                                       -> size_t;
     pub fn X509_get0_extensions(req: *const ::X509) -> *const stack_st_X509_EXTENSION;
     pub fn X509_STORE_CTX_get0_chain(ctx: *mut ::X509_STORE_CTX) -> *mut stack_st_X509;
+    pub fn X509_STORE_CTX_get0_store(ctx: *mut ::X509_STORE_CTX) -> *mut ::X509_STORE;
+    pub fn X509_STORE_CTX_get1_crls(ctx: *mut ::X509_STORE_CTX,
+                                    nm: *mut ::X509_NAME)
+                                    -> *mut stack_st_X509_CRL;
+    pub fn X509_STORE_set_lookup_crls(store: *mut ::X509_STORE,
+                                      lookup_crls: Option<unsafe extern fn(*mut ::X509_STORE_CTX,
+                                                                           *mut ::X509_NAME)
+                                                                           -> *mut stack_st_X509_CRL>);
+    pub fn X509_STORE_set_ex_data(store: *mut ::X509_STORE, idx: c_int, data: *mut c_void) -> c_int;
+    pub fn X509_STORE_get_ex_data(store: *mut ::X509_STORE, idx: c_int) -> *mut c_void;
+    pub fn X509_CRL_up_ref(x: *mut ::X509_CRL) -> c_int;
     pub fn EVP_MD_CTX_new() -> *mut EVP_MD_CTX;
     pub fn EVP_MD_CTX_free(ctx: *mut EVP_MD_CTX);
     pub fn EVP_PKEY_bits(key: *const EVP_PKEY) -> c_int;
 
     pub fn OpenSSL_version_num() -> c_ulong;
     pub fn OpenSSL_version(key: c_int) -> *const c_char;
+    pub fn OPENSSL_sk_new_null() -> *mut ::OPENSSL_STACK;
+    pub fn OPENSSL_sk_push(st: *mut ::OPENSSL_STACK, data: *const c_void) -> c_int;
     pub fn OPENSSL_sk_free(st: *mut ::OPENSSL_STACK);
     pub fn OPENSSL_sk_pop_free(st: *mut ::OPENSSL_STACK, free: Option<unsafe extern "C" fn (*mut c_void)>);
     pub fn OPENSSL_sk_pop(st: *mut ::OPENSSL_STACK) -> *mut c_void;
+    pub fn OPENSSL_sk_delete(st: *mut ::OPENSSL_STACK, loc: c_int) -> *mut c_void;
+    pub fn OPENSSL_sk_set(st: *mut ::OPENSSL_STACK, i: c_int, data: *const c_void) -> *mut c_void;
+    pub fn OPENSSL_sk_sort(st: *mut ::OPENSSL_STACK);
+    pub fn OPENSSL_sk_set_cmp_func(st: *mut ::OPENSSL_STACK,
+                                   cmp: Option<unsafe extern fn(*const c_void, *const c_void) -> c_int>)
+                                   -> Option<unsafe extern fn(*const c_void, *const c_void) -> c_int>;
 
+    pub fn PKCS12_SAFEBAG_get_nid(bag: *const PKCS12_SAFEBAG) -> c_int;
+    pub fn PKCS12_SAFEBAG_get0_p8inf(bag: *const PKCS12_SAFEBAG) -> *const ::PKCS8_PRIV_KEY_INFO;
+    pub fn PKCS12_SAFEBAG_get1_cert(bag: *const PKCS12_SAFEBAG) -> *mut ::X509;
+    pub fn PKCS12_SAFEBAG_get0_attr(bag: *const PKCS12_SAFEBAG, attr_nid: c_int) -> *const ::ASN1_TYPE;
     pub fn PKCS12_create(pass: *const c_char,
                          friendly_name: *const c_char,
                          pkey: *mut EVP_PKEY,
@@ -16,6 +16,10 @@ fn main() {
         _ => panic!("Unable to detect OpenSSL version"),
     }
 
+    if let Ok(_) = env::var("DEP_OPENSSL_OSSL111") {
+        println!("cargo:rustc-cfg=ossl111");
+    }
+
     if let Ok(_) = env::var("DEP_OPENSSL_LIBRESSL") {
         println!("cargo:rustc-cfg=libressl");
     }
@@ -1,14 +1,23 @@
 use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
-use libc::{c_long, c_char};
+use libc::{c_char, c_int, c_long, c_void};
+#[cfg(all(feature = "v111", ossl111))]
+use libc::time_t;
+use std::borrow::Borrow;
+use std::cmp;
+use std::ffi::CString;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ptr;
 use std::slice;
 use std::str;
 
 use {cvt, cvt_p};
 use bio::MemBio;
+use bn::BigNum;
 use error::ErrorStack;
+use nid::Nid;
+use stack::Stackable;
 use string::OpensslString;
 
 foreign_type! {
@@ -47,6 +56,42 @@ impl fmt::Display for Asn1TimeRef {
     }
 }
 
+impl Asn1TimeRef {
+    /// Converts the time to its canonical form.
+    ///
+    /// Times in the years 1950 through 2049 are encoded as UTCTime, and all others as
+    /// GeneralizedTime, with fractional seconds and time zone offsets removed. This can be used to
+    /// clean up times with unusual encodings produced by misbehaving CAs.
+    ///
+    /// This corresponds to `ASN1_TIME_normalize`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn normalize(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::ASN1_TIME_normalize(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Compares the time to `time`, a number of seconds since the Unix epoch.
+    ///
+    /// For example, a certificate has expired if comparing its `not_after` time to the current
+    /// time returns `Ordering::Less`.
+    ///
+    /// This corresponds to `ASN1_TIME_cmp_time_t`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn compare_time_t(&self, time: time_t) -> Result<cmp::Ordering, ErrorStack> {
+        unsafe {
+            match ffi::ASN1_TIME_cmp_time_t(self.as_ptr(), time) {
+                -1 => Ok(cmp::Ordering::Less),
+                0 => Ok(cmp::Ordering::Equal),
+                1 => Ok(cmp::Ordering::Greater),
+                _ => Err(ErrorStack::get()),
+            }
+        }
+    }
+}
+
 impl Asn1Time {
     fn from_period(period: c_long) -> Result<Asn1Time, ErrorStack> {
         ffi::init();
@@ -61,6 +106,214 @@ impl Asn1Time {
     pub fn days_from_now(days: u32) -> Result<Asn1Time, ErrorStack> {
         Asn1Time::from_period(days as c_long * 60 * 60 * 24)
     }
+
+    /// Parses a time from its ASN.1 string form.
+    ///
+    /// Both the UTCTime form, `YYMMDDHHMMSSZ`, and the GeneralizedTime form, `YYYYMMDDHHMMSSZ`,
+    /// are accepted. The resulting value has the same type and contents as the input, so no
+    /// precision is lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` contains an embedded null.
+    pub fn from_str_asn1(s: &str) -> Result<Asn1Time, ErrorStack> {
+        ffi::init();
+
+        let s = CString::new(s).unwrap();
+        unsafe {
+            let time = Asn1Time(try!(cvt_p(ffi::ASN1_TIME_new())));
+            try!(cvt(ffi::ASN1_TIME_set_string(time.as_ptr(), s.as_ptr())));
+            Ok(time)
+        }
+    }
+}
+
+foreign_type! {
+    type CType = ffi::ASN1_INTEGER;
+    fn drop = ffi::ASN1_INTEGER_free;
+
+    pub struct Asn1Integer;
+    pub struct Asn1IntegerRef;
+}
+
+impl Asn1IntegerRef {
+    /// Converts the integer to a `BigNum`.
+    pub fn to_bn(&self) -> Result<BigNum, ErrorStack> {
+        unsafe {
+            cvt_p(ffi::ASN1_INTEGER_to_BN(self.as_ptr(), ptr::null_mut())).map(|p| BigNum::from_ptr(p))
+        }
+    }
+
+    /// Returns a decimal string representation of the integer.
+    pub fn to_dec_str(&self) -> Result<OpensslString, ErrorStack> {
+        self.to_bn().and_then(|bn| bn.to_dec_str())
+    }
+
+    /// Returns a hexadecimal string representation of the integer, such as `8771F7BDEE982FA5`.
+    pub fn to_hex_str(&self) -> Result<OpensslString, ErrorStack> {
+        self.to_bn().and_then(|bn| bn.to_hex_str())
+    }
+}
+
+impl fmt::Debug for Asn1IntegerRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_dec_str() {
+            Ok(s) => f.write_str(&s),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl fmt::Debug for Asn1Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for Asn1IntegerRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_dec_str() {
+            Ok(s) => f.write_str(&s),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl fmt::Display for Asn1Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+foreign_type! {
+    type CType = ffi::ASN1_OBJECT;
+    fn drop = ffi::ASN1_OBJECT_free;
+
+    pub struct Asn1Object;
+    pub struct Asn1ObjectRef;
+}
+
+impl Stackable for Asn1Object {
+    type StackType = ffi::stack_st_ASN1_OBJECT;
+}
+
+impl str::FromStr for Asn1Object {
+    type Err = ErrorStack;
+
+    /// Constructs an object from a short name, long name, or dotted numerical OID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `txt` contains an embedded null.
+    fn from_str(txt: &str) -> Result<Asn1Object, ErrorStack> {
+        ffi::init();
+
+        let txt = CString::new(txt).unwrap();
+        unsafe { cvt_p(ffi::OBJ_txt2obj(txt.as_ptr(), 0)).map(Asn1Object) }
+    }
+}
+
+impl Asn1ObjectRef {
+    /// Returns the NID associated with this object, or `UNDEF` if it is unknown to OpenSSL.
+    pub fn nid(&self) -> Nid {
+        unsafe { Nid::from_raw(ffi::OBJ_obj2nid(self.as_ptr())) }
+    }
+
+    to_der!(ffi::i2d_ASN1_OBJECT);
+}
+
+impl fmt::Display for Asn1ObjectRef {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        unsafe {
+            let mut buf = [0u8; 80];
+            let len = ffi::OBJ_obj2txt(buf.as_mut_ptr() as *mut c_char,
+                                       buf.len() as c_int,
+                                       self.as_ptr(),
+                                       0);
+            if len < 0 {
+                return fmt.write_str("error");
+            }
+            let len = cmp::min(len as usize, buf.len() - 1);
+            fmt.write_str(str::from_utf8(&buf[..len]).unwrap_or("error"))
+        }
+    }
+}
+
+impl fmt::Debug for Asn1ObjectRef {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Asn1Object({})", self)
+    }
+}
+
+impl PartialEq for Asn1ObjectRef {
+    fn eq(&self, other: &Asn1ObjectRef) -> bool {
+        unsafe { ffi::OBJ_cmp(self.as_ptr(), other.as_ptr()) == 0 }
+    }
+}
+
+impl Eq for Asn1ObjectRef {}
+
+impl PartialOrd for Asn1ObjectRef {
+    fn partial_cmp(&self, other: &Asn1ObjectRef) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Asn1ObjectRef {
+    fn cmp(&self, other: &Asn1ObjectRef) -> cmp::Ordering {
+        unsafe { ffi::OBJ_cmp(self.as_ptr(), other.as_ptr()).cmp(&0) }
+    }
+}
+
+impl Hash for Asn1ObjectRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // OBJ_cmp compares the encoded OIDs, so hashing the DER encoding is consistent with it.
+        self.to_der().unwrap_or_else(|_| vec![]).hash(state)
+    }
+}
+
+impl fmt::Display for Asn1Object {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, fmt)
+    }
+}
+
+impl fmt::Debug for Asn1Object {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, fmt)
+    }
+}
+
+impl PartialEq for Asn1Object {
+    fn eq(&self, other: &Asn1Object) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Asn1Object {}
+
+impl PartialOrd for Asn1Object {
+    fn partial_cmp(&self, other: &Asn1Object) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl Ord for Asn1Object {
+    fn cmp(&self, other: &Asn1Object) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl Hash for Asn1Object {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl Borrow<Asn1ObjectRef> for Asn1Object {
+    fn borrow(&self) -> &Asn1ObjectRef {
+        &*self
+    }
 }
 
 foreign_type! {
@@ -93,6 +346,65 @@ impl Asn1StringRef {
     }
 }
 
+foreign_type! {
+    type CType = ffi::ASN1_TYPE;
+    fn drop = ffi::ASN1_TYPE_free;
+
+    /// An ASN.1 value of any type, as used in attribute values and algorithm parameters.
+    pub struct Asn1Type;
+    /// A reference to an `Asn1Type`.
+    pub struct Asn1TypeRef;
+}
+
+impl Asn1Type {
+    /// Creates a `BOOLEAN` value, such as the `cA` flag of a basic constraints extension.
+    pub fn boolean(value: bool) -> Result<Asn1Type, ErrorStack> {
+        unsafe {
+            let type_ = try!(Asn1Type::new());
+            // OpenSSL stores the pointer argument itself as the boolean
+            ffi::ASN1_TYPE_set(type_.as_ptr(), ffi::V_ASN1_BOOLEAN, value as usize as *mut c_void);
+            Ok(type_)
+        }
+    }
+
+    /// Creates a `NULL` value, such as the contents of an OCSP no-check extension.
+    pub fn null() -> Result<Asn1Type, ErrorStack> {
+        unsafe {
+            let type_ = try!(Asn1Type::new());
+            ffi::ASN1_TYPE_set(type_.as_ptr(), ffi::V_ASN1_NULL, ptr::null_mut());
+            Ok(type_)
+        }
+    }
+
+    unsafe fn new() -> Result<Asn1Type, ErrorStack> {
+        ffi::init();
+        cvt_p(ffi::ASN1_TYPE_new()).map(Asn1Type)
+    }
+
+    from_der!(Asn1Type, ffi::d2i_ASN1_TYPE);
+}
+
+impl Asn1TypeRef {
+    /// Returns the value if it is a `BOOLEAN`.
+    pub fn boolean(&self) -> Option<bool> {
+        unsafe {
+            let type_ = &*self.as_ptr();
+            if type_.type_ != ffi::V_ASN1_BOOLEAN {
+                return None;
+            }
+            // the boolean is the int member of the value union
+            Some(*(&type_.value as *const _ as *const c_int) != 0)
+        }
+    }
+
+    /// Determines if the value is a `NULL`.
+    pub fn is_null(&self) -> bool {
+        unsafe { ffi::ASN1_TYPE_get(self.as_ptr()) == ffi::V_ASN1_NULL }
+    }
+
+    to_der!(ffi::i2d_ASN1_TYPE);
+}
+
 #[cfg(any(ossl101, ossl102))]
 use ffi::ASN1_STRING_data;
 
@@ -101,3 +413,87 @@ use ffi::ASN1_STRING_data;
 unsafe fn ASN1_STRING_data(s: *mut ffi::ASN1_STRING) -> *mut ::libc::c_uchar {
     ffi::ASN1_STRING_get0_data(s) as *mut _
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use nid;
+    use super::{Asn1Object, Asn1Time, Asn1Type};
+
+    #[test]
+    fn time_from_str() {
+        let time = Asn1Time::from_str_asn1("20250101000000Z").unwrap();
+        assert_eq!(time.to_string(), "Jan  1 00:00:00 2025 GMT");
+
+        let time = Asn1Time::from_str_asn1("991231235959Z").unwrap();
+        assert_eq!(time.to_string(), "Dec 31 23:59:59 1999 GMT");
+
+        assert!(Asn1Time::from_str_asn1("20251301000000Z").is_err());
+        assert!(Asn1Time::from_str_asn1("not a time").is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn time_normalize_and_compare() {
+        use foreign_types::{ForeignType, ForeignTypeRef};
+        use std::cmp::Ordering;
+
+        use super::Asn1StringRef;
+
+        fn contents(time: &Asn1Time) -> Vec<u8> {
+            unsafe { Asn1StringRef::from_ptr(time.as_ptr() as *mut _).as_slice().to_vec() }
+        }
+
+        // a GeneralizedTime with an offset, which normalizes to a UTCTime in UTC
+        let mut time = Asn1Time::from_str_asn1("20250101010000+0100").unwrap();
+        assert_eq!(contents(&time), b"20250101010000+0100");
+        time.normalize().unwrap();
+        assert_eq!(contents(&time), b"250101000000Z");
+        assert_eq!(time.to_string(), "Jan  1 00:00:00 2025 GMT");
+
+        assert_eq!(time.compare_time_t(1735689600).unwrap(), Ordering::Equal);
+        assert_eq!(time.compare_time_t(1735689601).unwrap(), Ordering::Less);
+        assert_eq!(time.compare_time_t(1735689599).unwrap(), Ordering::Greater);
+    }
+
+    #[test]
+    fn object_eq_hash() {
+        let by_name = "CN".parse::<Asn1Object>().unwrap();
+        let by_oid = "2.5.4.3".parse::<Asn1Object>().unwrap();
+        let other = "2.5.4.10".parse::<Asn1Object>().unwrap();
+
+        assert_eq!(by_name, by_oid);
+        assert!(by_name != other);
+        assert!(by_name < other);
+        assert_eq!(by_name.nid(), nid::COMMONNAME);
+        assert_eq!(by_name.to_string(), "commonName");
+
+        let mut map = HashMap::new();
+        map.insert(by_name, 1);
+        map.insert(other, 2);
+        assert_eq!(map[&*by_oid], 1);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn type_boolean_null() {
+        let t = Asn1Type::boolean(true).unwrap();
+        assert_eq!(t.to_der().unwrap(), [0x01, 0x01, 0xff]);
+        assert_eq!(t.boolean(), Some(true));
+        assert!(!t.is_null());
+
+        let f = Asn1Type::boolean(false).unwrap();
+        assert_eq!(f.to_der().unwrap(), [0x01, 0x01, 0x00]);
+        assert_eq!(f.boolean(), Some(false));
+
+        let null = Asn1Type::null().unwrap();
+        assert_eq!(null.to_der().unwrap(), [0x05, 0x00]);
+        assert!(null.is_null());
+        assert_eq!(null.boolean(), None);
+
+        let decoded = Asn1Type::from_der(&[0x01, 0x01, 0xff]).unwrap();
+        assert_eq!(decoded.boolean(), Some(true));
+        assert!(Asn1Type::from_der(&[0x05, 0x00]).unwrap().is_null());
+    }
+}
@@ -0,0 +1,277 @@
+//! Base64 encoding and decoding.
+//!
+//! `encode_block` and `decode_block` convert a complete buffer to and from unwrapped base64.
+//! `Encoder` and `Decoder` process data incrementally in the format used by PEM files and
+//! `openssl enc -base64`, in which the encoded text is split into lines of 64 characters.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use openssl::base64;
+//!
+//! let encoded = base64::encode_block(b"hello, world");
+//! assert_eq!(encoded, "aGVsbG8sIHdvcmxk");
+//! assert_eq!(base64::decode_block(&encoded).unwrap(), b"hello, world");
+//! ```
+use ffi;
+use libc::c_int;
+
+use {cvt, cvt_n, cvt_p};
+use error::ErrorStack;
+
+/// Encodes `src` as base64, without line breaks.
+///
+/// # Panics
+///
+/// Panics if the encoded output would be longer than `c_int::max_value()` bytes.
+pub fn encode_block(src: &[u8]) -> String {
+    assert!(src.len() <= c_int::max_value() as usize / 4 * 3 - 2);
+    let mut out = vec![0; (src.len() + 2) / 3 * 4 + 1];
+    unsafe {
+        let len = ffi::EVP_EncodeBlock(out.as_mut_ptr(), src.as_ptr(), src.len() as c_int);
+        out.truncate(len as usize);
+        String::from_utf8_unchecked(out)
+    }
+}
+
+/// Decodes base64 without line breaks, such as that produced by `encode_block`.
+///
+/// Leading and trailing whitespace is ignored.
+///
+/// # Panics
+///
+/// Panics if `src.len() > c_int::max_value()`.
+pub fn decode_block(src: &str) -> Result<Vec<u8>, ErrorStack> {
+    let src = src.trim();
+    assert!(src.len() <= c_int::max_value() as usize);
+    let mut out = vec![0; src.len() / 4 * 3];
+    unsafe {
+        let len = try!(cvt_n(ffi::EVP_DecodeBlock(out.as_mut_ptr(),
+                                                  src.as_ptr(),
+                                                  src.len() as c_int)));
+        // EVP_DecodeBlock decodes padding characters to zero bytes rather than dropping them
+        let padding = src.bytes().rev().take_while(|&b| b == b'=').count();
+        out.truncate((len as usize).saturating_sub(padding));
+    }
+    Ok(out)
+}
+
+/// An incremental base64 encoder.
+///
+/// The output is split into lines of 64 characters, each followed by a newline, with a final,
+/// shorter line if required.
+pub struct Encoder(*mut ffi::EVP_ENCODE_CTX);
+
+unsafe impl Sync for Encoder {}
+unsafe impl Send for Encoder {}
+
+impl Encoder {
+    /// Creates a new `Encoder`.
+    pub fn new() -> Result<Encoder, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            let ctx = try!(cvt_p(EVP_ENCODE_CTX_new()));
+            ffi::EVP_EncodeInit(ctx);
+            Ok(Encoder(ctx))
+        }
+    }
+
+    /// Encodes `input`, returning the complete lines of output produced so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input.len() > c_int::max_value() / 2`.
+    pub fn update(&mut self, input: &[u8]) -> Result<String, ErrorStack> {
+        assert!(input.len() <= c_int::max_value() as usize / 2);
+        // each 48 bytes of input, including up to 47 bytes buffered by earlier calls, produce a
+        // line of 65 bytes, and a trailing nul is written
+        let mut out = vec![0; (input.len() + 47) / 48 * 65 + 1];
+        unsafe {
+            let mut outl = 0;
+            try!(cvt(encode_update(self.0,
+                                   out.as_mut_ptr(),
+                                   &mut outl,
+                                   input.as_ptr(),
+                                   input.len() as c_int)));
+            out.truncate(outl as usize);
+            Ok(String::from_utf8_unchecked(out))
+        }
+    }
+
+    /// Finishes encoding, returning the final line of output.
+    ///
+    /// `update` should not be called after this method.
+    pub fn finish(&mut self) -> String {
+        let mut out = vec![0; 66];
+        unsafe {
+            let mut outl = 0;
+            ffi::EVP_EncodeFinal(self.0, out.as_mut_ptr(), &mut outl);
+            out.truncate(outl as usize);
+            String::from_utf8_unchecked(out)
+        }
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        unsafe {
+            EVP_ENCODE_CTX_free(self.0);
+        }
+    }
+}
+
+/// An incremental base64 decoder.
+///
+/// Line breaks and other whitespace are ignored, so input may be split at any point.
+pub struct Decoder(*mut ffi::EVP_ENCODE_CTX);
+
+unsafe impl Sync for Decoder {}
+unsafe impl Send for Decoder {}
+
+impl Decoder {
+    /// Creates a new `Decoder`.
+    pub fn new() -> Result<Decoder, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            let ctx = try!(cvt_p(EVP_ENCODE_CTX_new()));
+            ffi::EVP_DecodeInit(ctx);
+            Ok(Decoder(ctx))
+        }
+    }
+
+    /// Decodes `input`, returning the bytes decoded so far.
+    ///
+    /// An error is returned if `input` contains characters which are not valid base64.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input.len() > c_int::max_value() / 2`.
+    pub fn update(&mut self, input: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        assert!(input.len() <= c_int::max_value() as usize / 2);
+        // up to 80 characters may be buffered by earlier calls
+        let mut out = vec![0; (input.len() + 80) / 4 * 3];
+        unsafe {
+            let mut outl = 0;
+            try!(cvt_n(ffi::EVP_DecodeUpdate(self.0,
+                                             out.as_mut_ptr(),
+                                             &mut outl,
+                                             input.as_ptr(),
+                                             input.len() as c_int)));
+            out.truncate(outl as usize);
+            Ok(out)
+        }
+    }
+
+    /// Finishes decoding, returning any remaining bytes.
+    ///
+    /// An error is returned if the input ended partway through a group of four characters.
+    ///
+    /// `update` should not be called after this method.
+    pub fn finish(&mut self) -> Result<Vec<u8>, ErrorStack> {
+        let mut out = vec![0; 3];
+        unsafe {
+            let mut outl = 0;
+            try!(cvt_n(ffi::EVP_DecodeFinal(self.0, out.as_mut_ptr(), &mut outl)));
+            out.truncate(outl as usize);
+            Ok(out)
+        }
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        unsafe {
+            EVP_ENCODE_CTX_free(self.0);
+        }
+    }
+}
+
+#[cfg(ossl110)]
+use ffi::{EVP_ENCODE_CTX_new, EVP_ENCODE_CTX_free};
+
+#[cfg(ossl110)]
+unsafe fn encode_update(ctx: *mut ffi::EVP_ENCODE_CTX,
+                        out: *mut u8,
+                        outl: *mut c_int,
+                        input: *const u8,
+                        inl: c_int)
+                        -> c_int {
+    ffi::EVP_EncodeUpdate(ctx, out, outl, input, inl)
+}
+
+#[cfg(ossl10x)]
+#[allow(bad_style)]
+unsafe fn EVP_ENCODE_CTX_new() -> *mut ffi::EVP_ENCODE_CTX {
+    Box::into_raw(Box::new(::std::mem::zeroed()))
+}
+
+#[cfg(ossl10x)]
+#[allow(bad_style)]
+unsafe fn EVP_ENCODE_CTX_free(ctx: *mut ffi::EVP_ENCODE_CTX) {
+    drop(Box::from_raw(ctx));
+}
+
+#[cfg(ossl10x)]
+unsafe fn encode_update(ctx: *mut ffi::EVP_ENCODE_CTX,
+                        out: *mut u8,
+                        outl: *mut c_int,
+                        input: *const u8,
+                        inl: c_int)
+                        -> c_int {
+    ffi::EVP_EncodeUpdate(ctx, out, outl, input, inl);
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_round_trip() {
+        for &(raw, encoded) in &[(&b""[..], ""),
+                                 (b"f", "Zg=="),
+                                 (b"fo", "Zm8="),
+                                 (b"foo", "Zm9v"),
+                                 (b"foob", "Zm9vYg=="),
+                                 (b"fooba", "Zm9vYmE="),
+                                 (b"foobar", "Zm9vYmFy")] {
+            assert_eq!(encode_block(raw), encoded);
+            assert_eq!(decode_block(encoded).unwrap(), raw);
+        }
+
+        assert_eq!(decode_block(" Zm9vYg==\n").unwrap(), b"foob");
+        assert!(decode_block("Zm9vY").is_err());
+        assert!(decode_block("Zm9v!A==").is_err());
+    }
+
+    #[test]
+    fn streaming() {
+        let data = (0..200u32).map(|i| i as u8).collect::<Vec<_>>();
+
+        let mut encoder = Encoder::new().unwrap();
+        let mut encoded = String::new();
+        for chunk in data.chunks(7) {
+            encoded.push_str(&encoder.update(chunk).unwrap());
+        }
+        encoded.push_str(&encoder.finish());
+
+        let lines = encoded.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[..4].iter().all(|l| l.len() == 64));
+        assert!(encoded.ends_with('\n'));
+        assert_eq!(lines.concat(), encode_block(&data));
+
+        let mut decoder = Decoder::new().unwrap();
+        let mut decoded = vec![];
+        for chunk in encoded.as_bytes().chunks(5) {
+            decoded.extend(decoder.update(chunk).unwrap());
+        }
+        decoded.extend(decoder.finish().unwrap());
+        assert_eq!(decoded, data);
+
+        let mut decoder = Decoder::new().unwrap();
+        assert!(decoder.update(b"Zm9v!A==").is_err());
+    }
+}
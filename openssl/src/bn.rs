@@ -20,6 +20,21 @@ use ffi::{get_rfc2409_prime_768 as BN_get_rfc2409_prime_768,
           get_rfc3526_prime_6144 as BN_get_rfc3526_prime_6144,
           get_rfc3526_prime_8192 as BN_get_rfc3526_prime_8192};
 
+#[cfg(ossl110)]
+use ffi::{BN_get_flags, BN_set_flags};
+
+#[cfg(ossl10x)]
+#[allow(bad_style)]
+unsafe fn BN_set_flags(b: *mut ffi::BIGNUM, n: c_int) {
+    (*b).flags |= n;
+}
+
+#[cfg(ossl10x)]
+#[allow(bad_style)]
+unsafe fn BN_get_flags(b: *const ffi::BIGNUM, n: c_int) -> c_int {
+    (*b).flags & n
+}
+
 #[cfg(ossl110)]
 use ffi::{BN_get_rfc2409_prime_768, BN_get_rfc2409_prime_1024, BN_get_rfc3526_prime_1536,
     BN_get_rfc3526_prime_2048, BN_get_rfc3526_prime_3072, BN_get_rfc3526_prime_4096,
@@ -206,6 +221,27 @@ impl BigNumRef {
         unsafe { ffi::BN_is_negative(self.as_ptr()) == 1 }
     }
 
+    /// Marks `self` as secret, so that OpenSSL uses constant time algorithms when operating on it.
+    ///
+    /// This should be set on private exponents and other secret values passed to functions such
+    /// as `mod_exp` to protect against timing attacks.
+    pub fn set_const_time(&mut self) {
+        unsafe { BN_set_flags(self.as_ptr(), ffi::BN_FLG_CONSTTIME) }
+    }
+
+    /// Determines if `self` has been marked as secret with `set_const_time`.
+    pub fn is_const_time(&self) -> bool {
+        unsafe { BN_get_flags(self.as_ptr(), ffi::BN_FLG_CONSTTIME) != 0 }
+    }
+
+    /// Determines if `self` was allocated from the secure heap.
+    ///
+    /// Requires the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(all(feature = "v110", ossl110))]
+    pub fn is_secure(&self) -> bool {
+        unsafe { BN_get_flags(self.as_ptr(), ffi::BN_FLG_SECURE) != 0 }
+    }
+
     /// Returns the number of significant bits in `self`.
     pub fn num_bits(&self) -> i32 {
         unsafe { ffi::BN_num_bits(self.as_ptr()) as i32 }
@@ -484,6 +520,66 @@ impl BigNumRef {
         v
     }
 
+    /// Returns a big-endian byte vector representation of the absolute value of `self`, left-padded
+    /// with zeroes to `pad_to` bytes.
+    ///
+    /// This is useful for fixed-width encodings such as EC scalars or RSA signatures. An error is
+    /// returned if `self` does not fit in `pad_to` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pad_to` is negative.
+    ///
+    /// ```
+    /// # use openssl::bn::BigNum;
+    /// let bn = BigNum::from_u32(0x4543).unwrap();
+    ///
+    /// assert_eq!(bn.to_vec_padded(4).unwrap(), [0, 0, 0x45, 0x43]);
+    /// assert!(bn.to_vec_padded(1).is_err());
+    /// ```
+    pub fn to_vec_padded(&self, pad_to: i32) -> Result<Vec<u8>, ErrorStack> {
+        assert!(pad_to >= 0);
+        let mut v = Vec::with_capacity(pad_to as usize);
+        unsafe {
+            try!(cvt_n(self._bn2binpad(v.as_mut_ptr(), pad_to)));
+            v.set_len(pad_to as usize);
+        }
+        Ok(v)
+    }
+
+    #[cfg(ossl10x)]
+    unsafe fn _bn2binpad(&self, to: *mut u8, pad_to: i32) -> c_int {
+        let size = self.num_bytes();
+        if pad_to < size {
+            return -1;
+        }
+        let pad = (pad_to - size) as usize;
+        ptr::write_bytes(to, 0, pad);
+        ffi::BN_bn2bin(self.as_ptr(), to.offset(pad as isize));
+        pad_to
+    }
+
+    #[cfg(ossl110)]
+    unsafe fn _bn2binpad(&self, to: *mut u8, pad_to: i32) -> c_int {
+        ffi::BN_bn2binpad(self.as_ptr(), to, pad_to)
+    }
+
+    /// Sets `self` to the unsigned, big-endian encoded number in `n`, reusing its allocation.
+    ///
+    /// ```
+    /// # use openssl::bn::BigNum;
+    /// let mut bn = BigNum::new().unwrap();
+    /// bn.copy_from_slice(&[0, 0, 0x12, 0x34]).unwrap();
+    ///
+    /// assert_eq!(bn, BigNum::from_u32(0x1234).unwrap());
+    /// ```
+    pub fn copy_from_slice(&mut self, n: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            assert!(n.len() <= c_int::max_value() as usize);
+            cvt_p(ffi::BN_bin2bn(n.as_ptr(), n.len() as c_int, self.as_ptr())).map(|_| ())
+        }
+    }
+
     /// Returns a decimal string representation of `self`.
     ///
     /// ```
@@ -533,6 +629,20 @@ impl BigNum {
         }
     }
 
+    /// Creates a new `BigNum` with the value 0, allocated from the secure heap.
+    ///
+    /// If the secure heap has not been initialized, this falls back to a normal allocation.
+    ///
+    /// Requires the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(all(feature = "v110", ossl110))]
+    pub fn new_secure() -> Result<BigNum, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let v = try!(cvt_p(ffi::BN_secure_new()));
+            Ok(BigNum::from_ptr(v))
+        }
+    }
+
     /// Creates a new `BigNum` with the given value.
     pub fn from_u32(n: u32) -> Result<BigNum, ErrorStack> {
         BigNum::new().and_then(|v| unsafe {
@@ -899,6 +1009,51 @@ impl Neg for BigNum {
 mod tests {
     use bn::{BigNumContext, BigNum};
 
+    #[test]
+    fn test_const_time() {
+        let mut p = BigNum::new().unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        p.generate_prime(128, true, None, None).unwrap();
+        assert!(p.is_prime(64, &mut ctx).unwrap());
+
+        let mut e = BigNum::from_u32(65537).unwrap();
+        assert!(!e.is_const_time());
+        e.set_const_time();
+        assert!(e.is_const_time());
+
+        // a^(p-1) = 1 mod p for any a not divisible by p
+        let a = BigNum::from_u32(3).unwrap();
+        let mut p1 = p.to_owned().unwrap();
+        p1.sub_word(1).unwrap();
+        p1.set_const_time();
+        let mut r = BigNum::new().unwrap();
+        r.mod_exp(&a, &p1, &p, &mut ctx).unwrap();
+        assert_eq!(r, BigNum::from_u32(1).unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "v110", ossl110))]
+    fn test_new_secure() {
+        let mut a = BigNum::new_secure().unwrap();
+        assert!(a.is_secure());
+        a.add_word(5).unwrap();
+        assert_eq!(a, BigNum::from_u32(5).unwrap());
+        assert!(!BigNum::new().unwrap().is_secure());
+    }
+
+    #[test]
+    fn test_to_vec_padded() {
+        let bn = BigNum::from_u32(0x010203).unwrap();
+        assert_eq!(bn.to_vec_padded(3).unwrap(), [1, 2, 3]);
+        assert_eq!(bn.to_vec_padded(6).unwrap(), [0, 0, 0, 1, 2, 3]);
+        assert!(bn.to_vec_padded(2).is_err());
+        assert_eq!(BigNum::new().unwrap().to_vec_padded(2).unwrap(), [0, 0]);
+
+        let mut copy = BigNum::from_u32(7).unwrap();
+        copy.copy_from_slice(&bn.to_vec_padded(32).unwrap()).unwrap();
+        assert_eq!(copy, bn);
+    }
+
     #[test]
     fn test_to_from_slice() {
         let v0 = BigNum::from_u32(10203004).unwrap();
@@ -0,0 +1,169 @@
+//! Shared secret derivation.
+//!
+//! The `Deriver` computes a shared secret from a private key and the public key of a peer, as
+//! used by key agreement algorithms such as ECDH and DH.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use openssl::derive::Deriver;
+//! use openssl::ec::{EcGroup, EcKey};
+//! use openssl::nid;
+//! use openssl::pkey::PKey;
+//!
+//! let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+//! let alice = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+//! let bob = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+//!
+//! let mut deriver = Deriver::new(&alice).unwrap();
+//! deriver.set_peer(&bob).unwrap();
+//! let secret = deriver.derive_to_vec().unwrap();
+//!
+//! let mut deriver = Deriver::new(&bob).unwrap();
+//! deriver.set_peer(&alice).unwrap();
+//! assert_eq!(secret, deriver.derive_to_vec().unwrap());
+//! ```
+use ffi;
+use foreign_types::ForeignTypeRef;
+use std::marker::PhantomData;
+use std::ptr;
+
+use {cvt, cvt_p};
+use error::ErrorStack;
+use pkey::PKeyRef;
+
+/// A type used to derive a shared secret between two keys.
+pub struct Deriver<'a>(*mut ffi::EVP_PKEY_CTX, PhantomData<&'a ()>);
+
+unsafe impl<'a> Sync for Deriver<'a> {}
+unsafe impl<'a> Send for Deriver<'a> {}
+
+impl<'a> Drop for Deriver<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_PKEY_CTX_free(self.0);
+        }
+    }
+}
+
+impl<'a> Deriver<'a> {
+    /// Creates a new `Deriver` using the provided private key.
+    pub fn new(key: &'a PKeyRef) -> Result<Deriver<'a>, ErrorStack> {
+        unsafe {
+            ffi::init();
+
+            let ctx = try!(cvt_p(ffi::EVP_PKEY_CTX_new(key.as_ptr(), ptr::null_mut())));
+            let deriver = Deriver(ctx, PhantomData);
+            try!(cvt(ffi::EVP_PKEY_derive_init(ctx)));
+            Ok(deriver)
+        }
+    }
+
+    /// Sets the peer key used for secret derivation.
+    ///
+    /// The peer key must use the same algorithm and parameters, such as the curve, as the
+    /// private key.
+    pub fn set_peer(&mut self, key: &'a PKeyRef) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::EVP_PKEY_derive_set_peer(self.0, key.as_ptr())).map(|_| ()) }
+    }
+
+    /// Returns the size of the shared secret.
+    ///
+    /// It can be used to size the buffer passed to `derive`.
+    pub fn derived_len(&mut self) -> Result<usize, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            cvt(ffi::EVP_PKEY_derive(self.0, ptr::null_mut(), &mut len)).map(|_| len)
+        }
+    }
+
+    /// Derives a shared secret between the two keys, writing it into `buf`.
+    ///
+    /// Returns the number of bytes written.
+    pub fn derive(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            let mut len = buf.len();
+            cvt(ffi::EVP_PKEY_derive(self.0, buf.as_mut_ptr(), &mut len)).map(|_| len)
+        }
+    }
+
+    /// A convenience function which derives a shared secret and returns it in a new buffer.
+    pub fn derive_to_vec(&mut self) -> Result<Vec<u8>, ErrorStack> {
+        let len = try!(self.derived_len());
+        let mut buf = vec![0; len];
+        let len = try!(self.derive(&mut buf));
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use dh::Dh;
+    use ec::{EcGroup, EcKey};
+    use nid;
+    use pkey::PKey;
+
+    #[test]
+    fn derive_without_peer() {
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut deriver = Deriver::new(&key).unwrap();
+        assert!(deriver.derive_to_vec().is_err());
+    }
+
+    #[test]
+    fn ecdh() {
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let alice = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let bob = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut deriver = Deriver::new(&alice).unwrap();
+        deriver.set_peer(&bob).unwrap();
+        assert_eq!(deriver.derived_len().unwrap(), 32);
+        let alice_secret = deriver.derive_to_vec().unwrap();
+
+        let mut deriver = Deriver::new(&bob).unwrap();
+        deriver.set_peer(&alice).unwrap();
+        assert_eq!(deriver.derive_to_vec().unwrap(), alice_secret);
+
+        let other_group = EcGroup::from_curve_name(nid::SECP384R1).unwrap();
+        let other = PKey::from_ec_key(EcKey::generate(&other_group).unwrap()).unwrap();
+        let mut deriver = Deriver::new(&alice).unwrap();
+        assert!(deriver.set_peer(&other).is_err());
+    }
+
+    #[test]
+    fn dh() {
+        let params = Dh::from_pem(include_bytes!("../test/dhparams.pem")).unwrap();
+        let pem = params.to_pem().unwrap();
+        let alice = PKey::from_dh(params.generate_key().unwrap()).unwrap();
+        let bob = PKey::from_dh(Dh::from_pem(&pem).unwrap().generate_key().unwrap()).unwrap();
+
+        let mut deriver = Deriver::new(&alice).unwrap();
+        deriver.set_peer(&bob).unwrap();
+        let alice_secret = deriver.derive_to_vec().unwrap();
+
+        let mut deriver = Deriver::new(&bob).unwrap();
+        deriver.set_peer(&alice).unwrap();
+        assert_eq!(deriver.derive_to_vec().unwrap(), alice_secret);
+    }
+
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn x25519() {
+        let alice = PKey::generate_x25519().unwrap();
+        let bob = PKey::generate_x25519().unwrap();
+
+        let mut deriver = Deriver::new(&alice).unwrap();
+        deriver.set_peer(&bob).unwrap();
+        let alice_secret = deriver.derive_to_vec().unwrap();
+        assert_eq!(alice_secret.len(), 32);
+
+        let mut deriver = Deriver::new(&bob).unwrap();
+        deriver.set_peer(&alice).unwrap();
+        assert_eq!(deriver.derive_to_vec().unwrap(), alice_secret);
+    }
+}
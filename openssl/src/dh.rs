@@ -1,11 +1,12 @@
 use error::ErrorStack;
 use ffi;
 use foreign_types::ForeignTypeRef;
+use libc::c_int;
 use std::mem;
 use std::ptr;
 
-use {cvt, cvt_p, init};
-use bn::BigNum;
+use {cvt, cvt_n, cvt_p, init};
+use bn::{BigNum, BigNumRef};
 
 foreign_type! {
     type CType = ffi::DH;
@@ -19,6 +20,44 @@ foreign_type! {
 impl DhRef {
     to_pem!(ffi::PEM_write_bio_DHparams);
     to_der!(ffi::i2d_DHparams);
+
+    /// Returns the public key, if one has been generated.
+    pub fn pub_key(&self) -> Option<&BigNumRef> {
+        unsafe {
+            let pub_key = compat::keys(self.as_ptr())[0];
+            if pub_key.is_null() {
+                None
+            } else {
+                Some(BigNumRef::from_ptr(pub_key as *mut _))
+            }
+        }
+    }
+
+    /// Returns the private key, if one has been generated.
+    pub fn priv_key(&self) -> Option<&BigNumRef> {
+        unsafe {
+            let priv_key = compat::keys(self.as_ptr())[1];
+            if priv_key.is_null() {
+                None
+            } else {
+                Some(BigNumRef::from_ptr(priv_key as *mut _))
+            }
+        }
+    }
+
+    /// Computes the shared secret from this key and the public key of the peer.
+    ///
+    /// `generate_key` must have been used to create the private key first.
+    pub fn compute_key(&self, peer_pub_key: &BigNumRef) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut buf = vec![0; ffi::DH_size(self.as_ptr()) as usize];
+            let len = try!(cvt_n(ffi::DH_compute_key(buf.as_mut_ptr(),
+                                                     peer_pub_key.as_ptr(),
+                                                     self.as_ptr())));
+            buf.truncate(len as usize);
+            Ok(buf)
+        }
+    }
 }
 
 impl Dh {
@@ -32,6 +71,42 @@ impl Dh {
         }
     }
 
+    /// Generates a new set of parameters with a safe prime `p` of `prime_len` bits.
+    ///
+    /// `generator` is typically 2 or 5. Generation of large parameters can take a long time.
+    pub fn generate_params(prime_len: u32, generator: u32) -> Result<Dh, ErrorStack> {
+        unsafe {
+            init();
+            let dh = Dh(try!(cvt_p(ffi::DH_new())));
+            try!(cvt(ffi::DH_generate_parameters_ex(dh.0,
+                                                    prime_len as c_int,
+                                                    generator as c_int,
+                                                    ptr::null_mut())));
+            Ok(dh)
+        }
+    }
+
+    /// Generates a new key pair using the parameters in `self`.
+    pub fn generate_key(self) -> Result<Dh, ErrorStack> {
+        unsafe {
+            try!(cvt(ffi::DH_generate_key(self.0)));
+            Ok(self)
+        }
+    }
+
+    /// Sets the public key of `self`, which must contain parameters but no key pair.
+    ///
+    /// This is used to construct a `Dh` from the public value of a peer which was received
+    /// separately from the parameters, so that it can be passed to `PKey::from_dh` and used as
+    /// the peer of a `Deriver`.
+    pub fn set_public_key(self, pub_key: BigNum) -> Result<Dh, ErrorStack> {
+        unsafe {
+            try!(cvt(compat::DH_set0_key(self.0, pub_key.as_ptr(), ptr::null_mut())));
+            mem::forget(pub_key);
+            Ok(self)
+        }
+    }
+
     from_pem!(Dh, ffi::PEM_read_bio_DHparams);
     from_der!(Dh, ffi::d2i_DHparams);
 
@@ -56,7 +131,16 @@ impl Dh {
 
 #[cfg(ossl110)]
 mod compat {
-    pub use ffi::DH_set0_pqg;
+    use std::ptr;
+    use ffi::{self, BIGNUM, DH};
+
+    pub use ffi::{DH_set0_pqg, DH_set0_key};
+
+    pub unsafe fn keys(dh: *const DH) -> [*const BIGNUM; 2] {
+        let (mut pub_key, mut priv_key) = (ptr::null(), ptr::null());
+        ffi::DH_get0_key(dh, &mut pub_key, &mut priv_key);
+        [pub_key, priv_key]
+    }
 }
 
 #[cfg(ossl10x)]
@@ -75,12 +159,33 @@ mod compat {
         (*dh).g = g;
         1
     }
+
+    pub unsafe fn DH_set0_key(dh: *mut ffi::DH,
+                              pub_key: *mut ffi::BIGNUM,
+                              priv_key: *mut ffi::BIGNUM)
+                              -> c_int {
+        if !pub_key.is_null() {
+            ffi::BN_clear_free((*dh).pub_key);
+            (*dh).pub_key = pub_key;
+        }
+        if !priv_key.is_null() {
+            ffi::BN_clear_free((*dh).priv_key);
+            (*dh).priv_key = priv_key;
+        }
+        1
+    }
+
+    pub unsafe fn keys(dh: *const ffi::DH) -> [*const ffi::BIGNUM; 2] {
+        [(*dh).pub_key, (*dh).priv_key]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use dh::Dh;
     use bn::BigNum;
+    use derive::Deriver;
+    use pkey::PKey;
     use ssl::{SslMethod, SslContext};
 
     #[test]
@@ -133,6 +238,53 @@ mod tests {
         ctx.set_tmp_dh(&dh).unwrap();
     }
 
+    #[test]
+    fn test_dh_key_exchange() {
+        let params = Dh::generate_params(512, 2).unwrap();
+        let pem = params.to_pem().unwrap();
+        assert!(params.pub_key().is_none());
+
+        let alice = params.generate_key().unwrap();
+        let bob = Dh::from_pem(&pem).unwrap().generate_key().unwrap();
+        assert!(alice.pub_key().is_some());
+        assert!(alice.priv_key().is_some());
+
+        let alice_secret = alice.compute_key(bob.pub_key().unwrap()).unwrap();
+        let bob_secret = bob.compute_key(alice.pub_key().unwrap()).unwrap();
+        assert_eq!(alice_secret, bob_secret);
+    }
+
+    #[test]
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    fn test_dh_rfc5114_key_exchange() {
+        let alice = Dh::get_2048_256().unwrap().generate_key().unwrap();
+        let bob = Dh::get_2048_256().unwrap().generate_key().unwrap();
+
+        let alice_secret = alice.compute_key(bob.pub_key().unwrap()).unwrap();
+        let bob_secret = bob.compute_key(alice.pub_key().unwrap()).unwrap();
+        assert_eq!(alice_secret, bob_secret);
+    }
+
+    #[test]
+    fn test_dh_set_public_key() {
+        let params = Dh::generate_params(512, 2).unwrap();
+        let pem = params.to_pem().unwrap();
+        let alice = params.generate_key().unwrap();
+        let bob = PKey::from_dh(Dh::from_pem(&pem).unwrap().generate_key().unwrap()).unwrap();
+
+        let alice_pub = alice.pub_key().unwrap().to_owned().unwrap();
+        let peer = Dh::from_pem(&pem).unwrap().set_public_key(alice_pub).unwrap();
+        assert!(peer.priv_key().is_none());
+        let peer = PKey::from_dh(peer).unwrap();
+
+        let mut deriver = Deriver::new(&bob).unwrap();
+        deriver.set_peer(&peer).unwrap();
+        let bob_secret = deriver.derive_to_vec().unwrap();
+
+        let bob_pub = bob.dh().unwrap().pub_key().unwrap().to_owned().unwrap();
+        assert_eq!(alice.compute_key(&bob_pub).unwrap(), bob_secret);
+    }
+
     #[test]
     fn test_dh_from_der() {
         let params = include_bytes!("../test/dhparams.pem");
@@ -2,11 +2,12 @@ use ffi;
 use foreign_types::ForeignTypeRef;
 use libc::{c_int, c_char, c_void};
 use std::fmt;
+use std::mem;
 use std::ptr;
 
-use {cvt, cvt_p};
+use {cvt, cvt_n, cvt_p};
 use bio::MemBioSlice;
-use bn::BigNumRef;
+use bn::{BigNum, BigNumRef};
 use error::ErrorStack;
 use util::{CallbackState, invoke_passwd_cb_old};
 
@@ -67,6 +68,61 @@ impl DsaRef {
         }
     }
 
+    /// Returns the public key component, if present.
+    pub fn pub_key(&self) -> Option<&BigNumRef> {
+        unsafe {
+            let pub_key = compat::keys(self.as_ptr())[0];
+            if pub_key.is_null() {
+                None
+            } else {
+                Some(BigNumRef::from_ptr(pub_key as *mut _))
+            }
+        }
+    }
+
+    /// Returns the private key component, if present.
+    pub fn priv_key(&self) -> Option<&BigNumRef> {
+        unsafe {
+            let priv_key = compat::keys(self.as_ptr())[1];
+            if priv_key.is_null() {
+                None
+            } else {
+                Some(BigNumRef::from_ptr(priv_key as *mut _))
+            }
+        }
+    }
+
+    /// Computes a signature of the digest `data`.
+    ///
+    /// `data` should be the output of a hash function, and the key must have a private
+    /// component.
+    pub fn sign(&self, data: &[u8]) -> Result<DsaSig, ErrorStack> {
+        unsafe {
+            assert!(data.len() <= c_int::max_value() as usize);
+            let sig = try!(cvt_p(ffi::DSA_do_sign(data.as_ptr(),
+                                                  data.len() as c_int,
+                                                  self.as_ptr())));
+            Ok(DsaSig(sig))
+        }
+    }
+
+    /// Verifies that `sig` is a valid signature of the digest `data` by this key.
+    pub fn verify(&self, data: &[u8], sig: &DsaSigRef) -> Result<bool, ErrorStack> {
+        unsafe {
+            assert!(data.len() <= c_int::max_value() as usize);
+            let r = try!(cvt_n(ffi::DSA_do_verify(data.as_ptr(),
+                                                  data.len() as c_int,
+                                                  sig.as_ptr(),
+                                                  self.as_ptr())));
+            Ok(r == 1)
+        }
+    }
+
+    to_pem_inner!(/// Serializes the parameters to PEM.
+        params_to_pem, ffi::PEM_write_bio_DSAparams);
+    to_der_inner!(/// Serializes the parameters to DER.
+        params_to_der, ffi::i2d_DSAparams);
+
     pub fn has_public_key(&self) -> bool {
         unsafe { !compat::keys(self.as_ptr())[0].is_null() }
     }
@@ -93,6 +149,37 @@ impl Dsa {
         }
     }
 
+    /// Generates a new set of DSA parameters with a prime `p` of the specified size.
+    ///
+    /// The returned value has no key; use `generate_key` to create one.
+    pub fn generate_params(bits: u32) -> Result<Dsa, ErrorStack> {
+        ffi::init();
+        unsafe {
+            let dsa = Dsa(try!(cvt_p(ffi::DSA_new())));
+            try!(cvt(ffi::DSA_generate_parameters_ex(dsa.0,
+                                                     bits as c_int,
+                                                     ptr::null(),
+                                                     0,
+                                                     ptr::null_mut(),
+                                                     ptr::null_mut(),
+                                                     ptr::null_mut())));
+            Ok(dsa)
+        }
+    }
+
+    /// Generates a new key pair using the parameters in `self`.
+    pub fn generate_key(self) -> Result<Dsa, ErrorStack> {
+        unsafe {
+            try!(cvt(ffi::DSA_generate_key(self.0)));
+            Ok(self)
+        }
+    }
+
+    from_pem_inner!(/// Deserializes a set of parameters from PEM-formatted data.
+        params_from_pem, Dsa, ffi::PEM_read_bio_DSAparams);
+    from_der_inner!(/// Deserializes a set of parameters from DER-formatted data.
+        params_from_der, Dsa, ffi::d2i_DSAparams);
+
     private_key_from_pem!(Dsa, ffi::PEM_read_bio_DSAPrivateKey);
     private_key_from_der!(Dsa, ffi::d2i_DSAPrivateKey);
     public_key_from_pem!(Dsa, ffi::PEM_read_bio_DSA_PUBKEY);
@@ -123,10 +210,49 @@ impl fmt::Debug for Dsa {
     }
 }
 
+foreign_type! {
+    type CType = ffi::DSA_SIG;
+    fn drop = ffi::DSA_SIG_free;
+
+    /// A DSA signature.
+    pub struct DsaSig;
+    /// A reference to a `DsaSig`.
+    pub struct DsaSigRef;
+}
+
+impl DsaSig {
+    /// Constructs a signature from its `r` and `s` components.
+    pub fn from_components(r: BigNum, s: BigNum) -> Result<DsaSig, ErrorStack> {
+        unsafe {
+            let sig = DsaSig(try!(cvt_p(ffi::DSA_SIG_new())));
+            try!(cvt(compat::set_sig(sig.as_ptr(), r.as_ptr(), s.as_ptr())));
+            mem::forget((r, s));
+            Ok(sig)
+        }
+    }
+
+    from_der!(DsaSig, ffi::d2i_DSA_SIG);
+}
+
+impl DsaSigRef {
+    /// Returns the `r` component of the signature.
+    pub fn r(&self) -> &BigNumRef {
+        unsafe { BigNumRef::from_ptr(compat::sig(self.as_ptr())[0] as *mut _) }
+    }
+
+    /// Returns the `s` component of the signature.
+    pub fn s(&self) -> &BigNumRef {
+        unsafe { BigNumRef::from_ptr(compat::sig(self.as_ptr())[1] as *mut _) }
+    }
+
+    to_der!(ffi::i2d_DSA_SIG);
+}
+
 #[cfg(ossl110)]
 mod compat {
     use std::ptr;
-    use ffi::{self, BIGNUM, DSA};
+    use ffi::{self, BIGNUM, DSA, DSA_SIG};
+    use libc::c_int;
 
     pub unsafe fn pqg(d: *const DSA) -> [*const BIGNUM; 3] {
         let (mut p, mut q, mut g) = (ptr::null(), ptr::null(), ptr::null());
@@ -139,11 +265,22 @@ mod compat {
         ffi::DSA_get0_key(d, &mut pub_key, &mut priv_key);
         [pub_key, priv_key]
     }
+
+    pub unsafe fn sig(sig: *const DSA_SIG) -> [*const BIGNUM; 2] {
+        let (mut r, mut s) = (ptr::null(), ptr::null());
+        ffi::DSA_SIG_get0(sig, &mut r, &mut s);
+        [r, s]
+    }
+
+    pub unsafe fn set_sig(sig: *mut DSA_SIG, r: *mut BIGNUM, s: *mut BIGNUM) -> c_int {
+        ffi::DSA_SIG_set0(sig, r, s)
+    }
 }
 
 #[cfg(ossl10x)]
 mod compat {
-    use ffi::{BIGNUM, DSA};
+    use ffi::{self, BIGNUM, DSA, DSA_SIG};
+    use libc::c_int;
 
     pub unsafe fn pqg(d: *const DSA) -> [*const BIGNUM; 3] {
         [(*d).p, (*d).q, (*d).g]
@@ -152,10 +289,24 @@ mod compat {
     pub unsafe fn keys(d: *const DSA) -> [*const BIGNUM; 2] {
         [(*d).pub_key, (*d).priv_key]
     }
+
+    pub unsafe fn sig(sig: *const DSA_SIG) -> [*const BIGNUM; 2] {
+        [(*sig).r, (*sig).s]
+    }
+
+    pub unsafe fn set_sig(sig: *mut DSA_SIG, r: *mut BIGNUM, s: *mut BIGNUM) -> c_int {
+        // Mirror DSA_SIG_set0 by releasing any components that are already set.
+        ffi::BN_clear_free((*sig).r);
+        ffi::BN_clear_free((*sig).s);
+        (*sig).r = r;
+        (*sig).s = s;
+        1
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use hash::{hash, MessageDigest};
     use symm::Cipher;
 
     use super::*;
@@ -165,6 +316,53 @@ mod test {
         Dsa::generate(1024).unwrap();
     }
 
+    #[test]
+    fn test_params_generate_key() {
+        let params = Dsa::generate_params(1024).unwrap();
+        assert!(params.p().is_some());
+        assert!(params.pub_key().is_none());
+        assert!(params.priv_key().is_none());
+
+        let der = params.params_to_der().unwrap();
+        let pem = params.params_to_pem().unwrap();
+        assert_eq!(Dsa::params_from_pem(&pem).unwrap().params_to_der().unwrap(), der);
+
+        let key = Dsa::params_from_der(&der).unwrap().generate_key().unwrap();
+        assert_eq!(key.p().unwrap(), params.p().unwrap());
+        assert_eq!(key.q().unwrap(), params.q().unwrap());
+        assert_eq!(key.g().unwrap(), params.g().unwrap());
+        assert!(key.pub_key().is_some());
+        assert!(key.priv_key().is_some());
+    }
+
+    #[test]
+    fn test_params_from_pem() {
+        let params = Dsa::params_from_pem(include_bytes!("../test/dsaparam.pem")).unwrap();
+        assert!(params.q().is_some());
+        assert!(!params.has_public_key());
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let key = Dsa::generate(1024).unwrap();
+        let public = Dsa::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+        let digest = hash(MessageDigest::sha256(), b"hello world").unwrap();
+
+        let sig = key.sign(&digest).unwrap();
+        assert!(public.verify(&digest, &sig).unwrap());
+
+        let wrong = hash(MessageDigest::sha256(), b"goodbye world").unwrap();
+        assert!(!public.verify(&wrong, &sig).unwrap());
+
+        let r = BigNum::from_slice(&sig.r().to_vec()).unwrap();
+        let s = BigNum::from_slice(&sig.s().to_vec()).unwrap();
+        let sig2 = DsaSig::from_components(r, s).unwrap();
+        assert_eq!(sig2.to_der().unwrap(), sig.to_der().unwrap());
+
+        let sig3 = DsaSig::from_der(&sig.to_der().unwrap()).unwrap();
+        assert!(public.verify(&digest, &sig3).unwrap());
+    }
+
     #[test]
     pub fn test_password() {
         let key = include_bytes!("../test/dsa-encrypted.pem");
@@ -227,6 +227,80 @@ impl EcPointRef {
         }
     }
 
+    /// Places the affine coordinates of a point on a curve over a prime field in the provided
+    /// `BigNum`s.
+    pub fn affine_coordinates_gfp(&self,
+                                  group: &EcGroupRef,
+                                  x: &mut BigNumRef,
+                                  y: &mut BigNumRef,
+                                  ctx: &mut BigNumContextRef)
+                                  -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EC_POINT_get_affine_coordinates_GFp(group.as_ptr(),
+                                                         self.as_ptr(),
+                                                         x.as_ptr(),
+                                                         y.as_ptr(),
+                                                         ctx.as_ptr()))
+                .map(|_| ())
+        }
+    }
+
+    /// Places the affine coordinates of a point on a curve over a binary field in the provided
+    /// `BigNum`s.
+    pub fn affine_coordinates_gf2m(&self,
+                                   group: &EcGroupRef,
+                                   x: &mut BigNumRef,
+                                   y: &mut BigNumRef,
+                                   ctx: &mut BigNumContextRef)
+                                   -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EC_POINT_get_affine_coordinates_GF2m(group.as_ptr(),
+                                                          self.as_ptr(),
+                                                          x.as_ptr(),
+                                                          y.as_ptr(),
+                                                          ctx.as_ptr()))
+                .map(|_| ())
+        }
+    }
+
+    /// Sets `self` to the point with the given affine coordinates on a curve over a prime field.
+    ///
+    /// Fails if the point is not on the curve.
+    pub fn set_affine_coordinates_gfp(&mut self,
+                                      group: &EcGroupRef,
+                                      x: &BigNumRef,
+                                      y: &BigNumRef,
+                                      ctx: &mut BigNumContextRef)
+                                      -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EC_POINT_set_affine_coordinates_GFp(group.as_ptr(),
+                                                         self.as_ptr(),
+                                                         x.as_ptr(),
+                                                         y.as_ptr(),
+                                                         ctx.as_ptr()))
+                .map(|_| ())
+        }
+    }
+
+    /// Sets `self` to the point with the given affine coordinates on a curve over a binary field.
+    ///
+    /// Fails if the point is not on the curve.
+    pub fn set_affine_coordinates_gf2m(&mut self,
+                                       group: &EcGroupRef,
+                                       x: &BigNumRef,
+                                       y: &BigNumRef,
+                                       ctx: &mut BigNumContextRef)
+                                       -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EC_POINT_set_affine_coordinates_GF2m(group.as_ptr(),
+                                                          self.as_ptr(),
+                                                          x.as_ptr(),
+                                                          y.as_ptr(),
+                                                          ctx.as_ptr()))
+                .map(|_| ())
+        }
+    }
+
     /// Determines if this point is equal to another.
     pub fn eq(&self,
               group: &EcGroupRef,
@@ -356,6 +430,21 @@ impl EcKey {
         Ok(builder.build())
     }
 
+    /// Constructs an `EcKey` from the specified group, private number, and public point.
+    ///
+    /// The public point is not derived from the private number, so callers should use
+    /// `check_key` to validate the pair if it comes from an untrusted source.
+    pub fn from_private_components(group: &EcGroupRef,
+                                   private_number: &BigNumRef,
+                                   public_key: &EcPointRef)
+                                   -> Result<EcKey, ErrorStack> {
+        let mut builder = try!(EcKeyBuilder::new());
+        try!(builder.set_group(group));
+        try!(builder.set_private_key(private_number));
+        try!(builder.set_public_key(public_key));
+        Ok(builder.build())
+    }
+
     /// Generates a new public/private key pair on the specified curve.
     pub fn generate(group: &EcGroupRef) -> Result<EcKey, ErrorStack> {
         let mut builder = try!(EcKeyBuilder::new());
@@ -414,6 +503,14 @@ impl EcKeyBuilderRef {
         }
     }
 
+    pub fn set_private_key(&mut self,
+                           private_key: &BigNumRef)
+                           -> Result<&mut EcKeyBuilderRef, ErrorStack> {
+        unsafe {
+            cvt(ffi::EC_KEY_set_private_key(self.as_ptr(), private_key.as_ptr())).map(|_| self)
+        }
+    }
+
     pub fn generate_key(&mut self) -> Result<&mut EcKeyBuilderRef, ErrorStack> {
         unsafe {
             cvt(ffi::EC_KEY_generate_key(self.as_ptr())).map(|_| self)
@@ -423,7 +520,7 @@ impl EcKeyBuilderRef {
 
 #[cfg(test)]
 mod test {
-    use bn::BigNumContext;
+    use bn::{BigNum, BigNumContext};
     use nid;
     use super::*;
 
@@ -481,4 +578,67 @@ mod test {
         assert!(ec_key.public_key().is_some());
         assert!(ec_key.private_key().is_none());
     }
+
+    #[test]
+    fn affine_coordinates() {
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+
+        let mut x = BigNum::new().unwrap();
+        let mut y = BigNum::new().unwrap();
+        key.public_key().unwrap().affine_coordinates_gfp(&group, &mut x, &mut y, &mut ctx).unwrap();
+
+        let mut point = EcPoint::new(&group).unwrap();
+        point.set_affine_coordinates_gfp(&group, &x, &y, &mut ctx).unwrap();
+        assert!(point.eq(&group, key.public_key().unwrap(), &mut ctx).unwrap());
+
+        let one = BigNum::from_u32(1).unwrap();
+        assert!(point.set_affine_coordinates_gfp(&group, &x, &one, &mut ctx).is_err());
+    }
+
+    #[test]
+    fn point_arithmetic() {
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+
+        let mut g = EcPoint::new(&group).unwrap();
+        g.mul_generator(&group, &BigNum::from_u32(1).unwrap(), &mut ctx).unwrap();
+        let mut three_g = EcPoint::new(&group).unwrap();
+        three_g.mul_generator(&group, &BigNum::from_u32(3).unwrap(), &mut ctx).unwrap();
+
+        let mut two_g = EcPoint::new(&group).unwrap();
+        two_g.mul_generator(&group, &BigNum::from_u32(2).unwrap(), &mut ctx).unwrap();
+        let mut sum = EcPoint::new(&group).unwrap();
+        sum.add(&group, &g, &two_g, &mut ctx).unwrap();
+        assert!(sum.eq(&group, &three_g, &mut ctx).unwrap());
+
+        let mut neg = EcPoint::new(&group).unwrap();
+        neg.mul_generator(&group, &BigNum::from_u32(3).unwrap(), &mut ctx).unwrap();
+        neg.invert(&group, &ctx).unwrap();
+        let mut zero = EcPoint::new(&group).unwrap();
+        zero.add(&group, &three_g, &neg, &mut ctx).unwrap();
+        let infinity = EcPoint::new(&group).unwrap();
+        assert!(zero.eq(&group, &infinity, &mut ctx).unwrap());
+    }
+
+    #[test]
+    fn key_from_private_components() {
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+
+        let dup_key = EcKey::from_private_components(&group,
+                                                     key.private_key().unwrap(),
+                                                     key.public_key().unwrap())
+            .unwrap();
+        dup_key.check_key().unwrap();
+        assert!(key.private_key() == dup_key.private_key());
+
+        let other = EcKey::generate(&group).unwrap();
+        let mismatched = EcKey::from_private_components(&group,
+                                                        key.private_key().unwrap(),
+                                                        other.public_key().unwrap())
+            .unwrap();
+        assert!(mismatched.check_key().is_err());
+    }
 }
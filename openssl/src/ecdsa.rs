@@ -0,0 +1,160 @@
+//! Low level Elliptic Curve Digital Signature Algorithm (ECDSA) functions.
+//!
+//! Unlike the `sign` module, these functions operate on an already computed digest and expose
+//! the `r` and `s` components of the signature directly, which is necessary for formats such as
+//! JWS that use the raw concatenation of the two values rather than a DER encoding.
+use ffi;
+use foreign_types::ForeignTypeRef;
+use libc::c_int;
+use std::mem;
+use std::ptr;
+
+use {cvt, cvt_n, cvt_p};
+use bn::{BigNum, BigNumRef};
+use ec::EcKeyRef;
+use error::ErrorStack;
+
+foreign_type! {
+    type CType = ffi::ECDSA_SIG;
+    fn drop = ffi::ECDSA_SIG_free;
+
+    /// An ECDSA signature.
+    pub struct EcdsaSig;
+    /// A reference to an `EcdsaSig`.
+    pub struct EcdsaSigRef;
+}
+
+impl EcdsaSig {
+    /// Computes a signature of the digest `data` with the private key `eckey`.
+    ///
+    /// `data` should be the output of a hash function. OpenSSL truncates it to the bit length of
+    /// the curve's order if it is longer.
+    pub fn sign(data: &[u8], eckey: &EcKeyRef) -> Result<EcdsaSig, ErrorStack> {
+        unsafe {
+            assert!(data.len() <= c_int::max_value() as usize);
+            let sig = try!(cvt_p(ffi::ECDSA_do_sign(data.as_ptr(),
+                                                    data.len() as c_int,
+                                                    eckey.as_ptr())));
+            Ok(EcdsaSig(sig))
+        }
+    }
+
+    /// Constructs a signature from its `r` and `s` components.
+    pub fn from_components(r: BigNum, s: BigNum) -> Result<EcdsaSig, ErrorStack> {
+        unsafe {
+            let sig = EcdsaSig(try!(cvt_p(ffi::ECDSA_SIG_new())));
+            try!(cvt(compat::set_numbers(sig.as_ptr(), r.as_ptr(), s.as_ptr())));
+            mem::forget((r, s));
+            Ok(sig)
+        }
+    }
+
+    from_der!(EcdsaSig, ffi::d2i_ECDSA_SIG);
+}
+
+impl EcdsaSigRef {
+    /// Verifies that this is a valid signature of the digest `data` by the public key `eckey`.
+    pub fn verify(&self, data: &[u8], eckey: &EcKeyRef) -> Result<bool, ErrorStack> {
+        unsafe {
+            assert!(data.len() <= c_int::max_value() as usize);
+            let r = try!(cvt_n(ffi::ECDSA_do_verify(data.as_ptr(),
+                                                    data.len() as c_int,
+                                                    self.as_ptr(),
+                                                    eckey.as_ptr())));
+            Ok(r == 1)
+        }
+    }
+
+    /// Returns the `r` component of the signature.
+    pub fn r(&self) -> &BigNumRef {
+        unsafe { BigNumRef::from_ptr(compat::numbers(self.as_ptr())[0] as *mut _) }
+    }
+
+    /// Returns the `s` component of the signature.
+    pub fn s(&self) -> &BigNumRef {
+        unsafe { BigNumRef::from_ptr(compat::numbers(self.as_ptr())[1] as *mut _) }
+    }
+
+    to_der!(ffi::i2d_ECDSA_SIG);
+}
+
+#[cfg(ossl110)]
+mod compat {
+    use std::ptr;
+
+    use ffi::{self, BIGNUM, ECDSA_SIG};
+    use libc::c_int;
+
+    pub unsafe fn numbers(sig: *const ECDSA_SIG) -> [*const BIGNUM; 2] {
+        let (mut r, mut s) = (ptr::null(), ptr::null());
+        ffi::ECDSA_SIG_get0(sig, &mut r, &mut s);
+        [r, s]
+    }
+
+    pub unsafe fn set_numbers(sig: *mut ECDSA_SIG, r: *mut BIGNUM, s: *mut BIGNUM) -> c_int {
+        ffi::ECDSA_SIG_set0(sig, r, s)
+    }
+}
+
+#[cfg(ossl10x)]
+mod compat {
+    use ffi::{self, BIGNUM, ECDSA_SIG};
+    use libc::c_int;
+
+    pub unsafe fn numbers(sig: *const ECDSA_SIG) -> [*const BIGNUM; 2] {
+        [(*sig).r, (*sig).s]
+    }
+
+    pub unsafe fn set_numbers(sig: *mut ECDSA_SIG, r: *mut BIGNUM, s: *mut BIGNUM) -> c_int {
+        // ECDSA_SIG_new allocates both components up front.
+        ffi::BN_clear_free((*sig).r);
+        ffi::BN_clear_free((*sig).s);
+        (*sig).r = r;
+        (*sig).s = s;
+        1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bn::BigNum;
+    use ec::{EcGroup, EcKey};
+    use hash::{hash, MessageDigest};
+    use nid;
+    use super::*;
+
+    #[test]
+    fn sign_verify() {
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let other = EcKey::generate(&group).unwrap();
+        let digest = hash(MessageDigest::sha256(), b"hello world").unwrap();
+
+        let sig = EcdsaSig::sign(&digest, &key).unwrap();
+        assert!(sig.verify(&digest, &key).unwrap());
+        assert!(!sig.verify(&digest, &other).unwrap());
+
+        let wrong = hash(MessageDigest::sha256(), b"goodbye world").unwrap();
+        assert!(!sig.verify(&wrong, &key).unwrap());
+    }
+
+    #[test]
+    fn components_round_trip() {
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let digest = hash(MessageDigest::sha256(), b"hello world").unwrap();
+        let sig = EcdsaSig::sign(&digest, &key).unwrap();
+
+        let r = BigNum::from_slice(&sig.r().to_vec()).unwrap();
+        let s = BigNum::from_slice(&sig.s().to_vec()).unwrap();
+        let sig2 = EcdsaSig::from_components(r, s).unwrap();
+        assert_eq!(sig.r(), sig2.r());
+        assert_eq!(sig.s(), sig2.s());
+        assert!(sig2.verify(&digest, &key).unwrap());
+
+        let der = sig2.to_der().unwrap();
+        assert_eq!(der, sig.to_der().unwrap());
+        let sig3 = EcdsaSig::from_der(&der).unwrap();
+        assert!(sig3.verify(&digest, &key).unwrap());
+    }
+}
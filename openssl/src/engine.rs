@@ -0,0 +1,204 @@
+//! Hardware and software cryptographic engines.
+//!
+//! Engines provide alternative implementations of cryptographic operations. They are commonly
+//! used to access keys held in hardware security modules or PKCS#11 tokens, which never leave the
+//! device.
+//!
+//! An `Engine` is looked up by its id, configured with control commands, and then initialized.
+//! Keys loaded from an initialized engine are ordinary `PKey`s which can be used with `Signer`,
+//! `SslContextBuilder::set_private_key` and other APIs, and keep the engine alive for as long as
+//! they exist.
+//!
+//! # Examples
+//!
+//! Load a key from a PKCS#11 token through a dynamically loaded engine:
+//!
+//! ```no_run
+//! use openssl::engine::Engine;
+//!
+//! let mut engine = Engine::by_id("dynamic").unwrap();
+//! engine.ctrl_cmd_string("SO_PATH", Some("/usr/lib/engines/pkcs11.so"), false).unwrap();
+//! engine.ctrl_cmd_string("ID", Some("pkcs11"), false).unwrap();
+//! engine.ctrl_cmd_string("LOAD", None, false).unwrap();
+//! engine.ctrl_cmd_string("PIN", Some("1234"), false).unwrap();
+//!
+//! let engine = engine.init().unwrap();
+//! let key = engine.load_private_key("pkcs11:object=server-key").unwrap();
+//! ```
+use ffi;
+use foreign_types::{ForeignType, ForeignTypeRef};
+use libc::{c_char, c_int};
+use std::ffi::{CStr, CString};
+use std::ops::Deref;
+use std::ptr;
+use std::str;
+use std::sync::{Once, ONCE_INIT};
+
+use {cvt, cvt_p};
+use error::ErrorStack;
+use pkey::PKey;
+
+unsafe fn free(e: *mut ffi::ENGINE) {
+    ffi::ENGINE_free(e);
+}
+
+foreign_type! {
+    type CType = ffi::ENGINE;
+    fn drop = free;
+
+    /// An engine which has been looked up, but not necessarily initialized.
+    pub struct Engine;
+    /// A reference to an `Engine`.
+    pub struct EngineRef;
+}
+
+impl Engine {
+    /// Looks up an engine by its id, such as `"dynamic"`.
+    ///
+    /// The engines built into OpenSSL are registered the first time this is called.
+    pub fn by_id(id: &str) -> Result<Engine, ErrorStack> {
+        static INIT: Once = ONCE_INIT;
+
+        ffi::init();
+        let id = CString::new(id).unwrap();
+        unsafe {
+            INIT.call_once(|| ffi::ENGINE_load_builtin_engines());
+            cvt_p(ffi::ENGINE_by_id(id.as_ptr())).map(Engine)
+        }
+    }
+}
+
+impl EngineRef {
+    /// Returns the id of the engine.
+    ///
+    /// `None` is returned if the engine has no id or it is not valid UTF-8.
+    pub fn id(&self) -> Option<&str> {
+        unsafe { to_str(ffi::ENGINE_get_id(self.as_ptr())) }
+    }
+
+    /// Returns a human readable name of the engine.
+    ///
+    /// `None` is returned if the engine has no name or it is not valid UTF-8.
+    pub fn name(&self) -> Option<&str> {
+        unsafe { to_str(ffi::ENGINE_get_name(self.as_ptr())) }
+    }
+
+    /// Sends the control command `cmd` to the engine, with an optional argument.
+    ///
+    /// The supported commands depend on the engine. If `optional` is `true`, it is not an error
+    /// for the engine not to support `cmd`.
+    ///
+    /// This corresponds to `ENGINE_ctrl_cmd_string`.
+    pub fn ctrl_cmd_string(&mut self,
+                           cmd: &str,
+                           arg: Option<&str>,
+                           optional: bool)
+                           -> Result<(), ErrorStack> {
+        let cmd = CString::new(cmd).unwrap();
+        let arg = arg.map(|arg| CString::new(arg).unwrap());
+        unsafe {
+            cvt(ffi::ENGINE_ctrl_cmd_string(self.as_ptr(),
+                                            cmd.as_ptr(),
+                                            arg.as_ref().map_or(ptr::null(), |arg| arg.as_ptr()),
+                                            optional as c_int))
+                .map(|_| ())
+        }
+    }
+
+    /// Initializes the engine so that it can be used.
+    ///
+    /// The engine remains initialized until the returned `InitializedEngine`, and any keys loaded
+    /// from it, have been dropped.
+    ///
+    /// This corresponds to `ENGINE_init`.
+    pub fn init(&self) -> Result<InitializedEngine, ErrorStack> {
+        unsafe {
+            try!(cvt(ffi::ENGINE_init(self.as_ptr())));
+            Ok(InitializedEngine(self.as_ptr()))
+        }
+    }
+}
+
+/// An engine which has been initialized, and can be used to load keys.
+pub struct InitializedEngine(*mut ffi::ENGINE);
+
+impl InitializedEngine {
+    /// Loads the private key identified by `key_id` from the engine.
+    ///
+    /// The format of `key_id` depends on the engine. For PKCS#11 engines it is typically a
+    /// `pkcs11:` URI.
+    ///
+    /// This corresponds to `ENGINE_load_private_key`.
+    pub fn load_private_key(&self, key_id: &str) -> Result<PKey, ErrorStack> {
+        let key_id = CString::new(key_id).unwrap();
+        unsafe {
+            cvt_p(ffi::ENGINE_load_private_key(self.as_ptr(),
+                                               key_id.as_ptr(),
+                                               ptr::null_mut(),
+                                               ptr::null_mut()))
+                .map(|p| PKey::from_ptr(p))
+        }
+    }
+
+    /// Loads the public key identified by `key_id` from the engine.
+    ///
+    /// This corresponds to `ENGINE_load_public_key`.
+    pub fn load_public_key(&self, key_id: &str) -> Result<PKey, ErrorStack> {
+        let key_id = CString::new(key_id).unwrap();
+        unsafe {
+            cvt_p(ffi::ENGINE_load_public_key(self.as_ptr(),
+                                              key_id.as_ptr(),
+                                              ptr::null_mut(),
+                                              ptr::null_mut()))
+                .map(|p| PKey::from_ptr(p))
+        }
+    }
+}
+
+impl Deref for InitializedEngine {
+    type Target = EngineRef;
+
+    fn deref(&self) -> &EngineRef {
+        unsafe { EngineRef::from_ptr(self.0) }
+    }
+}
+
+impl Drop for InitializedEngine {
+    fn drop(&mut self) {
+        // this releases the structural reference taken by ENGINE_init as well as the functional one
+        unsafe {
+            ffi::ENGINE_finish(self.0);
+        }
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        str::from_utf8(CStr::from_ptr(s).to_bytes()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn by_id() {
+        let engine = Engine::by_id("dynamic").unwrap();
+        assert_eq!(engine.id(), Some("dynamic"));
+        assert!(!engine.name().unwrap().is_empty());
+
+        assert!(Engine::by_id("no-such-engine").is_err());
+    }
+
+    #[test]
+    fn ctrl_cmd_string() {
+        let mut engine = Engine::by_id("dynamic").unwrap();
+        assert!(engine.ctrl_cmd_string("NO_SUCH_COMMAND", None, false).is_err());
+        engine.ctrl_cmd_string("NO_SUCH_COMMAND", None, true).unwrap();
+        assert!(engine.ctrl_cmd_string("SO_PATH", Some("/nonexistent/engine.so"), false).is_ok());
+        assert!(engine.ctrl_cmd_string("LOAD", None, false).is_err());
+    }
+}
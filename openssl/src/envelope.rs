@@ -0,0 +1,327 @@
+//! Envelope encryption.
+//!
+//! A `Sealer` encrypts data with a randomly generated symmetric key, which is in turn encrypted
+//! to the public key of each recipient. A recipient passes its encrypted copy of the key, along
+//! with the IV, to an `Opener` to decrypt the data. Only RSA keys are supported.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use openssl::envelope::{Opener, Sealer};
+//! use openssl::pkey::PKey;
+//! use openssl::rsa::Rsa;
+//! use openssl::symm::Cipher;
+//!
+//! let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+//! let public = PKey::public_key_from_der(&key.public_key_to_der().unwrap()).unwrap();
+//! let cipher = Cipher::aes_256_cbc();
+//! let secret = b"My secret message";
+//!
+//! let mut sealer = Sealer::new(cipher, &[public]).unwrap();
+//! let mut encrypted = vec![0; secret.len() + cipher.block_size()];
+//! let mut len = sealer.update(secret, &mut encrypted).unwrap();
+//! len += sealer.finalize(&mut encrypted[len..]).unwrap();
+//! encrypted.truncate(len);
+//!
+//! let mut opener = Opener::new(cipher,
+//!                              &key,
+//!                              &sealer.encrypted_keys()[0],
+//!                              sealer.iv())
+//!     .unwrap();
+//! let mut decrypted = vec![0; encrypted.len() + cipher.block_size()];
+//! let mut len = opener.update(&encrypted, &mut decrypted).unwrap();
+//! len += opener.finalize(&mut decrypted[len..]).unwrap();
+//! decrypted.truncate(len);
+//!
+//! assert_eq!(&decrypted[..], &secret[..]);
+//! ```
+use ffi;
+use foreign_types::ForeignTypeRef;
+use libc::c_int;
+use std::cmp;
+use std::ptr;
+
+use {cvt, cvt_p};
+use error::ErrorStack;
+use pkey::{PKey, PKeyRef};
+use symm::Cipher;
+
+/// A type used to seal data to one or more public keys.
+pub struct Sealer {
+    ctx: *mut ffi::EVP_CIPHER_CTX,
+    block_size: usize,
+    encrypted_keys: Vec<Vec<u8>>,
+    iv: Option<Vec<u8>>,
+}
+
+unsafe impl Sync for Sealer {}
+unsafe impl Send for Sealer {}
+
+impl Sealer {
+    /// Creates a new `Sealer`, generating a random key and IV for `cipher`.
+    ///
+    /// The key is encrypted to each of the public keys in `pub_keys`, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pub_keys` is empty.
+    pub fn new(cipher: Cipher, pub_keys: &[PKey]) -> Result<Sealer, ErrorStack> {
+        assert!(!pub_keys.is_empty(), "at least one public key is required");
+        assert!(pub_keys.len() <= c_int::max_value() as usize);
+        ffi::init();
+
+        unsafe {
+            let ctx = try!(cvt_p(ffi::EVP_CIPHER_CTX_new()));
+            let mut sealer = Sealer {
+                ctx: ctx,
+                block_size: cipher.block_size(),
+                encrypted_keys: pub_keys.iter()
+                    .map(|key| vec![0; ffi::EVP_PKEY_size(key.as_ptr()) as usize])
+                    .collect(),
+                iv: cipher.iv_len().map(|len| vec![0; len]),
+            };
+
+            let mut keys = pub_keys.iter().map(|key| key.as_ptr()).collect::<Vec<_>>();
+            let mut ek = sealer.encrypted_keys
+                .iter_mut()
+                .map(|key| key.as_mut_ptr())
+                .collect::<Vec<_>>();
+            let mut ekl = vec![0; pub_keys.len()];
+            let iv = sealer.iv.as_mut().map_or(ptr::null_mut(), |iv| iv.as_mut_ptr());
+
+            try!(cvt(ffi::EVP_SealInit(sealer.ctx,
+                                       cipher.as_ptr(),
+                                       ek.as_mut_ptr(),
+                                       ekl.as_mut_ptr(),
+                                       iv,
+                                       keys.as_mut_ptr(),
+                                       keys.len() as c_int)));
+
+            for (key, len) in sealer.encrypted_keys.iter_mut().zip(ekl) {
+                key.truncate(len as usize);
+            }
+
+            Ok(sealer)
+        }
+    }
+
+    /// Returns the IV which must be passed to `Opener::new`, if the cipher uses one.
+    pub fn iv(&self) -> Option<&[u8]> {
+        self.iv.as_ref().map(|iv| &**iv)
+    }
+
+    /// Returns the symmetric key encrypted to each public key passed to `new`, in order.
+    pub fn encrypted_keys(&self) -> &[Vec<u8>] {
+        &self.encrypted_keys
+    }
+
+    /// Feeds data from `input` through the cipher, writing encrypted bytes into `output`.
+    ///
+    /// The number of bytes written to `output` is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output.len() < input.len() + block_size` where `block_size` is the block size
+    /// of the cipher, or if `output.len() > c_int::max_value()`.
+    pub fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= input.len() + self.block_size);
+            assert!(output.len() <= c_int::max_value() as usize);
+            let mut outl = output.len() as c_int;
+
+            try!(cvt(ffi::EVP_EncryptUpdate(self.ctx,
+                                            output.as_mut_ptr(),
+                                            &mut outl,
+                                            input.as_ptr(),
+                                            input.len() as c_int)));
+
+            Ok(outl as usize)
+        }
+    }
+
+    /// Finishes the encryption process, writing any remaining data to `output`.
+    ///
+    /// The number of bytes written to `output` is returned.
+    ///
+    /// `update` should not be called after this method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` is less than the cipher's block size.
+    pub fn finalize(&mut self, output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= self.block_size);
+            let mut outl = cmp::min(output.len(), c_int::max_value() as usize) as c_int;
+
+            try!(cvt(ffi::EVP_SealFinal(self.ctx, output.as_mut_ptr(), &mut outl)));
+
+            Ok(outl as usize)
+        }
+    }
+}
+
+impl Drop for Sealer {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_CIPHER_CTX_free(self.ctx);
+        }
+    }
+}
+
+/// A type used to open data sealed by a `Sealer`.
+pub struct Opener {
+    ctx: *mut ffi::EVP_CIPHER_CTX,
+    block_size: usize,
+}
+
+unsafe impl Sync for Opener {}
+unsafe impl Send for Opener {}
+
+impl Opener {
+    /// Creates a new `Opener`.
+    ///
+    /// `encrypted_key` is the recipient's entry in `Sealer::encrypted_keys`, which is decrypted
+    /// with `priv_key`, and `iv` is the value of `Sealer::iv`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an IV is required by the cipher but not provided, or if its length differs from
+    /// `Cipher::iv_len`.
+    pub fn new(cipher: Cipher,
+               priv_key: &PKeyRef,
+               encrypted_key: &[u8],
+               iv: Option<&[u8]>)
+               -> Result<Opener, ErrorStack> {
+        assert!(encrypted_key.len() <= c_int::max_value() as usize);
+        let iv = match (iv, cipher.iv_len()) {
+            (Some(iv), Some(len)) => {
+                assert!(iv.len() == len, "an IV of {} bytes is required for this cipher", len);
+                iv.as_ptr()
+            }
+            (Some(_), None) | (None, None) => ptr::null(),
+            (None, Some(_)) => panic!("an IV is required for this cipher"),
+        };
+        ffi::init();
+
+        unsafe {
+            let ctx = try!(cvt_p(ffi::EVP_CIPHER_CTX_new()));
+            let opener = Opener {
+                ctx: ctx,
+                block_size: cipher.block_size(),
+            };
+
+            try!(cvt(ffi::EVP_OpenInit(opener.ctx,
+                                       cipher.as_ptr(),
+                                       encrypted_key.as_ptr(),
+                                       encrypted_key.len() as c_int,
+                                       iv,
+                                       priv_key.as_ptr())));
+
+            Ok(opener)
+        }
+    }
+
+    /// Feeds data from `input` through the cipher, writing decrypted bytes into `output`.
+    ///
+    /// The number of bytes written to `output` is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output.len() < input.len() + block_size` where `block_size` is the block size
+    /// of the cipher, or if `output.len() > c_int::max_value()`.
+    pub fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= input.len() + self.block_size);
+            assert!(output.len() <= c_int::max_value() as usize);
+            let mut outl = output.len() as c_int;
+
+            try!(cvt(ffi::EVP_DecryptUpdate(self.ctx,
+                                            output.as_mut_ptr(),
+                                            &mut outl,
+                                            input.as_ptr(),
+                                            input.len() as c_int)));
+
+            Ok(outl as usize)
+        }
+    }
+
+    /// Finishes the decryption process, writing any remaining data to `output`.
+    ///
+    /// The number of bytes written to `output` is returned.
+    ///
+    /// `update` should not be called after this method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` is less than the cipher's block size.
+    pub fn finalize(&mut self, output: &mut [u8]) -> Result<usize, ErrorStack> {
+        unsafe {
+            assert!(output.len() >= self.block_size);
+            let mut outl = cmp::min(output.len(), c_int::max_value() as usize) as c_int;
+
+            try!(cvt(ffi::EVP_OpenFinal(self.ctx, output.as_mut_ptr(), &mut outl)));
+
+            Ok(outl as usize)
+        }
+    }
+}
+
+impl Drop for Opener {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::EVP_CIPHER_CTX_free(self.ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pkey::PKey;
+    use rsa::Rsa;
+    use symm::Cipher;
+
+    use super::*;
+
+    fn seal(sealer: &mut Sealer, data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0; data.len() + Cipher::aes_128_cbc().block_size()];
+        let mut len = sealer.update(data, &mut out).unwrap();
+        len += sealer.finalize(&mut out[len..]).unwrap();
+        out.truncate(len);
+        out
+    }
+
+    fn open(opener: &mut Opener, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        let mut out = vec![0; data.len() + Cipher::aes_128_cbc().block_size()];
+        let mut len = try!(opener.update(data, &mut out));
+        len += try!(opener.finalize(&mut out[len..]));
+        out.truncate(len);
+        Ok(out)
+    }
+
+    #[test]
+    fn multiple_recipients() {
+        let cipher = Cipher::aes_128_cbc();
+        let alice = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let bob = PKey::from_rsa(Rsa::generate(1024).unwrap()).unwrap();
+        let public = [PKey::public_key_from_der(&alice.public_key_to_der().unwrap()).unwrap(),
+                      PKey::public_key_from_der(&bob.public_key_to_der().unwrap()).unwrap()];
+
+        let mut sealer = Sealer::new(cipher, &public).unwrap();
+        assert_eq!(sealer.encrypted_keys()[0].len(), 256);
+        assert_eq!(sealer.encrypted_keys()[1].len(), 128);
+        assert_eq!(sealer.iv().unwrap().len(), 16);
+        let data = b"hello, world! this spans more than one block";
+        let sealed = seal(&mut sealer, data);
+
+        for (key, ek) in [&alice, &bob].iter().zip(sealer.encrypted_keys()) {
+            let mut opener = Opener::new(cipher, key, ek, sealer.iv()).unwrap();
+            assert_eq!(open(&mut opener, &sealed).unwrap(), &data[..]);
+        }
+
+        // alice's key can't decrypt bob's copy of the symmetric key
+        let ek = &sealer.encrypted_keys()[1];
+        let opened = Opener::new(cipher, &alice, ek, sealer.iv())
+            .and_then(|mut opener| open(&mut opener, &sealed));
+        assert!(opened.map(|d| d != &data[..]).unwrap_or(true));
+    }
+}
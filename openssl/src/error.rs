@@ -1,8 +1,9 @@
-use libc::c_ulong;
+use libc::{c_int, c_ulong};
 use std::fmt;
 use std::error;
 use std::ffi::CStr;
 use std::io;
+use std::ptr;
 use std::str;
 
 use ffi;
@@ -26,6 +27,21 @@ impl ErrorStack {
     pub fn errors(&self) -> &[Error] {
         &self.0
     }
+
+    /// Determines if any error in the stack has the specified reason.
+    pub fn contains(&self, reason: Reason) -> bool {
+        self.0.iter().any(|e| e.is(reason))
+    }
+
+    /// Recovers an `ErrorStack` previously converted into an `io::Error`.
+    ///
+    /// The original `io::Error` is returned if it does not wrap an `ErrorStack`.
+    pub fn from_io_error(err: io::Error) -> Result<ErrorStack, io::Error> {
+        if !err.get_ref().map_or(false, |e| e.is::<ErrorStack>()) {
+            return Err(err);
+        }
+        Ok(*err.into_inner().unwrap().downcast::<ErrorStack>().unwrap())
+    }
 }
 
 impl fmt::Display for ErrorStack {
@@ -60,30 +76,196 @@ impl From<ErrorStack> for fmt::Error {
     }
 }
 
+/// A library and reason code pair identifying a kind of error, independent of the function
+/// which raised it.
+///
+/// Use `Error::is` or `ErrorStack::contains` to check for a specific kind of error rather than
+/// matching on the error's text, which varies between OpenSSL versions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Reason {
+    library: c_int,
+    reason: c_int,
+}
+
+impl Reason {
+    /// Creates a `Reason` from raw library and reason codes, such as `ffi::ERR_LIB_PEM` and
+    /// `ffi::PEM_R_NO_START_LINE`.
+    pub fn from_raw(library: c_int, reason: c_int) -> Reason {
+        Reason {
+            library: library,
+            reason: reason,
+        }
+    }
+
+    /// Returns the raw library code.
+    pub fn library_code(&self) -> c_int {
+        self.library
+    }
+
+    /// Returns the raw reason code.
+    pub fn reason_code(&self) -> c_int {
+        self.reason
+    }
+}
+
+/// Decryption failed, typically because of an incorrect key or password.
+pub const REASON_BAD_DECRYPT: Reason = Reason {
+    library: ffi::ERR_LIB_EVP,
+    reason: ffi::EVP_R_BAD_DECRYPT,
+};
+
+/// The peer's certificate could not be verified during a TLS handshake.
+pub const REASON_CERTIFICATE_VERIFY_FAILED: Reason = Reason {
+    library: ffi::ERR_LIB_SSL,
+    reason: ffi::SSL_R_CERTIFICATE_VERIFY_FAILED,
+};
+
+/// A private key does not match its certificate.
+pub const REASON_KEY_VALUES_MISMATCH: Reason = Reason {
+    library: ffi::ERR_LIB_X509,
+    reason: ffi::X509_R_KEY_VALUES_MISMATCH,
+};
+
+/// No PEM data was found in the input.
+pub const REASON_NO_START_LINE: Reason = Reason {
+    library: ffi::ERR_LIB_PEM,
+    reason: ffi::PEM_R_NO_START_LINE,
+};
+
+/// An IV of the wrong length was passed to a cipher which requires a fixed IV length.
+pub const REASON_INVALID_IV_LENGTH: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_INVALID_IV_LENGTH,
+};
+
+/// Data was encrypted or signed with an algorithm which is not supported where it was used.
+pub const REASON_UNSUPPORTED_ALGORITHM: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_UNSUPPORTED_ALGORITHM,
+};
+
+/// The peer started a renegotiation after it was disabled with
+/// `SslContextBuilder::set_renegotiation_disabled`.
+pub const REASON_RENEGOTIATION_REJECTED: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_RENEGOTIATION_REJECTED,
+};
+
+/// PEM data which should contain certificates contained none.
+pub const REASON_NO_CERTIFICATES: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_NO_CERTIFICATES,
+};
+
+/// A record was shorter than its authentication tag.
+pub const REASON_TRUNCATED: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_TRUNCATED,
+};
+
+/// The sequence number of a `RecordSealer` or `RecordOpener` was exhausted.
+pub const REASON_SEQUENCE_EXHAUSTED: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_SEQUENCE_EXHAUSTED,
+};
+
+/// A key of the wrong length was passed to a cipher.
+pub const REASON_INVALID_KEY_LENGTH: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_INVALID_KEY_LENGTH,
+};
+
+/// The buffer passed to a method such as `to_der_into` was too short for its output.
+pub const REASON_BUFFER_TOO_SMALL: Reason = Reason {
+    library: ffi::ERR_LIB_USER,
+    reason: ::R_BUFFER_TOO_SMALL,
+};
+
 /// An error reported from OpenSSL.
 #[derive(Clone)]
-pub struct Error(c_ulong);
+pub struct Error {
+    code: c_ulong,
+    file: &'static str,
+    line: u32,
+    data: Option<String>,
+}
 
 impl Error {
     /// Returns the first error on the OpenSSL error stack.
     pub fn get() -> Option<Error> {
         ffi::init();
 
-        match unsafe { ffi::ERR_get_error() } {
-            0 => None,
-            err => Some(Error(err)),
+        unsafe {
+            let mut file = ptr::null();
+            let mut line = 0;
+            let mut data = ptr::null();
+            let mut flags = 0;
+            match ffi::ERR_get_error_line_data(&mut file, &mut line, &mut data, &mut flags) {
+                0 => None,
+                code => {
+                    // the file name is a static string, but the data is owned by the error queue
+                    let file = str::from_utf8(CStr::from_ptr(file).to_bytes()).unwrap_or("");
+                    let data = if flags & ffi::ERR_TXT_STRING != 0 && !data.is_null() {
+                        let bytes = CStr::from_ptr(data).to_bytes();
+                        Some(String::from_utf8_lossy(bytes).into_owned())
+                    } else {
+                        None
+                    };
+                    Some(Error {
+                        code: code,
+                        file: file,
+                        line: line as u32,
+                        data: data,
+                    })
+                }
+            }
         }
     }
 
     /// Returns the raw OpenSSL error code for this error.
     pub fn code(&self) -> c_ulong {
-        self.0
+        self.code
+    }
+
+    /// Returns the raw code of the library reporting the error, such as `ffi::ERR_LIB_PEM`.
+    pub fn library_code(&self) -> c_int {
+        ffi::ERR_GET_LIB(self.code)
+    }
+
+    /// Returns the raw code of the function reporting the error.
+    pub fn function_code(&self) -> c_int {
+        ffi::ERR_GET_FUNC(self.code)
+    }
+
+    /// Returns the raw reason code of the error, such as `ffi::PEM_R_NO_START_LINE`.
+    pub fn reason_code(&self) -> c_int {
+        ffi::ERR_GET_REASON(self.code)
+    }
+
+    /// Determines if the error has the specified reason.
+    pub fn is(&self, reason: Reason) -> bool {
+        self.library_code() == reason.library && self.reason_code() == reason.reason
+    }
+
+    /// Returns the name of the OpenSSL source file which raised the error.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// Returns the line in `file` at which the error was raised.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Returns additional information about the error, if available.
+    pub fn data(&self) -> Option<&str> {
+        self.data.as_ref().map(|s| &**s)
     }
 
     /// Returns the name of the library reporting the error, if available.
     pub fn library(&self) -> Option<&'static str> {
         unsafe {
-            let cstr = ffi::ERR_lib_error_string(self.0);
+            let cstr = ffi::ERR_lib_error_string(self.code);
             if cstr.is_null() {
                 return None;
             }
@@ -95,7 +277,7 @@ impl Error {
     /// Returns the name of the function reporting the error.
     pub fn function(&self) -> Option<&'static str> {
         unsafe {
-            let cstr = ffi::ERR_func_error_string(self.0);
+            let cstr = ffi::ERR_func_error_string(self.code);
             if cstr.is_null() {
                 return None;
             }
@@ -107,7 +289,7 @@ impl Error {
     /// Returns the reason for the error.
     pub fn reason(&self) -> Option<&'static str> {
         unsafe {
-            let cstr = ffi::ERR_reason_error_string(self.0);
+            let cstr = ffi::ERR_reason_error_string(self.code);
             if cstr.is_null() {
                 return None;
             }
@@ -130,24 +312,33 @@ impl fmt::Debug for Error {
         if let Some(reason) = self.reason() {
             builder.field("reason", &reason);
         }
+        builder.field("file", &self.file());
+        builder.field("line", &self.line());
+        if let Some(data) = self.data() {
+            builder.field("data", &data);
+        }
         builder.finish()
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        try!(write!(fmt, "error:{:08X}", self.0));
+        try!(write!(fmt, "error:{:08X}", self.code));
         match self.library() {
             Some(l) => try!(write!(fmt, ":{}", l)),
-            None => try!(write!(fmt, ":lib({})", ffi::ERR_GET_LIB(self.0))),
+            None => try!(write!(fmt, ":lib({})", self.library_code())),
         }
         match self.function() {
             Some(f) => try!(write!(fmt, ":{}", f)),
-            None => try!(write!(fmt, ":func({})", ffi::ERR_GET_FUNC(self.0))),
+            None => try!(write!(fmt, ":func({})", self.function_code())),
         }
         match self.reason() {
-            Some(r) => write!(fmt, ":{}", r),
-            None => write!(fmt, ":reason({})", ffi::ERR_GET_FUNC(self.0)),
+            Some(r) => try!(write!(fmt, ":{}", r)),
+            None => try!(write!(fmt, ":reason({})", self.reason_code())),
+        }
+        match self.data() {
+            Some(d) => write!(fmt, ":{}", d),
+            None => Ok(()),
         }
     }
 }
@@ -157,3 +348,46 @@ impl error::Error for Error {
         "An OpenSSL error"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use x509::X509;
+    use super::ErrorStack;
+
+    #[test]
+    fn io_error_round_trip() {
+        let stack = X509::from_pem(b"not a certificate").err().unwrap();
+        let code = stack.errors()[0].code();
+
+        let err = io::Error::from(stack);
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        let stack = ErrorStack::from_io_error(err).unwrap();
+        assert_eq!(stack.errors()[0].code(), code);
+
+        let err = io::Error::new(io::ErrorKind::Other, "foo");
+        assert!(ErrorStack::from_io_error(err).is_err());
+    }
+
+    #[test]
+    fn structured_errors() {
+        let stack = X509::from_pem(b"not a certificate").err().unwrap();
+        let err = &stack.errors()[0];
+        assert_eq!(err.library(), Some("PEM routines"));
+        assert_eq!(err.reason(), Some("no start line"));
+        assert_eq!(err.reason_code(), ::ffi::PEM_R_NO_START_LINE);
+        assert!(err.file().contains("pem"), "{}", err.file());
+        assert!(err.line() > 0);
+
+        // the packing of library codes differs between OpenSSL versions, so the reason is
+        // compared with the one decoded from the error itself
+        let reason = super::Reason::from_raw(err.library_code(), err.reason_code());
+        assert!(err.is(reason));
+        assert!(stack.contains(reason));
+        assert!(!stack.contains(super::REASON_BAD_DECRYPT));
+
+        let stack = X509::from_der(b"\x30\x03\x02\x01\x01").err().unwrap();
+        assert!(stack.errors().iter().any(|e| e.data().is_some()));
+    }
+}
@@ -0,0 +1,40 @@
+//! FIPS 140-2 mode.
+//!
+//! When FIPS mode is enabled, only the algorithms validated by the FIPS module are available,
+//! and their self tests are run. This requires an OpenSSL build with a FIPS object module, and
+//! enabling it fails everywhere else.
+//!
+//! Requires the `fips` feature.
+use ffi;
+use libc::c_int;
+
+use cvt;
+use error::ErrorStack;
+
+/// Enables or disables FIPS mode for the whole process.
+///
+/// An error is returned if the FIPS module is not available or fails its self tests.
+///
+/// This corresponds to `FIPS_mode_set`.
+pub fn enable(enabled: bool) -> Result<(), ErrorStack> {
+    ffi::init();
+    unsafe { cvt(ffi::FIPS_mode_set(enabled as c_int)).map(|_| ()) }
+}
+
+/// Determines if FIPS mode is enabled.
+///
+/// This corresponds to `FIPS_mode`.
+pub fn enabled() -> bool {
+    unsafe { ffi::FIPS_mode() != 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disable() {
+        enable(false).unwrap();
+        assert!(!enabled());
+    }
+}
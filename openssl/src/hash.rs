@@ -1,5 +1,7 @@
+use libc::{c_int, c_uint};
 use std::io::prelude::*;
 use std::io;
+use std::ptr;
 use ffi;
 
 #[cfg(ossl110)]
@@ -9,42 +11,224 @@ use ffi::{EVP_MD_CTX_create as EVP_MD_CTX_new, EVP_MD_CTX_destroy as EVP_MD_CTX_
 
 use {cvt, cvt_p};
 use error::ErrorStack;
+use memcmp;
+use nid::{self, Nid};
 
-#[derive(Copy, Clone)]
-pub struct MessageDigest(*const ffi::EVP_MD);
+/// A message digest algorithm.
+///
+/// The digests are also available as constants such as `SHA256`, which can be used in `match`
+/// patterns. The built-in digests are identified by their `Nid`, while a `MessageDigest` for any
+/// other digest holds on to the OpenSSL implementation it was resolved to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MessageDigest(Nid, Option<*const ffi::EVP_MD>);
+
+// EVP_MDs are never modified once they have been created
+unsafe impl Sync for MessageDigest {}
+unsafe impl Send for MessageDigest {}
+
+pub const MD5: MessageDigest = MessageDigest(nid::MD5, None);
+pub const SHA1: MessageDigest = MessageDigest(nid::SHA1, None);
+pub const SHA224: MessageDigest = MessageDigest(nid::SHA224, None);
+pub const SHA256: MessageDigest = MessageDigest(nid::SHA256, None);
+pub const SHA384: MessageDigest = MessageDigest(nid::SHA384, None);
+pub const SHA512: MessageDigest = MessageDigest(nid::SHA512, None);
+pub const RIPEMD160: MessageDigest = MessageDigest(nid::RIPEMD160, None);
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const SHA3_224: MessageDigest = MessageDigest(nid::SHA3_224, None);
+
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const SHA3_256: MessageDigest = MessageDigest(nid::SHA3_256, None);
+
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const SHA3_384: MessageDigest = MessageDigest(nid::SHA3_384, None);
+
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const SHA3_512: MessageDigest = MessageDigest(nid::SHA3_512, None);
+
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const SHAKE128: MessageDigest = MessageDigest(nid::SHAKE128, None);
+
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const SHAKE256: MessageDigest = MessageDigest(nid::SHAKE256, None);
+
+/// Requires the `v110` feature and OpenSSL 1.1.0.
+#[cfg(all(feature = "v110", ossl110))]
+pub const BLAKE2B512: MessageDigest = MessageDigest(nid::BLAKE2B512, None);
+
+/// Requires the `v110` feature and OpenSSL 1.1.0.
+#[cfg(all(feature = "v110", ossl110))]
+pub const BLAKE2S256: MessageDigest = MessageDigest(nid::BLAKE2S256, None);
 
 impl MessageDigest {
     pub fn md5() -> MessageDigest {
-        unsafe { MessageDigest(ffi::EVP_md5()) }
+        MD5
     }
 
     pub fn sha1() -> MessageDigest {
-        unsafe { MessageDigest(ffi::EVP_sha1()) }
+        SHA1
     }
 
     pub fn sha224() -> MessageDigest {
-        unsafe { MessageDigest(ffi::EVP_sha224()) }
+        SHA224
     }
 
     pub fn sha256() -> MessageDigest {
-        unsafe { MessageDigest(ffi::EVP_sha256()) }
+        SHA256
     }
 
     pub fn sha384() -> MessageDigest {
-        unsafe { MessageDigest(ffi::EVP_sha384()) }
+        SHA384
     }
 
     pub fn sha512() -> MessageDigest {
-        unsafe { MessageDigest(ffi::EVP_sha512()) }
+        SHA512
     }
 
     pub fn ripemd160() -> MessageDigest {
-        unsafe { MessageDigest(ffi::EVP_ripemd160()) }
+        RIPEMD160
     }
 
-    pub fn as_ptr(&self) -> *const ffi::EVP_MD {
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn sha3_224() -> MessageDigest {
+        SHA3_224
+    }
+
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn sha3_256() -> MessageDigest {
+        SHA3_256
+    }
+
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn sha3_384() -> MessageDigest {
+        SHA3_384
+    }
+
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn sha3_512() -> MessageDigest {
+        SHA3_512
+    }
+
+    /// The SHAKE128 extendable-output function.
+    ///
+    /// Use `Hasher::finish_xof` to produce output of an arbitrary length.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn shake128() -> MessageDigest {
+        SHAKE128
+    }
+
+    /// The SHAKE256 extendable-output function.
+    ///
+    /// Use `Hasher::finish_xof` to produce output of an arbitrary length.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn shake256() -> MessageDigest {
+        SHAKE256
+    }
+
+    /// Requires the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(all(feature = "v110", ossl110))]
+    pub fn blake2b512() -> MessageDigest {
+        BLAKE2B512
+    }
+
+    /// Requires the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(all(feature = "v110", ossl110))]
+    pub fn blake2s256() -> MessageDigest {
+        BLAKE2S256
+    }
+
+    /// Returns the digest identified by `nid`, if it is known to OpenSSL.
+    pub fn from_nid(nid: Nid) -> Option<MessageDigest> {
+        unsafe {
+            let ptr = digest_by_nid(nid);
+            if ptr.is_null() {
+                None
+            } else {
+                // aliases such as signature algorithm names resolve to the digest's own identifier
+                let nid = Nid::from_raw(ffi::EVP_MD_type(ptr));
+                if builtin_digest(nid) == ptr {
+                    Some(MessageDigest(nid, None))
+                } else {
+                    Some(MessageDigest(nid, Some(ptr)))
+                }
+            }
+        }
+    }
+
+    /// Returns the identifier of the digest.
+    pub fn nid(&self) -> Nid {
         self.0
     }
+
+    /// Returns the OpenSSL implementation of the digest.
+    pub fn as_ptr(&self) -> *const ffi::EVP_MD {
+        match self.1 {
+            Some(ptr) => ptr,
+            None => unsafe { builtin_digest(self.0) },
+        }
+    }
+
+    /// Returns the size of the digest in bytes.
+    pub fn size(&self) -> usize {
+        unsafe { ffi::EVP_MD_size(self.as_ptr()) as usize }
+    }
+}
+
+// Returns the implementation of one of the digests with a constant in this module, or null.
+unsafe fn builtin_digest(nid: Nid) -> *const ffi::EVP_MD {
+    match nid {
+        nid::MD5 => ffi::EVP_md5(),
+        nid::SHA1 => ffi::EVP_sha1(),
+        nid::SHA224 => ffi::EVP_sha224(),
+        nid::SHA256 => ffi::EVP_sha256(),
+        nid::SHA384 => ffi::EVP_sha384(),
+        nid::SHA512 => ffi::EVP_sha512(),
+        nid::RIPEMD160 => ffi::EVP_ripemd160(),
+        #[cfg(all(feature = "v111", ossl111))]
+        nid::SHA3_224 => ffi::EVP_sha3_224(),
+        #[cfg(all(feature = "v111", ossl111))]
+        nid::SHA3_256 => ffi::EVP_sha3_256(),
+        #[cfg(all(feature = "v111", ossl111))]
+        nid::SHA3_384 => ffi::EVP_sha3_384(),
+        #[cfg(all(feature = "v111", ossl111))]
+        nid::SHA3_512 => ffi::EVP_sha3_512(),
+        #[cfg(all(feature = "v111", ossl111))]
+        nid::SHAKE128 => ffi::EVP_shake128(),
+        #[cfg(all(feature = "v111", ossl111))]
+        nid::SHAKE256 => ffi::EVP_shake256(),
+        #[cfg(all(feature = "v110", ossl110))]
+        nid::BLAKE2B512 => ffi::EVP_blake2b512(),
+        #[cfg(all(feature = "v110", ossl110))]
+        nid::BLAKE2S256 => ffi::EVP_blake2s256(),
+        _ => ptr::null(),
+    }
+}
+
+unsafe fn digest_by_nid(nid: Nid) -> *const ffi::EVP_MD {
+    let ptr = builtin_digest(nid);
+    if !ptr.is_null() {
+        return ptr;
+    }
+    ffi::init();
+    let name = ffi::OBJ_nid2sn(nid.as_raw());
+    if name.is_null() {
+        ffi::ERR_clear_error();
+        return ptr::null();
+    }
+    ffi::EVP_get_digestbyname(name)
 }
 
 #[derive(PartialEq, Copy, Clone)]
@@ -156,6 +340,23 @@ impl Hasher {
             Ok(res)
         }
     }
+
+    /// Returns `len` bytes of output from an extendable-output function such as SHAKE128
+    /// for the data written since creation or the last `finish`, and resets the hasher.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn finish_xof(&mut self, len: usize) -> Result<Vec<u8>, ErrorStack> {
+        if self.state == Finalized {
+            try!(self.init());
+        }
+        unsafe {
+            let mut res = vec![0; len];
+            try!(cvt(ffi::EVP_DigestFinalXOF(self.ctx, res.as_mut_ptr(), len)));
+            self.state = Finalized;
+            Ok(res)
+        }
+    }
 }
 
 impl Write for Hasher {
@@ -206,10 +407,43 @@ pub fn hash(t: MessageDigest, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
     h.finish()
 }
 
+/// Computes the HMAC of `data` with the key `key` and the hash `t`.
+pub fn hmac(t: MessageDigest, key: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    assert!(key.len() <= c_int::max_value() as usize);
+
+    let mut out = vec![0; ffi::EVP_MAX_MD_SIZE as usize];
+    let mut len = 0 as c_uint;
+    unsafe {
+        ffi::init();
+        try!(cvt_p(ffi::HMAC(t.as_ptr(),
+                             key.as_ptr() as *const _,
+                             key.len() as c_int,
+                             data.as_ptr(),
+                             data.len(),
+                             out.as_mut_ptr(),
+                             &mut len)));
+    }
+    out.truncate(len as usize);
+    Ok(out)
+}
+
+/// Checks that `tag` is the HMAC of `data` with the key `key` and the hash `t`.
+///
+/// The comparison is performed in constant time, so this should be used rather than comparing
+/// the output of `hmac` with `==`.
+pub fn hmac_verify(t: MessageDigest,
+                   key: &[u8],
+                   data: &[u8],
+                   tag: &[u8])
+                   -> Result<bool, ErrorStack> {
+    let expected = try!(hmac(t, key, data));
+    Ok(expected.len() == tag.len() && memcmp::eq(&expected, tag))
+}
+
 #[cfg(test)]
 mod tests {
     use hex::{FromHex, ToHex};
-    use super::{hash, Hasher, MessageDigest};
+    use super::{hash, hmac, hmac_verify, Hasher, MessageDigest};
     use std::io::prelude::*;
 
     fn hash_test(hashtype: MessageDigest, hashtest: &(&str, &str)) {
@@ -313,6 +547,66 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn test_sha3() {
+        let tests = [("616263",
+                      "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")];
+        for test in tests.iter() {
+            hash_test(MessageDigest::sha3_256(), test);
+        }
+
+        let tests = [("616263",
+                      "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+                       10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0")];
+        for test in tests.iter() {
+            hash_test(MessageDigest::sha3_512(), test);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn test_shake() {
+        let mut h = Hasher::new(MessageDigest::shake128()).unwrap();
+        h.update(b"").unwrap();
+        assert_eq!(h.finish_xof(32).unwrap().to_hex(),
+                   "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26");
+
+        let mut h = Hasher::new(MessageDigest::shake256()).unwrap();
+        h.update(b"").unwrap();
+        assert_eq!(h.finish_xof(16).unwrap().to_hex(), "46b9dd2b0ba88d13233b3feb743eeb24");
+    }
+
+    #[test]
+    #[cfg(all(feature = "v110", ossl110))]
+    fn test_blake2() {
+        let tests = [("616263",
+                      "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982")];
+        for test in tests.iter() {
+            hash_test(MessageDigest::blake2s256(), test);
+        }
+
+        let tests = [("616263",
+                      "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                       7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923")];
+        for test in tests.iter() {
+            hash_test(MessageDigest::blake2b512(), test);
+        }
+    }
+
+    #[test]
+    fn test_hmac() {
+        // RFC 4231 test case 1
+        let key = [0x0b; 20];
+        let tag = hmac(MessageDigest::sha256(), &key, b"Hi There").unwrap();
+        assert_eq!(tag.to_hex(),
+                   "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7");
+
+        assert!(hmac_verify(MessageDigest::sha256(), &key, b"Hi There", &tag).unwrap());
+        assert!(!hmac_verify(MessageDigest::sha256(), &key, b"Hi there", &tag).unwrap());
+        assert!(!hmac_verify(MessageDigest::sha256(), &key, b"Hi There", &tag[..16]).unwrap());
+    }
+
     #[test]
     fn test_ripemd160() {
         let tests = [("616263", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc")];
@@ -321,4 +615,30 @@ mod tests {
             hash_test(MessageDigest::ripemd160(), test);
         }
     }
+
+    #[test]
+    fn match_digest() {
+        use nid;
+
+        fn name(md: MessageDigest) -> &'static str {
+            match md {
+                super::SHA1 => "sha1",
+                super::SHA256 => "sha256",
+                _ => "other",
+            }
+        }
+
+        assert_eq!(name(MessageDigest::sha256()), "sha256");
+        assert_eq!(name(MessageDigest::from_nid(nid::SHA1).unwrap()), "sha1");
+        assert_eq!(name(MessageDigest::md5()), "other");
+        assert_eq!(MessageDigest::sha256().nid(), nid::SHA256);
+        assert_eq!(MessageDigest::sha256().size(), 32);
+        // signature algorithm names resolve to their digest
+        assert_eq!(MessageDigest::from_nid(nid::SHA256WITHRSAENCRYPTION),
+                   Some(super::SHA256));
+        assert!(MessageDigest::from_nid(nid::AES_128_CBC).is_none());
+        let md4 = MessageDigest::from_nid(nid::MD4).unwrap();
+        assert_eq!(name(md4), "other");
+        assert_eq!(md4.size(), 16);
+    }
 }
@@ -0,0 +1,191 @@
+//! The HMAC-based Extract-and-Expand Key Derivation Function (HKDF) described in RFC 5869.
+//!
+//! HKDF turns input keying material, such as a Diffie-Hellman shared secret, into one or more
+//! cryptographically strong keys. `hkdf` performs the whole derivation, while `extract` and
+//! `expand` expose its two steps for protocols which reuse a pseudorandom key to derive several
+//! keys with different `info` values.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use openssl::hash::MessageDigest;
+//! use openssl::hkdf;
+//!
+//! let secret = b"input keying material";
+//! let key = hkdf::hkdf(MessageDigest::sha256(), secret, b"salt", b"encryption key", 32).unwrap();
+//!
+//! let prk = hkdf::extract(MessageDigest::sha256(), secret, b"salt").unwrap();
+//! assert_eq!(key, hkdf::expand(MessageDigest::sha256(), &prk, b"encryption key", 32).unwrap());
+//! ```
+use std::cmp;
+
+use error::ErrorStack;
+use hash::{self, MessageDigest};
+
+/// Derives `out_len` bytes of keying material from `ikm` using `salt` and `info`.
+///
+/// An empty `salt` is equivalent to one consisting of zeros. OpenSSL's native implementation is
+/// used with OpenSSL 1.1.0 and newer, unless `ikm` is empty, which it does not support.
+///
+/// # Panics
+///
+/// Panics if `out_len` is greater than 255 times the size of the digest.
+pub fn hkdf(md: MessageDigest,
+            ikm: &[u8],
+            salt: &[u8],
+            info: &[u8],
+            out_len: usize)
+            -> Result<Vec<u8>, ErrorStack> {
+    assert!(out_len <= 255 * md.size());
+    if ikm.is_empty() {
+        let prk = try!(extract(md, ikm, salt));
+        return expand(md, &prk, info, out_len);
+    }
+    imp::hkdf(md, ikm, salt, info, out_len)
+}
+
+/// Performs the extract step of HKDF, returning a pseudorandom key the size of the digest.
+pub fn extract(md: MessageDigest, ikm: &[u8], salt: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    hmac(md, salt, &[ikm])
+}
+
+/// Performs the expand step of HKDF, deriving `out_len` bytes from the pseudorandom key `prk`.
+///
+/// # Panics
+///
+/// Panics if `out_len` is greater than 255 times the size of the digest.
+pub fn expand(md: MessageDigest,
+              prk: &[u8],
+              info: &[u8],
+              out_len: usize)
+              -> Result<Vec<u8>, ErrorStack> {
+    assert!(out_len <= 255 * md.size());
+
+    let mut out = Vec::with_capacity(out_len);
+    let mut block = vec![];
+    let mut counter = 1u8;
+    while out.len() < out_len {
+        block = try!(hmac(md, prk, &[&block, info, &[counter]]));
+        let len = cmp::min(block.len(), out_len - out.len());
+        out.extend_from_slice(&block[..len]);
+        counter = counter.wrapping_add(1);
+    }
+    Ok(out)
+}
+
+// Computes the HMAC of the concatenation of `data`.
+fn hmac(md: MessageDigest, key: &[u8], data: &[&[u8]]) -> Result<Vec<u8>, ErrorStack> {
+    hash::hmac(md, key, &data.concat())
+}
+
+#[cfg(ossl110)]
+mod imp {
+    use ffi;
+    use foreign_types::ForeignTypeRef;
+    use libc::c_int;
+
+    use cvt;
+    use error::ErrorStack;
+    use hash::MessageDigest;
+    use nid::Nid;
+    use pkey::PKeyCtx;
+
+    pub fn hkdf(md: MessageDigest,
+                ikm: &[u8],
+                salt: &[u8],
+                info: &[u8],
+                out_len: usize)
+                -> Result<Vec<u8>, ErrorStack> {
+        assert!(ikm.len() <= c_int::max_value() as usize);
+        assert!(salt.len() <= c_int::max_value() as usize);
+        assert!(info.len() <= c_int::max_value() as usize);
+
+        let ctx = try!(PKeyCtx::new_id(Nid::from_raw(ffi::EVP_PKEY_HKDF)));
+        unsafe {
+            try!(cvt(ffi::EVP_PKEY_derive_init(ctx.as_ptr())));
+            try!(cvt(ffi::EVP_PKEY_CTX_set_hkdf_md(ctx.as_ptr(), md.as_ptr())));
+            try!(cvt(ffi::EVP_PKEY_CTX_set1_hkdf_key(ctx.as_ptr(),
+                                                     ikm.as_ptr(),
+                                                     ikm.len() as c_int)));
+            // OpenSSL 1.1.0 rejects empty salts, but leaving it unset has the same effect.
+            if !salt.is_empty() {
+                try!(cvt(ffi::EVP_PKEY_CTX_set1_hkdf_salt(ctx.as_ptr(),
+                                                          salt.as_ptr(),
+                                                          salt.len() as c_int)));
+            }
+            if !info.is_empty() {
+                try!(cvt(ffi::EVP_PKEY_CTX_add1_hkdf_info(ctx.as_ptr(),
+                                                          info.as_ptr(),
+                                                          info.len() as c_int)));
+            }
+
+            let mut out = vec![0; out_len];
+            let mut len = out_len;
+            try!(cvt(ffi::EVP_PKEY_derive(ctx.as_ptr(), out.as_mut_ptr(), &mut len)));
+            out.truncate(len);
+            Ok(out)
+        }
+    }
+}
+
+#[cfg(ossl10x)]
+mod imp {
+    use error::ErrorStack;
+    use hash::MessageDigest;
+
+    pub fn hkdf(md: MessageDigest,
+                ikm: &[u8],
+                salt: &[u8],
+                info: &[u8],
+                out_len: usize)
+                -> Result<Vec<u8>, ErrorStack> {
+        let prk = try!(super::extract(md, ikm, salt));
+        super::expand(md, &prk, info, out_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::{FromHex, ToHex};
+
+    use hash::MessageDigest;
+    use super::*;
+
+    // Test cases 1 and 3 from RFC 5869.
+    #[test]
+    fn rfc5869_sha256() {
+        let ikm = Vec::from_hex("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b").unwrap();
+        let salt = Vec::from_hex("000102030405060708090a0b0c").unwrap();
+        let info = Vec::from_hex("f0f1f2f3f4f5f6f7f8f9").unwrap();
+
+        let prk = extract(MessageDigest::sha256(), &ikm, &salt).unwrap();
+        assert_eq!(prk.to_hex(),
+                   "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5");
+        let okm = "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b8\
+                   87185865";
+        assert_eq!(expand(MessageDigest::sha256(), &prk, &info, 42).unwrap().to_hex(), okm);
+        assert_eq!(hkdf(MessageDigest::sha256(), &ikm, &salt, &info, 42).unwrap().to_hex(),
+                   okm);
+
+        let prk = extract(MessageDigest::sha256(), &ikm, &[]).unwrap();
+        assert_eq!(prk.to_hex(),
+                   "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04");
+        let okm = "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4\
+                   b61a96c8";
+        assert_eq!(expand(MessageDigest::sha256(), &prk, &[], 42).unwrap().to_hex(), okm);
+        assert_eq!(hkdf(MessageDigest::sha256(), &ikm, &[], &[], 42).unwrap().to_hex(), okm);
+    }
+
+    #[test]
+    fn hkdf_empty_ikm() {
+        let okm = hkdf(MessageDigest::sha256(), &[], b"salt", b"info", 32).unwrap();
+        assert_eq!(okm.to_hex(),
+                   "7aac7b8120501c2c8e1ee50e6cde135361e99ceb9d8d406ac528b9e9175614c0");
+    }
+
+    #[test]
+    #[should_panic]
+    fn expand_too_long() {
+        let _ = expand(MessageDigest::sha256(), &[0; 32], &[], 255 * 32 + 1);
+    }
+}
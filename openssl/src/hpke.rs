@@ -0,0 +1,586 @@
+//! Hybrid Public Key Encryption (HPKE) as described in RFC 9180.
+//!
+//! HPKE encrypts messages to the holder of an X25519 private key. A sender encapsulates a fresh
+//! shared secret to the recipient's public key, producing an encapsulated key `enc` which is
+//! sent along with the ciphertexts. Both sides then derive the same AEAD context from the
+//! shared secret, optionally mixing in a pre-shared key.
+//!
+//! The KEM is always DHKEM(X25519, HKDF-SHA256). The KDF and AEAD are configurable.
+//!
+//! Requires the `v111` feature and OpenSSL 1.1.1.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use openssl::hpke::{Aead, Hpke, Kdf};
+//! use openssl::pkey::PKey;
+//!
+//! let hpke = Hpke::new(Kdf::hkdf_sha256(), Aead::aes_128_gcm());
+//! let recipient = PKey::generate_x25519().unwrap();
+//!
+//! let (enc, mut sender) = hpke.setup_sender(&recipient, b"app info").unwrap();
+//! let ciphertext = sender.seal(b"header", b"hello world").unwrap();
+//!
+//! let mut receiver = hpke.setup_receiver(&enc, &recipient, b"app info").unwrap();
+//! assert_eq!(receiver.open(b"header", &ciphertext).unwrap(), b"hello world");
+//! ```
+use std::error;
+use std::fmt;
+
+use derive::Deriver;
+use error::ErrorStack;
+use hash::MessageDigest;
+use hkdf;
+use nid;
+use pkey::{PKey, PKeyRef};
+use symm::{self, Cipher};
+
+const KEM_ID: u16 = 0x0020;
+const N_SECRET: usize = 32;
+const N_SK: usize = 32;
+const TAG_LEN: usize = 16;
+
+const MODE_BASE: u8 = 0x00;
+const MODE_PSK: u8 = 0x01;
+
+/// A key derivation function used by the HPKE key schedule.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Kdf(u16);
+
+impl Kdf {
+    pub fn hkdf_sha256() -> Kdf {
+        Kdf(0x0001)
+    }
+
+    pub fn hkdf_sha384() -> Kdf {
+        Kdf(0x0002)
+    }
+
+    pub fn hkdf_sha512() -> Kdf {
+        Kdf(0x0003)
+    }
+
+    /// Returns the IANA identifier of the KDF.
+    pub fn id(&self) -> u16 {
+        self.0
+    }
+
+    fn digest(&self) -> MessageDigest {
+        match self.0 {
+            0x0002 => MessageDigest::sha384(),
+            0x0003 => MessageDigest::sha512(),
+            _ => MessageDigest::sha256(),
+        }
+    }
+}
+
+/// An authenticated encryption algorithm used to protect HPKE messages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Aead(u16);
+
+impl Aead {
+    pub fn aes_128_gcm() -> Aead {
+        Aead(0x0001)
+    }
+
+    pub fn aes_256_gcm() -> Aead {
+        Aead(0x0002)
+    }
+
+    pub fn chacha20_poly1305() -> Aead {
+        Aead(0x0003)
+    }
+
+    /// Returns the IANA identifier of the AEAD.
+    pub fn id(&self) -> u16 {
+        self.0
+    }
+
+    fn cipher(&self) -> Cipher {
+        match self.0 {
+            0x0002 => Cipher::aes_256_gcm(),
+            0x0003 => Cipher::chacha20_poly1305(),
+            _ => Cipher::aes_128_gcm(),
+        }
+    }
+}
+
+/// An error returned by HPKE operations.
+#[derive(Debug)]
+pub enum Error {
+    /// A ciphertext could not be decrypted, either because it was corrupted or because it was
+    /// not sealed by the matching sender context.
+    Open,
+    /// The context has sealed or opened the maximum number of messages.
+    MessageLimitReached,
+    /// The PSK and PSK ID were not either both empty or both present.
+    InconsistentPsk,
+    /// OpenSSL reported an error, for example because a key was not a valid X25519 key.
+    ErrorStack(ErrorStack),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::ErrorStack(ref e) => fmt::Display::fmt(e, fmt),
+            _ => fmt.write_str(error::Error::description(self)),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Open => "failed to open HPKE ciphertext",
+            Error::MessageLimitReached => "HPKE message limit reached",
+            Error::InconsistentPsk => "inconsistent HPKE PSK inputs",
+            Error::ErrorStack(ref e) => error::Error::description(e),
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::ErrorStack(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorStack> for Error {
+    fn from(e: ErrorStack) -> Error {
+        Error::ErrorStack(e)
+    }
+}
+
+/// An HPKE cipher suite.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Hpke {
+    kdf: Kdf,
+    aead: Aead,
+}
+
+impl Hpke {
+    /// Creates a cipher suite using DHKEM(X25519, HKDF-SHA256) and the provided KDF and AEAD.
+    pub fn new(kdf: Kdf, aead: Aead) -> Hpke {
+        Hpke {
+            kdf: kdf,
+            aead: aead,
+        }
+    }
+
+    /// Sets up a base mode context for encrypting messages to the X25519 public key `pk_r`.
+    ///
+    /// Returns the encapsulated key which must be sent to the recipient along with the context.
+    pub fn setup_sender(&self,
+                        pk_r: &PKeyRef,
+                        info: &[u8])
+                        -> Result<(Vec<u8>, SenderContext), Error> {
+        let sk_e = try!(PKey::generate_x25519());
+        self.setup_sender_with_ephemeral(pk_r, info, &[], &[], &sk_e)
+    }
+
+    /// Like `setup_sender`, but additionally authenticates the sender by a pre-shared key.
+    pub fn setup_sender_psk(&self,
+                            pk_r: &PKeyRef,
+                            info: &[u8],
+                            psk: &[u8],
+                            psk_id: &[u8])
+                            -> Result<(Vec<u8>, SenderContext), Error> {
+        if psk.is_empty() || psk_id.is_empty() {
+            return Err(Error::InconsistentPsk);
+        }
+        let sk_e = try!(PKey::generate_x25519());
+        self.setup_sender_with_ephemeral(pk_r, info, psk, psk_id, &sk_e)
+    }
+
+    /// Sets up a base mode context for decrypting messages sent to the X25519 private key
+    /// `sk_r` with the encapsulated key `enc`.
+    pub fn setup_receiver(&self,
+                          enc: &[u8],
+                          sk_r: &PKeyRef,
+                          info: &[u8])
+                          -> Result<ReceiverContext, Error> {
+        let shared_secret = try!(decap(enc, sk_r));
+        let context = try!(self.key_schedule(MODE_BASE, &shared_secret, info, &[], &[]));
+        Ok(ReceiverContext(context))
+    }
+
+    /// Like `setup_receiver`, but additionally authenticates the sender by a pre-shared key.
+    pub fn setup_receiver_psk(&self,
+                              enc: &[u8],
+                              sk_r: &PKeyRef,
+                              info: &[u8],
+                              psk: &[u8],
+                              psk_id: &[u8])
+                              -> Result<ReceiverContext, Error> {
+        if psk.is_empty() || psk_id.is_empty() {
+            return Err(Error::InconsistentPsk);
+        }
+        let shared_secret = try!(decap(enc, sk_r));
+        let context = try!(self.key_schedule(MODE_PSK, &shared_secret, info, psk, psk_id));
+        Ok(ReceiverContext(context))
+    }
+
+    /// Encrypts a single message to `pk_r` in base mode, returning the encapsulated key and the
+    /// ciphertext.
+    pub fn seal(&self,
+                pk_r: &PKeyRef,
+                info: &[u8],
+                aad: &[u8],
+                plaintext: &[u8])
+                -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let (enc, mut context) = try!(self.setup_sender(pk_r, info));
+        let ciphertext = try!(context.seal(aad, plaintext));
+        Ok((enc, ciphertext))
+    }
+
+    /// Decrypts a single message produced by `seal`.
+    pub fn open(&self,
+                enc: &[u8],
+                sk_r: &PKeyRef,
+                info: &[u8],
+                aad: &[u8],
+                ciphertext: &[u8])
+                -> Result<Vec<u8>, Error> {
+        let mut context = try!(self.setup_receiver(enc, sk_r, info));
+        context.open(aad, ciphertext)
+    }
+
+    fn setup_sender_with_ephemeral(&self,
+                                   pk_r: &PKeyRef,
+                                   info: &[u8],
+                                   psk: &[u8],
+                                   psk_id: &[u8],
+                                   sk_e: &PKeyRef)
+                                   -> Result<(Vec<u8>, SenderContext), Error> {
+        let mode = if psk.is_empty() { MODE_BASE } else { MODE_PSK };
+        let (shared_secret, enc) = try!(encap(pk_r, sk_e));
+        let context = try!(self.key_schedule(mode, &shared_secret, info, psk, psk_id));
+        Ok((enc, SenderContext(context)))
+    }
+
+    fn suite_id(&self) -> Vec<u8> {
+        let mut suite_id = b"HPKE".to_vec();
+        suite_id.extend_from_slice(&i2osp2(KEM_ID));
+        suite_id.extend_from_slice(&i2osp2(self.kdf.id()));
+        suite_id.extend_from_slice(&i2osp2(self.aead.id()));
+        suite_id
+    }
+
+    fn key_schedule(&self,
+                    mode: u8,
+                    shared_secret: &[u8],
+                    info: &[u8],
+                    psk: &[u8],
+                    psk_id: &[u8])
+                    -> Result<Context, ErrorStack> {
+        let md = self.kdf.digest();
+        let suite_id = self.suite_id();
+        let cipher = self.aead.cipher();
+
+        let psk_id_hash = try!(labeled_extract(md, &suite_id, &[], b"psk_id_hash", psk_id));
+        let info_hash = try!(labeled_extract(md, &suite_id, &[], b"info_hash", info));
+        let mut key_schedule_context = vec![mode];
+        key_schedule_context.extend_from_slice(&psk_id_hash);
+        key_schedule_context.extend_from_slice(&info_hash);
+
+        let secret = try!(labeled_extract(md, &suite_id, shared_secret, b"secret", psk));
+        let key = try!(labeled_expand(md,
+                                      &suite_id,
+                                      &secret,
+                                      b"key",
+                                      &key_schedule_context,
+                                      cipher.key_len()));
+        let base_nonce = try!(labeled_expand(md,
+                                             &suite_id,
+                                             &secret,
+                                             b"base_nonce",
+                                             &key_schedule_context,
+                                             cipher.iv_len().unwrap_or(12)));
+        let exporter_secret = try!(labeled_expand(md,
+                                                  &suite_id,
+                                                  &secret,
+                                                  b"exp",
+                                                  &key_schedule_context,
+                                                  md.size()));
+
+        Ok(Context {
+            md: md,
+            cipher: cipher,
+            suite_id: suite_id,
+            key: key,
+            base_nonce: base_nonce,
+            exporter_secret: exporter_secret,
+            seq: 0,
+        })
+    }
+}
+
+struct Context {
+    md: MessageDigest,
+    cipher: Cipher,
+    suite_id: Vec<u8>,
+    key: Vec<u8>,
+    base_nonce: Vec<u8>,
+    exporter_secret: Vec<u8>,
+    seq: u64,
+}
+
+impl Context {
+    fn next_nonce(&mut self) -> Result<Vec<u8>, Error> {
+        if self.seq == u64::max_value() {
+            return Err(Error::MessageLimitReached);
+        }
+
+        let mut nonce = self.base_nonce.clone();
+        let len = nonce.len();
+        for (i, b) in nonce[len - 8..].iter_mut().enumerate() {
+            *b ^= (self.seq >> (56 - 8 * i)) as u8;
+        }
+        self.seq += 1;
+        Ok(nonce)
+    }
+
+    fn export(&self, exporter_context: &[u8], len: usize) -> Result<Vec<u8>, ErrorStack> {
+        labeled_expand(self.md,
+                       &self.suite_id,
+                       &self.exporter_secret,
+                       b"sec",
+                       exporter_context,
+                       len)
+    }
+}
+
+/// An HPKE context used to encrypt messages.
+pub struct SenderContext(Context);
+
+impl SenderContext {
+    /// Encrypts and authenticates `plaintext` along with the associated data `aad`.
+    ///
+    /// Messages must be opened in the order they were sealed.
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = try!(self.0.next_nonce());
+        let mut tag = [0; TAG_LEN];
+        let mut out = try!(symm::encrypt_aead(self.0.cipher,
+                                              &self.0.key,
+                                              Some(&nonce),
+                                              aad,
+                                              plaintext,
+                                              &mut tag));
+        out.extend_from_slice(&tag);
+        Ok(out)
+    }
+
+    /// Derives `len` bytes of secret keying material bound to `exporter_context`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than 255 times the size of the KDF's digest.
+    pub fn export(&self, exporter_context: &[u8], len: usize) -> Result<Vec<u8>, ErrorStack> {
+        self.0.export(exporter_context, len)
+    }
+}
+
+/// An HPKE context used to decrypt messages.
+pub struct ReceiverContext(Context);
+
+impl ReceiverContext {
+    /// Decrypts and authenticates `ciphertext` along with the associated data `aad`.
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        if ciphertext.len() < TAG_LEN {
+            return Err(Error::Open);
+        }
+        let (data, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+
+        // The sequence number is only advanced by successfully opened messages.
+        let seq = self.0.seq;
+        let nonce = try!(self.0.next_nonce());
+        match symm::decrypt_aead(self.0.cipher, &self.0.key, Some(&nonce), aad, data, tag) {
+            Ok(plaintext) => Ok(plaintext),
+            Err(_) => {
+                self.0.seq = seq;
+                Err(Error::Open)
+            }
+        }
+    }
+
+    /// Derives `len` bytes of secret keying material bound to `exporter_context`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than 255 times the size of the KDF's digest.
+    pub fn export(&self, exporter_context: &[u8], len: usize) -> Result<Vec<u8>, ErrorStack> {
+        self.0.export(exporter_context, len)
+    }
+}
+
+/// Deterministically derives an X25519 key pair from the input keying material `ikm`.
+///
+/// This implements the `DeriveKeyPair` function of DHKEM(X25519, HKDF-SHA256).
+pub fn derive_key_pair(ikm: &[u8]) -> Result<PKey, ErrorStack> {
+    let md = MessageDigest::sha256();
+    let suite_id = kem_suite_id();
+    let dkp_prk = try!(labeled_extract(md, &suite_id, &[], b"dkp_prk", ikm));
+    let sk = try!(labeled_expand(md, &suite_id, &dkp_prk, b"sk", &[], N_SK));
+    PKey::private_key_from_raw_bytes(&sk, nid::X25519)
+}
+
+fn encap(pk_r: &PKeyRef, sk_e: &PKeyRef) -> Result<(Vec<u8>, Vec<u8>), ErrorStack> {
+    let dh = try!(dh(sk_e, pk_r));
+    let enc = try!(sk_e.raw_public_key());
+    let mut kem_context = enc.clone();
+    kem_context.extend_from_slice(&try!(pk_r.raw_public_key()));
+    let shared_secret = try!(extract_and_expand(&dh, &kem_context));
+    Ok((shared_secret, enc))
+}
+
+fn decap(enc: &[u8], sk_r: &PKeyRef) -> Result<Vec<u8>, ErrorStack> {
+    let pk_e = try!(PKey::public_key_from_raw_bytes(enc, nid::X25519));
+    let dh = try!(dh(sk_r, &pk_e));
+    let mut kem_context = enc.to_vec();
+    kem_context.extend_from_slice(&try!(sk_r.raw_public_key()));
+    extract_and_expand(&dh, &kem_context)
+}
+
+fn dh(sk: &PKeyRef, pk: &PKeyRef) -> Result<Vec<u8>, ErrorStack> {
+    let mut deriver = try!(Deriver::new(sk));
+    try!(deriver.set_peer(pk));
+    deriver.derive_to_vec()
+}
+
+fn extract_and_expand(dh: &[u8], kem_context: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let md = MessageDigest::sha256();
+    let suite_id = kem_suite_id();
+    let eae_prk = try!(labeled_extract(md, &suite_id, &[], b"eae_prk", dh));
+    labeled_expand(md, &suite_id, &eae_prk, b"shared_secret", kem_context, N_SECRET)
+}
+
+fn kem_suite_id() -> Vec<u8> {
+    let mut suite_id = b"KEM".to_vec();
+    suite_id.extend_from_slice(&i2osp2(KEM_ID));
+    suite_id
+}
+
+fn labeled_extract(md: MessageDigest,
+                   suite_id: &[u8],
+                   salt: &[u8],
+                   label: &[u8],
+                   ikm: &[u8])
+                   -> Result<Vec<u8>, ErrorStack> {
+    let labeled_ikm = [b"HPKE-v1", suite_id, label, ikm].concat();
+    hkdf::extract(md, &labeled_ikm, salt)
+}
+
+fn labeled_expand(md: MessageDigest,
+                  suite_id: &[u8],
+                  prk: &[u8],
+                  label: &[u8],
+                  info: &[u8],
+                  len: usize)
+                  -> Result<Vec<u8>, ErrorStack> {
+    assert!(len <= u16::max_value() as usize);
+    let labeled_info = [&i2osp2(len as u16)[..], b"HPKE-v1", suite_id, label, info].concat();
+    hkdf::expand(md, prk, &labeled_info, len)
+}
+
+fn i2osp2(v: u16) -> [u8; 2] {
+    [(v >> 8) as u8, v as u8]
+}
+
+#[cfg(test)]
+mod test {
+    use hex::{FromHex, ToHex};
+
+    use pkey::PKey;
+    use super::*;
+
+    // RFC 9180 appendix A.1.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, base mode.
+    #[test]
+    fn base_vector() {
+        let info = Vec::from_hex("4f6465206f6e2061204772656369616e2055726e").unwrap();
+        let ikm_e = Vec::from_hex("7268600d403fce431561aef583ee1613527cff655c1343f29812e66706df3234")
+            .unwrap();
+        let ikm_r = Vec::from_hex("6db9df30aa07dd42ee5e8181afdb977e538f5e1fec8a06223f33f7013e525037")
+            .unwrap();
+
+        let sk_e = derive_key_pair(&ikm_e).unwrap();
+        assert_eq!(sk_e.raw_private_key().unwrap().to_hex(),
+                   "52c4a758a802cd8b936eceea314432798d5baf2d7e9235dc084ab1b9cfa2f736");
+        let sk_r = derive_key_pair(&ikm_r).unwrap();
+        assert_eq!(sk_r.raw_private_key().unwrap().to_hex(),
+                   "4612c550263fc8ad58375df3f557aac531d26850903e55a9f23f21d8534e8ac8");
+
+        let hpke = Hpke::new(Kdf::hkdf_sha256(), Aead::aes_128_gcm());
+        let (enc, mut sender) = hpke.setup_sender_with_ephemeral(&sk_r, &info, &[], &[], &sk_e)
+            .unwrap();
+        assert_eq!(enc.to_hex(),
+                   "37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431");
+        assert_eq!(sender.0.key.to_hex(), "4531685d41d65f03dc48f6b8302c05b0");
+        assert_eq!(sender.0.base_nonce.to_hex(), "56d890e5accaaf011cff4b7d");
+
+        let pt = Vec::from_hex("4265617574792069732074727574682c20747275746820626561757479")
+            .unwrap();
+        let ct = sender.seal(b"Count-0", &pt).unwrap();
+        assert_eq!(ct.to_hex(),
+                   "f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a9\
+                    6d8770ac83d07bea87e13c512a");
+
+        let mut receiver = hpke.setup_receiver(&enc, &sk_r, &info).unwrap();
+        assert_eq!(receiver.open(b"Count-0", &ct).unwrap(), pt);
+    }
+
+    #[test]
+    fn round_trip() {
+        let recipient = PKey::generate_x25519().unwrap();
+
+        for &aead in &[Aead::aes_128_gcm(), Aead::aes_256_gcm(), Aead::chacha20_poly1305()] {
+            let hpke = Hpke::new(Kdf::hkdf_sha384(), aead);
+            let (enc, mut sender) = hpke.setup_sender(&recipient, b"info").unwrap();
+            let ct1 = sender.seal(b"aad", b"first").unwrap();
+            let ct2 = sender.seal(b"aad", b"second").unwrap();
+
+            let mut receiver = hpke.setup_receiver(&enc, &recipient, b"info").unwrap();
+            match receiver.open(b"aad", &ct2) {
+                Err(Error::Open) => {}
+                _ => panic!("opened a message out of order"),
+            }
+            assert_eq!(receiver.open(b"aad", &ct1).unwrap(), b"first");
+            assert_eq!(receiver.open(b"aad", &ct2).unwrap(), b"second");
+            assert_eq!(sender.export(b"ctx", 32).unwrap(),
+                       receiver.export(b"ctx", 32).unwrap());
+        }
+    }
+
+    #[test]
+    fn psk_mode() {
+        let recipient = PKey::generate_x25519().unwrap();
+        let hpke = Hpke::new(Kdf::hkdf_sha256(), Aead::chacha20_poly1305());
+
+        let (enc, mut sender) = hpke.setup_sender_psk(&recipient, b"", b"secret", b"id").unwrap();
+        let ct = sender.seal(b"", b"hello").unwrap();
+
+        let mut receiver = hpke.setup_receiver_psk(&enc, &recipient, b"", b"secret", b"id")
+            .unwrap();
+        assert_eq!(receiver.open(b"", &ct).unwrap(), b"hello");
+
+        let mut receiver = hpke.setup_receiver_psk(&enc, &recipient, b"", b"wrong", b"id")
+            .unwrap();
+        assert!(receiver.open(b"", &ct).is_err());
+        assert!(hpke.open(&enc, &recipient, b"", b"", &ct).is_err());
+
+        match hpke.setup_sender_psk(&recipient, b"", b"secret", b"") {
+            Err(Error::InconsistentPsk) => {}
+            _ => panic!("accepted a PSK without an ID"),
+        }
+    }
+
+    #[test]
+    fn single_shot() {
+        let recipient = PKey::generate_x25519().unwrap();
+        let hpke = Hpke::new(Kdf::hkdf_sha512(), Aead::aes_256_gcm());
+        let (enc, ct) = hpke.seal(&recipient, b"info", b"aad", b"message").unwrap();
+        assert_eq!(hpke.open(&enc, &recipient, b"info", b"aad", &ct).unwrap(), b"message");
+        assert!(hpke.open(&enc, &recipient, b"info", b"other", &ct).is_err());
+        assert!(hpke.open(&enc, &recipient, b"info", b"aad", &ct[..8]).is_err());
+    }
+}
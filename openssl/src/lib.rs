@@ -1,3 +1,47 @@
+//! Bindings to OpenSSL.
+//!
+//! Most of the crate mirrors the OpenSSL API closely, but common tasks can be handled with a few
+//! higher level entry points:
+//!
+//! * `tls::client::connect` connects to a TLS server, verifying its certificate chain and
+//!   hostname.
+//! * `tls::server::acceptor_from_pem` creates an acceptor for TLS connections from a certificate
+//!   and private key.
+//! * `sign::simple` signs and verifies messages with a `pkey::PKey`.
+//!
+//! They are thin wrappers over `ssl::SslConnector`, `ssl::SslAcceptor` and `sign::Signer`, which
+//! can be configured further. The types needed in most programs can be imported at once from
+//! `prelude`.
+//!
+//! # Examples
+//!
+//! Connect to a server:
+//!
+//! ```no_run
+//! use openssl::tls;
+//! use std::io::{Read, Write};
+//! use std::net::TcpStream;
+//!
+//! let stream = TcpStream::connect("google.com:443").unwrap();
+//! let mut stream = tls::client::connect("google.com", stream).unwrap();
+//!
+//! stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+//! let mut res = vec![];
+//! stream.read_to_end(&mut res).unwrap();
+//! ```
+//!
+//! Sign and verify a message:
+//!
+//! ```rust
+//! use openssl::prelude::*;
+//! use openssl::rsa::Rsa;
+//! use openssl::sign::simple;
+//!
+//! let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+//!
+//! let signature = simple::sign(MessageDigest::sha256(), &key, b"hello, world!").unwrap();
+//! assert!(simple::verify(MessageDigest::sha256(), &key, b"hello, world!", &signature).unwrap());
+//! ```
 #![doc(html_root_url="https://docs.rs/openssl/0.9.6")]
 
 #[macro_use]
@@ -18,6 +62,8 @@ extern crate tempdir;
 pub use ffi::init;
 
 use libc::c_int;
+use std::ffi::CString;
+use std::ptr;
 
 use error::ErrorStack;
 
@@ -28,27 +74,41 @@ mod bio;
 mod util;
 pub mod aes;
 pub mod asn1;
+pub mod base64;
 pub mod bn;
 pub mod crypto;
+pub mod derive;
 pub mod dh;
 pub mod dsa;
 pub mod ec;
+pub mod ecdsa;
 pub mod ec_key;
+#[cfg(not(osslconf = "OPENSSL_NO_ENGINE"))]
+pub mod engine;
+pub mod envelope;
 pub mod error;
+#[cfg(all(feature = "fips", not(libressl)))]
+pub mod fips;
 pub mod hash;
+pub mod hkdf;
+#[cfg(all(feature = "v111", ossl111))]
+pub mod hpke;
 pub mod memcmp;
 pub mod nid;
 pub mod ocsp;
 pub mod pkcs12;
 pub mod pkcs5;
 pub mod pkey;
+pub mod prelude;
 pub mod rand;
+pub mod record;
 pub mod rsa;
 pub mod sign;
 pub mod ssl;
 pub mod stack;
 pub mod string;
 pub mod symm;
+pub mod tls;
 pub mod types;
 pub mod version;
 pub mod x509;
@@ -74,3 +134,25 @@ fn cvt(r: c_int) -> Result<c_int, ErrorStack> {
 fn cvt_n(r: c_int) -> Result<c_int, ErrorStack> {
     if r < 0 { Err(ErrorStack::get()) } else { Ok(r) }
 }
+
+// Reasons for the errors raised by this crate itself, which are reported under `ERR_LIB_USER`.
+const R_INVALID_IV_LENGTH: c_int = 100;
+const R_UNSUPPORTED_ALGORITHM: c_int = 101;
+const R_NO_CERTIFICATES: c_int = 102;
+const R_TRUNCATED: c_int = 103;
+const R_SEQUENCE_EXHAUSTED: c_int = 104;
+const R_INVALID_KEY_LENGTH: c_int = 105;
+const R_BUFFER_TOO_SMALL: c_int = 106;
+const R_RENEGOTIATION_REJECTED: c_int = 107;
+
+// Adds an error raised by this crate to OpenSSL's error queue, so that the next
+// `ErrorStack::get` reports it in the same way as the errors raised by OpenSSL.
+fn put_error(reason: c_int, data: &str) {
+    ffi::init();
+
+    let data = CString::new(data).unwrap();
+    unsafe {
+        ffi::ERR_put_error(ffi::ERR_LIB_USER, 0, reason, ptr::null(), 0);
+        ffi::ERR_add_error_data(1, data.as_ptr());
+    }
+}
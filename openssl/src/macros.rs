@@ -85,6 +85,31 @@ macro_rules! private_key_to_pem {
                 Ok(bio.get_buf().to_owned())
             }
         }
+
+        /// Serializes the private key to PEM, encrypting it with the specified symmetric cipher and
+        /// a passphrase retrieved from a callback.
+        ///
+        /// The callback should copy the passphrase into the provided buffer and return the number
+        /// of bytes written.
+        pub fn private_key_to_pem_callback<F>(&self,
+                                              cipher: ::symm::Cipher,
+                                              callback: F)
+                                              -> Result<Vec<u8>, ::error::ErrorStack>
+            where F: FnOnce(&mut [u8]) -> Result<usize, ::error::ErrorStack>
+        {
+            unsafe {
+                let mut cb = ::util::CallbackState::new(callback);
+                let bio = try!(::bio::MemBio::new());
+                try!(cvt($f(bio.as_ptr(),
+                            self.as_ptr(),
+                            cipher.as_ptr(),
+                            ptr::null_mut(),
+                            0,
+                            Some(::util::invoke_passwd_cb::<F>),
+                            &mut cb as *mut _ as *mut _)));
+                Ok(bio.get_buf().to_owned())
+            }
+        }
     }
 }
 
@@ -152,6 +177,57 @@ macro_rules! public_key_to_der {
     }
 }
 
+macro_rules! to_der_into_inner {
+    (#[$m:meta] $len:ident, #[$m2:meta] $n:ident, $f:path) => {
+        #[$m]
+        pub fn $len(&self) -> Result<usize, ::error::ErrorStack> {
+            unsafe {
+                ::cvt($f(::foreign_types::ForeignTypeRef::as_ptr(self), ::std::ptr::null_mut()))
+                    .map(|len| len as usize)
+            }
+        }
+
+        #[$m2]
+        ///
+        /// Unlike the variant returning a `Vec`, this does not allocate. The number of bytes written
+        /// is returned.
+        ///
+        /// An error with the reason `error::REASON_BUFFER_TOO_SMALL` is returned if `buf` is too
+        /// short to hold the encoding.
+        pub fn $n(&self, buf: &mut [u8]) -> Result<usize, ::error::ErrorStack> {
+            let len = try!(self.$len());
+            if buf.len() < len {
+                ::put_error(::R_BUFFER_TOO_SMALL,
+                            &format!("{} bytes are required for the encoding", len));
+                return Err(::error::ErrorStack::get());
+            }
+            unsafe {
+                let mut ptr = buf.as_mut_ptr();
+                ::cvt($f(::foreign_types::ForeignTypeRef::as_ptr(self), &mut ptr))
+                    .map(|len| len as usize)
+            }
+        }
+    };
+}
+
+macro_rules! to_der_into {
+    ($f:path) => {
+        to_der_into_inner!(/// Returns the length of the DER encoding of this value.
+            der_len,
+            /// Serializes this value to DER in `buf`.
+            to_der_into, $f);
+    }
+}
+
+macro_rules! public_key_to_der_into {
+    ($f:path) => {
+        to_der_into_inner!(/// Returns the length of the DER encoding of the public key.
+            public_key_der_len,
+            /// Serializes the public key to DER in `buf`.
+            public_key_to_der_into, $f);
+    }
+}
+
 macro_rules! from_der_inner {
     (#[$m:meta] $n:ident, $t:ident, $f:path) => {
         #[$m]
@@ -6,6 +6,20 @@ use ffi;
 /// This operation takes an amount of time dependent on the length of the two
 /// arrays given, but is independent of the contents of a and b.
 ///
+/// This should be used instead of `==` when comparing MACs, password hashes, or other secret
+/// values, as a comparison which returns early on the first mismatched byte leaks the length of
+/// the matching prefix to an attacker who can measure timing.
+///
+/// # Examples
+///
+/// ```
+/// use openssl::memcmp;
+///
+/// let expected = [0x4f, 0x1c, 0x8a, 0x21];
+/// let received = [0x4f, 0x1c, 0x8a, 0x21];
+/// assert!(memcmp::eq(&expected, &received));
+/// ```
+///
 /// # Panics
 ///
 /// This function will panic the current task if `a` and `b` do not have the same
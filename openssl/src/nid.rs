@@ -1,5 +1,17 @@
 use ffi;
-use libc::c_int;
+use libc::{c_char, c_int};
+use std::ffi::{CStr, CString};
+use std::str;
+
+use cvt;
+use error::ErrorStack;
+
+/// The digest and public key algorithms making up a signature algorithm.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SignatureAlgorithms {
+    pub digest: Nid,
+    pub pkey: Nid,
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Nid(c_int);
@@ -13,6 +25,97 @@ impl Nid {
     pub fn as_raw(&self) -> c_int {
         self.0
     }
+
+    /// Returns the `Nid` with the short name `name`, such as `"CN"`.
+    ///
+    /// This corresponds to `OBJ_sn2nid`.
+    pub fn from_short_name(name: &str) -> Option<Nid> {
+        ffi::init();
+        let name = CString::new(name).unwrap();
+        unsafe { from_name_result(ffi::OBJ_sn2nid(name.as_ptr())) }
+    }
+
+    /// Returns the `Nid` with the long name `name`, such as `"commonName"`.
+    ///
+    /// This corresponds to `OBJ_ln2nid`.
+    pub fn from_long_name(name: &str) -> Option<Nid> {
+        ffi::init();
+        let name = CString::new(name).unwrap();
+        unsafe { from_name_result(ffi::OBJ_ln2nid(name.as_ptr())) }
+    }
+
+    /// Registers a new object, returning its `Nid`.
+    ///
+    /// `oid` is the object identifier in dotted decimal form, and `short_name` and `long_name`
+    /// are the names it will be known by. This is used to give names to private OIDs, which can
+    /// then be used anywhere a `Nid` is accepted.
+    ///
+    /// This corresponds to `OBJ_create`.
+    ///
+    /// # Safety
+    ///
+    /// OpenSSL 1.0.x and 1.1.x do not lock the object table while it is modified. This must not
+    /// be called while another thread may be using the table, which includes looking up names
+    /// and OIDs as well as parsing or printing certificates and other ASN.1 structures. Objects
+    /// are typically registered at startup, before any other threads are spawned.
+    pub unsafe fn create(oid: &str, short_name: &str, long_name: &str) -> Result<Nid, ErrorStack> {
+        ffi::init();
+        let oid = CString::new(oid).unwrap();
+        let short_name = CString::new(short_name).unwrap();
+        let long_name = CString::new(long_name).unwrap();
+        cvt(ffi::OBJ_create(oid.as_ptr(), short_name.as_ptr(), long_name.as_ptr())).map(Nid)
+    }
+
+    /// Returns the short name of the object, such as `"CN"`.
+    ///
+    /// This corresponds to `OBJ_nid2sn`.
+    pub fn short_name(&self) -> Result<&'static str, ErrorStack> {
+        unsafe { name_result(ffi::OBJ_nid2sn(self.0)) }
+    }
+
+    /// Returns the long name of the object, such as `"commonName"`.
+    ///
+    /// This corresponds to `OBJ_nid2ln`.
+    pub fn long_name(&self) -> Result<&'static str, ErrorStack> {
+        unsafe { name_result(ffi::OBJ_nid2ln(self.0)) }
+    }
+
+    /// Returns the digest and public key algorithms of a signature algorithm, such as
+    /// `SHA256WITHRSAENCRYPTION`.
+    ///
+    /// `None` is returned if this is not a known signature algorithm.
+    ///
+    /// This corresponds to `OBJ_find_sigid_algs`.
+    pub fn signature_algorithms(&self) -> Option<SignatureAlgorithms> {
+        unsafe {
+            let mut digest = 0;
+            let mut pkey = 0;
+            if ffi::OBJ_find_sigid_algs(self.0, &mut digest, &mut pkey) == 1 {
+                Some(SignatureAlgorithms {
+                    digest: Nid(digest),
+                    pkey: Nid(pkey),
+                })
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn from_name_result(nid: c_int) -> Option<Nid> {
+    if nid == ffi::NID_undef {
+        None
+    } else {
+        Some(Nid(nid))
+    }
+}
+
+unsafe fn name_result(name: *const c_char) -> Result<&'static str, ErrorStack> {
+    if name.is_null() {
+        Err(ErrorStack::get())
+    } else {
+        Ok(str::from_utf8(CStr::from_ptr(name).to_bytes()).unwrap())
+    }
 }
 
 pub const UNDEF: Nid = Nid(ffi::NID_undef);
@@ -952,3 +1055,38 @@ pub const RC4_HMAC_MD5: Nid = Nid(ffi::NID_rc4_hmac_md5);
 pub const AES_128_CBC_HMAC_SHA1: Nid = Nid(ffi::NID_aes_128_cbc_hmac_sha1);
 pub const AES_192_CBC_HMAC_SHA1: Nid = Nid(ffi::NID_aes_192_cbc_hmac_sha1);
 pub const AES_256_CBC_HMAC_SHA1: Nid = Nid(ffi::NID_aes_256_cbc_hmac_sha1);
+pub const X25519: Nid = Nid(ffi::NID_X25519);
+pub const ED25519: Nid = Nid(ffi::NID_ED25519);
+pub const CHACHA20_POLY1305: Nid = Nid(ffi::NID_chacha20_poly1305);
+pub const CHACHA20: Nid = Nid(ffi::NID_chacha20);
+pub const BLAKE2B512: Nid = Nid(ffi::NID_blake2b512);
+pub const BLAKE2S256: Nid = Nid(ffi::NID_blake2s256);
+pub const SHA3_224: Nid = Nid(ffi::NID_sha3_224);
+pub const SHA3_256: Nid = Nid(ffi::NID_sha3_256);
+pub const SHA3_384: Nid = Nid(ffi::NID_sha3_384);
+pub const SHA3_512: Nid = Nid(ffi::NID_sha3_512);
+pub const SHAKE128: Nid = Nid(ffi::NID_shake128);
+pub const SHAKE256: Nid = Nid(ffi::NID_shake256);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(COMMONNAME.short_name().unwrap(), "CN");
+        assert_eq!(COMMONNAME.long_name().unwrap(), "commonName");
+        assert_eq!(Nid::from_short_name("CN"), Some(COMMONNAME));
+        assert_eq!(Nid::from_long_name("commonName"), Some(COMMONNAME));
+        assert_eq!(Nid::from_short_name("no-such-object"), None);
+        assert!(Nid::from_raw(-1).short_name().is_err());
+    }
+
+    #[test]
+    fn signature_algorithms() {
+        let algs = SHA256WITHRSAENCRYPTION.signature_algorithms().unwrap();
+        assert_eq!(algs.digest, SHA256);
+        assert_eq!(algs.pkey, RSAENCRYPTION);
+        assert_eq!(COMMONNAME.signature_algorithms(), None);
+    }
+}
@@ -285,6 +285,7 @@ impl OcspRequest {
 
 impl OcspRequestRef {
     to_der!(ffi::i2d_OCSP_REQUEST);
+    to_der_into!(ffi::i2d_OCSP_REQUEST);
 
     pub fn add_id(&mut self, id: OcspCertId) -> Result<&mut OcspOneReqRef, ErrorStack> {
         unsafe {
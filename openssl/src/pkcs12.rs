@@ -2,16 +2,24 @@
 
 use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
-use libc::c_int;
+use libc::{c_int, c_void};
 use std::ptr;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
+
+#[cfg(ossl10x)]
+use ffi::{sk_num as OPENSSL_sk_num, sk_value as OPENSSL_sk_value,
+          sk_pop_free as OPENSSL_sk_pop_free};
+#[cfg(ossl110)]
+use ffi::{OPENSSL_sk_num, OPENSSL_sk_value, OPENSSL_sk_pop_free};
 
 use {cvt, cvt_p};
+use hash::MessageDigest;
 use pkey::{PKey, PKeyRef};
 use error::ErrorStack;
+use string::OpensslString;
 use x509::X509;
 use stack::Stack;
-use nid;
+use nid::{self, Nid};
 
 foreign_type! {
     type CType = ffi::PKCS12;
@@ -51,6 +59,110 @@ impl Pkcs12Ref {
             })
         }
     }
+
+    /// Extracts every safe bag of the `Pkcs12`.
+    ///
+    /// Unlike `parse`, this returns all keys and certificates in the archive along with their
+    /// attributes, so archives holding several identities or unrelated certificates can be
+    /// inspected. The MAC is verified before any bag is decrypted.
+    pub fn safe_bags(&self, pass: &str) -> Result<Vec<SafeBag>, ErrorStack> {
+        unsafe {
+            let pass = CString::new(pass).unwrap();
+
+            try!(cvt(ffi::PKCS12_verify_mac(self.as_ptr(), pass.as_ptr(), -1)));
+            let safes = try!(cvt_p(ffi::PKCS12_unpack_authsafes(self.as_ptr())));
+            let bags = read_safes(safes, &pass);
+            OPENSSL_sk_pop_free(safes as *mut _, Some(free_pkcs7));
+            bags
+        }
+    }
+}
+
+unsafe extern "C" fn free_pkcs7(p7: *mut c_void) {
+    ffi::PKCS7_free(p7 as *mut _);
+}
+
+unsafe extern "C" fn free_safebag(bag: *mut c_void) {
+    ffi::PKCS12_SAFEBAG_free(bag as *mut _);
+}
+
+unsafe fn read_safes(safes: *mut ffi::stack_st_PKCS7,
+                     pass: &CStr)
+                     -> Result<Vec<SafeBag>, ErrorStack> {
+    let mut out = vec![];
+    for i in 0..OPENSSL_sk_num(safes as *mut _) {
+        let p7 = OPENSSL_sk_value(safes as *mut _, i) as *mut ffi::PKCS7;
+        let bags = match ffi::OBJ_obj2nid((*p7).type_) {
+            ffi::NID_pkcs7_data => ffi::PKCS12_unpack_p7data(p7),
+            ffi::NID_pkcs7_encrypted => ffi::PKCS12_unpack_p7encdata(p7, pass.as_ptr(), -1),
+            _ => continue,
+        };
+        let bags = try!(cvt_p(bags));
+
+        let mut r = Ok(());
+        for j in 0..OPENSSL_sk_num(bags as *mut _) {
+            let bag = OPENSSL_sk_value(bags as *mut _, j) as *mut ffi::PKCS12_SAFEBAG;
+            match read_bag(bag, pass) {
+                Ok(bag) => out.push(bag),
+                Err(e) => {
+                    r = Err(e);
+                    break;
+                }
+            }
+        }
+        OPENSSL_sk_pop_free(bags as *mut _, Some(free_safebag));
+        try!(r);
+    }
+    Ok(out)
+}
+
+unsafe fn read_bag(bag: *mut ffi::PKCS12_SAFEBAG, pass: &CStr) -> Result<SafeBag, ErrorStack> {
+    let bag_type = Nid::from_raw(compat::bag_nid(bag));
+
+    let friendly_name = ffi::PKCS12_get_friendlyname(bag);
+    let friendly_name = if friendly_name.is_null() {
+        None
+    } else {
+        Some(OpensslString::from_ptr(friendly_name).to_string())
+    };
+
+    let attr = compat::bag_attr(bag, ffi::NID_localKeyID);
+    let mut local_key_id = None;
+    if !attr.is_null() {
+        let len = ffi::ASN1_TYPE_get_octetstring(attr, ptr::null_mut(), 0);
+        if len >= 0 {
+            let mut buf = vec![0; len as usize];
+            ffi::ASN1_TYPE_get_octetstring(attr, buf.as_mut_ptr(), len);
+            local_key_id = Some(buf);
+        }
+    }
+
+    let mut pkey = None;
+    let mut cert = None;
+    match bag_type.as_raw() {
+        ffi::NID_keyBag => {
+            let p8 = try!(cvt_p(compat::bag_p8inf(bag) as *mut ffi::PKCS8_PRIV_KEY_INFO));
+            pkey = Some(PKey::from_ptr(try!(cvt_p(ffi::EVP_PKCS82PKEY(p8)))));
+        }
+        ffi::NID_pkcs8ShroudedKeyBag => {
+            let p8 = try!(cvt_p(ffi::PKCS12_decrypt_skey(bag, pass.as_ptr(), -1)));
+            let key = ffi::EVP_PKCS82PKEY(p8);
+            ffi::PKCS8_PRIV_KEY_INFO_free(p8);
+            pkey = Some(PKey::from_ptr(try!(cvt_p(key))));
+        }
+        ffi::NID_certBag => {
+            cert = Some(X509::from_ptr(try!(cvt_p(compat::bag_cert(bag)))));
+        }
+        _ => {}
+    }
+
+    Ok(SafeBag {
+        bag_type: bag_type,
+        friendly_name: friendly_name,
+        local_key_id: local_key_id,
+        pkey: pkey,
+        cert: cert,
+    })
 }
 
 impl Pkcs12 {
@@ -73,6 +185,7 @@ impl Pkcs12 {
             iter: ffi::PKCS12_DEFAULT_ITER,
             mac_iter: ffi::PKCS12_DEFAULT_ITER,
             ca: None,
+            key_pairs: vec![],
         }
     }
 }
@@ -83,12 +196,27 @@ pub struct ParsedPkcs12 {
     pub chain: Stack<X509>,
 }
 
+/// A safe bag extracted from a `Pkcs12`.
+pub struct SafeBag {
+    /// The type of the bag, such as `nid::CERTBAG` or `nid::PKCS8SHROUDEDKEYBAG`.
+    pub bag_type: Nid,
+    /// The bag's `friendlyName` attribute.
+    pub friendly_name: Option<String>,
+    /// The bag's `localKeyID` attribute, which links a key to its certificate.
+    pub local_key_id: Option<Vec<u8>>,
+    /// The private key of a key bag.
+    pub pkey: Option<PKey>,
+    /// The certificate of a certificate bag.
+    pub cert: Option<X509>,
+}
+
 pub struct Pkcs12Builder {
     nid_key: nid::Nid,
     nid_cert: nid::Nid,
     iter: c_int,
     mac_iter: c_int,
     ca: Option<Stack<X509>>,
+    key_pairs: Vec<(String, PKey, X509)>,
 }
 
 impl Pkcs12Builder {
@@ -126,6 +254,100 @@ impl Pkcs12Builder {
         self
     }
 
+    /// Adds a private key and its certificate to the archive under `friendly_name`.
+    ///
+    /// Key pairs are only included by `build_key_pairs`. The key and certificate are linked by
+    /// a `localKeyID` attribute holding the SHA-1 fingerprint of the certificate.
+    pub fn key_pair(&mut self, friendly_name: &str, pkey: PKey, cert: X509) -> &mut Self {
+        self.key_pairs.push((friendly_name.to_owned(), pkey, cert));
+        self
+    }
+
+    /// Builds a PKCS #12 object holding every key pair added with `key_pair` along with the
+    /// certificates provided to `ca`.
+    ///
+    /// Unlike `build`, keys and certificates are encrypted with
+    /// `nid::PBE_WITHSHA1AND3_KEY_TRIPLEDES_CBC` unless other algorithms have been configured.
+    pub fn build_key_pairs(self, password: &str) -> Result<Pkcs12, ErrorStack> {
+        unsafe {
+            let pass = CString::new(password).unwrap();
+            let mut certs = ptr::null_mut();
+            let mut keys = ptr::null_mut();
+            let mut safes = ptr::null_mut();
+
+            let r = self.build_safes(&pass, &mut certs, &mut keys, &mut safes);
+
+            if !certs.is_null() {
+                OPENSSL_sk_pop_free(certs as *mut _, Some(free_safebag));
+            }
+            if !keys.is_null() {
+                OPENSSL_sk_pop_free(keys as *mut _, Some(free_safebag));
+            }
+            if !safes.is_null() {
+                OPENSSL_sk_pop_free(safes as *mut _, Some(free_pkcs7));
+            }
+            r
+        }
+    }
+
+    unsafe fn build_safes(&self,
+                          pass: &CStr,
+                          certs: &mut *mut ffi::stack_st_PKCS12_SAFEBAG,
+                          keys: &mut *mut ffi::stack_st_PKCS12_SAFEBAG,
+                          safes: &mut *mut ffi::stack_st_PKCS7)
+                          -> Result<Pkcs12, ErrorStack> {
+        let nid_key = match self.nid_key.as_raw() {
+            ffi::NID_undef => ffi::NID_pbe_WithSHA1And3_Key_TripleDES_CBC,
+            nid => nid,
+        };
+        let nid_cert = match self.nid_cert.as_raw() {
+            ffi::NID_undef => ffi::NID_pbe_WithSHA1And3_Key_TripleDES_CBC,
+            nid => nid,
+        };
+
+        for &(ref name, ref pkey, ref cert) in &self.key_pairs {
+            let name = CString::new(&name[..]).unwrap();
+            let mut key_id = try!(cert.fingerprint(MessageDigest::sha1()));
+
+            let bag = try!(cvt_p(ffi::PKCS12_add_cert(certs, cert.as_ptr())));
+            try!(cvt(ffi::PKCS12_add_friendlyname_asc(bag, name.as_ptr(), -1)));
+            try!(cvt(ffi::PKCS12_add_localkeyid(bag, key_id.as_mut_ptr(), key_id.len() as c_int)));
+
+            let bag = try!(cvt_p(ffi::PKCS12_add_key(keys,
+                                                     pkey.as_ptr(),
+                                                     0,
+                                                     self.iter,
+                                                     nid_key,
+                                                     pass.as_ptr())));
+            try!(cvt(ffi::PKCS12_add_friendlyname_asc(bag, name.as_ptr(), -1)));
+            try!(cvt(ffi::PKCS12_add_localkeyid(bag, key_id.as_mut_ptr(), key_id.len() as c_int)));
+        }
+
+        if let Some(ref ca) = self.ca {
+            for cert in ca {
+                try!(cvt_p(ffi::PKCS12_add_cert(certs, cert.as_ptr())));
+            }
+        }
+
+        if !certs.is_null() {
+            try!(cvt(ffi::PKCS12_add_safe(safes, *certs, nid_cert, self.iter, pass.as_ptr())));
+        }
+        if !keys.is_null() {
+            // The keys are already shrouded, so the safe itself is left unencrypted.
+            try!(cvt(ffi::PKCS12_add_safe(safes, *keys, -1, 0, ptr::null())));
+        }
+
+        let pkcs12 = Pkcs12(try!(cvt_p(ffi::PKCS12_add_safes(*safes, 0))));
+        try!(cvt(ffi::PKCS12_set_mac(pkcs12.as_ptr(),
+                                     pass.as_ptr(),
+                                     -1,
+                                     ptr::null_mut(),
+                                     0,
+                                     self.mac_iter,
+                                     ptr::null())));
+        Ok(pkcs12)
+    }
+
     /// Builds the PKCS #12 object
     ///
     /// # Arguments
@@ -168,6 +390,50 @@ impl Pkcs12Builder {
     }
 }
 
+#[cfg(ossl110)]
+mod compat {
+    use ffi::{self, ASN1_TYPE, PKCS12_SAFEBAG, PKCS8_PRIV_KEY_INFO, X509};
+    use libc::c_int;
+
+    pub unsafe fn bag_nid(bag: *mut PKCS12_SAFEBAG) -> c_int {
+        ffi::PKCS12_SAFEBAG_get_nid(bag)
+    }
+
+    pub unsafe fn bag_attr(bag: *mut PKCS12_SAFEBAG, nid: c_int) -> *const ASN1_TYPE {
+        ffi::PKCS12_SAFEBAG_get0_attr(bag, nid)
+    }
+
+    pub unsafe fn bag_p8inf(bag: *mut PKCS12_SAFEBAG) -> *const PKCS8_PRIV_KEY_INFO {
+        ffi::PKCS12_SAFEBAG_get0_p8inf(bag)
+    }
+
+    pub unsafe fn bag_cert(bag: *mut PKCS12_SAFEBAG) -> *mut X509 {
+        ffi::PKCS12_SAFEBAG_get1_cert(bag)
+    }
+}
+
+#[cfg(ossl10x)]
+mod compat {
+    use ffi::{self, ASN1_TYPE, PKCS12_SAFEBAG, PKCS8_PRIV_KEY_INFO, X509};
+    use libc::c_int;
+
+    pub unsafe fn bag_nid(bag: *mut PKCS12_SAFEBAG) -> c_int {
+        ffi::OBJ_obj2nid((*bag).type_)
+    }
+
+    pub unsafe fn bag_attr(bag: *mut PKCS12_SAFEBAG, nid: c_int) -> *const ASN1_TYPE {
+        ffi::PKCS12_get_attr(bag, nid)
+    }
+
+    pub unsafe fn bag_p8inf(bag: *mut PKCS12_SAFEBAG) -> *const PKCS8_PRIV_KEY_INFO {
+        (*bag).value as *const _
+    }
+
+    pub unsafe fn bag_cert(bag: *mut PKCS12_SAFEBAG) -> *mut X509 {
+        ffi::PKCS12_certbag2x509(bag)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use hash::MessageDigest;
@@ -218,4 +484,62 @@ mod test {
         assert_eq!(parsed.cert.fingerprint(MessageDigest::sha1()).unwrap(), cert.fingerprint(MessageDigest::sha1()).unwrap());
         assert!(parsed.pkey.public_eq(&pkey));
     }
+
+    fn identity(name: &str) -> (PKey, X509) {
+        let pkey = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let cert = X509Generator::new()
+            .set_valid_period(365)
+            .add_name("CN".to_owned(), name.to_owned())
+            .set_sign_hash(MessageDigest::sha256())
+            .sign(&pkey)
+            .unwrap();
+        (pkey, cert)
+    }
+
+    #[test]
+    fn safe_bags() {
+        let (pkey, cert) = identity("ns.example.com");
+        let pkcs12 = Pkcs12::builder().build("mypass", "ns", &pkey, &cert).unwrap();
+        let bags = pkcs12.safe_bags("mypass").unwrap();
+        assert_eq!(bags.len(), 2);
+
+        let key = bags.iter().find(|b| b.pkey.is_some()).unwrap();
+        assert!(key.pkey.as_ref().unwrap().public_eq(&pkey));
+        let cert_bag = bags.iter().find(|b| b.cert.is_some()).unwrap();
+        assert_eq!(cert_bag.bag_type, ::nid::CERTBAG);
+        assert_eq!(cert_bag.friendly_name, Some("ns".to_owned()));
+        assert_eq!(cert_bag.local_key_id, key.local_key_id);
+        assert_eq!(cert_bag.cert.as_ref().unwrap().to_der().unwrap(), cert.to_der().unwrap());
+
+        assert!(pkcs12.safe_bags("wrong").is_err());
+    }
+
+    #[test]
+    fn create_key_pairs() {
+        let (key1, cert1) = identity("one.example.com");
+        let (key2, cert2) = identity("two.example.com");
+        let fingerprint1 = cert1.fingerprint(MessageDigest::sha1()).unwrap();
+        let fingerprint2 = cert2.fingerprint(MessageDigest::sha1()).unwrap();
+
+        let mut builder = Pkcs12::builder();
+        builder.key_pair("one", key1, cert1).key_pair("two", key2, cert2);
+        let pkcs12 = builder.build_key_pairs("mypass").unwrap();
+        let pkcs12 = Pkcs12::from_der(&pkcs12.to_der().unwrap()).unwrap();
+
+        let bags = pkcs12.safe_bags("mypass").unwrap();
+        assert_eq!(bags.len(), 4);
+        assert_eq!(bags.iter().filter(|b| b.bag_type == ::nid::CERTBAG).count(), 2);
+        assert_eq!(bags.iter()
+                       .filter(|b| b.bag_type == ::nid::PKCS8SHROUDEDKEYBAG)
+                       .count(),
+                   2);
+
+        let key = bags.iter().find(|b| b.pkey.is_some() && b.friendly_name == Some("two".to_owned()))
+            .unwrap();
+        assert_eq!(key.local_key_id.as_ref(), Some(&fingerprint2));
+        let cert = bags.iter().find(|b| b.local_key_id.as_ref() == Some(&fingerprint2) && b.cert.is_some())
+            .unwrap();
+        assert!(cert.cert.as_ref().unwrap().public_key_matches(key.pkey.as_ref().unwrap()).unwrap());
+        assert!(bags.iter().any(|b| b.local_key_id.as_ref() == Some(&fingerprint1)));
+    }
 }
@@ -1,11 +1,16 @@
-use libc::c_int;
+use libc::{c_char, c_int, c_long, c_void};
+use std::mem;
 use std::ptr;
+use std::slice;
 use ffi;
+use foreign_types::ForeignTypeRef;
 
-use cvt;
+use {cvt, cvt_p};
+use asn1::Asn1StringRef;
 use hash::MessageDigest;
 use symm::Cipher;
 use error::ErrorStack;
+use util::crypto_free;
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub struct KeyIvPair {
@@ -96,11 +101,195 @@ pub fn pbkdf2_hmac(pass: &[u8],
     }
 }
 
+/// Derives a key from a password and salt using the scrypt algorithm.
+///
+/// `n` is the CPU/memory cost parameter and must be a power of two greater than 1, `r` is the
+/// block size and `p` the parallelization parameter. The derivation fails if it would need more
+/// than `maxmem` bytes of memory; a `maxmem` of 0 selects OpenSSL's default limit of 32 MiB.
+///
+/// Requires the `v110` feature and OpenSSL 1.1.0.
+#[cfg(all(feature = "v110", ossl110))]
+pub fn scrypt(pass: &[u8],
+              salt: &[u8],
+              n: u64,
+              r: u64,
+              p: u64,
+              maxmem: u64,
+              key: &mut [u8])
+              -> Result<(), ErrorStack> {
+    unsafe {
+        ffi::init();
+        cvt(ffi::EVP_PBE_scrypt(pass.as_ptr() as *const _,
+                                pass.len(),
+                                salt.as_ptr(),
+                                salt.len(),
+                                n,
+                                r,
+                                p,
+                                maxmem,
+                                key.as_mut_ptr(),
+                                key.len()))
+            .map(|_| ())
+    }
+}
+
+/// Encrypts `data` with a password using the PBES2 scheme from PKCS#5 v2.0.
+///
+/// The key and IV are derived from `pass` with PBKDF2, using HMAC-SHA256, `iter` iterations
+/// and a random salt. The result is a DER-encoded `EncryptedPrivateKeyInfo`-style structure
+/// containing the algorithm parameters followed by the ciphertext, which can be passed to
+/// `pbes2_decrypt`.
+pub fn pbes2_encrypt(cipher: Cipher,
+                     pass: &[u8],
+                     iter: usize,
+                     data: &[u8])
+                     -> Result<Vec<u8>, ErrorStack> {
+    unsafe {
+        assert!(pass.len() <= c_int::max_value() as usize);
+        assert!(iter <= c_int::max_value() as usize);
+        assert!(data.len() <= c_int::max_value() as usize);
+
+        ffi::init();
+        let algor = Algor(try!(cvt_p(ffi::PKCS5_pbe2_set_iv(cipher.as_ptr(),
+                                                            iter as c_int,
+                                                            ptr::null_mut(),
+                                                            0,
+                                                            ptr::null_mut(),
+                                                            ffi::NID_hmacWithSHA256))));
+        let ciphertext = try!(pbe_crypt(algor.0, pass, data, 1));
+
+        let sig = Sig(try!(cvt_p(ffi::X509_SIG_new())));
+        let mut palg = ptr::null_mut();
+        let mut pdigest = ptr::null_mut();
+        X509_SIG_getm(sig.0, &mut palg, &mut pdigest);
+        mem::swap(&mut *palg, &mut *algor.0);
+        try!(cvt(ffi::ASN1_STRING_set(pdigest,
+                                      ciphertext.as_ptr() as *const c_void,
+                                      ciphertext.len() as c_int)));
+
+        let len = try!(cvt(ffi::i2d_X509_SIG(sig.0, ptr::null_mut())));
+        let mut buf = vec![0; len as usize];
+        try!(cvt(ffi::i2d_X509_SIG(sig.0, &mut buf.as_mut_ptr())));
+        Ok(buf)
+    }
+}
+
+/// Decrypts data encrypted by `pbes2_encrypt`.
+///
+/// The cipher and key derivation parameters are read from `der`. An error is returned if the
+/// password is incorrect, although an incorrect password may occasionally yield garbage instead.
+/// Data encrypted with other password-based schemes, such as those of PKCS#12, is rejected.
+pub fn pbes2_decrypt(pass: &[u8], der: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    unsafe {
+        assert!(pass.len() <= c_int::max_value() as usize);
+        assert!(der.len() <= c_long::max_value() as usize);
+
+        ffi::init();
+        let mut p = der.as_ptr();
+        let sig = Sig(try!(cvt_p(ffi::d2i_X509_SIG(ptr::null_mut(), &mut p, der.len() as c_long))));
+        let mut palg = ptr::null();
+        let mut pdigest = ptr::null();
+        X509_SIG_get0(sig.0, &mut palg, &mut pdigest);
+        if ffi::OBJ_obj2nid((*palg).algorithm) != ffi::NID_pbes2 {
+            ::put_error(::R_UNSUPPORTED_ALGORITHM, "the data was not encrypted with PBES2");
+            return Err(ErrorStack::get());
+        }
+        let ciphertext = Asn1StringRef::from_ptr(pdigest as *mut _).as_slice();
+        pbe_crypt(palg as *mut _, pass, ciphertext, 0)
+    }
+}
+
+unsafe fn pbe_crypt(algor: *mut ffi::X509_ALGOR,
+                    pass: &[u8],
+                    data: &[u8],
+                    en_de: c_int)
+                    -> Result<Vec<u8>, ErrorStack> {
+    let mut out = ptr::null_mut();
+    let mut outlen = 0;
+    try!(cvt_p(ffi::PKCS12_pbe_crypt(algor,
+                                     pass.as_ptr() as *const c_char,
+                                     pass.len() as c_int,
+                                     data.as_ptr() as *mut _,
+                                     data.len() as c_int,
+                                     &mut out,
+                                     &mut outlen,
+                                     en_de)));
+    let buf = slice::from_raw_parts(out, outlen as usize).to_vec();
+    crypto_free(out as *mut c_void);
+    Ok(buf)
+}
+
+struct Algor(*mut ffi::X509_ALGOR);
+
+impl Drop for Algor {
+    fn drop(&mut self) {
+        unsafe { ffi::X509_ALGOR_free(self.0) }
+    }
+}
+
+struct Sig(*mut ffi::X509_SIG);
+
+impl Drop for Sig {
+    fn drop(&mut self) {
+        unsafe { ffi::X509_SIG_free(self.0) }
+    }
+}
+
+#[cfg(ossl110)]
+use ffi::{X509_SIG_get0, X509_SIG_getm};
+
+#[cfg(ossl10x)]
+#[allow(bad_style)]
+unsafe fn X509_SIG_get0(sig: *const ffi::X509_SIG,
+                        palg: *mut *const ffi::X509_ALGOR,
+                        pdigest: *mut *const ffi::ASN1_STRING) {
+    *palg = (*sig).algor;
+    *pdigest = (*sig).digest;
+}
+
+#[cfg(ossl10x)]
+#[allow(bad_style)]
+unsafe fn X509_SIG_getm(sig: *mut ffi::X509_SIG,
+                        palg: *mut *mut ffi::X509_ALGOR,
+                        pdigest: *mut *mut ffi::ASN1_STRING) {
+    *palg = (*sig).algor;
+    *pdigest = (*sig).digest;
+}
+
 #[cfg(test)]
 mod tests {
+    use ffi;
     use hash::MessageDigest;
     use symm::Cipher;
 
+    #[test]
+    fn pbes2_round_trip() {
+        let data = b"a secret which is longer than one block";
+        let der = super::pbes2_encrypt(Cipher::aes_256_cbc(), b"password", 2048, data).unwrap();
+        assert_eq!(super::pbes2_decrypt(b"password", &der).unwrap(), &data[..]);
+
+        // the salt and IV are random
+        let other = super::pbes2_encrypt(Cipher::aes_256_cbc(), b"password", 2048, data).unwrap();
+        assert!(der != other);
+
+        let wrong = super::pbes2_decrypt(b"passw0rd", &der);
+        assert!(wrong.map(|d| d != &data[..]).unwrap_or(true));
+        assert!(super::pbes2_decrypt(b"password", &der[..der.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn pbes2_decrypt_other_algorithm() {
+        let mut der = super::pbes2_encrypt(Cipher::aes_256_cbc(), b"password", 2048, b"foo")
+            .unwrap();
+        // change the outer OID from id-PBES2 to id-PBKDF2
+        let oid = [0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0d];
+        let pos = der.windows(oid.len()).position(|w| w == oid).unwrap();
+        der[pos + oid.len() - 1] = 0x0c;
+
+        let err = super::pbes2_decrypt(b"password", &der).unwrap_err();
+        assert_eq!(ffi::ERR_GET_REASON(err.errors()[0].code()), ::R_UNSUPPORTED_ALGORITHM);
+    }
+
     // Test vectors from
     // https://git.lysator.liu.se/nettle/nettle/blob/nettle_3.1.1_release_20150424/testsuite/pbkdf2-test.c
     #[test]
@@ -172,6 +361,27 @@ mod tests {
                      0x2e_u8, 0x52_u8, 0x4c_u8, 0x7d_u8, 0x51_u8, 0x15_u8, 0x4b_u8, 0x1a_u8,
                      0x85_u8, 0x34_u8, 0x57_u8, 0x5b_u8, 0xd0_u8, 0x2d_u8, 0xee_u8, 0x39_u8][..]);
     }
+    // Test vectors from RFC 7914
+    #[test]
+    #[cfg(all(feature = "v110", ossl110))]
+    fn scrypt() {
+        use hex::ToHex;
+
+        let mut buf = [0; 64];
+
+        super::scrypt(b"", b"", 16, 1, 1, 0, &mut buf).unwrap();
+        assert_eq!(buf.to_hex(),
+                   "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948\
+                    f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906");
+
+        super::scrypt(b"password", b"NaCl", 1024, 8, 16, 0, &mut buf).unwrap();
+        assert_eq!(buf.to_hex(),
+                   "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3\
+                    886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640");
+
+        assert!(super::scrypt(b"password", b"NaCl", 1024, 8, 16, 1024, &mut buf).is_err());
+    }
+
     #[test]
     fn bytes_to_key() {
         let salt = [16_u8, 34_u8, 19_u8, 23_u8, 141_u8, 4_u8, 207_u8, 221_u8];
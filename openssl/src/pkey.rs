@@ -1,17 +1,45 @@
-use libc::{c_void, c_char, c_int};
+use libc::{c_void, c_char, c_int, c_long};
+use std::cmp;
 use std::ptr;
 use std::mem;
 use ffi;
-use foreign_types::{Opaque, ForeignType, ForeignTypeRef};
+use foreign_types::{ForeignType, ForeignTypeRef};
 
 use {cvt, cvt_p};
 use bio::MemBioSlice;
 use dh::Dh;
 use dsa::Dsa;
-use ec::EcKey;
-use rsa::{Rsa, Padding};
+use ec::{EcGroup, EcKey, NAMED_CURVE};
+use rsa::{Rsa, Padding, PssSaltLen};
 use error::ErrorStack;
-use util::{CallbackState, invoke_passwd_cb_old};
+use nid::{self, Nid};
+use symm::Cipher;
+use util::{CallbackState, invoke_passwd_cb_old, invoke_pkey_gen_cb};
+
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+use hash::MessageDigest;
+
+/// An identifier of a kind of key.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Id(c_int);
+
+impl Id {
+    pub fn from_raw(raw: c_int) -> Id {
+        Id(raw)
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+}
+
+pub const ID_RSA: Id = Id(ffi::EVP_PKEY_RSA);
+pub const ID_HMAC: Id = Id(ffi::EVP_PKEY_HMAC);
+pub const ID_DSA: Id = Id(ffi::EVP_PKEY_DSA);
+pub const ID_DH: Id = Id(ffi::EVP_PKEY_DH);
+pub const ID_EC: Id = Id(ffi::EVP_PKEY_EC);
+pub const ID_X25519: Id = Id(ffi::EVP_PKEY_X25519);
+pub const ID_ED25519: Id = Id(ffi::EVP_PKEY_ED25519);
 
 foreign_type! {
     type CType = ffi::EVP_PKEY;
@@ -59,6 +87,7 @@ impl PKeyRef {
 
     private_key_to_der!(ffi::i2d_PrivateKey);
     public_key_to_der!(ffi::i2d_PUBKEY);
+    public_key_to_der_into!(ffi::i2d_PUBKEY);
 
     /// Returns the size of the key.
     ///
@@ -68,10 +97,50 @@ impl PKeyRef {
         unsafe { ffi::EVP_PKEY_bits(self.as_ptr()) as u32 }
     }
 
+    /// Returns the kind of this key.
+    pub fn id(&self) -> Id {
+        unsafe { Id(ffi::EVP_PKEY_id(self.as_ptr())) }
+    }
+
+    /// Returns the maximum size in bytes of a signature or ciphertext produced with this key.
+    pub fn size(&self) -> usize {
+        unsafe { ffi::EVP_PKEY_size(self.as_ptr()) as usize }
+    }
+
     /// Compares the public component of this key with another.
     pub fn public_eq(&self, other: &PKeyRef) -> bool {
         unsafe { ffi::EVP_PKEY_cmp(self.as_ptr(), other.as_ptr()) == 1 }
     }
+
+    /// Returns the raw private key bytes of an X25519 or Ed25519 key.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn raw_private_key(&self) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            try!(cvt(ffi::EVP_PKEY_get_raw_private_key(self.as_ptr(), ptr::null_mut(), &mut len)));
+            let mut buf = vec![0; len];
+            try!(cvt(ffi::EVP_PKEY_get_raw_private_key(self.as_ptr(), buf.as_mut_ptr(), &mut len)));
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
+
+    /// Returns the raw public key bytes of an X25519 or Ed25519 key.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn raw_public_key(&self) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            try!(cvt(ffi::EVP_PKEY_get_raw_public_key(self.as_ptr(), ptr::null_mut(), &mut len)));
+            let mut buf = vec![0; len];
+            try!(cvt(ffi::EVP_PKEY_get_raw_public_key(self.as_ptr(), buf.as_mut_ptr(), &mut len)));
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
 }
 
 unsafe impl Send for PKey {}
@@ -137,9 +206,102 @@ impl PKey {
         }
     }
 
+    /// Generates a new Ed25519 keypair.
+    ///
+    /// Ed25519 keys sign messages directly rather than a digest of them; create a `Signer` for
+    /// one with `Signer::new_without_digest` and use `Signer::sign_oneshot`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn generate_ed25519() -> Result<PKey, ErrorStack> {
+        PKey::generate_id(::nid::ED25519)
+    }
+
+    /// Generates a new X25519 keypair.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn generate_x25519() -> Result<PKey, ErrorStack> {
+        PKey::generate_id(::nid::X25519)
+    }
+
+    fn generate_id(nid: Nid) -> Result<PKey, ErrorStack> {
+        let mut ctx = try!(PKeyCtx::new_id(nid));
+        try!(ctx.keygen_init());
+        ctx.keygen()
+    }
+
+    /// Creates a new `PKey` from the raw bytes of an X25519 or Ed25519 private key.
+    ///
+    /// `nid` is either `nid::X25519` or `nid::ED25519`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn private_key_from_raw_bytes(bytes: &[u8], nid: Nid) -> Result<PKey, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            cvt_p(ffi::EVP_PKEY_new_raw_private_key(nid.as_raw(),
+                                                    ptr::null_mut(),
+                                                    bytes.as_ptr(),
+                                                    bytes.len()))
+                .map(PKey)
+        }
+    }
+
+    /// Creates a new `PKey` from the raw bytes of an X25519 or Ed25519 public key.
+    ///
+    /// `nid` is either `nid::X25519` or `nid::ED25519`. An X25519 key created this way from a
+    /// value received from a peer can be passed directly to `Deriver::set_peer`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn public_key_from_raw_bytes(bytes: &[u8], nid: Nid) -> Result<PKey, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            cvt_p(ffi::EVP_PKEY_new_raw_public_key(nid.as_raw(),
+                                                   ptr::null_mut(),
+                                                   bytes.as_ptr(),
+                                                   bytes.len()))
+                .map(PKey)
+        }
+    }
+
     private_key_from_pem!(PKey, ffi::PEM_read_bio_PrivateKey);
     public_key_from_pem!(PKey, ffi::PEM_read_bio_PUBKEY);
 
+    /// Deserializes a private key from DER-formatted data.
+    ///
+    /// The key type is detected automatically. Both unencrypted PKCS#8 structures and the
+    /// traditional algorithm-specific formats, such as PKCS#1 for RSA keys, are accepted.
+    pub fn private_key_from_der(der: &[u8]) -> Result<PKey, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let len = cmp::min(der.len(), c_long::max_value() as usize) as c_long;
+            cvt_p(ffi::d2i_AutoPrivateKey(ptr::null_mut(), &mut der.as_ptr(), len)).map(PKey)
+        }
+    }
+
+    /// Deserializes a public key from DER-formatted data.
+    ///
+    /// The key type is detected automatically. Both SubjectPublicKeyInfo structures and PKCS#1
+    /// RSA public keys are accepted.
+    pub fn public_key_from_der(der: &[u8]) -> Result<PKey, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let len = cmp::min(der.len(), c_long::max_value() as usize) as c_long;
+            let pkey = ffi::d2i_PUBKEY(ptr::null_mut(), &mut der.as_ptr(), len);
+            if !pkey.is_null() {
+                return Ok(PKey(pkey));
+            }
+        }
+
+        ErrorStack::get(); // discard error stack
+        let rsa = try!(Rsa::public_key_from_der_pkcs1(der));
+        PKey::from_rsa(rsa)
+    }
+
     #[deprecated(since = "0.9.2", note = "use private_key_from_pem_callback")]
     pub fn private_key_from_pem_cb<F>(buf: &[u8], pass_cb: F) -> Result<PKey, ErrorStack>
         where F: FnOnce(&mut [c_char]) -> usize
@@ -157,9 +319,218 @@ impl PKey {
     }
 }
 
-pub struct PKeyCtxRef(Opaque);
+/// The kinds of key which can be created by `generate_keypair`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyType {
+    /// A 2048 bit RSA key.
+    Rsa2048,
+    /// A 3072 bit RSA key.
+    Rsa3072,
+    /// A 4096 bit RSA key.
+    Rsa4096,
+    /// An elliptic curve key on the NIST P-256 curve.
+    P256,
+    /// An elliptic curve key on the NIST P-384 curve.
+    P384,
+    /// An Ed25519 key.
+    ///
+    /// Generating one fails with OpenSSL versions older than 1.1.1, which don't support Ed25519.
+    Ed25519,
+}
+
+/// How the private key PEM produced by `generate_keypair` is protected.
+#[derive(Copy, Clone)]
+pub enum Encryption<'a> {
+    /// The private key is stored unencrypted.
+    None,
+    /// The private key is encrypted with the cipher and a key derived from the passphrase.
+    Passphrase(Cipher, &'a [u8]),
+}
+
+/// A keypair created by `generate_keypair`.
+pub struct GeneratedKeyPair {
+    /// The key itself.
+    pub pkey: PKey,
+    /// The PKCS#8 PEM encoding of the private key.
+    pub private_key_pem: Vec<u8>,
+    /// The SubjectPublicKeyInfo PEM encoding of the public key.
+    pub public_key_pem: Vec<u8>,
+}
+
+/// Generates a new keypair and serializes it to PEM in one step.
+///
+/// Elliptic curve keys are encoded with a named curve so they can be read by other
+/// implementations.
+pub fn generate_keypair(key_type: KeyType,
+                        encryption: Encryption)
+                        -> Result<GeneratedKeyPair, ErrorStack> {
+    let pkey = match key_type {
+        KeyType::Rsa2048 => try!(PKey::from_rsa(try!(Rsa::generate(2048)))),
+        KeyType::Rsa3072 => try!(PKey::from_rsa(try!(Rsa::generate(3072)))),
+        KeyType::Rsa4096 => try!(PKey::from_rsa(try!(Rsa::generate(4096)))),
+        KeyType::P256 => try!(generate_ec(nid::X9_62_PRIME256V1)),
+        KeyType::P384 => try!(generate_ec(nid::SECP384R1)),
+        KeyType::Ed25519 => try!(PKey::generate_id(nid::ED25519)),
+    };
+
+    let private_key_pem = match encryption {
+        Encryption::None => try!(pkey.private_key_to_pem()),
+        Encryption::Passphrase(cipher, passphrase) => {
+            try!(pkey.private_key_to_pem_passphrase(cipher, passphrase))
+        }
+    };
+    let public_key_pem = try!(pkey.public_key_to_pem());
+
+    Ok(GeneratedKeyPair {
+        pkey: pkey,
+        private_key_pem: private_key_pem,
+        public_key_pem: public_key_pem,
+    })
+}
+
+fn generate_ec(nid: Nid) -> Result<PKey, ErrorStack> {
+    let mut group = try!(EcGroup::from_curve_name(nid));
+    group.set_asn1_flag(NAMED_CURVE);
+    PKey::from_ec_key(try!(EcKey::generate(&group)))
+}
+
+foreign_type! {
+    type CType = ffi::EVP_PKEY_CTX;
+    fn drop = ffi::EVP_PKEY_CTX_free;
+
+    pub struct PKeyCtx;
+    pub struct PKeyCtxRef;
+}
+
+impl PKeyCtx {
+    /// Creates a new context for operations using the algorithm and parameters of `pkey`.
+    ///
+    /// A context created from a parameter `PKey`, such as one returned by `paramgen`, can be used
+    /// to generate any number of keypairs sharing those parameters.
+    pub fn new(pkey: &PKeyRef) -> Result<PKeyCtx, ErrorStack> {
+        unsafe {
+            cvt_p(ffi::EVP_PKEY_CTX_new(pkey.as_ptr(), ptr::null_mut())).map(PKeyCtx)
+        }
+    }
+
+    /// Creates a new context for operations using the algorithm identified by `nid`.
+    ///
+    /// For parameter generation, this is one of `nid::DSA`, `nid::DHKEYAGREEMENT`, or
+    /// `nid::X9_62_ID_ECPUBLICKEY`.
+    pub fn new_id(nid: Nid) -> Result<PKeyCtx, ErrorStack> {
+        ffi::init();
+
+        unsafe {
+            cvt_p(ffi::EVP_PKEY_CTX_new_id(nid.as_raw(), ptr::null_mut())).map(PKeyCtx)
+        }
+    }
+}
 
 impl PKeyCtxRef {
+    /// Prepares the context for parameter generation.
+    pub fn paramgen_init(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::EVP_PKEY_paramgen_init(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Generates a new set of parameters.
+    ///
+    /// `paramgen_init` must have been called first.
+    pub fn paramgen(&mut self) -> Result<PKey, ErrorStack> {
+        unsafe {
+            let mut pkey = ptr::null_mut();
+            try!(cvt(ffi::EVP_PKEY_paramgen(self.as_ptr(), &mut pkey)));
+            Ok(PKey::from_ptr(pkey))
+        }
+    }
+
+    /// Like `paramgen`, but reports progress to a callback.
+    ///
+    /// The callback is passed the two progress values reported by OpenSSL, which for DSA and DH
+    /// have the same meaning as those passed to `BN_GENCB` callbacks. Generation is aborted if
+    /// the callback returns `false`.
+    pub fn paramgen_callback<F>(&mut self, callback: F) -> Result<PKey, ErrorStack>
+        where F: FnMut(i32, i32) -> bool
+    {
+        self.generate_with_callback(callback, ffi::EVP_PKEY_paramgen)
+    }
+
+    /// Prepares the context for key generation.
+    pub fn keygen_init(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::EVP_PKEY_keygen_init(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Generates a new keypair.
+    ///
+    /// `keygen_init` must have been called first.
+    pub fn keygen(&mut self) -> Result<PKey, ErrorStack> {
+        unsafe {
+            let mut pkey = ptr::null_mut();
+            try!(cvt(ffi::EVP_PKEY_keygen(self.as_ptr(), &mut pkey)));
+            Ok(PKey::from_ptr(pkey))
+        }
+    }
+
+    /// Like `keygen`, but reports progress to a callback.
+    ///
+    /// See `paramgen_callback` for the meaning of the callback's arguments and return value.
+    pub fn keygen_callback<F>(&mut self, callback: F) -> Result<PKey, ErrorStack>
+        where F: FnMut(i32, i32) -> bool
+    {
+        self.generate_with_callback(callback, ffi::EVP_PKEY_keygen)
+    }
+
+    fn generate_with_callback<F>(&mut self,
+                                 callback: F,
+                                 generate: unsafe extern "C" fn(*mut ffi::EVP_PKEY_CTX,
+                                                                *mut *mut ffi::EVP_PKEY)
+                                                                -> c_int)
+                                 -> Result<PKey, ErrorStack>
+        where F: FnMut(i32, i32) -> bool
+    {
+        let mut cb = CallbackState::new(callback);
+        unsafe {
+            ffi::EVP_PKEY_CTX_set_app_data(self.as_ptr(), &mut cb as *mut _ as *mut c_void);
+            ffi::EVP_PKEY_CTX_set_cb(self.as_ptr(), Some(invoke_pkey_gen_cb::<F>));
+            let mut pkey = ptr::null_mut();
+            let r = generate(self.as_ptr(), &mut pkey);
+            ffi::EVP_PKEY_CTX_set_cb(self.as_ptr(), None);
+            ffi::EVP_PKEY_CTX_set_app_data(self.as_ptr(), ptr::null_mut());
+            try!(cvt(r));
+            Ok(PKey::from_ptr(pkey))
+        }
+    }
+
+    /// Sets the size in bits of the prime `p` of generated DSA parameters.
+    pub fn set_dsa_paramgen_bits(&mut self, bits: u32) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EVP_PKEY_CTX_set_dsa_paramgen_bits(self.as_ptr(), bits as c_int)).map(|_| ())
+        }
+    }
+
+    /// Sets the size in bits of the prime of generated DH parameters.
+    pub fn set_dh_paramgen_prime_len(&mut self, bits: u32) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EVP_PKEY_CTX_set_dh_paramgen_prime_len(self.as_ptr(), bits as c_int))
+                .map(|_| ())
+        }
+    }
+
+    /// Sets the generator of generated DH parameters.
+    pub fn set_dh_paramgen_generator(&mut self, generator: u32) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EVP_PKEY_CTX_set_dh_paramgen_generator(self.as_ptr(), generator as c_int))
+                .map(|_| ())
+        }
+    }
+
+    /// Sets the named curve of generated elliptic curve parameters.
+    pub fn set_ec_paramgen_curve_nid(&mut self, nid: Nid) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EVP_PKEY_CTX_set_ec_paramgen_curve_nid(self.as_ptr(), nid.as_raw()))
+                .map(|_| ())
+        }
+    }
+
     pub fn set_rsa_padding(&mut self, pad: Padding) -> Result<(), ErrorStack> {
         unsafe {
             try!(cvt(ffi::EVP_PKEY_CTX_set_rsa_padding(self.as_ptr(), pad.as_raw())));
@@ -174,10 +545,127 @@ impl PKeyCtxRef {
         };
         Ok(Padding::from_raw(pad))
     }
-}
 
-impl ForeignTypeRef for PKeyCtxRef {
-    type CType = ffi::EVP_PKEY_CTX;
+    /// Sets the salt length used for RSA PSS padding.
+    ///
+    /// The padding must be set to `rsa::PKCS1_PSS_PADDING` first.
+    pub fn set_rsa_pss_saltlen(&mut self, len: PssSaltLen) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EVP_PKEY_CTX_set_rsa_pss_saltlen(self.as_ptr(), len.as_raw())).map(|_| ())
+        }
+    }
+
+    /// Sets the digest used by the MGF1 mask generation function for RSA OAEP and PSS padding.
+    ///
+    /// Defaults to the OAEP or signature digest.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_rsa_mgf1_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EVP_PKEY_CTX_set_rsa_mgf1_md(self.as_ptr(), md.as_ptr())).map(|_| ())
+        }
+    }
+
+    /// Sets the digest used for RSA OAEP padding.
+    ///
+    /// Defaults to SHA-1.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_rsa_oaep_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::EVP_PKEY_CTX_set_rsa_oaep_md(self.as_ptr(), md.as_ptr())).map(|_| ())
+        }
+    }
+
+    /// Sets the label used for RSA OAEP padding.
+    ///
+    /// Defaults to an empty label.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_rsa_oaep_label(&mut self, label: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            assert!(label.len() <= c_int::max_value() as usize);
+            if label.is_empty() {
+                // CRYPTO_malloc may return null for a zero length allocation
+                return cvt(ffi::EVP_PKEY_CTX_set0_rsa_oaep_label(self.as_ptr(),
+                                                                 ptr::null_mut(),
+                                                                 0))
+                    .map(|_| ());
+            }
+            // OpenSSL takes ownership of the label, so it must be allocated with OPENSSL_malloc.
+            let p = try!(cvt_p(ffi::CRYPTO_malloc(label.len() as _,
+                                                  concat!(file!(), "\0").as_ptr() as *const _,
+                                                  line!() as c_int)));
+            ptr::copy_nonoverlapping(label.as_ptr(), p as *mut u8, label.len());
+
+            let r = cvt(ffi::EVP_PKEY_CTX_set0_rsa_oaep_label(self.as_ptr(),
+                                                              p,
+                                                              label.len() as c_int));
+            if r.is_err() {
+                ::util::crypto_free(p);
+            }
+            r.map(|_| ())
+        }
+    }
+
+    /// Prepares the context for public key encryption.
+    pub fn encrypt_init(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::EVP_PKEY_encrypt_init(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Encrypts `from` with the context's public key.
+    ///
+    /// `encrypt_init` must have been called first. Padding options such as `set_rsa_padding` must
+    /// be set after initialization.
+    pub fn encrypt(&mut self, from: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            try!(cvt(ffi::EVP_PKEY_encrypt(self.as_ptr(),
+                                           ptr::null_mut(),
+                                           &mut len,
+                                           from.as_ptr(),
+                                           from.len())));
+            let mut buf = vec![0; len];
+            try!(cvt(ffi::EVP_PKEY_encrypt(self.as_ptr(),
+                                           buf.as_mut_ptr(),
+                                           &mut len,
+                                           from.as_ptr(),
+                                           from.len())));
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
+
+    /// Prepares the context for private key decryption.
+    pub fn decrypt_init(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::EVP_PKEY_decrypt_init(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Decrypts `from` with the context's private key.
+    ///
+    /// `decrypt_init` must have been called first, and the padding options must match those used
+    /// for encryption.
+    pub fn decrypt(&mut self, from: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            try!(cvt(ffi::EVP_PKEY_decrypt(self.as_ptr(),
+                                           ptr::null_mut(),
+                                           &mut len,
+                                           from.as_ptr(),
+                                           from.len())));
+            let mut buf = vec![0; len];
+            try!(cvt(ffi::EVP_PKEY_decrypt(self.as_ptr(),
+                                           buf.as_mut_ptr(),
+                                           &mut len,
+                                           from.as_ptr(),
+                                           from.len())));
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -185,9 +673,10 @@ mod tests {
     use symm::Cipher;
     use dh::Dh;
     use dsa::Dsa;
-    use ec::EcKey;
-    use rsa::Rsa;
+    use ec::{EcGroup, EcKey};
+    use rsa::{self, Rsa};
     use nid;
+    use x509::X509;
 
     use super::*;
 
@@ -200,6 +689,22 @@ mod tests {
         assert!(PKey::private_key_from_pem_passphrase(&pem, b"fizzbuzz").is_err());
     }
 
+    #[test]
+    fn test_to_password_callback() {
+        let pkey = PKey::from_rsa(Rsa::generate(1024).unwrap()).unwrap();
+        let pem = pkey.private_key_to_pem_callback(Cipher::aes_256_cbc(), |password| {
+                password[..6].copy_from_slice(b"foobar");
+                Ok(6)
+            })
+            .unwrap();
+        let key = PKey::private_key_from_pem_callback(&pem, |password| {
+                password[..6].copy_from_slice(b"foobar");
+                Ok(6)
+            })
+            .unwrap();
+        assert!(key.public_eq(&pkey));
+    }
+
     #[test]
     fn test_private_key_from_pem() {
         let key = include_bytes!("../test/key.pem");
@@ -212,6 +717,39 @@ mod tests {
         PKey::public_key_from_pem(key).unwrap();
     }
 
+    #[test]
+    fn test_private_key_from_der() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let pkey = PKey::private_key_from_der(&rsa.private_key_to_der().unwrap()).unwrap();
+        assert!(pkey.rsa().is_ok());
+
+        let pkcs8 = PKey::private_key_from_pem(include_bytes!("../test/key.pem")).unwrap();
+        let der = pkcs8.private_key_to_der().unwrap();
+        assert!(PKey::private_key_from_der(&der).unwrap().rsa().is_ok());
+
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let ec = EcKey::generate(&group).unwrap();
+        let pkey = PKey::private_key_from_der(&ec.private_key_to_der().unwrap()).unwrap();
+        assert!(pkey.ec_key().is_ok());
+
+        assert!(PKey::private_key_from_der(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_public_key_from_der() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let pkey = PKey::from_rsa(rsa).unwrap();
+
+        let spki = PKey::public_key_from_der(&pkey.public_key_to_der().unwrap()).unwrap();
+        assert!(spki.public_eq(&pkey));
+
+        let pkcs1 = pkey.rsa().unwrap().public_key_to_der_pkcs1().unwrap();
+        let pkcs1 = PKey::public_key_from_der(&pkcs1).unwrap();
+        assert!(pkcs1.public_eq(&pkey));
+
+        assert!(PKey::public_key_from_der(b"garbage").is_err());
+    }
+
     #[test]
     fn test_pem() {
         let key = include_bytes!("../test/key.pem");
@@ -258,4 +796,185 @@ mod tests {
         pkey.ec_key().unwrap();
         assert!(pkey.rsa().is_err());
     }
+
+    #[test]
+    fn test_generate_keypair() {
+        let pair = generate_keypair(KeyType::P256, Encryption::None).unwrap();
+        let key = PKey::private_key_from_pem(&pair.private_key_pem).unwrap();
+        assert!(key.public_eq(&pair.pkey));
+        let public = PKey::public_key_from_pem(&pair.public_key_pem).unwrap();
+        assert!(public.public_eq(&pair.pkey));
+
+        let encryption = Encryption::Passphrase(Cipher::aes_256_cbc(), b"foobar");
+        let pair = generate_keypair(KeyType::Rsa2048, encryption).unwrap();
+        assert_eq!(pair.pkey.bits(), 2048);
+        assert!(PKey::private_key_from_pem_passphrase(&pair.private_key_pem, b"fizzbuzz").is_err());
+        let key = PKey::private_key_from_pem_passphrase(&pair.private_key_pem, b"foobar").unwrap();
+        assert!(key.public_eq(&pair.pkey));
+    }
+
+    #[test]
+    #[cfg(ossl111)]
+    fn test_generate_keypair_ed25519() {
+        let pair = generate_keypair(KeyType::Ed25519, Encryption::None).unwrap();
+        assert_eq!(pair.pkey.id(), ID_ED25519);
+        let key = PKey::private_key_from_pem(&pair.private_key_pem).unwrap();
+        assert!(key.public_eq(&pair.pkey));
+    }
+
+    #[test]
+    #[cfg(not(ossl111))]
+    fn test_generate_keypair_ed25519_unsupported() {
+        assert!(generate_keypair(KeyType::Ed25519, Encryption::None).is_err());
+    }
+
+    #[test]
+    fn test_id_size() {
+        let rsa = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        assert_eq!(rsa.id(), ID_RSA);
+        assert_eq!(rsa.bits(), 2048);
+        assert_eq!(rsa.size(), 256);
+
+        let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+        let ec = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        assert_eq!(ec.id(), ID_EC);
+        assert_eq!(ec.bits(), 256);
+        assert!(ec.size() >= 64);
+
+        let hmac = PKey::hmac(b"key").unwrap();
+        assert_eq!(hmac.id(), ID_HMAC);
+    }
+
+    #[test]
+    fn test_public_eq() {
+        let cert = X509::from_pem(include_bytes!("../test/cert.pem")).unwrap();
+        let key = PKey::private_key_from_pem(include_bytes!("../test/key.pem")).unwrap();
+        assert!(cert.public_key().unwrap().public_eq(&key));
+
+        let other = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        assert!(!cert.public_key().unwrap().public_eq(&other));
+    }
+
+    #[test]
+    fn test_ec_paramgen_keygen() {
+        let mut ctx = PKeyCtx::new_id(nid::X9_62_ID_ECPUBLICKEY).unwrap();
+        ctx.paramgen_init().unwrap();
+        ctx.set_ec_paramgen_curve_nid(nid::X9_62_PRIME256V1).unwrap();
+        let params = ctx.paramgen().unwrap();
+
+        let mut ctx = PKeyCtx::new(&params).unwrap();
+        ctx.keygen_init().unwrap();
+        let a = ctx.keygen().unwrap();
+        let b = ctx.keygen().unwrap();
+        a.ec_key().unwrap();
+        assert!(!a.public_eq(&b));
+    }
+
+    #[test]
+    fn test_dsa_paramgen_callback() {
+        let mut ctx = PKeyCtx::new_id(nid::DSA).unwrap();
+        ctx.paramgen_init().unwrap();
+        ctx.set_dsa_paramgen_bits(1024).unwrap();
+        let mut called = false;
+        let params = ctx.paramgen_callback(|_, _| {
+            called = true;
+            true
+        }).unwrap();
+        assert!(called);
+
+        let mut ctx = PKeyCtx::new(&params).unwrap();
+        ctx.keygen_init().unwrap();
+        let key = ctx.keygen().unwrap();
+        assert_eq!(key.bits(), 1024);
+        assert!(ctx.keygen().unwrap().dsa().is_ok());
+    }
+
+    #[test]
+    fn test_paramgen_callback_abort() {
+        let mut ctx = PKeyCtx::new_id(nid::DSA).unwrap();
+        ctx.paramgen_init().unwrap();
+        ctx.set_dsa_paramgen_bits(1024).unwrap();
+        assert!(ctx.paramgen_callback(|_, _| false).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn test_raw_x25519() {
+        let key = PKey::generate_x25519().unwrap();
+        let private = key.raw_private_key().unwrap();
+        let public = key.raw_public_key().unwrap();
+        assert_eq!(private.len(), 32);
+        assert_eq!(public.len(), 32);
+
+        let key2 = PKey::private_key_from_raw_bytes(&private, nid::X25519).unwrap();
+        assert_eq!(key2.raw_public_key().unwrap(), public);
+        let public_key = PKey::public_key_from_raw_bytes(&public, nid::X25519).unwrap();
+        assert!(key.public_eq(&public_key));
+        assert!(public_key.raw_private_key().is_err());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+        let mut ctx = PKeyCtx::new(&key).unwrap();
+        ctx.encrypt_init().unwrap();
+        ctx.set_rsa_padding(rsa::PKCS1_OAEP_PADDING).unwrap();
+        let encrypted = ctx.encrypt(b"hello world").unwrap();
+        assert_eq!(encrypted.len(), 256);
+
+        let rsa = key.rsa().unwrap();
+        let mut buf = vec![0; rsa.size()];
+        let len = rsa.private_decrypt(&encrypted, &mut buf, rsa::PKCS1_OAEP_PADDING).unwrap();
+        assert_eq!(&buf[..len], b"hello world");
+
+        let mut ctx = PKeyCtx::new(&key).unwrap();
+        ctx.decrypt_init().unwrap();
+        ctx.set_rsa_padding(rsa::PKCS1_OAEP_PADDING).unwrap();
+        assert_eq!(ctx.decrypt(&encrypted).unwrap(), b"hello world");
+    }
+
+    #[test]
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    fn test_oaep_options() {
+        use hash::MessageDigest;
+
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let ctx = |label: &[u8], encrypt: bool| {
+            let mut ctx = PKeyCtx::new(&key).unwrap();
+            if encrypt {
+                ctx.encrypt_init().unwrap();
+            } else {
+                ctx.decrypt_init().unwrap();
+            }
+            ctx.set_rsa_padding(rsa::PKCS1_OAEP_PADDING).unwrap();
+            ctx.set_rsa_oaep_md(MessageDigest::sha256()).unwrap();
+            ctx.set_rsa_mgf1_md(MessageDigest::sha256()).unwrap();
+            ctx.set_rsa_oaep_label(label).unwrap();
+            ctx
+        };
+
+        let encrypted = ctx(b"label", true).encrypt(b"hello world").unwrap();
+        assert_eq!(ctx(b"label", false).decrypt(&encrypted).unwrap(), b"hello world");
+        assert!(ctx(b"other", false).decrypt(&encrypted).is_err());
+
+        let encrypted = ctx(b"", true).encrypt(b"hello world").unwrap();
+        assert_eq!(ctx(b"", false).decrypt(&encrypted).unwrap(), b"hello world");
+
+        let rsa = key.rsa().unwrap();
+        let mut buf = vec![0; rsa.size()];
+        assert!(rsa.private_decrypt(&encrypted, &mut buf, rsa::PKCS1_OAEP_PADDING).is_err());
+    }
+
+    #[test]
+    fn public_key_to_der_into() {
+        let key = include_bytes!("../test/key.pem");
+        let key = PKey::private_key_from_pem(key).unwrap();
+        let der = key.public_key_to_der().unwrap();
+        assert_eq!(key.public_key_der_len().unwrap(), der.len());
+
+        let mut buf = [0; 1024];
+        let len = key.public_key_to_der_into(&mut buf).unwrap();
+        assert_eq!(&buf[..len], &der[..]);
+    }
 }
@@ -0,0 +1,11 @@
+//! The types needed for most uses of the crate.
+//!
+//! ```
+//! use openssl::prelude::*;
+//! ```
+pub use error::ErrorStack;
+pub use foreign_types::{ForeignType, ForeignTypeRef};
+pub use hash::MessageDigest;
+pub use pkey::{PKey, PKeyRef};
+pub use ssl::{HandshakeError, SslAcceptor, SslConnector, SslMethod, SslStream};
+pub use x509::{X509, X509Ref};
@@ -1,9 +1,42 @@
-use libc::c_int;
+//! Utilities for secure random number generation.
+//!
+//! OpenSSL's cryptographically secure pseudo-random number generator is seeded automatically from
+//! the operating system. Additional entropy can be mixed into its state with `add` or `seed`, or
+//! it can be replaced entirely with `set_rand_callback`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use openssl::rand::rand_bytes;
+//!
+//! let mut buf = [0; 256];
+//! rand_bytes(&mut buf).unwrap();
+//! ```
+use libc::{c_double, c_int};
+#[cfg(not(libressl))]
+use libc::{c_uchar, c_void};
 use ffi;
+#[cfg(not(libressl))]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(not(libressl))]
+use std::ptr;
+#[cfg(not(libressl))]
+use std::slice;
+#[cfg(not(libressl))]
+use std::sync::RwLock;
 
 use cvt;
 use error::ErrorStack;
 
+/// Fills `buf` with cryptographically strong pseudo-random bytes.
+///
+/// An error is returned if the generator has not been seeded with enough entropy.
+///
+/// This corresponds to `RAND_bytes`.
+///
+/// # Panics
+///
+/// Panics if `buf.len() > c_int::max_value()`.
 pub fn rand_bytes(buf: &mut [u8]) -> Result<(), ErrorStack> {
     unsafe {
         ffi::init();
@@ -12,13 +45,188 @@ pub fn rand_bytes(buf: &mut [u8]) -> Result<(), ErrorStack> {
     }
 }
 
+/// Mixes `buf` into the generator's state, estimating that it contains `entropy` bytes of
+/// randomness.
+///
+/// This corresponds to `RAND_add`.
+///
+/// # Panics
+///
+/// Panics if `buf.len() > c_int::max_value()`.
+pub fn add(buf: &[u8], entropy: f64) {
+    unsafe {
+        ffi::init();
+        assert!(buf.len() <= c_int::max_value() as usize);
+        ffi::RAND_add(buf.as_ptr() as *const _, buf.len() as c_int, entropy as c_double);
+    }
+}
+
+/// Mixes `buf` into the generator's state, assuming that all of it is random.
+///
+/// This is equivalent to calling `add` with an entropy estimate of `buf.len()`.
+///
+/// This corresponds to `RAND_seed`.
+///
+/// # Panics
+///
+/// Panics if `buf.len() > c_int::max_value()`.
+pub fn seed(buf: &[u8]) {
+    unsafe {
+        ffi::init();
+        assert!(buf.len() <= c_int::max_value() as usize);
+        ffi::RAND_seed(buf.as_ptr() as *const _, buf.len() as c_int);
+    }
+}
+
+/// Determines if the generator has been seeded with enough entropy.
+///
+/// This corresponds to `RAND_status`.
+pub fn status() -> bool {
+    unsafe {
+        ffi::init();
+        ffi::RAND_status() == 1
+    }
+}
+
+/// Controls whether the random devices used to seed the generator, such as `/dev/urandom`, are
+/// kept open between uses.
+///
+/// Keeping them open can be useful in a process which will later enter a chroot or sandbox.
+///
+/// This corresponds to `RAND_keep_random_devices_open`.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub fn keep_random_devices_open(keep: bool) {
+    unsafe {
+        ffi::init();
+        ffi::RAND_keep_random_devices_open(keep as c_int);
+    }
+}
+
+/// Replaces the generator used for all of OpenSSL's random numbers with `generate`.
+///
+/// Every request for random bytes made in the process, whether by `rand_bytes`, key and IV
+/// generation or TLS connections, is passed to the callback. It should fill the buffer and return
+/// `true`, or return `false` if it cannot, in which case the request fails. A panic is treated as
+/// a failure. Entropy passed to `add` and `seed` is discarded while the callback is installed.
+///
+/// This can be used to draw random numbers from a hardware generator, or to produce reproducible
+/// output in tests. The callback must not itself request random bytes from OpenSSL.
+///
+/// The generator is global: it affects every thread and every user of OpenSSL in the process,
+/// including other libraries linked against it, until `clear_rand_callback` is called.
+///
+/// OpenSSL 3.x deprecates `RAND_METHOD` in favor of its DRBG providers and seed sources, but still
+/// passes every request made through `RAND_bytes` and `RAND_priv_bytes` to an installed method,
+/// which is what OpenSSL itself uses, so the callback replaces the DRBG together with its seed
+/// source there as well. Only the FIPS provider, which always uses its own generator, is not
+/// affected.
+///
+/// This corresponds to `RAND_set_rand_method`.
+#[cfg(not(libressl))]
+pub fn set_rand_callback<F>(generate: F) -> Result<(), ErrorStack>
+    where F: Fn(&mut [u8]) -> bool + 'static + Sync + Send
+{
+    ffi::init();
+    *RAND_CALLBACK.write().unwrap() = Some(Box::new(generate));
+    unsafe { cvt(ffi::RAND_set_rand_method(&RAND_METHOD)).map(|_| ()) }
+}
+
+/// Restores OpenSSL's own generator after a call to `set_rand_callback`.
+#[cfg(not(libressl))]
+pub fn clear_rand_callback() {
+    unsafe {
+        ffi::init();
+        ffi::RAND_set_rand_method(ptr::null());
+    }
+    *RAND_CALLBACK.write().unwrap() = None;
+}
+
+#[cfg(not(libressl))]
+lazy_static! {
+    static ref RAND_CALLBACK: RwLock<Option<Box<Fn(&mut [u8]) -> bool + Sync + Send>>> =
+        RwLock::new(None);
+}
+
+#[cfg(not(libressl))]
+static RAND_METHOD: ffi::RAND_METHOD = ffi::RAND_METHOD {
+    seed: Some(raw_seed),
+    bytes: Some(raw_bytes),
+    cleanup: None,
+    add: Some(raw_add),
+    pseudorand: Some(raw_bytes),
+    status: Some(raw_status),
+};
+
+#[cfg(not(libressl))]
+unsafe extern "C" fn raw_bytes(buf: *mut c_uchar, num: c_int) -> c_int {
+    if num <= 0 {
+        return 1;
+    }
+    let buf = slice::from_raw_parts_mut(buf, num as usize);
+    let guard = match RAND_CALLBACK.read() {
+        Ok(guard) => guard,
+        Err(err) => err.into_inner(),
+    };
+    match *guard {
+        Some(ref generate) => {
+            panic::catch_unwind(AssertUnwindSafe(|| generate(buf))).unwrap_or(false) as c_int
+        }
+        None => 0,
+    }
+}
+
+#[cfg(not(libressl))]
+unsafe extern "C" fn raw_status() -> c_int {
+    1
+}
+
+#[cfg(all(ossl10x, not(libressl)))]
+unsafe extern "C" fn raw_seed(_buf: *const c_void, _num: c_int) {}
+
+#[cfg(all(ossl10x, not(libressl)))]
+unsafe extern "C" fn raw_add(_buf: *const c_void, _num: c_int, _randomness: c_double) {}
+
+#[cfg(ossl110)]
+unsafe extern "C" fn raw_seed(_buf: *const c_void, _num: c_int) -> c_int {
+    1
+}
+
+#[cfg(ossl110)]
+unsafe extern "C" fn raw_add(_buf: *const c_void, _num: c_int, _randomness: c_double) -> c_int {
+    1
+}
+
 #[cfg(test)]
 mod tests {
-    use super::rand_bytes;
+    use super::*;
 
     #[test]
     fn test_rand_bytes() {
         let mut buf = [0; 32];
         rand_bytes(&mut buf).unwrap();
     }
+
+    #[test]
+    fn test_seed() {
+        add(b"some additional data", 0.0);
+        seed(&[0x42; 32]);
+        assert!(status());
+
+        let mut a = [0; 32];
+        let mut b = [0; 32];
+        rand_bytes(&mut a).unwrap();
+        rand_bytes(&mut b).unwrap();
+        assert!(a != b);
+    }
+
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn test_keep_random_devices_open() {
+        keep_random_devices_open(true);
+        let mut buf = [0; 32];
+        rand_bytes(&mut buf).unwrap();
+        keep_random_devices_open(false);
+    }
 }
@@ -0,0 +1,330 @@
+//! Record protection with AEAD ciphers.
+//!
+//! A `RecordSealer` encrypts a sequence of records with a single key, deriving a unique nonce for
+//! each record from a 64-bit sequence number, and a `RecordOpener` configured with the same key
+//! and IV decrypts them in the same order. This is the scheme used by protocols such as TLS and
+//! QUIC, and avoids the need for callers to manage nonces themselves.
+//!
+//! Each direction of a connection should use its own key.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use openssl::record::{NonceMode, RecordOpener, RecordSealer};
+//! use openssl::symm::Cipher;
+//!
+//! let key = [0x42; 16];
+//! let iv = [0x24; 12];
+//!
+//! let mut sealer = RecordSealer::new(Cipher::aes_128_gcm(), &key, &iv, NonceMode::Xor).unwrap();
+//! let first = sealer.seal_next(b"header 1", b"hello").unwrap();
+//! let second = sealer.seal_next(b"header 2", b"world").unwrap();
+//!
+//! let mut opener = RecordOpener::new(Cipher::aes_128_gcm(), &key, &iv, NonceMode::Xor).unwrap();
+//! assert_eq!(opener.open_next(b"header 1", &first).unwrap(), b"hello");
+//! assert_eq!(opener.open_next(b"header 2", &second).unwrap(), b"world");
+//! ```
+use std::u64;
+
+use error::ErrorStack;
+use symm::{self, Cipher};
+
+/// The length of the nonces used by the supported ciphers.
+const NONCE_LEN: usize = 12;
+
+/// The length of the authentication tag appended to each record.
+pub const TAG_LEN: usize = 16;
+
+/// The way a record's nonce is derived from the IV and its sequence number.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NonceMode {
+    /// The big-endian sequence number is left-padded to 12 bytes and XORed with a 12-byte IV.
+    ///
+    /// This is the construction used by TLS 1.3 and by TLS 1.2 with ChaCha20-Poly1305.
+    Xor,
+    /// The big-endian sequence number is appended to a 4-byte fixed IV.
+    ///
+    /// This is the partially implicit nonce of RFC 5116, as used by TLS 1.2 with AES-GCM.
+    Concat,
+}
+
+impl NonceMode {
+    fn iv_len(&self) -> usize {
+        match *self {
+            NonceMode::Xor => NONCE_LEN,
+            NonceMode::Concat => NONCE_LEN - 8,
+        }
+    }
+}
+
+struct RecordState {
+    cipher: Cipher,
+    key: Vec<u8>,
+    iv: Vec<u8>,
+    mode: NonceMode,
+    seq: u64,
+}
+
+impl RecordState {
+    fn new(cipher: Cipher,
+           key: &[u8],
+           iv: &[u8],
+           mode: NonceMode)
+           -> Result<RecordState, ErrorStack> {
+        if !is_supported(cipher) {
+            ::put_error(::R_UNSUPPORTED_ALGORITHM,
+                        "the cipher must be AES-GCM or ChaCha20-Poly1305");
+            return Err(ErrorStack::get());
+        }
+        if key.len() != cipher.key_len() {
+            ::put_error(::R_INVALID_KEY_LENGTH,
+                        &format!("a key of {} bytes is required for this cipher",
+                                 cipher.key_len()));
+            return Err(ErrorStack::get());
+        }
+        if iv.len() != mode.iv_len() {
+            ::put_error(::R_INVALID_IV_LENGTH,
+                        &format!("an IV of {} bytes is required for this nonce mode",
+                                 mode.iv_len()));
+            return Err(ErrorStack::get());
+        }
+
+        Ok(RecordState {
+            cipher: cipher,
+            key: key.to_vec(),
+            iv: iv.to_vec(),
+            mode: mode,
+            seq: 0,
+        })
+    }
+
+    // The last sequence number is never used, so that `seq` can't overflow.
+    fn check_sequence(&self) -> Result<(), ErrorStack> {
+        if self.seq == u64::MAX {
+            ::put_error(::R_SEQUENCE_EXHAUSTED, "the record sequence number is exhausted");
+            return Err(ErrorStack::get());
+        }
+        Ok(())
+    }
+
+    fn nonce(&self) -> [u8; NONCE_LEN] {
+        let mut seq = [0; 8];
+        for (i, b) in seq.iter_mut().enumerate() {
+            *b = (self.seq >> (56 - 8 * i)) as u8;
+        }
+
+        let mut nonce = [0; NONCE_LEN];
+        match self.mode {
+            NonceMode::Xor => {
+                nonce.copy_from_slice(&self.iv);
+                for (n, s) in nonce[NONCE_LEN - 8..].iter_mut().zip(&seq) {
+                    *n ^= *s;
+                }
+            }
+            NonceMode::Concat => {
+                nonce[..NONCE_LEN - 8].copy_from_slice(&self.iv);
+                nonce[NONCE_LEN - 8..].copy_from_slice(&seq);
+            }
+        }
+        nonce
+    }
+
+    fn advance(&mut self) {
+        self.seq += 1;
+    }
+}
+
+#[cfg(all(ossl110, feature = "v110"))]
+fn is_supported(cipher: Cipher) -> bool {
+    cipher == Cipher::aes_128_gcm() || cipher == Cipher::aes_256_gcm() ||
+    cipher == Cipher::chacha20_poly1305()
+}
+
+#[cfg(not(all(ossl110, feature = "v110")))]
+fn is_supported(cipher: Cipher) -> bool {
+    cipher == Cipher::aes_128_gcm() || cipher == Cipher::aes_256_gcm()
+}
+
+/// Encrypts a sequence of records.
+pub struct RecordSealer(RecordState);
+
+impl RecordSealer {
+    /// Creates a new `RecordSealer` starting at sequence number 0.
+    ///
+    /// An error is returned if `cipher` is not AES-GCM or ChaCha20-Poly1305, if `key` is not the
+    /// cipher's key length, or if `iv` is not the length required by `mode`.
+    pub fn new(cipher: Cipher,
+               key: &[u8],
+               iv: &[u8],
+               mode: NonceMode)
+               -> Result<RecordSealer, ErrorStack> {
+        RecordState::new(cipher, key, iv, mode).map(RecordSealer)
+    }
+
+    /// Returns the sequence number of the next record to be sealed.
+    pub fn sequence_number(&self) -> u64 {
+        self.0.seq
+    }
+
+    /// Encrypts the next record, authenticating `aad` along with it.
+    ///
+    /// The ciphertext is returned with the `TAG_LEN` byte authentication tag appended. An error is
+    /// returned once the sequence number is exhausted, after `2^64 - 1` records.
+    pub fn seal_next(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        try!(self.0.check_sequence());
+        let nonce = self.0.nonce();
+        let mut tag = [0; TAG_LEN];
+        let mut out = try!(symm::encrypt_aead(self.0.cipher,
+                                              &self.0.key,
+                                              Some(&nonce),
+                                              aad,
+                                              plaintext,
+                                              &mut tag));
+        out.extend_from_slice(&tag);
+        self.0.advance();
+        Ok(out)
+    }
+}
+
+/// Decrypts a sequence of records produced by a `RecordSealer`.
+pub struct RecordOpener(RecordState);
+
+impl RecordOpener {
+    /// Creates a new `RecordOpener` starting at sequence number 0.
+    ///
+    /// An error is returned if `cipher` is not AES-GCM or ChaCha20-Poly1305, if `key` is not the
+    /// cipher's key length, or if `iv` is not the length required by `mode`.
+    pub fn new(cipher: Cipher,
+               key: &[u8],
+               iv: &[u8],
+               mode: NonceMode)
+               -> Result<RecordOpener, ErrorStack> {
+        RecordState::new(cipher, key, iv, mode).map(RecordOpener)
+    }
+
+    /// Returns the sequence number of the next record to be opened.
+    pub fn sequence_number(&self) -> u64 {
+        self.0.seq
+    }
+
+    /// Decrypts the next record, checking that it and `aad` are authentic.
+    ///
+    /// `ciphertext` must include the authentication tag. An error is returned if it is too short to
+    /// do so or if authentication fails, in which case the sequence number is not advanced, and
+    /// once the sequence number is exhausted.
+    pub fn open_next(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        try!(self.0.check_sequence());
+        // OpenSSL would otherwise accept a truncated tag
+        if ciphertext.len() < TAG_LEN {
+            ::put_error(::R_TRUNCATED, "the record is shorter than its authentication tag");
+            return Err(ErrorStack::get());
+        }
+        let (data, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+        let nonce = self.0.nonce();
+        let out = try!(symm::decrypt_aead(self.0.cipher,
+                                          &self.0.key,
+                                          Some(&nonce),
+                                          aad,
+                                          data,
+                                          tag));
+        self.0.advance();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ffi;
+    use hex::FromHex;
+    use symm::{self, Cipher};
+
+    use super::*;
+
+    fn round_trip(cipher: Cipher, mode: NonceMode) {
+        let key = vec![7; cipher.key_len()];
+        let iv = vec![9; mode.iv_len()];
+
+        let mut sealer = RecordSealer::new(cipher, &key, &iv, mode).unwrap();
+        let records = (0..3)
+            .map(|i| sealer.seal_next(&[i], &[i; 20]).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(sealer.sequence_number(), 3);
+        assert!(records.iter().all(|r| r.len() == 20 + TAG_LEN));
+        assert!(records[0] != records[1]);
+
+        let mut opener = RecordOpener::new(cipher, &key, &iv, mode).unwrap();
+        // out of order records and tampered aad are rejected without advancing
+        assert!(opener.open_next(&[1], &records[1]).is_err());
+        assert!(opener.open_next(&[1], &records[0]).is_err());
+        let err = opener.open_next(&[0], &records[0][..TAG_LEN - 1]).unwrap_err();
+        assert_eq!(ffi::ERR_GET_REASON(err.errors()[0].code()), ::R_TRUNCATED);
+        assert_eq!(opener.sequence_number(), 0);
+        for (i, record) in records.iter().enumerate() {
+            let i = i as u8;
+            assert_eq!(opener.open_next(&[i], record).unwrap(), &[i; 20][..]);
+        }
+        assert_eq!(opener.sequence_number(), 3);
+    }
+
+    #[test]
+    fn aes_gcm() {
+        round_trip(Cipher::aes_128_gcm(), NonceMode::Xor);
+        round_trip(Cipher::aes_256_gcm(), NonceMode::Concat);
+    }
+
+    #[test]
+    #[cfg(all(ossl110, feature = "v110"))]
+    fn chacha20_poly1305() {
+        round_trip(Cipher::chacha20_poly1305(), NonceMode::Xor);
+    }
+
+    #[test]
+    fn nonce_construction() {
+        let key = Vec::from_hex("feffe9928665731c6d6a8f9467308308").unwrap();
+        let iv = Vec::from_hex("cafebabefacedbaddecaf888").unwrap();
+
+        let mut sealer =
+            RecordSealer::new(Cipher::aes_128_gcm(), &key, &iv, NonceMode::Xor).unwrap();
+        sealer.seal_next(b"", b"").unwrap();
+        let record = sealer.seal_next(b"aad", b"data").unwrap();
+        let nonce = Vec::from_hex("cafebabefacedbaddecaf889").unwrap();
+        let mut tag = [0; TAG_LEN];
+        let mut expected =
+            symm::encrypt_aead(Cipher::aes_128_gcm(), &key, Some(&nonce), b"aad", b"data", &mut tag)
+                .unwrap();
+        expected.extend_from_slice(&tag);
+        assert_eq!(record, expected);
+
+        let mut sealer =
+            RecordSealer::new(Cipher::aes_128_gcm(), &key, &iv[..4], NonceMode::Concat).unwrap();
+        sealer.seal_next(b"", b"").unwrap();
+        let record = sealer.seal_next(b"aad", b"data").unwrap();
+        let nonce = Vec::from_hex("cafebabe0000000000000001").unwrap();
+        let mut expected =
+            symm::encrypt_aead(Cipher::aes_128_gcm(), &key, Some(&nonce), b"aad", b"data", &mut tag)
+                .unwrap();
+        expected.extend_from_slice(&tag);
+        assert_eq!(record, expected);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(RecordSealer::new(Cipher::aes_128_gcm(), &[0; 16], &[0; 12], NonceMode::Concat)
+            .is_err());
+        assert!(RecordOpener::new(Cipher::aes_128_gcm(), &[0; 32], &[0; 12], NonceMode::Xor)
+            .is_err());
+        assert!(RecordSealer::new(Cipher::aes_128_cbc(), &[0; 16], &[0; 12], NonceMode::Xor)
+            .is_err());
+    }
+
+    #[test]
+    fn sequence_exhausted() {
+        let mut sealer =
+            RecordSealer::new(Cipher::aes_128_gcm(), &[0; 16], &[0; 12], NonceMode::Xor).unwrap();
+        sealer.0.seq = u64::MAX - 1;
+        sealer.seal_next(b"", b"data").unwrap();
+        let err = sealer.seal_next(b"", b"data").unwrap_err();
+        assert_eq!(ffi::ERR_GET_REASON(err.errors()[0].code()), ::R_SEQUENCE_EXHAUSTED);
+        assert_eq!(sealer.sequence_number(), u64::MAX);
+    }
+}
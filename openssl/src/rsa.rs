@@ -2,13 +2,20 @@ use ffi;
 use std::fmt;
 use std::ptr;
 use std::mem;
-use libc::{c_int, c_void, c_char};
+use libc::{c_int, c_uint, c_void, c_char};
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+use foreign_types::ForeignType;
 use foreign_types::ForeignTypeRef;
 
 use {cvt, cvt_p, cvt_n};
 use bn::{BigNum, BigNumRef};
 use bio::MemBioSlice;
 use error::ErrorStack;
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+use hash::MessageDigest;
+use nid::Nid;
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+use pkey::{PKey, PKeyCtx};
 use util::{CallbackState, invoke_passwd_cb_old};
 
 /// Type of encryption padding to use.
@@ -28,6 +35,34 @@ impl Padding {
 pub const NO_PADDING: Padding = Padding(ffi::RSA_NO_PADDING);
 pub const PKCS1_PADDING: Padding = Padding(ffi::RSA_PKCS1_PADDING);
 pub const PKCS1_OAEP_PADDING: Padding = Padding(ffi::RSA_PKCS1_OAEP_PADDING);
+/// Probabilistic signature scheme padding, only usable for signatures.
+pub const PKCS1_PSS_PADDING: Padding = Padding(ffi::RSA_PKCS1_PSS_PADDING);
+
+/// The salt length used for RSA PSS padding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PssSaltLen(c_int);
+
+impl PssSaltLen {
+    /// Uses a salt of exactly `len` bytes.
+    pub fn custom(len: u32) -> PssSaltLen {
+        assert!(len <= c_int::max_value() as u32);
+        PssSaltLen(len as c_int)
+    }
+
+    pub fn from_raw(value: c_int) -> PssSaltLen {
+        PssSaltLen(value)
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+}
+
+/// Uses a salt as long as the message digest.
+pub const PSS_SALTLEN_DIGEST: PssSaltLen = PssSaltLen(ffi::RSA_PSS_SALTLEN_DIGEST);
+/// Uses the longest salt that fits in the key when signing, and accepts any salt length when
+/// verifying.
+pub const PSS_SALTLEN_MAX: PssSaltLen = PssSaltLen(ffi::RSA_PSS_SALTLEN_MAX);
 
 foreign_type! {
     type CType = ffi::RSA;
@@ -44,6 +79,9 @@ impl RsaRef {
     private_key_to_der!(ffi::i2d_RSAPrivateKey);
     public_key_to_der!(ffi::i2d_RSA_PUBKEY);
 
+    to_der_inner!(/// Serializes the public key to DER-encoded PKCS#1.
+        public_key_to_der_pkcs1, ffi::i2d_RSAPublicKey);
+
     // FIXME should return u32
     pub fn size(&self) -> usize {
         unsafe {
@@ -149,6 +187,140 @@ impl RsaRef {
         }
     }
 
+    /// Encrypts data using the public key with OAEP padding, returning the number of encrypted
+    /// bytes.
+    ///
+    /// Unlike `public_encrypt` with `PKCS1_OAEP_PADDING`, which always uses SHA-1 and an empty
+    /// label, this uses `md` for both OAEP and its MGF1 mask generation function, and `label` as
+    /// the OAEP label.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `to` is smaller than `self.size()`.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn public_encrypt_oaep(&self,
+                               from: &[u8],
+                               to: &mut [u8],
+                               md: MessageDigest,
+                               label: &[u8])
+                               -> Result<usize, ErrorStack> {
+        assert!(to.len() >= self.size());
+
+        let mut ctx = try!(self.oaep_ctx(md, label, true));
+        let out = try!(ctx.encrypt(from));
+        to[..out.len()].copy_from_slice(&out);
+        Ok(out.len())
+    }
+
+    /// Decrypts data using the private key with OAEP padding, returning the number of decrypted
+    /// bytes.
+    ///
+    /// `md` and `label` must match those passed to `public_encrypt_oaep`.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` has no private components, or if `to` is smaller
+    /// than `self.size()`.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn private_decrypt_oaep(&self,
+                                from: &[u8],
+                                to: &mut [u8],
+                                md: MessageDigest,
+                                label: &[u8])
+                                -> Result<usize, ErrorStack> {
+        assert!(self.d().is_some(), "private components missing");
+        assert!(to.len() >= self.size());
+
+        let mut ctx = try!(self.oaep_ctx(md, label, false));
+        let out = try!(ctx.decrypt(from));
+        to[..out.len()].copy_from_slice(&out);
+        Ok(out.len())
+    }
+
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    fn oaep_ctx(&self,
+                md: MessageDigest,
+                label: &[u8],
+                encrypt: bool)
+                -> Result<PKeyCtx, ErrorStack> {
+        unsafe {
+            let pkey = PKey::from_ptr(try!(cvt_p(ffi::EVP_PKEY_new())));
+            try!(cvt(ffi::EVP_PKEY_set1_RSA(pkey.as_ptr(), self.as_ptr())));
+
+            // the context keeps its own reference to the key
+            let mut ctx = try!(PKeyCtx::new(&pkey));
+            if encrypt {
+                try!(ctx.encrypt_init());
+            } else {
+                try!(ctx.decrypt_init());
+            }
+            try!(ctx.set_rsa_padding(PKCS1_OAEP_PADDING));
+            try!(ctx.set_rsa_oaep_md(md));
+            try!(ctx.set_rsa_mgf1_md(md));
+            try!(ctx.set_rsa_oaep_label(label));
+            Ok(ctx)
+        }
+    }
+
+    /// Signs a precomputed digest with PKCS#1 v1.5 padding.
+    ///
+    /// `digest_type` identifies the digest algorithm which produced `digest`, such as
+    /// `nid::SHA256`. The `DigestInfo` structure containing it is constructed internally.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` has no private components.
+    pub fn sign_digest(&self, digest_type: Nid, digest: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        assert!(self.d().is_some(), "private components missing");
+        assert!(digest.len() <= c_uint::max_value() as usize);
+
+        unsafe {
+            let mut sig = vec![0; self.size()];
+            let mut len = 0;
+            try!(cvt(ffi::RSA_sign(digest_type.as_raw(),
+                                   digest.as_ptr(),
+                                   digest.len() as c_uint,
+                                   sig.as_mut_ptr(),
+                                   &mut len,
+                                   self.as_ptr())));
+            sig.truncate(len as usize);
+            Ok(sig)
+        }
+    }
+
+    /// Verifies a PKCS#1 v1.5 signature over a precomputed digest.
+    ///
+    /// This is the counterpart of `sign_digest`, and can be used to check signatures in formats
+    /// where only the digest of the signed data is available. `false` is returned if the
+    /// signature is invalid, or if it was made over a different digest or digest algorithm.
+    pub fn verify_digest(&self,
+                         digest_type: Nid,
+                         digest: &[u8],
+                         signature: &[u8])
+                         -> Result<bool, ErrorStack> {
+        assert!(digest.len() <= c_uint::max_value() as usize);
+        assert!(signature.len() <= c_uint::max_value() as usize);
+
+        unsafe {
+            let r = ffi::RSA_verify(digest_type.as_raw(),
+                                    digest.as_ptr(),
+                                    digest.len() as c_uint,
+                                    signature.as_ptr(),
+                                    signature.len() as c_uint,
+                                    self.as_ptr());
+            if r == 1 {
+                Ok(true)
+            } else {
+                ErrorStack::get(); // discard error stack
+                Ok(false)
+            }
+        }
+    }
+
     pub fn n(&self) -> Option<&BigNumRef> {
         unsafe {
             let n = compat::key(self.as_ptr())[0];
@@ -203,6 +375,42 @@ impl RsaRef {
             }
         }
     }
+
+    /// Returns the first CRT exponent, `d mod (p - 1)`.
+    pub fn dmp1(&self) -> Option<&BigNumRef> {
+        unsafe {
+            let dmp1 = compat::crt_params(self.as_ptr())[0];
+            if dmp1.is_null() {
+                None
+            } else {
+                Some(BigNumRef::from_ptr(dmp1 as *mut _))
+            }
+        }
+    }
+
+    /// Returns the second CRT exponent, `d mod (q - 1)`.
+    pub fn dmq1(&self) -> Option<&BigNumRef> {
+        unsafe {
+            let dmq1 = compat::crt_params(self.as_ptr())[1];
+            if dmq1.is_null() {
+                None
+            } else {
+                Some(BigNumRef::from_ptr(dmq1 as *mut _))
+            }
+        }
+    }
+
+    /// Returns the CRT coefficient, `q^-1 mod p`.
+    pub fn iqmp(&self) -> Option<&BigNumRef> {
+        unsafe {
+            let iqmp = compat::crt_params(self.as_ptr())[2];
+            if iqmp.is_null() {
+                None
+            } else {
+                Some(BigNumRef::from_ptr(iqmp as *mut _))
+            }
+        }
+    }
 }
 
 impl Rsa {
@@ -260,6 +468,9 @@ impl Rsa {
     public_key_from_pem!(Rsa, ffi::PEM_read_bio_RSA_PUBKEY);
     public_key_from_der!(Rsa, ffi::d2i_RSA_PUBKEY);
 
+    from_der_inner!(/// Deserializes a public key from DER-encoded PKCS#1.
+        public_key_from_der_pkcs1, Rsa, ffi::d2i_RSAPublicKey);
+
     #[deprecated(since = "0.9.2", note = "use private_key_from_pem_callback")]
     pub fn private_key_from_pem_cb<F>(buf: &[u8], pass_cb: F) -> Result<Rsa, ErrorStack>
         where F: FnOnce(&mut [c_char]) -> usize
@@ -304,6 +515,12 @@ mod compat {
         [p, q]
     }
 
+    pub unsafe fn crt_params(r: *const RSA) -> [*const BIGNUM; 3] {
+        let (mut dmp1, mut dmq1, mut iqmp) = (ptr::null(), ptr::null(), ptr::null());
+        ffi::RSA_get0_crt_params(r, &mut dmp1, &mut dmq1, &mut iqmp);
+        [dmp1, dmq1, iqmp]
+    }
+
     pub unsafe fn set_key(r: *mut RSA, n: *mut BIGNUM, e: *mut BIGNUM, d: *mut BIGNUM) -> c_int {
         ffi::RSA_set0_key(r, n, e, d)
     }
@@ -334,6 +551,10 @@ mod compat {
         [(*r).p, (*r).q]
     }
 
+    pub unsafe fn crt_params(r: *const RSA) -> [*const BIGNUM; 3] {
+        [(*r).dmp1, (*r).dmq1, (*r).iqmp]
+    }
+
     pub unsafe fn set_key(r: *mut RSA, n: *mut BIGNUM, e: *mut BIGNUM, d: *mut BIGNUM) -> c_int {
         (*r).n = n;
         (*r).e = e;
@@ -361,6 +582,10 @@ mod compat {
 
 #[cfg(test)]
 mod test {
+    use hash::{self, MessageDigest};
+    use nid;
+    use pkey::PKey;
+    use sign::{Signer, Verifier};
     use symm::Cipher;
 
     use super::*;
@@ -393,6 +618,67 @@ mod test {
         assert!(Rsa::private_key_from_pem_passphrase(&pem, b"fizzbuzz").is_err());
     }
 
+    #[test]
+    fn test_to_password_callback() {
+        let key = Rsa::generate(1024).unwrap();
+        let pem = key.private_key_to_pem_callback(Cipher::aes_128_cbc(), |password| {
+                password[..6].copy_from_slice(b"foobar");
+                Ok(6)
+            })
+            .unwrap();
+        Rsa::private_key_from_pem_passphrase(&pem, b"foobar").unwrap();
+
+        let err = key.private_key_to_pem_callback(Cipher::aes_128_cbc(), |_| Ok(0));
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn sign_verify_digest() {
+        let pem = include_bytes!("../test/rsa.pem");
+        let key = Rsa::private_key_from_pem(pem).unwrap();
+        let pkey = PKey::private_key_from_pem(pem).unwrap();
+        let data = b"hello, world";
+        let digest = hash::hash(MessageDigest::sha256(), data).unwrap();
+
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey).unwrap();
+        signer.update(data).unwrap();
+        let signature = signer.finish().unwrap();
+        assert!(key.verify_digest(nid::SHA256, &digest, &signature).unwrap());
+        assert!(!key.verify_digest(nid::SHA1, &digest, &signature).unwrap());
+        assert!(!key.verify_digest(nid::SHA256, &digest[1..], &signature).unwrap());
+
+        let signature = key.sign_digest(nid::SHA256, &digest).unwrap();
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &pkey).unwrap();
+        verifier.update(data).unwrap();
+        assert!(verifier.finish(&signature).unwrap());
+    }
+
+    #[test]
+    fn components_round_trip() {
+        let key = Rsa::generate(1024).unwrap();
+        assert!(key.dmp1().is_some());
+        assert!(key.dmq1().is_some());
+        assert!(key.iqmp().is_some());
+
+        let copy = Rsa::from_private_components(key.n().unwrap().to_owned().unwrap(),
+                                                key.e().unwrap().to_owned().unwrap(),
+                                                key.d().unwrap().to_owned().unwrap(),
+                                                key.p().unwrap().to_owned().unwrap(),
+                                                key.q().unwrap().to_owned().unwrap(),
+                                                key.dmp1().unwrap().to_owned().unwrap(),
+                                                key.dmq1().unwrap().to_owned().unwrap(),
+                                                key.iqmp().unwrap().to_owned().unwrap())
+            .unwrap();
+        assert_eq!(copy.private_key_to_der().unwrap(), key.private_key_to_der().unwrap());
+
+        let public = Rsa::from_public_components(key.n().unwrap().to_owned().unwrap(),
+                                                 key.e().unwrap().to_owned().unwrap())
+            .unwrap();
+        assert!(public.d().is_none());
+        assert!(public.dmp1().is_none());
+        assert_eq!(public.public_key_to_der().unwrap(), key.public_key_to_der().unwrap());
+    }
+
     #[test]
     fn test_public_encrypt_private_decrypt_with_padding() {
         let key = include_bytes!("../test/rsa.pem.pub");
@@ -441,4 +727,24 @@ mod test {
         assert_eq!(msg, &dmesg[..len]);
     }
 
+    #[test]
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    fn test_oaep() {
+        let key = Rsa::generate(2048).unwrap();
+        let msg = b"hello world";
+        let sha1 = MessageDigest::sha1();
+        let sha256 = MessageDigest::sha256();
+
+        let mut encrypted = vec![0; key.size()];
+        let len = key.public_encrypt_oaep(msg, &mut encrypted, sha256, b"label").unwrap();
+        assert_eq!(len, 256);
+
+        let mut decrypted = vec![0; key.size()];
+        let len = key.private_decrypt_oaep(&encrypted, &mut decrypted, sha256, b"label").unwrap();
+        assert_eq!(&decrypted[..len], msg);
+
+        assert!(key.private_decrypt_oaep(&encrypted, &mut decrypted, sha256, b"").is_err());
+        assert!(key.private_decrypt_oaep(&encrypted, &mut decrypted, sha1, b"label").is_err());
+        assert!(key.private_decrypt(&encrypted, &mut decrypted, PKCS1_OAEP_PADDING).is_err());
+    }
 }
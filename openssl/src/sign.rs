@@ -70,6 +70,7 @@ use std::ptr;
 use {cvt, cvt_p};
 use hash::MessageDigest;
 use pkey::{PKeyRef, PKeyCtxRef};
+use rsa::{Padding, PssSaltLen};
 use error::ErrorStack;
 
 #[cfg(ossl110)]
@@ -94,6 +95,20 @@ impl<'a> Drop for Signer<'a> {
 
 impl<'a> Signer<'a> {
     pub fn new(type_: MessageDigest, pkey: &'a PKeyRef) -> Result<Signer<'a>, ErrorStack> {
+        Signer::new_intern(Some(type_), pkey)
+    }
+
+    /// Creates a new `Signer` which does not digest the data before signing.
+    ///
+    /// This is required for key types such as Ed25519 which operate on the message itself, and
+    /// otherwise selects the key type's default digest.
+    pub fn new_without_digest(pkey: &'a PKeyRef) -> Result<Signer<'a>, ErrorStack> {
+        Signer::new_intern(None, pkey)
+    }
+
+    fn new_intern(type_: Option<MessageDigest>,
+                  pkey: &'a PKeyRef)
+                  -> Result<Signer<'a>, ErrorStack> {
         unsafe {
             ffi::init();
 
@@ -101,7 +116,7 @@ impl<'a> Signer<'a> {
             let mut pctx: *mut ffi::EVP_PKEY_CTX = ptr::null_mut();
             let r = ffi::EVP_DigestSignInit(ctx,
                                             &mut pctx,
-                                            type_.as_ptr(),
+                                            type_.map_or(ptr::null(), |t| t.as_ptr()),
                                             ptr::null_mut(),
                                             pkey.as_ptr());
             if r != 1 {
@@ -127,6 +142,30 @@ impl<'a> Signer<'a> {
         unsafe { PKeyCtxRef::from_ptr_mut(self.pkey_ctx) }
     }
 
+    /// Sets the RSA padding scheme, such as `rsa::PKCS1_PSS_PADDING`.
+    ///
+    /// This is a shortcut for `pkey_ctx_mut().set_rsa_padding(padding)`.
+    pub fn set_rsa_padding(&mut self, padding: Padding) -> Result<(), ErrorStack> {
+        self.pkey_ctx_mut().set_rsa_padding(padding)
+    }
+
+    /// Sets the salt length used for RSA PSS padding.
+    ///
+    /// This is a shortcut for `pkey_ctx_mut().set_rsa_pss_saltlen(len)`.
+    pub fn set_rsa_pss_saltlen(&mut self, len: PssSaltLen) -> Result<(), ErrorStack> {
+        self.pkey_ctx_mut().set_rsa_pss_saltlen(len)
+    }
+
+    /// Sets the digest used by the MGF1 mask generation function for RSA PSS padding.
+    ///
+    /// This is a shortcut for `pkey_ctx_mut().set_rsa_mgf1_md(md)`.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_rsa_mgf1_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        self.pkey_ctx_mut().set_rsa_mgf1_md(md)
+    }
+
     pub fn update(&mut self, buf: &[u8]) -> Result<(), ErrorStack> {
         unsafe {
             cvt(ffi::EVP_DigestUpdate(self.md_ctx, buf.as_ptr() as *const _, buf.len())).map(|_| ())
@@ -144,6 +183,32 @@ impl<'a> Signer<'a> {
             Ok(buf)
         }
     }
+
+    /// Signs `data` in a single operation.
+    ///
+    /// This is the only way to sign with key types such as Ed25519 which do not support
+    /// incremental updates. It cannot be combined with `update`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn sign_oneshot(&mut self, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut len = 0;
+            try!(cvt(ffi::EVP_DigestSign(self.md_ctx,
+                                         ptr::null_mut(),
+                                         &mut len,
+                                         data.as_ptr(),
+                                         data.len())));
+            let mut buf = vec![0; len];
+            try!(cvt(ffi::EVP_DigestSign(self.md_ctx,
+                                         buf.as_mut_ptr(),
+                                         &mut len,
+                                         data.as_ptr(),
+                                         data.len())));
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
 }
 
 impl<'a> Write for Signer<'a> {
@@ -174,6 +239,20 @@ impl<'a> Drop for Verifier<'a> {
 
 impl<'a> Verifier<'a> {
     pub fn new(type_: MessageDigest, pkey: &'a PKeyRef) -> Result<Verifier<'a>, ErrorStack> {
+        Verifier::new_intern(Some(type_), pkey)
+    }
+
+    /// Creates a new `Verifier` which does not digest the data before verifying.
+    ///
+    /// This is required for key types such as Ed25519 which operate on the message itself, and
+    /// otherwise selects the key type's default digest.
+    pub fn new_without_digest(pkey: &'a PKeyRef) -> Result<Verifier<'a>, ErrorStack> {
+        Verifier::new_intern(None, pkey)
+    }
+
+    fn new_intern(type_: Option<MessageDigest>,
+                  pkey: &'a PKeyRef)
+                  -> Result<Verifier<'a>, ErrorStack> {
         unsafe {
             ffi::init();
 
@@ -181,7 +260,7 @@ impl<'a> Verifier<'a> {
             let mut pctx: *mut ffi::EVP_PKEY_CTX = ptr::null_mut();
             let r = ffi::EVP_DigestVerifyInit(ctx,
                                               &mut pctx,
-                                              type_.as_ptr(),
+                                              type_.map_or(ptr::null(), |t| t.as_ptr()),
                                               ptr::null_mut(),
                                               pkey.as_ptr());
             if r != 1 {
@@ -207,6 +286,30 @@ impl<'a> Verifier<'a> {
         unsafe { PKeyCtxRef::from_ptr_mut(self.pkey_ctx) }
     }
 
+    /// Sets the RSA padding scheme, such as `rsa::PKCS1_PSS_PADDING`.
+    ///
+    /// This is a shortcut for `pkey_ctx_mut().set_rsa_padding(padding)`.
+    pub fn set_rsa_padding(&mut self, padding: Padding) -> Result<(), ErrorStack> {
+        self.pkey_ctx_mut().set_rsa_padding(padding)
+    }
+
+    /// Sets the salt length used for RSA PSS padding.
+    ///
+    /// This is a shortcut for `pkey_ctx_mut().set_rsa_pss_saltlen(len)`.
+    pub fn set_rsa_pss_saltlen(&mut self, len: PssSaltLen) -> Result<(), ErrorStack> {
+        self.pkey_ctx_mut().set_rsa_pss_saltlen(len)
+    }
+
+    /// Sets the digest used by the MGF1 mask generation function for RSA PSS padding.
+    ///
+    /// This is a shortcut for `pkey_ctx_mut().set_rsa_mgf1_md(md)`.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_rsa_mgf1_md(&mut self, md: MessageDigest) -> Result<(), ErrorStack> {
+        self.pkey_ctx_mut().set_rsa_mgf1_md(md)
+    }
+
     pub fn update(&mut self, buf: &[u8]) -> Result<(), ErrorStack> {
         unsafe {
             cvt(ffi::EVP_DigestUpdate(self.md_ctx, buf.as_ptr() as *const _, buf.len())).map(|_| ())
@@ -226,6 +329,31 @@ impl<'a> Verifier<'a> {
             }
         }
     }
+
+    /// Verifies the signature of `data` in a single operation.
+    ///
+    /// This is the only way to verify signatures from key types such as Ed25519 which do not
+    /// support incremental updates. It cannot be combined with `update`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn verify_oneshot(&mut self, signature: &[u8], data: &[u8]) -> Result<bool, ErrorStack> {
+        unsafe {
+            let r = ffi::EVP_DigestVerify(self.md_ctx,
+                                          signature.as_ptr(),
+                                          signature.len(),
+                                          data.as_ptr(),
+                                          data.len());
+            match r {
+                1 => Ok(true),
+                0 => {
+                    ErrorStack::get(); // discard error stack
+                    Ok(false)
+                }
+                _ => Err(ErrorStack::get()),
+            }
+        }
+    }
 }
 
 impl<'a> Write for Verifier<'a> {
@@ -239,6 +367,50 @@ impl<'a> Write for Verifier<'a> {
     }
 }
 
+/// One-shot signing and verification of messages which are already in memory.
+///
+/// # Examples
+///
+/// ```
+/// use openssl::hash::MessageDigest;
+/// use openssl::pkey::PKey;
+/// use openssl::rsa::Rsa;
+/// use openssl::sign::simple;
+///
+/// let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+///
+/// let signature = simple::sign(MessageDigest::sha256(), &key, b"hello, world!").unwrap();
+/// assert!(simple::verify(MessageDigest::sha256(), &key, b"hello, world!", &signature).unwrap());
+/// ```
+pub mod simple {
+    use error::ErrorStack;
+    use hash::MessageDigest;
+    use pkey::PKeyRef;
+    use super::{Signer, Verifier};
+
+    /// Signs `data` with the private key `pkey` and the hash `type_`.
+    ///
+    /// This is a shortcut for creating a `Signer`, feeding it `data`, and finishing it.
+    pub fn sign(type_: MessageDigest, pkey: &PKeyRef, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+        let mut signer = try!(Signer::new(type_, pkey));
+        try!(signer.update(data));
+        signer.finish()
+    }
+
+    /// Checks that `signature` is a signature of `data` by the key `pkey` with the hash `type_`.
+    ///
+    /// This is a shortcut for creating a `Verifier`, feeding it `data`, and finishing it.
+    pub fn verify(type_: MessageDigest,
+                  pkey: &PKeyRef,
+                  data: &[u8],
+                  signature: &[u8])
+                  -> Result<bool, ErrorStack> {
+        let mut verifier = try!(Verifier::new(type_, pkey));
+        try!(verifier.update(data));
+        verifier.finish(signature)
+    }
+}
+
 #[cfg(not(ossl101))]
 use ffi::EVP_DigestVerifyFinal;
 
@@ -257,10 +429,10 @@ mod test {
     use std::iter;
 
     use hash::MessageDigest;
-    use sign::{Signer, Verifier};
+    use sign::{simple, Signer, Verifier};
     use ec::{EcGroup, EcKey};
     use nid;
-    use rsa::{Rsa, PKCS1_PADDING};
+    use rsa::{Rsa, PssSaltLen, PKCS1_PADDING, PKCS1_PSS_PADDING, PSS_SALTLEN_DIGEST};
     use dsa::Dsa;
     use pkey::PKey;
 
@@ -315,6 +487,61 @@ mod test {
         assert!(verifier.finish(SIGNATURE).unwrap());
     }
 
+    #[test]
+    fn rsa_pss_sign_verify() {
+        let key = include_bytes!("../test/rsa.pem");
+        let private_key = Rsa::private_key_from_pem(key).unwrap();
+        let pkey = PKey::from_rsa(private_key).unwrap();
+
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey).unwrap();
+        signer.set_rsa_padding(PKCS1_PSS_PADDING).unwrap();
+        signer.set_rsa_pss_saltlen(PSS_SALTLEN_DIGEST).unwrap();
+        signer.update(INPUT).unwrap();
+        let signature = signer.finish().unwrap();
+        assert!(signature != SIGNATURE);
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &pkey).unwrap();
+        verifier.set_rsa_padding(PKCS1_PSS_PADDING).unwrap();
+        verifier.set_rsa_pss_saltlen(PSS_SALTLEN_DIGEST).unwrap();
+        verifier.update(INPUT).unwrap();
+        assert!(verifier.finish(&signature).unwrap());
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &pkey).unwrap();
+        verifier.set_rsa_padding(PKCS1_PSS_PADDING).unwrap();
+        verifier.set_rsa_pss_saltlen(PssSaltLen::custom(20)).unwrap();
+        verifier.update(INPUT).unwrap();
+        assert!(!verifier.finish(&signature).unwrap());
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &pkey).unwrap();
+        verifier.update(INPUT).unwrap();
+        assert!(!verifier.finish(&signature).unwrap());
+    }
+
+    #[test]
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    fn rsa_pss_mgf1_md() {
+        let key = include_bytes!("../test/rsa.pem");
+        let private_key = Rsa::private_key_from_pem(key).unwrap();
+        let pkey = PKey::from_rsa(private_key).unwrap();
+
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey).unwrap();
+        signer.set_rsa_padding(PKCS1_PSS_PADDING).unwrap();
+        signer.set_rsa_mgf1_md(MessageDigest::sha1()).unwrap();
+        signer.update(INPUT).unwrap();
+        let signature = signer.finish().unwrap();
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &pkey).unwrap();
+        verifier.set_rsa_padding(PKCS1_PSS_PADDING).unwrap();
+        verifier.set_rsa_mgf1_md(MessageDigest::sha1()).unwrap();
+        verifier.update(INPUT).unwrap();
+        assert!(verifier.finish(&signature).unwrap());
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &pkey).unwrap();
+        verifier.set_rsa_padding(PKCS1_PSS_PADDING).unwrap();
+        verifier.update(INPUT).unwrap();
+        assert!(!verifier.finish(&signature).unwrap());
+    }
+
     #[test]
     fn rsa_verify_invalid() {
         let key = include_bytes!("../test/rsa.pem");
@@ -327,6 +554,18 @@ mod test {
         assert!(!verifier.finish(SIGNATURE).unwrap());
     }
 
+    #[test]
+    fn sign_verify() {
+        let key = include_bytes!("../test/rsa.pem");
+        let private_key = Rsa::private_key_from_pem(key).unwrap();
+        let pkey = PKey::from_rsa(private_key).unwrap();
+
+        let signature = simple::sign(MessageDigest::sha256(), &pkey, INPUT).unwrap();
+        assert_eq!(signature, SIGNATURE);
+        assert!(simple::verify(MessageDigest::sha256(), &pkey, INPUT, &signature).unwrap());
+        assert!(!simple::verify(MessageDigest::sha256(), &pkey, b"foobar", &signature).unwrap());
+    }
+
     #[test]
     pub fn dsa_sign_verify() {
         let input: Vec<u8> = (0..25).cycle().take(1024).collect();
@@ -462,4 +701,57 @@ mod test {
         verifier.update(b"hello world").unwrap();
         assert!(verifier.finish(&signature).unwrap());
     }
+
+    #[test]
+    fn rsa_without_digest() {
+        let key = include_bytes!("../test/rsa.pem");
+        let key = PKey::from_rsa(Rsa::private_key_from_pem(key).unwrap()).unwrap();
+
+        let mut signer = Signer::new_without_digest(&key).unwrap();
+        signer.update(b"hello world").unwrap();
+        let signature = signer.finish().unwrap();
+
+        let mut verifier = Verifier::new_without_digest(&key).unwrap();
+        verifier.update(b"hello world").unwrap();
+        assert!(verifier.finish(&signature).unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn ed25519() {
+        // RFC 8032 section 7.1, test 2
+        let private = Vec::from_hex("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb")
+            .unwrap();
+        let public = Vec::from_hex("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c")
+            .unwrap();
+        let expected = Vec::from_hex("92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                                      085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00")
+            .unwrap();
+
+        let key = PKey::private_key_from_raw_bytes(&private, nid::ED25519).unwrap();
+        assert_eq!(key.raw_public_key().unwrap(), public);
+
+        let mut signer = Signer::new_without_digest(&key).unwrap();
+        let signature = signer.sign_oneshot(&[0x72]).unwrap();
+        assert_eq!(signature, expected);
+
+        let key = PKey::public_key_from_raw_bytes(&public, nid::ED25519).unwrap();
+        let mut verifier = Verifier::new_without_digest(&key).unwrap();
+        assert!(verifier.verify_oneshot(&signature, &[0x72]).unwrap());
+        let mut verifier = Verifier::new_without_digest(&key).unwrap();
+        assert!(!verifier.verify_oneshot(&signature, &[0x73]).unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "v111", ossl111))]
+    fn ed25519_generate() {
+        let key = PKey::generate_ed25519().unwrap();
+
+        let mut signer = Signer::new_without_digest(&key).unwrap();
+        let signature = signer.sign_oneshot(b"hello world").unwrap();
+        assert_eq!(signature.len(), 64);
+
+        let mut verifier = Verifier::new_without_digest(&key).unwrap();
+        assert!(verifier.verify_oneshot(&signature, b"hello world").unwrap());
+    }
 }
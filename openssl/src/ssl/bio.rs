@@ -16,6 +16,7 @@ pub struct StreamState<S> {
     pub stream: S,
     pub error: Option<io::Error>,
     pub panic: Option<Box<Any + Send>>,
+    pub dgram: bool,
 }
 
 /// Safe wrapper for BIO_METHOD
@@ -30,13 +31,18 @@ impl BioMethod {
 unsafe impl Sync for BioMethod {}
 unsafe impl Send for BioMethod {}
 
-pub fn new<S: Read + Write>(stream: S) -> Result<(*mut BIO, BioMethod), ErrorStack> {
+/// Creates a BIO wrapping `stream`.
+///
+/// If `dgram` is set, each write to the BIO is a single datagram which must be passed to the
+/// stream in one `write` call.
+pub fn new<S: Read + Write>(stream: S, dgram: bool) -> Result<(*mut BIO, BioMethod), ErrorStack> {
     let method = BioMethod::new::<S>();
 
     let state = Box::new(StreamState {
         stream: stream,
         error: None,
         panic: None,
+        dgram: dgram,
     });
 
     unsafe {
@@ -78,6 +84,12 @@ unsafe extern fn bwrite<S: Write>(bio: *mut BIO, buf: *const c_char, len: c_int)
     let buf = slice::from_raw_parts(buf as *const _, len as usize);
 
     match catch_unwind(AssertUnwindSafe(|| state.stream.write(buf))) {
+        // OpenSSL would send the rest of a short write as a separate datagram
+        Ok(Ok(written)) if state.dgram && written != buf.len() => {
+            state.error = Some(io::Error::new(io::ErrorKind::WriteZero,
+                                              "datagram was only partially written"));
+            -1
+        }
         Ok(Ok(len)) => len as c_int,
         Ok(Err(err)) => {
             if retriable_error(&err) {
@@ -1,11 +1,18 @@
 use std::io::{Read, Write};
+use std::sync::Arc;
+
+use ffi;
+use libc::c_long;
 
 use dh::Dh;
 use error::ErrorStack;
-use ssl::{self, SslMethod, SslContextBuilder, SslContext, Ssl, SSL_VERIFY_PEER, SslStream,
-          HandshakeError};
+use hash::{self, MessageDigest};
+use ocsp::{self, OcspCertId, OcspResponse};
+use ssl::{self, SslMethod, SslContextBuilder, SslContext, Ssl, SslRef, SSL_VERIFY_PEER, SslStream,
+          HandshakeError, STATUS_TYPE_OCSP};
 use pkey::PKeyRef;
-use x509::X509Ref;
+use x509::{X509, X509Ref, X509StoreContextRef, X509VerifyError};
+use x509::store::X509_V_FLAG_CRL_CHECK;
 
 // ffdhe2048 from https://wiki.mozilla.org/Security/Server_Side_TLS#ffdhe2048
 const DHPARAM_PEM: &'static str = "
@@ -41,8 +48,24 @@ fn ctx(method: SslMethod) -> Result<SslContextBuilder, ErrorStack> {
     Ok(ctx)
 }
 
+/// How an `SslConnector` treats a peer certificate whose revocation status cannot be determined.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RevocationPolicy {
+    /// Only reject certificates which are known to be revoked.
+    ///
+    /// A missing CRL, a missing stapled OCSP response, or a stapled response which cannot be
+    /// verified does not cause the handshake to fail.
+    SoftFail,
+    /// Reject certificates which are not known to be valid.
+    ///
+    /// The CRL for the peer's certificate must be present in the context's certificate store, and
+    /// a stapled OCSP response, if provided, must successfully verify and report the certificate
+    /// as good.
+    HardFail,
+}
+
 /// A builder for `SslConnector`s.
-pub struct SslConnectorBuilder(SslContextBuilder);
+pub struct SslConnectorBuilder(SslContextBuilder, Option<RevocationPolicy>, Option<Arc<Vec<[u8; 32]>>>);
 
 impl SslConnectorBuilder {
     /// Creates a new builder for TLS connections.
@@ -57,7 +80,38 @@ impl SslConnectorBuilder {
                                   RSA+AES:RSA+HIGH:!aNULL:!eNULL:!MD5:!3DES"));
         ctx.set_verify(SSL_VERIFY_PEER);
 
-        Ok(SslConnectorBuilder(ctx))
+        Ok(SslConnectorBuilder(ctx, None, None))
+    }
+
+    /// Enables revocation checking of the server's certificate.
+    ///
+    /// The leaf certificate is checked against the CRLs in the context's certificate store, which
+    /// can be added with `X509StoreBuilderRef::add_crl`. In addition, a stapled OCSP response is
+    /// requested from the server and, if provided, is verified against the peer's certificate
+    /// chain and the context's certificate store. A certificate reported as revoked by either
+    /// mechanism always causes the handshake to fail; `policy` determines how an unknown status is
+    /// treated.
+    ///
+    /// This replaces any status callback previously set on the inner `SslContextBuilder`.
+    pub fn set_revocation_check(&mut self, policy: RevocationPolicy) -> Result<(), ErrorStack> {
+        try!(self.0.cert_store_mut().set_flags(X509_V_FLAG_CRL_CHECK));
+        try!(self.0.set_status_callback(move |ssl| Ok(check_ocsp_status(ssl, policy))));
+        self.1 = Some(policy);
+        Ok(())
+    }
+
+    /// Restricts the server's certificate chain to one containing a pinned public key.
+    ///
+    /// Each pin is the SHA-256 digest of a DER-encoded SubjectPublicKeyInfo, which can be
+    /// computed by hashing the output of `PKeyRef::public_key_to_der`. The pins are checked
+    /// against the verified chain once standard certificate and hostname verification of the
+    /// chain has succeeded, and the handshake fails unless at least one certificate in it matches.
+    /// Pinning therefore only further restricts the set of trusted servers.
+    ///
+    /// Pins are not checked by
+    /// `danger_connect_without_providing_domain_for_certificate_verification_and_server_name_indication`.
+    pub fn set_spki_pins(&mut self, pins: &[[u8; 32]]) {
+        self.2 = Some(Arc::new(pins.to_vec()));
     }
 
     /// Returns a shared reference to the inner `SslContextBuilder`.
@@ -72,7 +126,7 @@ impl SslConnectorBuilder {
 
     /// Consumes the builder, returning a `SslConnector`.
     pub fn build(self) -> SslConnector {
-        SslConnector(self.0.build())
+        SslConnector(self.0.build(), self.1, self.2)
     }
 }
 
@@ -84,7 +138,7 @@ impl SslConnectorBuilder {
 /// OpenSSL's built in hostname verification is used when linking against OpenSSL 1.0.2 or 1.1.0,
 /// and a custom implementation is used when linking against OpenSSL 1.0.1.
 #[derive(Clone)]
-pub struct SslConnector(SslContext);
+pub struct SslConnector(SslContext, Option<RevocationPolicy>, Option<Arc<Vec<[u8; 32]>>>);
 
 impl SslConnector {
     /// Initiates a client-side TLS session on a stream.
@@ -95,7 +149,10 @@ impl SslConnector {
     {
         let mut ssl = try!(Ssl::new(&self.0));
         try!(ssl.set_hostname(domain));
-        try!(setup_verify(&mut ssl, domain));
+        try!(setup_verify(&mut ssl, domain, self.1, self.2.clone()));
+        if self.1.is_some() {
+            try!(ssl.set_status_type(STATUS_TYPE_OCSP));
+        }
 
         ssl.connect(stream)
     }
@@ -260,26 +317,196 @@ impl SslAcceptor {
         let ssl = try!(Ssl::new(&self.0));
         ssl.accept(stream)
     }
+
+    /// Like `accept`, but additionally returns a summary of the negotiated session.
+    pub fn accept_with_summary<S>(&self,
+                                  stream: S)
+                                  -> Result<(SslStream<S>, HandshakeSummary), HandshakeError<S>>
+        where S: Read + Write
+    {
+        let stream = try!(self.accept(stream));
+        let summary = HandshakeSummary::new(stream.ssl());
+        Ok((stream, summary))
+    }
+}
+
+/// Information about a session negotiated by a completed handshake.
+pub struct HandshakeSummary {
+    /// The server name requested by the client through SNI, if any.
+    pub servername: Option<String>,
+    /// The protocol selected through ALPN, if any.
+    ///
+    /// This is always `None` unless the `v102` or `v110` features are enabled with OpenSSL 1.0.2
+    /// or OpenSSL 1.1.0.
+    pub alpn_protocol: Option<Vec<u8>>,
+    /// The certificate presented by the peer, if any.
+    pub peer_certificate: Option<X509>,
+}
+
+impl HandshakeSummary {
+    /// Collects a summary of the session of a stream which has completed its handshake.
+    pub fn new(ssl: &SslRef) -> HandshakeSummary {
+        HandshakeSummary {
+            servername: ssl.servername().map(|s| s.to_owned()),
+            alpn_protocol: alpn_protocol(ssl),
+            peer_certificate: ssl.peer_certificate(),
+        }
+    }
+}
+
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+fn alpn_protocol(ssl: &SslRef) -> Option<Vec<u8>> {
+    ssl.selected_alpn_protocol().map(|p| p.to_owned())
+}
+
+#[cfg(not(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110))))]
+fn alpn_protocol(_: &SslRef) -> Option<Vec<u8>> {
+    None
 }
 
 #[cfg(any(ossl102, ossl110))]
-fn setup_verify(ssl: &mut Ssl, domain: &str) -> Result<(), ErrorStack> {
-    // pass a noop closure in here to ensure that we consistently override any callback on the
+fn setup_verify(ssl: &mut Ssl,
+                domain: &str,
+                revocation: Option<RevocationPolicy>,
+                pins: Option<Arc<Vec<[u8; 32]>>>)
+                -> Result<(), ErrorStack> {
+    // pass a closure in here to ensure that we consistently override any callback on the
     // context
-    ssl.set_verify_callback(SSL_VERIFY_PEER, |p, _| p);
+    ssl.set_verify_callback(SSL_VERIFY_PEER, move |p, x| {
+        let p = p || ignore_missing_crl(revocation, x);
+        check_pins(pins.as_ref().map(|p| &***p), p, x)
+    });
     let param = ssl._param_mut();
     param.set_hostflags(::verify::X509_CHECK_FLAG_NO_PARTIAL_WILDCARDS);
-    param.set_host(domain)
+    try!(param.set_host(domain));
+    ssl.record_verify_hostname(domain);
+    Ok(())
 }
 
 #[cfg(ossl101)]
-fn setup_verify(ssl: &mut Ssl, domain: &str) -> Result<(), ErrorStack> {
+fn setup_verify(ssl: &mut Ssl,
+                domain: &str,
+                revocation: Option<RevocationPolicy>,
+                pins: Option<Arc<Vec<[u8; 32]>>>)
+                -> Result<(), ErrorStack> {
+    ssl.record_verify_hostname(domain);
     let domain = domain.to_owned();
-    ssl.set_verify_callback(SSL_VERIFY_PEER,
-                            move |p, x| verify::verify_callback(&domain, p, x));
+    ssl.set_verify_callback(SSL_VERIFY_PEER, move |p, x| {
+        let p = p || ignore_missing_crl(revocation, x);
+        let p = verify::verify_callback(&domain, p, x);
+        check_pins(pins.as_ref().map(|p| &***p), p, x)
+    });
     Ok(())
 }
 
+// Under the soft-fail policy, allows verification to continue when no CRL is available.
+fn ignore_missing_crl(revocation: Option<RevocationPolicy>,
+                      x509_ctx: &mut X509StoreContextRef)
+                      -> bool {
+    if revocation != Some(RevocationPolicy::SoftFail) {
+        return false;
+    }
+
+    match x509_ctx.error() {
+        Some(ref e) if e.as_raw() == ffi::X509_V_ERR_UNABLE_TO_GET_CRL as c_long => {
+            x509_ctx.set_error(None);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Checks the verified chain against the pinned keys.
+//
+// OpenSSL calls the verify callback for the leaf last, after the rest of the chain and the
+// hostname have been checked, so a successful call at depth 0 means that the chain is otherwise
+// trusted.
+fn check_pins(pins: Option<&[[u8; 32]]>,
+              preverify_ok: bool,
+              x509_ctx: &mut X509StoreContextRef)
+              -> bool {
+    let pins = match pins {
+        Some(pins) => pins,
+        None => return preverify_ok,
+    };
+    if !preverify_ok || x509_ctx.error_depth() != 0 {
+        return preverify_ok;
+    }
+
+    let matched = match x509_ctx.chain() {
+        Some(chain) => chain.iter().any(|cert| spki_pin_matches(pins, cert)),
+        None => false,
+    };
+    if !matched {
+        let error = ffi::X509_V_ERR_APPLICATION_VERIFICATION as c_long;
+        x509_ctx.set_error(unsafe { X509VerifyError::from_raw(error) });
+    }
+    matched
+}
+
+fn spki_pin_matches(pins: &[[u8; 32]], cert: &X509Ref) -> bool {
+    let spki = match cert.public_key().and_then(|k| k.public_key_to_der()) {
+        Ok(spki) => spki,
+        Err(_) => return false,
+    };
+    let digest = match hash::hash(MessageDigest::sha256(), &spki) {
+        Ok(digest) => digest,
+        Err(_) => return false,
+    };
+    pins.iter().any(|pin| pin[..] == digest[..])
+}
+
+// Returns whether the handshake should continue given the server's stapled OCSP response.
+fn check_ocsp_status(ssl: &SslRef, policy: RevocationPolicy) -> bool {
+    let unknown = policy == RevocationPolicy::SoftFail;
+
+    let response = match ssl.ocsp_status() {
+        Some(response) => response,
+        None => return true,
+    };
+    let response = match OcspResponse::from_der(response) {
+        Ok(response) => response,
+        Err(_) => return unknown,
+    };
+    if response.status() != ocsp::RESPONSE_STATUS_SUCCESSFUL {
+        return unknown;
+    }
+    let basic = match response.basic() {
+        Ok(basic) => basic,
+        Err(_) => return unknown,
+    };
+
+    let chain = match ssl.peer_cert_chain() {
+        Some(chain) => chain,
+        None => return unknown,
+    };
+    if basic.verify(chain, ssl.ssl_context().cert_store(), ocsp::Flag::empty()).is_err() {
+        return unknown;
+    }
+
+    let leaf = match ssl.peer_certificate() {
+        Some(leaf) => leaf,
+        None => return unknown,
+    };
+    let issuer = match chain.iter().find(|c| c.issued(&leaf).is_ok()) {
+        Some(issuer) => issuer,
+        None => return unknown,
+    };
+    let id = match OcspCertId::from_cert(MessageDigest::sha1(), &leaf, issuer) {
+        Ok(id) => id,
+        Err(_) => return unknown,
+    };
+
+    match basic.find_status(&id) {
+        Some(ref status) if status.status == ocsp::CERT_STATUS_REVOKED => false,
+        Some(ref status) if status.status == ocsp::CERT_STATUS_GOOD => {
+            // allow five minutes of clock skew
+            status.check_validity(300, None).is_ok() || unknown
+        }
+        _ => unknown,
+    }
+}
+
 #[cfg(ossl101)]
 mod verify {
     use std::net::IpAddr;
@@ -1,3 +1,5 @@
+use ffi;
+use libc::c_int;
 use std::any::Any;
 use std::error;
 use std::error::Error as StdError;
@@ -5,7 +7,7 @@ use std::fmt;
 use std::io;
 
 use error::ErrorStack;
-use ssl::MidHandshakeSslStream;
+use ssl::{MidHandshakeSslStream, SslAlert};
 
 /// An SSL error.
 #[derive(Debug)]
@@ -26,6 +28,23 @@ pub enum Error {
     Ssl(ErrorStack),
 }
 
+impl Error {
+    /// Returns the OpenSSL error code corresponding to this error.
+    ///
+    /// Nonblocking streams should retry the operation once the underlying
+    /// socket is ready if this returns `ERROR_WANT_READ` or `ERROR_WANT_WRITE`.
+    pub fn code(&self) -> ErrorCode {
+        match *self {
+            Error::ZeroReturn => ERROR_ZERO_RETURN,
+            Error::WantRead(_) => ERROR_WANT_READ,
+            Error::WantWrite(_) => ERROR_WANT_WRITE,
+            Error::WantX509Lookup => ERROR_WANT_X509_LOOKUP,
+            Error::Stream(_) => ERROR_SYSCALL,
+            Error::Ssl(_) => ERROR_SSL,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         try!(fmt.write_str(self.description()));
@@ -66,6 +85,67 @@ impl From<ErrorStack> for Error {
     }
 }
 
+/// Errors reported by the underlying stream are returned as-is, and all other errors are wrapped
+/// in an `io::Error` of kind `Other`.
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        match e {
+            Error::Stream(e) |
+            Error::WantRead(e) |
+            Error::WantWrite(e) => e,
+            e => io::Error::new(io::ErrorKind::Other, e),
+        }
+    }
+}
+
+impl Error {
+    /// Recovers an `Error` previously converted into an `io::Error`.
+    ///
+    /// An `io::Error` wrapping an `ErrorStack` is returned as `Error::Ssl`. The original
+    /// `io::Error` is returned if it wraps neither type, which includes errors from the underlying
+    /// stream.
+    pub fn from_io_error(err: io::Error) -> Result<Error, io::Error> {
+        if !err.get_ref().map_or(false, |e| e.is::<Error>()) {
+            return ErrorStack::from_io_error(err).map(Error::Ssl);
+        }
+        Ok(*err.into_inner().unwrap().downcast::<Error>().unwrap())
+    }
+}
+
+/// An error code returned from SSL I/O functions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorCode(c_int);
+
+impl ErrorCode {
+    pub fn from_raw(raw: c_int) -> ErrorCode {
+        ErrorCode(raw)
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+}
+
+/// The SSL session has been closed by the other end.
+pub const ERROR_ZERO_RETURN: ErrorCode = ErrorCode(ffi::SSL_ERROR_ZERO_RETURN);
+
+/// The operation could not complete because the underlying stream was not
+/// readable.
+pub const ERROR_WANT_READ: ErrorCode = ErrorCode(ffi::SSL_ERROR_WANT_READ);
+
+/// The operation could not complete because the underlying stream was not
+/// writable.
+pub const ERROR_WANT_WRITE: ErrorCode = ErrorCode(ffi::SSL_ERROR_WANT_WRITE);
+
+/// The client certificate callback requested to be called again.
+pub const ERROR_WANT_X509_LOOKUP: ErrorCode = ErrorCode(ffi::SSL_ERROR_WANT_X509_LOOKUP);
+
+/// An error was reported by the underlying stream.
+pub const ERROR_SYSCALL: ErrorCode = ErrorCode(ffi::SSL_ERROR_SYSCALL);
+
+/// An error occurred in the OpenSSL library.
+pub const ERROR_SSL: ErrorCode = ErrorCode(ffi::SSL_ERROR_SSL);
+
 /// An error or intermediate state after a TLS handshake attempt.
 #[derive(Debug)]
 pub enum HandshakeError<S> {
@@ -77,6 +157,21 @@ pub enum HandshakeError<S> {
     Interrupted(MidHandshakeSslStream<S>),
 }
 
+impl<S> HandshakeError<S> {
+    /// Returns the alert sent by the peer before the handshake failed, if any.
+    ///
+    /// The alert describes why the peer rejected the handshake, for example `ALERT_UNKNOWN_CA`
+    /// if it did not trust this side's certificate or `ALERT_PROTOCOL_VERSION` if no protocol
+    /// version was acceptable to both sides.
+    pub fn peer_alert(&self) -> Option<SslAlert> {
+        match *self {
+            HandshakeError::SetupFailure(_) => None,
+            HandshakeError::Failure(ref s) |
+            HandshakeError::Interrupted(ref s) => s.ssl().peer_alert(),
+        }
+    }
+}
+
 impl<S: Any + fmt::Debug> StdError for HandshakeError<S> {
     fn description(&self) -> &str {
         match *self {
@@ -106,6 +201,9 @@ impl<S: Any + fmt::Debug> fmt::Display for HandshakeError<S> {
                 if let Some(err) = s.ssl().verify_result() {
                     try!(write!(f, ": {}", err));
                 }
+                if let Some(alert) = s.ssl().peer_alert() {
+                    try!(write!(f, ": received {}", alert));
+                }
             }
         }
         Ok(())
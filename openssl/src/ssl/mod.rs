@@ -70,9 +70,17 @@
 //!     }
 //! }
 //! ```
+//!
+//! # Panics in callbacks
+//!
+//! Callbacks such as those for certificate verification or SNI are invoked from within OpenSSL,
+//! which cannot be safely unwound through. If one panics, the panic is caught and OpenSSL is told
+//! the callback failed, which typically aborts the handshake. The panic is then resumed from the
+//! `Ssl` or `SslStream` method which was running when it occurred, once OpenSSL has returned
+//! control to Rust.
 use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
-use libc::{c_int, c_void, c_long, c_ulong};
+use libc::{c_char, c_int, c_void, c_long, c_ulong};
 use libc::{c_uchar, c_uint};
 use std::any::Any;
 use std::any::TypeId;
@@ -84,8 +92,9 @@ use std::io;
 use std::io::prelude::*;
 use std::marker::PhantomData;
 use std::mem;
+use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
-use std::panic::resume_unwind;
+use std::panic::{self, resume_unwind, AssertUnwindSafe};
 use std::path::Path;
 use std::ptr;
 use std::slice;
@@ -97,14 +106,15 @@ use dh::{Dh, DhRef};
 use ec::EcKeyRef;
 #[cfg(any(all(feature = "v101", ossl101), all(feature = "v102", ossl102)))]
 use ec::EcKey;
-use x509::{X509StoreContextRef, X509FileType, X509, X509Ref, X509VerifyError, X509Name};
+use x509::{X509StoreContext, X509StoreContextRef, X509FileType, X509, X509Ref, X509VerifyError, X509Name};
 use x509::store::{X509StoreBuilderRef, X509StoreRef};
 #[cfg(any(ossl102, ossl110))]
 use verify::X509VerifyParamRef;
 use pkey::PKeyRef;
 use error::ErrorStack;
+use nid::Nid;
 use util::Opaque;
-use stack::{Stack, StackRef};
+use stack::{Stack, StackRef, Stackable};
 
 mod error;
 mod connector;
@@ -114,8 +124,10 @@ mod tests;
 
 use self::bio::BioMethod;
 
-pub use ssl::connector::{SslConnectorBuilder, SslConnector, SslAcceptorBuilder, SslAcceptor};
-pub use ssl::error::{Error, HandshakeError};
+pub use ssl::connector::{SslConnectorBuilder, SslConnector, SslAcceptorBuilder, SslAcceptor,
+                         HandshakeSummary, RevocationPolicy};
+pub use ssl::error::{Error, ErrorCode, HandshakeError, ERROR_ZERO_RETURN, ERROR_WANT_READ,
+                     ERROR_WANT_WRITE, ERROR_WANT_X509_LOOKUP, ERROR_SYSCALL, ERROR_SSL};
 
 bitflags! {
     pub flags SslOption: c_ulong {
@@ -156,6 +168,13 @@ bitflags! {
         /// Requires the `v102` or `v110` features and OpenSSL 1.0.2 or OpenSSL 1.1.0.
         #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
         const SSL_OP_NO_SSL_MASK = ffi::SSL_OP_NO_SSL_MASK,
+        /// Requires OpenSSL 1.1.1. See `SslContextBuilder::set_renegotiation_disabled` for a
+        /// portable alternative.
+        #[cfg(ossl111)]
+        const SSL_OP_NO_RENEGOTIATION = ffi::SSL_OP_NO_RENEGOTIATION,
+        /// Requires OpenSSL 1.1.1.
+        #[cfg(ossl111)]
+        const SSL_OP_NO_TLSV1_3 = ffi::SSL_OP_NO_TLSv1_3,
     }
 }
 
@@ -175,6 +194,25 @@ bitflags! {
     }
 }
 
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+bitflags! {
+    /// Options for `SslContextBuilder::build_cert_chain`.
+    pub flags SslBuildChainFlags: c_long {
+        /// Use the existing chain as untrusted intermediates in addition to the chain store.
+        const SSL_BUILD_CHAIN_FLAG_UNTRUSTED = ffi::SSL_BUILD_CHAIN_FLAG_UNTRUSTED,
+        /// Leave the root certificate out of the chain.
+        const SSL_BUILD_CHAIN_FLAG_NO_ROOT = ffi::SSL_BUILD_CHAIN_FLAG_NO_ROOT,
+        /// Build the chain from the existing chain certificates only, which checks that they are
+        /// complete and reorders them.
+        const SSL_BUILD_CHAIN_FLAG_CHECK = ffi::SSL_BUILD_CHAIN_FLAG_CHECK,
+        /// Ignore errors verifying the built chain.
+        const SSL_BUILD_CHAIN_FLAG_IGNORE_ERROR = ffi::SSL_BUILD_CHAIN_FLAG_IGNORE_ERROR,
+        /// Clear the error stack if verification fails and `SSL_BUILD_CHAIN_FLAG_IGNORE_ERROR`
+        /// is set.
+        const SSL_BUILD_CHAIN_FLAG_CLEAR_ERROR = ffi::SSL_BUILD_CHAIN_FLAG_CLEAR_ERROR,
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct SslMethod(*const ffi::SSL_METHOD);
 
@@ -191,10 +229,30 @@ impl SslMethod {
     ///
     /// This corresponds to `DTLS_method` on OpenSSL 1.1.0 and `DTLSv1_method`
     /// on OpenSSL 1.0.x.
+    ///
+    /// The stream used with a DTLS `Ssl` must preserve message boundaries, as
+    /// a connected `UdpSocket` does: each `write` must send a single datagram
+    /// and each `read` must return a single datagram.
     pub fn dtls() -> SslMethod {
         SslMethod(compat::dtls_method())
     }
 
+    /// Support all versions of the DTLS protocol, for use by clients only.
+    ///
+    /// This corresponds to `DTLS_client_method` on OpenSSL 1.1.0 and
+    /// `DTLSv1_client_method` on OpenSSL 1.0.x.
+    pub fn dtls_client() -> SslMethod {
+        SslMethod(compat::dtls_client_method())
+    }
+
+    /// Support all versions of the DTLS protocol, for use by servers only.
+    ///
+    /// This corresponds to `DTLS_server_method` on OpenSSL 1.1.0 and
+    /// `DTLSv1_server_method` on OpenSSL 1.0.x.
+    pub fn dtls_server() -> SslMethod {
+        SslMethod(compat::dtls_server_method())
+    }
+
     pub unsafe fn from_ptr(ptr: *const ffi::SSL_METHOD) -> SslMethod {
         SslMethod(ptr)
     }
@@ -214,6 +272,13 @@ bitflags! {
         /// Terminate handshake if client did not return a certificate.
         /// Use together with SSL_VERIFY_PEER.
         const SSL_VERIFY_FAIL_IF_NO_PEER_CERT = ::ffi::SSL_VERIFY_FAIL_IF_NO_PEER_CERT,
+        /// Do not request a client certificate during the initial TLS 1.3 handshake, only when
+        /// `SslRef::verify_client_post_handshake` is called.
+        /// Use together with SSL_VERIFY_PEER.
+        ///
+        /// Requires the `v111` feature and OpenSSL 1.1.1.
+        #[cfg(all(feature = "v111", ossl111))]
+        const SSL_VERIFY_POST_HANDSHAKE = ::ffi::SSL_VERIFY_POST_HANDSHAKE,
     }
 }
 
@@ -230,9 +295,243 @@ impl StatusType {
     }
 }
 
-/// An OSCP status.
+/// An OCSP status.
 pub const STATUS_TYPE_OCSP: StatusType = StatusType(ffi::TLSEXT_STATUSTYPE_ocsp);
 
+/// A maximum fragment length negotiated with the RFC 6066 max_fragment_length extension.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaxFragmentLength(u8);
+
+#[cfg(all(feature = "v111", ossl111))]
+impl MaxFragmentLength {
+    /// Creates a `MaxFragmentLength` from the code used for it by the extension
+    /// (`TLSEXT_max_fragment_length_*`).
+    pub fn from_raw(raw: u8) -> MaxFragmentLength {
+        MaxFragmentLength(raw)
+    }
+
+    /// Returns the code used for this length by the extension.
+    pub fn as_raw(&self) -> u8 {
+        self.0
+    }
+}
+
+/// An identifier of a DTLS-SRTP protection profile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SrtpProfileId(c_ulong);
+
+impl SrtpProfileId {
+    pub fn from_raw(raw: c_ulong) -> SrtpProfileId {
+        SrtpProfileId(raw)
+    }
+
+    pub fn as_raw(&self) -> c_ulong {
+        self.0
+    }
+}
+
+/// `SRTP_AES128_CM_HMAC_SHA1_80`
+pub const SRTP_AES128_CM_SHA1_80: SrtpProfileId = SrtpProfileId(ffi::SRTP_AES128_CM_SHA1_80);
+
+/// `SRTP_AES128_CM_HMAC_SHA1_32`
+pub const SRTP_AES128_CM_SHA1_32: SrtpProfileId = SrtpProfileId(ffi::SRTP_AES128_CM_SHA1_32);
+
+/// An SSL/TLS protocol version.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SslVersion(c_int);
+
+impl SslVersion {
+    pub fn from_raw(raw: c_int) -> SslVersion {
+        SslVersion(raw)
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+}
+
+/// Does not request a maximum fragment length.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const MAX_FRAGMENT_LENGTH_DISABLED: MaxFragmentLength =
+    MaxFragmentLength(ffi::TLSEXT_max_fragment_length_DISABLED);
+
+/// Fragments of at most 512 bytes.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const MAX_FRAGMENT_LENGTH_512: MaxFragmentLength =
+    MaxFragmentLength(ffi::TLSEXT_max_fragment_length_512);
+
+/// Fragments of at most 1024 bytes.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const MAX_FRAGMENT_LENGTH_1024: MaxFragmentLength =
+    MaxFragmentLength(ffi::TLSEXT_max_fragment_length_1024);
+
+/// Fragments of at most 2048 bytes.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const MAX_FRAGMENT_LENGTH_2048: MaxFragmentLength =
+    MaxFragmentLength(ffi::TLSEXT_max_fragment_length_2048);
+
+/// Fragments of at most 4096 bytes.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const MAX_FRAGMENT_LENGTH_4096: MaxFragmentLength =
+    MaxFragmentLength(ffi::TLSEXT_max_fragment_length_4096);
+
+/// SSLv3
+pub const SSL3_VERSION: SslVersion = SslVersion(ffi::SSL3_VERSION);
+
+/// TLSv1.0
+pub const TLS1_VERSION: SslVersion = SslVersion(ffi::TLS1_VERSION);
+
+/// TLSv1.1
+pub const TLS1_1_VERSION: SslVersion = SslVersion(ffi::TLS1_1_VERSION);
+
+/// TLSv1.2
+pub const TLS1_2_VERSION: SslVersion = SslVersion(ffi::TLS1_2_VERSION);
+
+/// TLSv1.3
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub const TLS1_3_VERSION: SslVersion = SslVersion(ffi::TLS1_3_VERSION);
+
+/// A TLS alert sent by the peer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SslAlert(c_int);
+
+impl SslAlert {
+    /// Creates an alert from the value reported to info callbacks, which holds the alert level in
+    /// its second byte and the description in its first.
+    pub fn from_raw(raw: c_int) -> SslAlert {
+        SslAlert(raw)
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+
+    /// Returns `true` if the alert is fatal, rather than a warning.
+    pub fn is_fatal(&self) -> bool {
+        (self.0 >> 8) & 0xff == ffi::SSL3_AL_FATAL
+    }
+
+    /// Returns the description of the alert.
+    pub fn description(&self) -> AlertDescription {
+        AlertDescription(self.0 & 0xff)
+    }
+}
+
+impl fmt::Display for SslAlert {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let level = unsafe { CStr::from_ptr(ffi::SSL_alert_type_string_long(self.0)) };
+        write!(fmt, "{} alert: {}", String::from_utf8_lossy(level.to_bytes()), self.description())
+    }
+}
+
+/// The description of a TLS alert.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AlertDescription(c_int);
+
+impl AlertDescription {
+    pub fn from_raw(raw: c_int) -> AlertDescription {
+        AlertDescription(raw)
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+}
+
+impl fmt::Display for AlertDescription {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let desc = unsafe { CStr::from_ptr(ffi::SSL_alert_desc_string_long(self.0)) };
+        fmt.write_str(&String::from_utf8_lossy(desc.to_bytes()))
+    }
+}
+
+/// `close_notify`
+pub const ALERT_CLOSE_NOTIFY: AlertDescription = AlertDescription(ffi::SSL_AD_CLOSE_NOTIFY);
+
+/// `unexpected_message`
+pub const ALERT_UNEXPECTED_MESSAGE: AlertDescription = AlertDescription(ffi::SSL_AD_UNEXPECTED_MESSAGE);
+
+/// `bad_record_mac`
+pub const ALERT_BAD_RECORD_MAC: AlertDescription = AlertDescription(ffi::SSL_AD_BAD_RECORD_MAC);
+
+/// `record_overflow`
+pub const ALERT_RECORD_OVERFLOW: AlertDescription = AlertDescription(ffi::SSL_AD_RECORD_OVERFLOW);
+
+/// `decompression_failure`
+pub const ALERT_DECOMPRESSION_FAILURE: AlertDescription = AlertDescription(ffi::SSL_AD_DECOMPRESSION_FAILURE);
+
+/// `handshake_failure`
+pub const ALERT_HANDSHAKE_FAILURE: AlertDescription = AlertDescription(ffi::SSL_AD_HANDSHAKE_FAILURE);
+
+/// `bad_certificate`
+pub const ALERT_BAD_CERTIFICATE: AlertDescription = AlertDescription(ffi::SSL_AD_BAD_CERTIFICATE);
+
+/// `unsupported_certificate`
+pub const ALERT_UNSUPPORTED_CERTIFICATE: AlertDescription = AlertDescription(ffi::SSL_AD_UNSUPPORTED_CERTIFICATE);
+
+/// `certificate_revoked`
+pub const ALERT_CERTIFICATE_REVOKED: AlertDescription = AlertDescription(ffi::SSL_AD_CERTIFICATE_REVOKED);
+
+/// `certificate_expired`
+pub const ALERT_CERTIFICATE_EXPIRED: AlertDescription = AlertDescription(ffi::SSL_AD_CERTIFICATE_EXPIRED);
+
+/// `certificate_unknown`
+pub const ALERT_CERTIFICATE_UNKNOWN: AlertDescription = AlertDescription(ffi::SSL_AD_CERTIFICATE_UNKNOWN);
+
+/// `illegal_parameter`
+pub const ALERT_ILLEGAL_PARAMETER: AlertDescription = AlertDescription(ffi::SSL_AD_ILLEGAL_PARAMETER);
+
+/// `unknown_ca`
+pub const ALERT_UNKNOWN_CA: AlertDescription = AlertDescription(ffi::SSL_AD_UNKNOWN_CA);
+
+/// `access_denied`
+pub const ALERT_ACCESS_DENIED: AlertDescription = AlertDescription(ffi::SSL_AD_ACCESS_DENIED);
+
+/// `decode_error`
+pub const ALERT_DECODE_ERROR: AlertDescription = AlertDescription(ffi::SSL_AD_DECODE_ERROR);
+
+/// `decrypt_error`
+pub const ALERT_DECRYPT_ERROR: AlertDescription = AlertDescription(ffi::SSL_AD_DECRYPT_ERROR);
+
+/// `protocol_version`
+pub const ALERT_PROTOCOL_VERSION: AlertDescription = AlertDescription(ffi::SSL_AD_PROTOCOL_VERSION);
+
+/// `insufficient_security`
+pub const ALERT_INSUFFICIENT_SECURITY: AlertDescription = AlertDescription(ffi::SSL_AD_INSUFFICIENT_SECURITY);
+
+/// `internal_error`
+pub const ALERT_INTERNAL_ERROR: AlertDescription = AlertDescription(ffi::SSL_AD_INTERNAL_ERROR);
+
+/// `user_canceled`
+pub const ALERT_USER_CANCELLED: AlertDescription = AlertDescription(ffi::SSL_AD_USER_CANCELLED);
+
+/// `no_renegotiation`
+pub const ALERT_NO_RENEGOTIATION: AlertDescription = AlertDescription(ffi::SSL_AD_NO_RENEGOTIATION);
+
+/// `unsupported_extension`
+pub const ALERT_UNSUPPORTED_EXTENSION: AlertDescription = AlertDescription(ffi::SSL_AD_UNSUPPORTED_EXTENSION);
+
+/// `unrecognized_name`
+pub const ALERT_UNRECOGNIZED_NAME: AlertDescription = AlertDescription(ffi::SSL_AD_UNRECOGNIZED_NAME);
+
+/// `no_application_protocol`
+pub const ALERT_NO_APPLICATION_PROTOCOL: AlertDescription = AlertDescription(ffi::SSL_AD_NO_APPLICATION_PROTOCOL);
+
 lazy_static! {
     static ref INDEXES: Mutex<HashMap<TypeId, c_int>> = Mutex::new(HashMap::new());
     static ref SSL_INDEXES: Mutex<HashMap<TypeId, c_int>> = Mutex::new(HashMap::new());
@@ -249,6 +548,15 @@ fn get_ssl_callback_idx<T: Any + 'static>() -> c_int {
     *SSL_INDEXES.lock().unwrap().entry(TypeId::of::<T>()).or_insert_with(|| get_new_ssl_idx::<T>())
 }
 
+/// User data attached with `set_app_data`.
+///
+/// The ex_data indexes are keyed by type, so the data is wrapped to keep it from colliding with
+/// values the crate stores itself.
+struct AppData<T>(T);
+
+/// The host name recorded by `set_verify_hostname`.
+struct VerifyHostname(String);
+
 lazy_static! {
     static ref NPN_PROTOS_IDX: c_int = get_new_idx::<Vec<u8>>();
 }
@@ -287,34 +595,218 @@ fn get_new_ssl_idx<T>() -> c_int {
     }
 }
 
+/// A panic raised by a callback, stashed on the `Ssl` it was invoked for.
+struct CallbackPanic(Box<Any + Send>);
+
+/// Runs the body of a callback invoked by OpenSSL on behalf of `ssl`.
+///
+/// Unwinding into OpenSSL is undefined behavior, so a panic is caught and stored on the `Ssl`,
+/// and `failure` is returned to OpenSSL in place of the callback's result. `SslStream` resumes
+/// the panic once OpenSSL returns control to it.
+unsafe fn catch_callback_panic<T, F, G>(ssl: *const ffi::SSL, failure: G, f: F) -> T
+    where F: FnOnce() -> T,
+          G: FnOnce() -> T
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(ret) => ret,
+        Err(err) => {
+            let idx = get_ssl_callback_idx::<CallbackPanic>();
+            // Only the first panic is resumed; later ones are usually a consequence of it.
+            if ffi::SSL_get_ex_data(ssl, idx).is_null() {
+                let state = Box::into_raw(Box::new(CallbackPanic(err)));
+                ffi::SSL_set_ex_data(ssl as *mut _, idx, state as *mut c_void);
+            }
+            failure()
+        }
+    }
+}
+
+unsafe fn take_callback_panic(ssl: *const ffi::SSL) -> Option<Box<Any + Send>> {
+    let idx = get_ssl_callback_idx::<CallbackPanic>();
+    let state = ffi::SSL_get_ex_data(ssl, idx) as *mut CallbackPanic;
+    if state.is_null() {
+        return None;
+    }
+    ffi::SSL_set_ex_data(ssl as *mut _, idx, ptr::null_mut());
+    Some(Box::from_raw(state).0)
+}
+
+/// Renegotiation state of a connection, tracked by `raw_info`.
+struct RenegotiationState {
+    handshake_done: bool,
+    renegotiations: u32,
+}
+
+/// Whether renegotiation is disabled for connections created from a context.
+struct RenegotiationPolicy(bool);
+
+/// The error reported by `SslStream` once a disabled renegotiation has been seen.
+fn renegotiation_rejected_error() -> Error {
+    ::put_error(::R_RENEGOTIATION_REJECTED,
+                "the peer started a renegotiation after it was disabled");
+    Error::Ssl(ErrorStack::get())
+}
+
+/// A key log callback, configured with `SslContextBuilder::set_keylog_callback`.
+struct KeylogCallback(Box<Fn(&SslRef, &str) + 'static + Sync + Send>);
+
+unsafe extern "C" fn raw_info(ssl: *const ffi::SSL, where_: c_int, ret: c_int) {
+    let ssl = ssl as *mut ffi::SSL;
+    if where_ & ffi::SSL_CB_READ_ALERT == ffi::SSL_CB_READ_ALERT {
+        record_alert(ssl, ret);
+        return;
+    }
+
+    if where_ & (ffi::SSL_CB_HANDSHAKE_START | ffi::SSL_CB_HANDSHAKE_DONE) == 0 {
+        return;
+    }
+
+    let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
+    if !ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<RenegotiationPolicy>()).is_null() {
+        track_renegotiation(ssl, where_);
+    }
+
+    #[cfg(not(all(feature = "v111", ossl111)))]
+    {
+        if where_ & ffi::SSL_CB_HANDSHAKE_DONE != 0 {
+            catch_callback_panic(ssl, || (), || log_keys(SslRef::from_ptr(ssl)));
+        }
+    }
+}
+
+unsafe fn record_alert(ssl: *mut ffi::SSL, alert: c_int) {
+    let idx = get_ssl_callback_idx::<SslAlert>();
+    let old = ffi::SSL_get_ex_data(ssl, idx) as *mut SslAlert;
+    if old.is_null() {
+        let alert = Box::new(SslAlert(alert));
+        ffi::SSL_set_ex_data(ssl, idx, Box::into_raw(alert) as *mut c_void);
+    } else {
+        (*old).0 = alert;
+    }
+}
+
+unsafe fn track_renegotiation(ssl: *mut ffi::SSL, where_: c_int) {
+    let idx = get_ssl_callback_idx::<RenegotiationState>();
+    let mut state = ffi::SSL_get_ex_data(ssl, idx) as *mut RenegotiationState;
+    if state.is_null() {
+        state = Box::into_raw(Box::new(RenegotiationState {
+            handshake_done: false,
+            renegotiations: 0,
+        }));
+        ffi::SSL_set_ex_data(ssl, idx, state as *mut c_void);
+    }
+    let state = &mut *state;
+
+    if where_ & ffi::SSL_CB_HANDSHAKE_DONE != 0 {
+        state.handshake_done = true;
+    } else if state.handshake_done && ffi::SSL_version(ssl) != ffi::TLS1_3_VERSION {
+        // TLS 1.3 has no renegotiation, but reports post-handshake messages such as session
+        // tickets as new handshakes.
+        state.renegotiations += 1;
+    }
+}
+
+#[cfg(all(feature = "v111", ossl111))]
+unsafe extern "C" fn raw_keylog(ssl: *const ffi::SSL, line: *const c_char) {
+    let callback = ffi::SSL_CTX_get_ex_data(ffi::SSL_get_SSL_CTX(ssl),
+                                            get_callback_idx::<KeylogCallback>());
+    if callback.is_null() {
+        return;
+    }
+    let callback = &*(callback as *const KeylogCallback);
+    // The lines only contain labels and hex digits.
+    let line = match str::from_utf8(CStr::from_ptr(line).to_bytes()) {
+        Ok(line) => line,
+        Err(_) => return,
+    };
+    catch_callback_panic(ssl,
+                         || (),
+                         || (callback.0)(SslRef::from_ptr(ssl as *mut _), line));
+}
+
+// Emulates the key log callback of OpenSSL 1.1.1 for TLS 1.2 and older connections.
+#[cfg(not(all(feature = "v111", ossl111)))]
+unsafe fn log_keys(ssl: &SslRef) {
+
+    let callback = ffi::SSL_CTX_get_ex_data(ffi::SSL_get_SSL_CTX(ssl.as_ptr()),
+                                            get_callback_idx::<KeylogCallback>());
+    if callback.is_null() || ffi::SSL_version(ssl.as_ptr()) == ffi::TLS1_3_VERSION {
+        return;
+    }
+    let callback = &*(callback as *const KeylogCallback);
+
+    let session = match ssl.session() {
+        Some(session) => session,
+        None => return,
+    };
+
+    let mut line = String::new();
+    #[cfg(ossl110)]
+    {
+        let mut client_random = [0; 32];
+        let random_len = ffi::SSL_get_client_random(ssl.as_ptr(),
+                                                    client_random.as_mut_ptr(),
+                                                    client_random.len());
+        line.push_str("CLIENT_RANDOM ");
+        push_hex(&mut line, &client_random[..random_len]);
+        line.push(' ');
+    }
+    // OpenSSL 1.0.x has no SSL_get_client_random, so the session is identified by its ID instead,
+    // which is only possible if the server assigned one.
+    #[cfg(not(ossl110))]
+    {
+        if session.id().is_empty() {
+            return;
+        }
+        line.push_str("RSA Session-ID:");
+        push_hex(&mut line, session.id());
+        line.push_str(" Master-Key:");
+    }
+
+    let mut master_key = [0; 48];
+    let key_len = session.master_key(&mut master_key);
+    push_hex(&mut line, &master_key[..key_len]);
+
+    (callback.0)(ssl, &line);
+}
+
+#[cfg(not(all(feature = "v111", ossl111)))]
+fn push_hex(s: &mut String, bytes: &[u8]) {
+    use std::fmt::Write;
+
+    for b in bytes {
+        write!(s, "{:02x}", b).unwrap();
+    }
+}
+
 extern "C" fn raw_verify<F>(preverify_ok: c_int, x509_ctx: *mut ffi::X509_STORE_CTX) -> c_int
-    where F: Fn(bool, &X509StoreContextRef) -> bool + Any + 'static + Sync + Send
+    where F: Fn(bool, &mut X509StoreContextRef) -> bool + Any + 'static + Sync + Send
 {
     unsafe {
         let idx = ffi::SSL_get_ex_data_X509_STORE_CTX_idx();
-        let ssl = ffi::X509_STORE_CTX_get_ex_data(x509_ctx, idx);
-        let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl as *const _);
+        let ssl = ffi::X509_STORE_CTX_get_ex_data(x509_ctx, idx) as *const ffi::SSL;
+        let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
         let verify = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
         let verify: &F = &*(verify as *mut F);
 
-        let ctx = X509StoreContextRef::from_ptr(x509_ctx);
+        let ctx = X509StoreContextRef::from_ptr_mut(x509_ctx);
 
-        verify(preverify_ok != 0, ctx) as c_int
+        catch_callback_panic(ssl, || 0, || verify(preverify_ok != 0, ctx) as c_int)
     }
 }
 
 extern "C" fn ssl_raw_verify<F>(preverify_ok: c_int, x509_ctx: *mut ffi::X509_STORE_CTX) -> c_int
-    where F: Fn(bool, &X509StoreContextRef) -> bool + Any + 'static + Sync + Send
+    where F: Fn(bool, &mut X509StoreContextRef) -> bool + Any + 'static + Sync + Send
 {
     unsafe {
         let idx = ffi::SSL_get_ex_data_X509_STORE_CTX_idx();
-        let ssl = ffi::X509_STORE_CTX_get_ex_data(x509_ctx, idx);
-        let verify = ffi::SSL_get_ex_data(ssl as *const _, get_ssl_callback_idx::<F>());
+        let ssl = ffi::X509_STORE_CTX_get_ex_data(x509_ctx, idx) as *const ffi::SSL;
+        let verify = ffi::SSL_get_ex_data(ssl, get_ssl_callback_idx::<F>());
         let verify: &F = &*(verify as *mut F);
 
-        let ctx = X509StoreContextRef::from_ptr(x509_ctx);
+        let ctx = X509StoreContextRef::from_ptr_mut(x509_ctx);
 
-        verify(preverify_ok != 0, ctx) as c_int
+        catch_callback_panic(ssl, || 0, || verify(preverify_ok != 0, ctx) as c_int)
     }
 }
 
@@ -325,9 +817,12 @@ extern "C" fn raw_sni<F>(ssl: *mut ffi::SSL, al: *mut c_int, _arg: *mut c_void)
         let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
         let callback = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
         let callback: &F = &*(callback as *mut F);
-        let ssl = SslRef::from_ptr_mut(ssl);
+        let ssl_ref = SslRef::from_ptr_mut(ssl);
 
-        match callback(ssl) {
+        let ret = catch_callback_panic(ssl,
+                                       || Err(SniError::Fatal(ffi::SSL_AD_INTERNAL_ERROR)),
+                                       || callback(ssl_ref));
+        match ret {
             Ok(()) => ffi::SSL_TLSEXT_ERR_OK,
             Err(SniError::Fatal(e)) => {
                 *al = e;
@@ -342,6 +837,30 @@ extern "C" fn raw_sni<F>(ssl: *mut ffi::SSL, al: *mut c_int, _arg: *mut c_void)
     }
 }
 
+#[cfg(all(feature = "v111", ossl111))]
+unsafe extern "C" fn raw_client_hello<F>(ssl: *mut ffi::SSL,
+                                         al: *mut c_int,
+                                         _arg: *mut c_void)
+                                         -> c_int
+    where F: Fn(&mut SslRef) -> Result<(), ClientHelloError> + Any + 'static + Sync + Send
+{
+    let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
+    let callback = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
+    let callback: &F = &*(callback as *mut F);
+    let ssl_ref = SslRef::from_ptr_mut(ssl);
+
+    let ret = catch_callback_panic(ssl,
+                                   || Err(ClientHelloError(ffi::SSL_AD_INTERNAL_ERROR)),
+                                   || callback(ssl_ref));
+    match ret {
+        Ok(()) => ffi::SSL_CLIENT_HELLO_SUCCESS,
+        Err(ClientHelloError(e)) => {
+            *al = e;
+            ffi::SSL_CLIENT_HELLO_ERROR
+        }
+    }
+}
+
 unsafe fn select_proto_using(ssl: *mut ffi::SSL,
                              out: *mut *mut c_uchar,
                              outlen: *mut c_uchar,
@@ -405,8 +924,11 @@ unsafe extern fn raw_tmp_dh<F>(ssl: *mut ffi::SSL,
     let callback = ffi::SSL_CTX_get_ex_data(ctx, get_callback_idx::<F>());
     let callback = &*(callback as *mut F);
 
-    let ssl = SslRef::from_ptr_mut(ssl);
-    match callback(ssl, is_export != 0, keylength as u32) {
+    let ssl_ref = SslRef::from_ptr_mut(ssl);
+    let ret = catch_callback_panic(ssl,
+                                   || Err(ErrorStack::get()),
+                                   || callback(ssl_ref, is_export != 0, keylength as u32));
+    match ret {
         Ok(dh) => {
             let ptr = dh.as_ptr();
             mem::forget(dh);
@@ -430,8 +952,11 @@ unsafe extern fn raw_tmp_ecdh<F>(ssl: *mut ffi::SSL,
     let callback = ffi::SSL_CTX_get_ex_data(ctx, get_callback_idx::<F>());
     let callback = &*(callback as *mut F);
 
-    let ssl = SslRef::from_ptr_mut(ssl);
-    match callback(ssl, is_export != 0, keylength as u32) {
+    let ssl_ref = SslRef::from_ptr_mut(ssl);
+    let ret = catch_callback_panic(ssl,
+                                   || Err(ErrorStack::get()),
+                                   || callback(ssl_ref, is_export != 0, keylength as u32));
+    match ret {
         Ok(ec_key) => {
             let ptr = ec_key.as_ptr();
             mem::forget(ec_key);
@@ -453,8 +978,11 @@ unsafe extern fn raw_tmp_dh_ssl<F>(ssl: *mut ffi::SSL,
     let callback = ffi::SSL_get_ex_data(ssl, get_ssl_callback_idx::<F>());
     let callback = &*(callback as *mut F);
 
-    let ssl = SslRef::from_ptr_mut(ssl);
-    match callback(ssl, is_export != 0, keylength as u32) {
+    let ssl_ref = SslRef::from_ptr_mut(ssl);
+    let ret = catch_callback_panic(ssl,
+                                   || Err(ErrorStack::get()),
+                                   || callback(ssl_ref, is_export != 0, keylength as u32));
+    match ret {
         Ok(dh) => {
             let ptr = dh.as_ptr();
             mem::forget(dh);
@@ -477,8 +1005,11 @@ unsafe extern fn raw_tmp_ecdh_ssl<F>(ssl: *mut ffi::SSL,
     let callback = ffi::SSL_get_ex_data(ssl, get_ssl_callback_idx::<F>());
     let callback = &*(callback as *mut F);
 
-    let ssl = SslRef::from_ptr_mut(ssl);
-    match callback(ssl, is_export != 0, keylength as u32) {
+    let ssl_ref = SslRef::from_ptr_mut(ssl);
+    let ret = catch_callback_panic(ssl,
+                                   || Err(ErrorStack::get()),
+                                   || callback(ssl_ref, is_export != 0, keylength as u32));
+    match ret {
         Ok(ec_key) => {
             let ptr = ec_key.as_ptr();
             mem::forget(ec_key);
@@ -498,10 +1029,10 @@ unsafe extern fn raw_tlsext_status<F>(ssl: *mut ffi::SSL, _: *mut c_void) -> c_i
     let callback = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
     let callback = &*(callback as *mut F);
 
-    let ssl = SslRef::from_ptr_mut(ssl);
-    let ret = callback(ssl);
+    let ssl_ref = SslRef::from_ptr_mut(ssl);
+    let ret = catch_callback_panic(ssl, || Err(ErrorStack::get()), || callback(ssl_ref));
 
-    if ssl.is_server() {
+    if ssl_ref.is_server() {
         match ret {
             Ok(true) => ffi::SSL_TLSEXT_ERR_OK,
             Ok(false) => ffi::SSL_TLSEXT_ERR_NOACK,
@@ -522,6 +1053,153 @@ unsafe extern fn raw_tlsext_status<F>(ssl: *mut ffi::SSL, _: *mut c_void) -> c_i
     }
 }
 
+extern "C" fn raw_cookie_generate<F>(ssl: *mut ffi::SSL,
+                                     cookie: *mut c_uchar,
+                                     cookie_len: *mut c_uint)
+                                     -> c_int
+    where F: Fn(&mut SslRef, &mut [u8]) -> Result<usize, ErrorStack> + Any + 'static + Sync + Send
+{
+    unsafe {
+        let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
+        let callback = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
+        let callback: &F = &*(callback as *mut F);
+        let ssl = SslRef::from_ptr_mut(ssl);
+
+        // The cookie's length is sent as a single byte, so only DTLS1_COOKIE_LENGTH - 1 bytes of
+        // the buffer can actually be used.
+        let buf = slice::from_raw_parts_mut(cookie as *mut u8,
+                                            ffi::DTLS1_COOKIE_LENGTH as usize - 1);
+        let ret = catch_callback_panic(ssl.as_ptr(),
+                                       || Err(ErrorStack::get()),
+                                       || callback(ssl, buf));
+        match ret {
+            Ok(len) if len <= buf.len() => {
+                *cookie_len = len as c_uint;
+                1
+            }
+            _ => {
+                // FIXME reset error stack
+                0
+            }
+        }
+    }
+}
+
+#[cfg(ossl110)]
+extern "C" fn raw_cookie_verify<F>(ssl: *mut ffi::SSL,
+                                   cookie: *const c_uchar,
+                                   cookie_len: c_uint)
+                                   -> c_int
+    where F: Fn(&mut SslRef, &[u8]) -> bool + Any + 'static + Sync + Send
+{
+    unsafe { cookie_verify_using::<F>(ssl, cookie, cookie_len) }
+}
+
+#[cfg(ossl10x)]
+extern "C" fn raw_cookie_verify<F>(ssl: *mut ffi::SSL,
+                                   cookie: *mut c_uchar,
+                                   cookie_len: c_uint)
+                                   -> c_int
+    where F: Fn(&mut SslRef, &[u8]) -> bool + Any + 'static + Sync + Send
+{
+    unsafe { cookie_verify_using::<F>(ssl, cookie, cookie_len) }
+}
+
+unsafe fn cookie_verify_using<F>(ssl: *mut ffi::SSL,
+                                 cookie: *const c_uchar,
+                                 cookie_len: c_uint)
+                                 -> c_int
+    where F: Fn(&mut SslRef, &[u8]) -> bool + Any + 'static + Sync + Send
+{
+    let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
+    let callback = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
+    let callback: &F = &*(callback as *mut F);
+    let ssl = SslRef::from_ptr_mut(ssl);
+    let cookie = slice::from_raw_parts(cookie as *const u8, cookie_len as usize);
+
+    catch_callback_panic(ssl.as_ptr(), || 0, || callback(ssl, cookie) as c_int)
+}
+
+#[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+extern "C" fn raw_psk_client<F>(ssl: *mut ffi::SSL,
+                                hint: *const c_char,
+                                identity: *mut c_char,
+                                max_identity_len: c_uint,
+                                psk: *mut c_uchar,
+                                max_psk_len: c_uint)
+                                -> c_uint
+    where F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8], &mut [u8]) -> Result<usize, ErrorStack>
+                 + Any + 'static + Sync + Send
+{
+    unsafe {
+        let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
+        let callback = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
+        let callback: &F = &*(callback as *mut F);
+        let ssl = SslRef::from_ptr_mut(ssl);
+
+        let hint = if hint.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(hint).to_bytes())
+        };
+        if max_identity_len == 0 {
+            return 0;
+        }
+        let identity = slice::from_raw_parts_mut(identity as *mut u8, max_identity_len as usize);
+        // The identity is terminated by the zeroed bytes following it, and the last byte is kept
+        // out of the callback's reach so that the terminator can't be overwritten.
+        for b in identity.iter_mut() {
+            *b = 0;
+        }
+        let (identity, _) = identity.split_at_mut(max_identity_len as usize - 1);
+        let psk = slice::from_raw_parts_mut(psk as *mut u8, max_psk_len as usize);
+        let ret = catch_callback_panic(ssl.as_ptr(),
+                                       || Err(ErrorStack::get()),
+                                       || callback(ssl, hint, identity, &mut *psk));
+        match ret {
+            Ok(len) if len <= psk.len() => len as c_uint,
+            _ => {
+                // FIXME reset error stack
+                0
+            }
+        }
+    }
+}
+
+#[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+extern "C" fn raw_psk_server<F>(ssl: *mut ffi::SSL,
+                                identity: *const c_char,
+                                psk: *mut c_uchar,
+                                max_psk_len: c_uint)
+                                -> c_uint
+    where F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8]) -> Result<usize, ErrorStack>
+                 + Any + 'static + Sync + Send
+{
+    unsafe {
+        let ssl_ctx = ffi::SSL_get_SSL_CTX(ssl);
+        let callback = ffi::SSL_CTX_get_ex_data(ssl_ctx, get_callback_idx::<F>());
+        let callback: &F = &*(callback as *mut F);
+        let ssl = SslRef::from_ptr_mut(ssl);
+
+        let identity = if identity.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(identity).to_bytes())
+        };
+        let psk = slice::from_raw_parts_mut(psk as *mut u8, max_psk_len as usize);
+        let ret = catch_callback_panic(ssl.as_ptr(),
+                                       || Err(ErrorStack::get()),
+                                       || callback(ssl, identity, &mut *psk));
+        match ret {
+            Ok(len) if len <= psk.len() => len as c_uint,
+            _ => {
+                // FIXME reset error stack
+                0
+            }
+        }
+    }
+}
+
 /// The function is given as the callback to `SSL_CTX_set_next_protos_advertised_cb`.
 ///
 /// It causes the parameter `out` to point at a `*const c_uchar` instance that
@@ -569,6 +1247,44 @@ fn ssl_encode_byte_strings(strings: &[&[u8]]) -> Vec<u8> {
     enc
 }
 
+/// Selects the first protocol in `server` which is also present in `client`.
+///
+/// Both lists are in the wire format used by ALPN and NPN, in which each protocol is preceded by
+/// a byte containing its length. `server` is in order of preference. This is intended for use in
+/// an ALPN selection callback, where `client` is the list sent by the client.
+///
+/// `None` is returned if the lists have no protocol in common, or if either is empty or
+/// malformed.
+///
+/// This matches the behavior of `SSL_select_next_proto`, but validates both lists first.
+pub fn select_next_proto<'a>(server: &'a [u8], client: &'a [u8]) -> Option<&'a [u8]> {
+    // `SSL_select_next_proto` doesn't validate its inputs, so the lists are matched here instead.
+    let server = match parse_protocols(server) {
+        Some(server) => server,
+        None => return None,
+    };
+    let client = match parse_protocols(client) {
+        Some(client) => client,
+        None => return None,
+    };
+    server.into_iter().find(|proto| client.contains(proto))
+}
+
+/// Splits a list of protocols in the ALPN wire format, returning `None` if it is empty or
+/// malformed.
+fn parse_protocols(mut list: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut protos = vec![];
+    while let Some((&len, rest)) = list.split_first() {
+        let len = len as usize;
+        if len == 0 || len > rest.len() {
+            return None;
+        }
+        protos.push(&rest[..len]);
+        list = &rest[len..];
+    }
+    if protos.is_empty() { None } else { Some(protos) }
+}
+
 /// An error returned from an SNI callback.
 pub enum SniError {
     Fatal(c_int),
@@ -576,9 +1292,25 @@ pub enum SniError {
     NoAck,
 }
 
-/// A builder for `SslContext`s.
-pub struct SslContextBuilder(*mut ffi::SSL_CTX);
-
+/// An error returned from a ClientHello callback.
+///
+/// The handshake is aborted and the contained value is sent to the client as a fatal alert.
+///
+/// Requires the `v111` feature and OpenSSL 1.1.1.
+#[cfg(all(feature = "v111", ossl111))]
+pub struct ClientHelloError(pub c_int);
+
+#[cfg(all(feature = "v111", ossl111))]
+impl ClientHelloError {
+    /// Rejects the handshake with a `handshake_failure` alert.
+    pub fn handshake_failure() -> ClientHelloError {
+        ClientHelloError(ffi::SSL_AD_HANDSHAKE_FAILURE)
+    }
+}
+
+/// A builder for `SslContext`s.
+pub struct SslContextBuilder(*mut ffi::SSL_CTX);
+
 unsafe impl Sync for SslContextBuilder {}
 unsafe impl Send for SslContextBuilder {}
 
@@ -593,6 +1325,8 @@ impl SslContextBuilder {
         unsafe {
             init();
             let ctx = try!(cvt_p(ffi::SSL_CTX_new(method.as_ptr())));
+            // shared by alert reporting, renegotiation tracking and key logging
+            ffi::SSL_CTX_set_info_callback(ctx, Some(raw_info));
 
             Ok(SslContextBuilder::from_ptr(ctx))
         }
@@ -615,8 +1349,21 @@ impl SslContextBuilder {
 
     /// Configures the certificate verification method for new connections and
     /// registers a verification callback.
+    ///
+    /// The callback is invoked for each certificate in the chain with a
+    /// boolean indicating if the preverification process was successful, and
+    /// the `X509StoreContextRef` providing access to the current certificate,
+    /// its depth, and the verification error. It should return `true` if the
+    /// certificate is acceptable and `false` otherwise. Callbacks which accept
+    /// a certificate OpenSSL rejected should also clear the context's error so
+    /// that it is not reported by `SslRef::verify_result`.
+    ///
+    /// The connection being verified is available through
+    /// `X509StoreContextRef::ssl`, so a single callback shared by several
+    /// contexts can take its server name, verification host name and role
+    /// into account.
     pub fn set_verify_callback<F>(&mut self, mode: SslVerifyMode, verify: F)
-        where F: Fn(bool, &X509StoreContextRef) -> bool + Any + 'static + Sync + Send
+        where F: Fn(bool, &mut X509StoreContextRef) -> bool + Any + 'static + Sync + Send
     {
         unsafe {
             let verify = Box::new(verify);
@@ -645,6 +1392,28 @@ impl SslContextBuilder {
         }
     }
 
+    /// Configures a callback invoked when a ClientHello is received by a server.
+    ///
+    /// The callback runs before any certificate selection or key exchange is performed, so
+    /// servers can use it to cheaply reject abusive clients, for example based on the address
+    /// recorded with `SslRef::set_peer_addr`. Returning an error aborts the handshake.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn set_client_hello_callback<F>(&mut self, callback: F)
+        where F: Fn(&mut SslRef) -> Result<(), ClientHelloError> + Any + 'static + Sync + Send
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::SSL_CTX_set_ex_data(self.as_ptr(),
+                                     get_callback_idx::<F>(),
+                                     Box::into_raw(callback) as *mut c_void);
+            ffi::SSL_CTX_set_client_hello_cb(self.as_ptr(),
+                                             Some(raw_client_hello::<F>),
+                                             ptr::null_mut());
+        }
+    }
+
     /// Sets verification depth
     pub fn set_verify_depth(&mut self, depth: u32) {
         unsafe {
@@ -652,12 +1421,93 @@ impl SslContextBuilder {
         }
     }
 
+    /// Configures whether clients created from this context offer to authenticate after the
+    /// handshake.
+    ///
+    /// TLS 1.3 servers can only request a client certificate once the connection is established,
+    /// with `SslRef::verify_client_post_handshake`, if the client has enabled this. It is disabled
+    /// by default.
+    ///
+    /// This corresponds to `SSL_CTX_set_post_handshake_auth`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn set_post_handshake_auth(&mut self, enable: bool) {
+        unsafe { ffi::SSL_CTX_set_post_handshake_auth(self.as_ptr(), enable as c_int) }
+    }
+
     pub fn set_read_ahead(&mut self, read_ahead: bool) {
         unsafe {
             ffi::SSL_CTX_set_read_ahead(self.as_ptr(), read_ahead as c_long);
         }
     }
 
+    /// Sets the maximum size of the certificate chain a peer may send.
+    ///
+    /// Handshakes with a larger chain fail before the certificates are parsed. The default is
+    /// 100 kilobytes.
+    pub fn set_max_cert_list(&mut self, len: usize) {
+        unsafe {
+            assert!(len <= c_long::max_value() as usize);
+            ffi::SSL_CTX_set_max_cert_list(self.as_ptr(), len as c_long);
+        }
+    }
+
+    /// Sets the default size of the buffer used to read records from the underlying stream.
+    ///
+    /// When read ahead is enabled with `set_read_ahead`, OpenSSL reads up to this much data from
+    /// the stream at a time.
+    ///
+    /// Requires the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(all(feature = "v110", ossl110))]
+    pub fn set_default_read_buffer_len(&mut self, len: usize) {
+        unsafe {
+            ffi::SSL_CTX_set_default_read_buffer_len(self.as_ptr(), len);
+        }
+    }
+
+    /// Sets the maximum amount of plaintext data sent in a single TLS record.
+    ///
+    /// The value must be between 512 and 16384 bytes. Smaller records reduce latency and memory
+    /// usage on constrained peers at the cost of some throughput.
+    pub fn set_max_send_fragment(&mut self, len: usize) -> Result<(), ErrorStack> {
+        unsafe {
+            assert!(len <= c_long::max_value() as usize);
+            cvt(ffi::SSL_CTX_set_max_send_fragment(self.as_ptr(), len as c_long) as c_int)
+                .map(|_| ())
+        }
+    }
+
+    /// Sets the size of the plaintext fragments used when pipelining writes.
+    ///
+    /// The value must be between 512 and the maximum send fragment length.
+    ///
+    /// Requires the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(all(feature = "v110", ossl110))]
+    pub fn set_split_send_fragment(&mut self, len: usize) -> Result<(), ErrorStack> {
+        unsafe {
+            assert!(len <= c_long::max_value() as usize);
+            cvt(ffi::SSL_CTX_set_split_send_fragment(self.as_ptr(), len as c_long) as c_int)
+                .map(|_| ())
+        }
+    }
+
+    /// Sets the maximum fragment length the client requests with the RFC 6066
+    /// max_fragment_length extension.
+    ///
+    /// If the server accepts the extension, neither peer sends records with more plaintext than
+    /// this. Servers honour the extension automatically.
+    ///
+    /// This corresponds to `SSL_CTX_set_tlsext_max_fragment_length`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn set_max_fragment_length(&mut self, len: MaxFragmentLength) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::SSL_CTX_set_tlsext_max_fragment_length(self.as_ptr(), len.0)).map(|_| ())
+        }
+    }
+
     pub fn set_mode(&mut self, mode: SslMode) -> SslMode {
         unsafe {
             let mode = ffi::SSL_CTX_set_mode(self.as_ptr(), mode.bits());
@@ -731,6 +1581,44 @@ impl SslContextBuilder {
         }
     }
 
+    /// Sets the list of CAs sent to the client to the subject names of the PEM-formatted
+    /// certificates in a file.
+    ///
+    /// This is equivalent to passing the result of `X509Name::load_client_ca_file` to
+    /// `set_client_ca_list`. The CA certificates must still be added to the trust root.
+    pub fn set_client_ca_file<P: AsRef<Path>>(&mut self, file: P) -> Result<(), ErrorStack> {
+        let list = try!(X509Name::load_client_ca_file(file));
+        self.set_client_ca_list(list);
+        Ok(())
+    }
+
+    /// Enables the DTLS extension for establishing SRTP keying material.
+    ///
+    /// `protocols` is a colon-separated list of protection profile names in order of preference,
+    /// such as `SRTP_AES128_CM_SHA1_80:SRTP_AES128_CM_SHA1_32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `protocols` contains an embedded null.
+    pub fn set_tlsext_use_srtp(&mut self, protocols: &str) -> Result<(), ErrorStack> {
+        let protocols = CString::new(protocols).unwrap();
+        unsafe {
+            // This function returns 0 on success, unlike the rest of the library.
+            if ffi::SSL_CTX_set_tlsext_use_srtp(self.as_ptr(), protocols.as_ptr()) == 0 {
+                Ok(())
+            } else {
+                Err(ErrorStack::get())
+            }
+        }
+    }
+
+    /// Adds the subject name of a certificate to the list of CAs sent to the client.
+    ///
+    /// The CA certificate must still be added to the trust root.
+    pub fn add_client_ca(&mut self, cacert: &X509Ref) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_CTX_add_client_CA(self.as_ptr(), cacert.as_ptr())).map(|_| ()) }
+    }
+
     /// Set the context identifier for sessions
     ///
     /// This value identifies the server's session cache to a clients, telling them when they're
@@ -795,6 +1683,87 @@ impl SslContextBuilder {
         }
     }
 
+    /// Rebuilds the certificate chain of the current certificate.
+    ///
+    /// This can be used once the certificate and its chain have been configured to check that a
+    /// valid chain can be built, and to send it in the correct order. By default the chain is
+    /// built from the certificates in the context's chain store, falling back to its
+    /// verification store (see `cert_store_mut`) if no chain store is set. With
+    /// `SSL_BUILD_CHAIN_FLAG_UNTRUSTED`, the certificates already in the chain, such as those
+    /// loaded by `set_certificate_chain_file`, may also be used as intermediates. With
+    /// `SSL_BUILD_CHAIN_FLAG_CHECK`, only those existing chain certificates are used, so this
+    /// checks that they form a complete chain and puts them in order. The new chain is
+    /// returned by `SslContextRef::extra_chain_certs` if no extra chain certificates have been
+    /// added.
+    ///
+    /// This corresponds to `SSL_CTX_build_cert_chain`.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn build_cert_chain(&mut self, flags: SslBuildChainFlags) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::SSL_CTX_build_cert_chain(self.as_ptr(), flags.bits()) as c_int).map(|_| ())
+        }
+    }
+
+    /// Sets extension data to be sent alongside the current certificate.
+    ///
+    /// `serverinfo` is a sequence of `SERVERINFO` version 1 records, each a 2 byte extension type
+    /// followed by a 2 byte length and the extension data. The data is sent to clients which
+    /// include the corresponding extension in their ClientHello. A certificate must be set before
+    /// this is called.
+    ///
+    /// This corresponds to `SSL_CTX_use_serverinfo`.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_serverinfo(&mut self, serverinfo: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::SSL_CTX_use_serverinfo(self.as_ptr(),
+                                            serverinfo.as_ptr(),
+                                            serverinfo.len()))
+                .map(|_| ())
+        }
+    }
+
+    /// Sets extension data to be sent alongside the current certificate, using the `SERVERINFO`
+    /// version 2 format.
+    ///
+    /// Each record is a 4 byte context, indicating the handshake messages the extension may appear
+    /// in, followed by a 2 byte extension type, a 2 byte length and the extension data. A
+    /// certificate must be set before this is called.
+    ///
+    /// This corresponds to `SSL_CTX_use_serverinfo_ex`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn set_serverinfo_v2(&mut self, serverinfo: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(ffi::SSL_CTX_use_serverinfo_ex(self.as_ptr(),
+                                               ffi::SSL_SERVERINFOV2,
+                                               serverinfo.as_ptr(),
+                                               serverinfo.len()))
+                .map(|_| ())
+        }
+    }
+
+    /// Loads extension data to be sent alongside the current certificate from a PEM file.
+    ///
+    /// The file contains one or more `SERVERINFO FOR <extension>` PEM blocks. A certificate must
+    /// be set before this is called.
+    ///
+    /// This corresponds to `SSL_CTX_use_serverinfo_file`.
+    ///
+    /// Requires the `v102` feature and OpenSSL 1.0.2, or the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_serverinfo_file<P: AsRef<Path>>(&mut self, file: P) -> Result<(), ErrorStack> {
+        let file = CString::new(file.as_ref().as_os_str().to_str().unwrap()).unwrap();
+        unsafe {
+            cvt(ffi::SSL_CTX_use_serverinfo_file(self.as_ptr(), file.as_ptr() as *const _))
+                .map(|_| ())
+        }
+    }
+
     /// Loads the private key from a file.
     pub fn set_private_key_file<P: AsRef<Path>>(&mut self,
                                                 file: P,
@@ -853,6 +1822,90 @@ impl SslContextBuilder {
         SslOption::from_bits(ret).unwrap()
     }
 
+    /// Sets the minimum supported protocol version.
+    ///
+    /// If `None` is passed, the lowest version supported by OpenSSL is used. On OpenSSL 1.0.x
+    /// this is emulated by setting the corresponding `SSL_OP_NO_*` options.
+    pub fn set_min_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(compat::SSL_CTX_set_min_proto_version(self.as_ptr(),
+                                                      version.map_or(0, |v| v.0)))
+                .map(|_| ())
+        }
+    }
+
+    /// Sets the maximum supported protocol version.
+    ///
+    /// If `None` is passed, the highest version supported by OpenSSL is used. On OpenSSL 1.0.x
+    /// this is emulated by setting the corresponding `SSL_OP_NO_*` options.
+    pub fn set_max_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(compat::SSL_CTX_set_max_proto_version(self.as_ptr(),
+                                                      version.map_or(0, |v| v.0)))
+                .map(|_| ())
+        }
+    }
+
+    /// Enables or disables renegotiation for connections created from this context.
+    ///
+    /// Renegotiations started by the peer are counted for every connection (see
+    /// `SslRef::renegotiations`). If renegotiation is disabled, OpenSSL 1.1.1 and newer will
+    /// refuse it outright. Older versions cannot abort the handshake in progress, so
+    /// `SslStream` instead fails all reads and writes with an `Error::Ssl` once a renegotiation
+    /// has been seen, and the connection should be closed. `SslRef::renegotiation_rejected`
+    /// distinguishes this case from other errors.
+    pub fn set_renegotiation_disabled(&mut self, disabled: bool) {
+        unsafe {
+            let idx = get_callback_idx::<RenegotiationPolicy>();
+            let policy = ffi::SSL_CTX_get_ex_data(self.as_ptr(), idx) as *mut RenegotiationPolicy;
+            if policy.is_null() {
+                let policy = Box::new(RenegotiationPolicy(disabled));
+                ffi::SSL_CTX_set_ex_data(self.as_ptr(), idx, Box::into_raw(policy) as *mut c_void);
+            } else {
+                (*policy).0 = disabled;
+            }
+        }
+
+        #[cfg(ossl111)]
+        {
+            if disabled {
+                self.set_options(SSL_OP_NO_RENEGOTIATION);
+            } else {
+                self.clear_options(SSL_OP_NO_RENEGOTIATION);
+            }
+        }
+    }
+
+    /// Sets a callback which is called with the key material of each connection, formatted as
+    /// a line of the NSS key log format.
+    ///
+    /// This allows traffic to be decrypted by tools such as Wireshark, and should only be used
+    /// for debugging. With the `v111` feature and OpenSSL 1.1.1, the lines are those produced by
+    /// OpenSSL itself, which covers TLS 1.3 connections. Otherwise a line is produced from the
+    /// client random and master secret once each handshake completes, so TLS 1.3 connections,
+    /// which do not use a master secret, are not logged.
+    ///
+    /// OpenSSL 1.0.x doesn't expose the client random, so there the line identifies the session by
+    /// its ID instead (`RSA Session-ID:... Master-Key:...`). Sessions without an ID, such as those
+    /// resumed from a session ticket, are not logged.
+    ///
+    /// This corresponds to `SSL_CTX_set_keylog_callback` on OpenSSL 1.1.1.
+    pub fn set_keylog_callback<F>(&mut self, callback: F)
+        where F: Fn(&SslRef, &str) + Any + 'static + Sync + Send
+    {
+        unsafe {
+            let idx = get_callback_idx::<KeylogCallback>();
+            let old = ffi::SSL_CTX_get_ex_data(self.as_ptr(), idx) as *mut KeylogCallback;
+            if !old.is_null() {
+                drop(Box::from_raw(old));
+            }
+            let callback = Box::new(KeylogCallback(Box::new(callback)));
+            ffi::SSL_CTX_set_ex_data(self.as_ptr(), idx, Box::into_raw(callback) as *mut c_void);
+            #[cfg(all(feature = "v111", ossl111))]
+            ffi::SSL_CTX_set_keylog_callback(self.as_ptr(), Some(raw_keylog));
+        }
+    }
+
     /// Set the protocols to be used during Next Protocol Negotiation (the protocols
     /// supported by the application).
     pub fn set_npn_protocols(&mut self, protocols: &[&[u8]]) -> Result<(), ErrorStack> {
@@ -938,11 +1991,13 @@ impl SslContextBuilder {
     ///
     /// On the client side, this callback is responsible for validating the OCSP status response
     /// returned by the server. The status may be retrieved with the `SslRef::ocsp_status` method.
+    /// Clients must request a status response with `SslRef::set_status_type` for the server to
+    /// provide one.
     /// A response of `Ok(true)` indicates that the OCSP status is valid, and a response of
     /// `Ok(false)` indicates that the OCSP status is invalid and the handshake should be
     /// terminated.
     ///
-    /// On the server side, this callback is resopnsible for setting the OCSP status response to be
+    /// On the server side, this callback is responsible for setting the OCSP status response to be
     /// returned to clients. The status may be set with the `SslRef::set_ocsp_status` method. A
     /// response of `Ok(true)` indicates that the OCSP status should be returned to the client, and
     /// `Ok(false)` indicates that the status should not be returned to the client.
@@ -959,6 +2014,113 @@ impl SslContextBuilder {
         }
     }
 
+    /// Sets the callback used by DTLS servers to generate a cookie for a `HelloVerifyRequest`.
+    ///
+    /// The callback should write the cookie into the provided buffer and return the number of
+    /// bytes written. Cookie exchange must be enabled with the `SSL_OP_COOKIE_EXCHANGE` option.
+    pub fn set_cookie_generate_cb<F>(&mut self, callback: F)
+        where F: Fn(&mut SslRef, &mut [u8]) -> Result<usize, ErrorStack> + Any + 'static + Sync + Send
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::SSL_CTX_set_ex_data(self.as_ptr(),
+                                     get_callback_idx::<F>(),
+                                     Box::into_raw(callback) as *mut c_void);
+            ffi::SSL_CTX_set_cookie_generate_cb(self.as_ptr(), Some(raw_cookie_generate::<F>));
+        }
+    }
+
+    /// Sets the callback used by DTLS servers to verify a cookie echoed back by a client.
+    ///
+    /// The callback should return `true` if the cookie is valid.
+    pub fn set_cookie_verify_cb<F>(&mut self, callback: F)
+        where F: Fn(&mut SslRef, &[u8]) -> bool + Any + 'static + Sync + Send
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::SSL_CTX_set_ex_data(self.as_ptr(),
+                                     get_callback_idx::<F>(),
+                                     Box::into_raw(callback) as *mut c_void);
+            ffi::SSL_CTX_set_cookie_verify_cb(self.as_ptr(), Some(raw_cookie_verify::<F>));
+        }
+    }
+
+    /// Sets the callback used by clients to provide a pre-shared key for TLS-PSK cipher suites.
+    ///
+    /// The callback is passed the identity hint provided by the server, if any, a buffer into
+    /// which the identity should be written, and a buffer into which the key should be written.
+    /// It should return the length of the key.
+    ///
+    /// The identity buffer is zeroed before the callback is called and is one byte shorter than
+    /// the identity OpenSSL accepts, so the identity is always null-terminated and cannot contain
+    /// null bytes.
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn set_psk_client_callback<F>(&mut self, callback: F)
+        where F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8], &mut [u8]) -> Result<usize, ErrorStack>
+                     + Any + 'static + Sync + Send
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::SSL_CTX_set_ex_data(self.as_ptr(),
+                                     get_callback_idx::<F>(),
+                                     Box::into_raw(callback) as *mut c_void);
+            ffi::SSL_CTX_set_psk_client_callback(self.as_ptr(), Some(raw_psk_client::<F>));
+        }
+    }
+
+    /// Sets the callback used by servers to look up the pre-shared key for a client's identity.
+    ///
+    /// The callback is passed the identity provided by the client and a buffer into which the
+    /// key should be written. It should return the length of the key, or 0 if the identity is
+    /// unknown.
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn set_psk_server_callback<F>(&mut self, callback: F)
+        where F: Fn(&mut SslRef, Option<&[u8]>, &mut [u8]) -> Result<usize, ErrorStack>
+                     + Any + 'static + Sync + Send
+    {
+        unsafe {
+            let callback = Box::new(callback);
+            ffi::SSL_CTX_set_ex_data(self.as_ptr(),
+                                     get_callback_idx::<F>(),
+                                     Box::into_raw(callback) as *mut c_void);
+            ffi::SSL_CTX_set_psk_server_callback(self.as_ptr(), Some(raw_psk_server::<F>));
+        }
+    }
+
+    /// Sets the identity hint sent by servers to clients using TLS-PSK cipher suites.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hint` contains an embedded null.
+    #[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+    pub fn set_psk_identity_hint(&mut self, hint: &str) -> Result<(), ErrorStack> {
+        let hint = CString::new(hint).unwrap();
+        unsafe {
+            cvt(ffi::SSL_CTX_use_psk_identity_hint(self.as_ptr(), hint.as_ptr())).map(|_| ())
+        }
+    }
+
+    /// Attaches a value of type `T` to the context, replacing any value of that type already
+    /// attached.
+    ///
+    /// The value is dropped when the context is freed, and can be retrieved with
+    /// `SslContextRef::app_data`, for example from the context of the `SslRef` passed to a
+    /// callback.
+    pub fn set_app_data<T>(&mut self, data: T)
+        where T: Any + 'static + Sync + Send
+    {
+        unsafe {
+            let idx = get_callback_idx::<AppData<T>>();
+            let old = ffi::SSL_CTX_get_ex_data(self.as_ptr(), idx);
+            ffi::SSL_CTX_set_ex_data(self.as_ptr(),
+                                     idx,
+                                     Box::into_raw(Box::new(AppData(data))) as *mut c_void);
+            if !old.is_null() {
+                drop(Box::from_raw(old as *mut AppData<T>));
+            }
+        }
+    }
+
     pub fn build(self) -> SslContext {
         let ctx = SslContext(self.0);
         mem::forget(self);
@@ -1045,6 +2207,118 @@ impl SslContextRef {
             StackRef::from_ptr(chain)
         }
     }
+
+    /// Returns the cipher suites enabled for new connections, in order of preference.
+    pub fn ciphers(&self) -> Option<&StackRef<SslCipher>> {
+        unsafe {
+            let ptr = compat::SSL_CTX_get_ciphers(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Returns whether renegotiation has been disabled with
+    /// `SslContextBuilder::set_renegotiation_disabled`.
+    pub fn renegotiation_disabled(&self) -> bool {
+        unsafe {
+            let policy = ffi::SSL_CTX_get_ex_data(self.as_ptr(),
+                                                  get_callback_idx::<RenegotiationPolicy>());
+            !policy.is_null() && (*(policy as *const RenegotiationPolicy)).0
+        }
+    }
+
+    /// Returns a snapshot of the handshake and session cache counters of this context.
+    ///
+    /// The counters cover every `Ssl` created from this context since it was created.
+    pub fn stats(&self) -> SslContextStats {
+        unsafe {
+            let ctx = self.as_ptr();
+            SslContextStats {
+                sessions: ffi::SSL_CTX_sess_number(ctx) as u64,
+                connect: ffi::SSL_CTX_sess_connect(ctx) as u64,
+                connect_good: ffi::SSL_CTX_sess_connect_good(ctx) as u64,
+                connect_renegotiate: ffi::SSL_CTX_sess_connect_renegotiate(ctx) as u64,
+                accept: ffi::SSL_CTX_sess_accept(ctx) as u64,
+                accept_good: ffi::SSL_CTX_sess_accept_good(ctx) as u64,
+                accept_renegotiate: ffi::SSL_CTX_sess_accept_renegotiate(ctx) as u64,
+                hits: ffi::SSL_CTX_sess_hits(ctx) as u64,
+                cb_hits: ffi::SSL_CTX_sess_cb_hits(ctx) as u64,
+                misses: ffi::SSL_CTX_sess_misses(ctx) as u64,
+                timeouts: ffi::SSL_CTX_sess_timeouts(ctx) as u64,
+                cache_full: ffi::SSL_CTX_sess_cache_full(ctx) as u64,
+            }
+        }
+    }
+
+    /// Returns the list of CA names sent to clients requesting a certificate.
+    pub fn client_ca_list(&self) -> Option<&StackRef<X509Name>> {
+        unsafe {
+            let ptr = ffi::SSL_CTX_get_client_CA_list(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Returns the value of type `T` attached with `SslContextBuilder::set_app_data`, if any.
+    pub fn app_data<T: Any + 'static>(&self) -> Option<&T> {
+        unsafe {
+            let data = ffi::SSL_CTX_get_ex_data(self.as_ptr(), get_callback_idx::<AppData<T>>());
+            if data.is_null() {
+                None
+            } else {
+                Some(&(*(data as *const AppData<T>)).0)
+            }
+        }
+    }
+}
+
+/// Connection and session cache statistics of an `SslContext`.
+///
+/// This is returned by `SslContextRef::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SslContextStats {
+    /// The number of sessions currently in the internal session cache.
+    pub sessions: u64,
+
+    /// The number of handshakes started in client mode.
+    pub connect: u64,
+
+    /// The number of handshakes successfully completed in client mode.
+    pub connect_good: u64,
+
+    /// The number of renegotiations started in client mode.
+    pub connect_renegotiate: u64,
+
+    /// The number of handshakes started in server mode.
+    pub accept: u64,
+
+    /// The number of handshakes successfully completed in server mode.
+    pub accept_good: u64,
+
+    /// The number of renegotiations started in server mode.
+    pub accept_renegotiate: u64,
+
+    /// The number of sessions successfully reused from the internal session cache.
+    pub hits: u64,
+
+    /// The number of sessions successfully retrieved from an external session cache in server
+    /// mode.
+    pub cb_hits: u64,
+
+    /// The number of sessions proposed by clients which were not found in the cache.
+    pub misses: u64,
+
+    /// The number of sessions proposed by clients which were found in the cache but had expired.
+    pub timeouts: u64,
+
+    /// The number of sessions removed from the cache because it was full.
+    pub cache_full: u64,
 }
 
 pub struct CipherBits {
@@ -1055,6 +2329,38 @@ pub struct CipherBits {
     pub algorithm: i32,
 }
 
+/// A signature algorithm supported by both ends of a connection.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SignatureAlgorithm {
+    /// The public key algorithm, such as `nid::RSAENCRYPTION`.
+    pub sign: Nid,
+
+    /// The digest algorithm, such as `nid::SHA256`.
+    pub hash: Nid,
+
+    /// The combined signature algorithm, such as `nid::SHA256WITHRSAENCRYPTION`.
+    pub sign_hash: Nid,
+}
+
+/// A DTLS-SRTP protection profile.
+pub struct SrtpProtectionProfileRef(Opaque);
+
+impl ForeignTypeRef for SrtpProtectionProfileRef {
+    type CType = ffi::SRTP_PROTECTION_PROFILE;
+}
+
+impl SrtpProtectionProfileRef {
+    /// Returns the identifier of the profile.
+    pub fn id(&self) -> SrtpProfileId {
+        unsafe { SrtpProfileId::from_raw((*self.as_ptr()).id) }
+    }
+
+    /// Returns the name of the profile, such as `SRTP_AES128_CM_SHA1_80`.
+    pub fn name(&self) -> &'static str {
+        unsafe { CStr::from_ptr((*self.as_ptr()).name).to_str().unwrap() }
+    }
+}
+
 pub struct SslCipher(*mut ffi::SSL_CIPHER);
 
 impl ForeignType for SslCipher {
@@ -1080,7 +2386,11 @@ impl DerefMut for SslCipher {
     }
 }
 
-pub struct SslCipherRef(Opaque);
+impl Stackable for SslCipher {
+    type StackType = ffi::stack_st_SSL_CIPHER;
+}
+
+pub struct SslCipherRef(Opaque);
 
 impl ForeignTypeRef for SslCipherRef {
     type CType = ffi::SSL_CIPHER;
@@ -1138,6 +2448,10 @@ foreign_type! {
     pub struct SslSessionRef;
 }
 
+impl SslSession {
+    from_der!(SslSession, ffi::d2i_SSL_SESSION);
+}
+
 impl SslSessionRef {
     /// Returns the SSL session ID.
     pub fn id(&self) -> &[u8] {
@@ -1159,6 +2473,9 @@ impl SslSessionRef {
     pub fn master_key(&self, buf: &mut [u8]) -> usize {
         unsafe { compat::SSL_SESSION_get_master_key(self.as_ptr(), buf.as_mut_ptr(), buf.len()) }
     }
+
+    to_der!(ffi::i2d_SSL_SESSION);
+    to_der_into!(ffi::i2d_SSL_SESSION);
 }
 
 foreign_type! {
@@ -1195,6 +2512,15 @@ impl SslRef {
         unsafe { ffi::SSL_write(self.as_ptr(), buf.as_ptr() as *const c_void, len) }
     }
 
+    fn peek(&mut self, buf: &mut [u8]) -> c_int {
+        let len = cmp::min(c_int::max_value() as usize, buf.len()) as c_int;
+        unsafe { ffi::SSL_peek(self.as_ptr(), buf.as_ptr() as *mut c_void, len) }
+    }
+
+    fn is_dtls(&self) -> bool {
+        unsafe { compat::SSL_is_dtls(self.as_ptr()) != 0 }
+    }
+
     fn get_error(&self, ret: c_int) -> c_int {
         unsafe { ffi::SSL_get_error(self.as_ptr(), ret) }
     }
@@ -1213,8 +2539,11 @@ impl SslRef {
     /// preveification process was successful, and an object providing access
     /// to the certificate chain. It should return `true` if the certificate
     /// chain is valid and `false` otherwise.
+    ///
+    /// This overrides any callback set on the context with
+    /// `SslContextBuilder::set_verify_callback`.
     pub fn set_verify_callback<F>(&mut self, mode: SslVerifyMode, verify: F)
-        where F: Fn(bool, &X509StoreContextRef) -> bool + Any + 'static + Sync + Send
+        where F: Fn(bool, &mut X509StoreContextRef) -> bool + Any + 'static + Sync + Send
     {
         unsafe {
             let verify = Box::new(verify);
@@ -1225,6 +2554,107 @@ impl SslRef {
         }
     }
 
+    /// Configures whether the client offers to authenticate after the handshake.
+    ///
+    /// This overrides the setting of `SslContextBuilder::set_post_handshake_auth`.
+    ///
+    /// This corresponds to `SSL_set_post_handshake_auth`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn set_post_handshake_auth(&mut self, enable: bool) {
+        unsafe { ffi::SSL_set_post_handshake_auth(self.as_ptr(), enable as c_int) }
+    }
+
+    /// Requests a certificate from the client of an established TLS 1.3 connection.
+    ///
+    /// This lets a server ask for client authentication only once it is needed, for example when
+    /// a protected resource is requested. The request is sent with the next write to the stream,
+    /// and the client's response is processed, and checked according to the verify mode and
+    /// callback, by a later read. An error is returned if the connection is not using TLS 1.3 or
+    /// the client has not enabled post-handshake authentication.
+    ///
+    /// This corresponds to `SSL_verify_client_post_handshake`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn verify_client_post_handshake(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_verify_client_post_handshake(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Records the address of the remote end of the connection.
+    ///
+    /// OpenSSL does not know about the underlying transport, so servers which want to make
+    /// decisions based on the client's address in callbacks, such as the one registered with
+    /// `SslContextBuilder::set_client_hello_callback`, must supply it here before the handshake.
+    pub fn set_peer_addr(&mut self, addr: SocketAddr) {
+        unsafe {
+            let idx = get_ssl_callback_idx::<SocketAddr>();
+            let old = ffi::SSL_get_ex_data(self.as_ptr(), idx);
+            ffi::SSL_set_ex_data(self.as_ptr(), idx, Box::into_raw(Box::new(addr)) as *mut c_void);
+            if !old.is_null() {
+                drop(Box::from_raw(old as *mut SocketAddr));
+            }
+        }
+    }
+
+    /// Returns the address recorded with `set_peer_addr`, if any.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        unsafe {
+            let addr = ffi::SSL_get_ex_data(self.as_ptr(), get_ssl_callback_idx::<SocketAddr>());
+            if addr.is_null() {
+                None
+            } else {
+                Some(*(addr as *const SocketAddr))
+            }
+        }
+    }
+
+    /// Attaches a value of type `T` to the connection, replacing any value of that type already
+    /// attached.
+    ///
+    /// The value is dropped when the `Ssl` is freed. This gives state shared between several
+    /// callbacks, such as the server name, verify and ALPN callbacks, a place to live for the
+    /// duration of the connection.
+    pub fn set_app_data<T>(&mut self, data: T)
+        where T: Any + 'static + Sync + Send
+    {
+        unsafe {
+            let idx = get_ssl_callback_idx::<AppData<T>>();
+            let old = ffi::SSL_get_ex_data(self.as_ptr(), idx);
+            ffi::SSL_set_ex_data(self.as_ptr(),
+                                 idx,
+                                 Box::into_raw(Box::new(AppData(data))) as *mut c_void);
+            if !old.is_null() {
+                drop(Box::from_raw(old as *mut AppData<T>));
+            }
+        }
+    }
+
+    /// Returns the value of type `T` attached with `set_app_data`, if any.
+    pub fn app_data<T: Any + 'static>(&self) -> Option<&T> {
+        unsafe {
+            let data = ffi::SSL_get_ex_data(self.as_ptr(), get_ssl_callback_idx::<AppData<T>>());
+            if data.is_null() {
+                None
+            } else {
+                Some(&(*(data as *const AppData<T>)).0)
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value of type `T` attached with `set_app_data`, if any.
+    pub fn app_data_mut<T: Any + 'static>(&mut self) -> Option<&mut T> {
+        unsafe {
+            let data = ffi::SSL_get_ex_data(self.as_ptr(), get_ssl_callback_idx::<AppData<T>>());
+            if data.is_null() {
+                None
+            } else {
+                Some(&mut (*(data as *mut AppData<T>)).0)
+            }
+        }
+    }
+
     pub fn set_tmp_dh(&mut self, dh: &DhRef) -> Result<(), ErrorStack> {
         unsafe { cvt(ffi::SSL_set_tmp_dh(self.as_ptr(), dh.as_ptr()) as c_int).map(|_| ()) }
     }
@@ -1271,6 +2701,7 @@ impl SslRef {
         unsafe { cvt(ffi::SSL_set_ecdh_auto(self.as_ptr(), onoff as c_int)).map(|_| ()) }
     }
 
+    /// Returns the cipher suite negotiated for the session, if the handshake has completed.
     pub fn current_cipher(&self) -> Option<&SslCipherRef> {
         unsafe {
             let ptr = ffi::SSL_get_current_cipher(self.as_ptr());
@@ -1283,6 +2714,106 @@ impl SslRef {
         }
     }
 
+    /// Derives `out_len` bytes of keying material from the session's master secret, as defined
+    /// in RFC 5705.
+    ///
+    /// `label` identifies the application protocol using the material, and `context` is an
+    /// optional value mixed into the derivation. Note that no context and an empty context
+    /// produce different results.
+    pub fn export_keying_material(&self,
+                                  label: &str,
+                                  context: Option<&[u8]>,
+                                  out_len: usize)
+                                  -> Result<Vec<u8>, ErrorStack> {
+        unsafe {
+            let mut out = vec![0; out_len];
+            let (context, contextlen, use_context) = match context {
+                Some(context) => (context.as_ptr(), context.len(), 1),
+                None => (ptr::null(), 0, 0),
+            };
+            try!(cvt(ffi::SSL_export_keying_material(self.as_ptr(),
+                                                     out.as_mut_ptr(),
+                                                     out.len(),
+                                                     label.as_ptr() as *const c_char,
+                                                     label.len(),
+                                                     context,
+                                                     contextlen,
+                                                     use_context)));
+            Ok(out)
+        }
+    }
+
+    /// Returns the cipher suites enabled for this connection, in order of preference.
+    ///
+    /// Unless overridden on this `Ssl`, these are the ciphers configured on its context.
+    pub fn ciphers(&self) -> Option<&StackRef<SslCipher>> {
+        unsafe {
+            let ptr = ffi::SSL_get_ciphers(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Sets the minimum supported protocol version for this connection.
+    ///
+    /// See `SslContextBuilder::set_min_proto_version`.
+    pub fn set_min_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(compat::SSL_set_min_proto_version(self.as_ptr(), version.map_or(0, |v| v.0)))
+                .map(|_| ())
+        }
+    }
+
+    /// Sets the maximum supported protocol version for this connection.
+    ///
+    /// See `SslContextBuilder::set_max_proto_version`.
+    pub fn set_max_proto_version(&mut self, version: Option<SslVersion>) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(compat::SSL_set_max_proto_version(self.as_ptr(), version.map_or(0, |v| v.0)))
+                .map(|_| ())
+        }
+    }
+
+    /// Returns the number of renegotiations started on this connection.
+    ///
+    /// Renegotiations are only tracked on connections whose context was configured with
+    /// `SslContextBuilder::set_renegotiation_disabled`, whether or not it was disabled.
+    pub fn renegotiations(&self) -> u32 {
+        unsafe {
+            let state = ffi::SSL_get_ex_data(self.as_ptr(),
+                                             get_ssl_callback_idx::<RenegotiationState>());
+            if state.is_null() {
+                0
+            } else {
+                (*(state as *const RenegotiationState)).renegotiations
+            }
+        }
+    }
+
+    /// Determines if the peer started a renegotiation after it was disabled with
+    /// `SslContextBuilder::set_renegotiation_disabled`.
+    pub fn renegotiation_rejected(&self) -> bool {
+        self.renegotiations() > 0 && self.ssl_context().renegotiation_disabled()
+    }
+
+    /// Returns the list of CA names associated with this connection.
+    ///
+    /// On the client side this is the list of acceptable CAs sent by the server when it requested
+    /// a client certificate. On the server side it is the list that will be sent to the client.
+    pub fn client_ca_list(&self) -> Option<&StackRef<X509Name>> {
+        unsafe {
+            let ptr = ffi::SSL_get_client_CA_list(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
     pub fn state_string(&self) -> &'static str {
         let state = unsafe {
             let ptr = ffi::SSL_state_string(self.as_ptr());
@@ -1322,6 +2853,21 @@ impl SslRef {
         }
     }
 
+    /// Returns the certificate chain presented by the peer, if present.
+    ///
+    /// On the client side, the chain includes the leaf certificate. On the server side, it does
+    /// not.
+    pub fn peer_cert_chain(&self) -> Option<&StackRef<X509>> {
+        unsafe {
+            let ptr = ffi::SSL_get_peer_cert_chain(self.as_ptr());
+            if ptr.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(ptr))
+            }
+        }
+    }
+
     /// Returns the certificate associated with this `Ssl`, if present.
     pub fn certificate(&self) -> Option<&X509Ref> {
         unsafe {
@@ -1468,11 +3014,195 @@ impl SslRef {
         unsafe { X509VerifyParamRef::from_ptr_mut(ffi::SSL_get0_param(self.as_ptr())) }
     }
 
+    /// Sets the host name the peer's certificate will be checked against.
+    ///
+    /// This is equivalent to `param_mut().set_host(host)`, except that the name is also recorded
+    /// so that it can be retrieved with `verify_hostname`, for example from a verify callback.
+    ///
+    /// Requires the `v102` or `v110` features and OpenSSL 1.0.2 or 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn set_verify_hostname(&mut self, host: &str) -> Result<(), ErrorStack> {
+        try!(self._param_mut().set_host(host));
+        self.record_verify_hostname(host);
+        Ok(())
+    }
+
+    fn record_verify_hostname(&mut self, host: &str) {
+        unsafe {
+            let idx = get_ssl_callback_idx::<VerifyHostname>();
+            let old = ffi::SSL_get_ex_data(self.as_ptr(), idx);
+            let host = Box::new(VerifyHostname(host.to_owned()));
+            ffi::SSL_set_ex_data(self.as_ptr(), idx, Box::into_raw(host) as *mut c_void);
+            if !old.is_null() {
+                drop(Box::from_raw(old as *mut VerifyHostname));
+            }
+        }
+    }
+
+    /// Returns the host name the peer's certificate is checked against, if any.
+    ///
+    /// This is the name passed to `set_verify_hostname` or, for connections created by an
+    /// `SslConnector`, the domain being connected to. Names configured directly through
+    /// `param_mut` are not reported.
+    pub fn verify_hostname(&self) -> Option<&str> {
+        unsafe {
+            let host = ffi::SSL_get_ex_data(self.as_ptr(), get_ssl_callback_idx::<VerifyHostname>());
+            if host.is_null() {
+                None
+            } else {
+                Some(&(*(host as *const VerifyHostname)).0)
+            }
+        }
+    }
+
+    /// Returns the signature algorithms supported by both the peer and the local configuration,
+    /// in order of preference.
+    ///
+    /// This is only meaningful once the peer's signature algorithms have been received, such as
+    /// after the handshake or from within the server name callback on the server side.
+    ///
+    /// Requires the `v102` or `v110` features and OpenSSL 1.0.2 or 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn shared_sigalgs(&self) -> Vec<SignatureAlgorithm> {
+        unsafe {
+            let ptr = self.as_ptr();
+            let n = ffi::SSL_get_shared_sigalgs(ptr,
+                                                0,
+                                                ptr::null_mut(),
+                                                ptr::null_mut(),
+                                                ptr::null_mut(),
+                                                ptr::null_mut(),
+                                                ptr::null_mut());
+            (0..n)
+                .map(|i| {
+                    let (mut sign, mut hash, mut sign_hash) = (0, 0, 0);
+                    ffi::SSL_get_shared_sigalgs(ptr,
+                                                i,
+                                                &mut sign,
+                                                &mut hash,
+                                                &mut sign_hash,
+                                                ptr::null_mut(),
+                                                ptr::null_mut());
+                    SignatureAlgorithm {
+                        sign: Nid::from_raw(sign),
+                        hash: Nid::from_raw(hash),
+                        sign_hash: Nid::from_raw(sign_hash),
+                    }
+                })
+                .collect()
+        }
+    }
+
+    /// Returns the digest used by the peer to sign its handshake messages, if known.
+    ///
+    /// Requires the `v102` or `v110` features and OpenSSL 1.0.2 or 1.1.0.
+    #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+    pub fn peer_signature_nid(&self) -> Option<Nid> {
+        unsafe {
+            let mut nid = 0;
+            if ffi::SSL_get_peer_signature_nid(self.as_ptr(), &mut nid) > 0 {
+                Some(Nid::from_raw(nid))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the public key algorithm used by the peer to sign its handshake messages, if known.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn peer_signature_type_nid(&self) -> Option<Nid> {
+        unsafe {
+            let mut nid = 0;
+            if ffi::SSL_get_peer_signature_type_nid(self.as_ptr(), &mut nid) > 0 {
+                Some(Nid::from_raw(nid))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the most recent alert sent by the peer, if any.
+    ///
+    /// A handshake which fails because the peer rejected it will typically have received a fatal
+    /// alert, such as `unknown_ca` when the peer does not trust this side's certificate.
+    ///
+    /// Alerts are recorded by an info callback installed by `SslContextBuilder::new`, so this
+    /// always returns `None` for contexts created with `SslContextBuilder::from_ptr`.
+    pub fn peer_alert(&self) -> Option<SslAlert> {
+        unsafe {
+            let alert = ffi::SSL_get_ex_data(self.as_ptr(), get_ssl_callback_idx::<SslAlert>()) as
+                        *const SslAlert;
+            if alert.is_null() { None } else { Some(*alert) }
+        }
+    }
+
     /// Returns the result of X509 certificate verification.
     pub fn verify_result(&self) -> Option<X509VerifyError> {
         unsafe { X509VerifyError::from_raw(ffi::SSL_get_verify_result(self.as_ptr())) }
     }
 
+    /// Returns a detailed description of the X509 certificate verification failure, if any.
+    ///
+    /// In addition to the error reported by `verify_result`, the description identifies the
+    /// certificate in the peer's chain which failed verification and suggests a likely fix for
+    /// common failures, which makes it suitable for logs and error reports.
+    ///
+    /// The failing certificate is located by verifying the peer's chain again against the
+    /// context's certificate store. If that does not reproduce the original error, for example
+    /// because a verification callback overrode an earlier failure, only the error is reported.
+    pub fn verify_error_detail(&self) -> Option<VerifyErrorDetail> {
+        let error = match self.verify_result() {
+            Some(error) => error,
+            None => return None,
+        };
+
+        let failure = self.locate_verify_failure().ok().and_then(|f| f);
+        let (depth, cert) = match failure {
+            Some((e, depth, cert)) if e.as_raw() == error.as_raw() => (Some(depth), Some(cert)),
+            _ => (None, None),
+        };
+
+        Some(VerifyErrorDetail {
+            error: error,
+            depth: depth,
+            cert: cert,
+        })
+    }
+
+    fn locate_verify_failure(&self) -> Result<Option<(X509VerifyError, u32, X509)>, ErrorStack> {
+        // The peer's certificate is not recorded if the handshake failed, but on the client side
+        // it is still available as the first entry of the chain.
+        let leaf = match self.peer_certificate() {
+            Some(cert) => cert,
+            None => {
+                match self.peer_cert_chain() {
+                    Some(chain) if !self.is_server() && chain.len() > 0 => chain[0].to_owned(),
+                    _ => return Ok(None),
+                }
+            }
+        };
+
+        unsafe {
+            let ctx = X509StoreContext::from_ptr(try!(cvt_p(ffi::X509_STORE_CTX_new())));
+            let chain = self.peer_cert_chain().map_or(ptr::null_mut(), |c| c.as_ptr());
+            try!(cvt(ffi::X509_STORE_CTX_init(ctx.as_ptr(),
+                                              self.ssl_context().cert_store().as_ptr(),
+                                              leaf.as_ptr(),
+                                              chain)));
+            try!(copy_verify_param(&ctx, self));
+
+            if ffi::X509_verify_cert(ctx.as_ptr()) > 0 {
+                return Ok(None);
+            }
+            match (ctx.error(), ctx.current_cert()) {
+                (Some(error), Some(cert)) => Ok(Some((error, ctx.error_depth(), cert.to_owned()))),
+                _ => Ok(None),
+            }
+        }
+    }
+
     /// Returns the SSL session.
     pub fn session(&self) -> Option<&SslSessionRef> {
         unsafe {
@@ -1521,6 +3251,62 @@ impl SslRef {
         }
     }
 
+    /// Sets the MTU used for DTLS connections.
+    ///
+    /// By default OpenSSL queries the underlying stream for its MTU, which the stream wrapper
+    /// cannot answer, so the minimum permitted value is used. The `SSL_OP_NO_QUERY_MTU` option
+    /// should be set along with this.
+    pub fn set_mtu(&mut self, mtu: u32) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_set_mtu(self.as_ptr(), mtu as c_long) as c_int).map(|_| ()) }
+    }
+
+    /// Sets the maximum fragment length the client requests with the RFC 6066
+    /// max_fragment_length extension.
+    ///
+    /// This overrides the setting of `SslContextBuilder::set_max_fragment_length`.
+    ///
+    /// This corresponds to `SSL_set_tlsext_max_fragment_length`.
+    ///
+    /// Requires the `v111` feature and OpenSSL 1.1.1.
+    #[cfg(all(feature = "v111", ossl111))]
+    pub fn set_max_fragment_length(&mut self, len: MaxFragmentLength) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::SSL_set_tlsext_max_fragment_length(self.as_ptr(), len.0)).map(|_| ()) }
+    }
+
+    /// Enables the DTLS extension for establishing SRTP keying material on this connection.
+    ///
+    /// See `SslContextBuilder::set_tlsext_use_srtp`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `protocols` contains an embedded null.
+    pub fn set_tlsext_use_srtp(&mut self, protocols: &str) -> Result<(), ErrorStack> {
+        let protocols = CString::new(protocols).unwrap();
+        unsafe {
+            // This function returns 0 on success, unlike the rest of the library.
+            if ffi::SSL_set_tlsext_use_srtp(self.as_ptr(), protocols.as_ptr()) == 0 {
+                Ok(())
+            } else {
+                Err(ErrorStack::get())
+            }
+        }
+    }
+
+    /// Returns the SRTP protection profile negotiated by the DTLS handshake, if any.
+    ///
+    /// The keying material for the profile can be obtained with `export_keying_material` using
+    /// the label `EXTRACTOR-dtls_srtp`.
+    pub fn selected_srtp_profile(&self) -> Option<&SrtpProtectionProfileRef> {
+        unsafe {
+            let profile = ffi::SSL_get_selected_srtp_profile(self.as_ptr());
+            if profile.is_null() {
+                None
+            } else {
+                Some(SrtpProtectionProfileRef::from_ptr(profile))
+            }
+        }
+    }
+
     /// Determines if this `Ssl` is configured for server-side or client-side use.
     pub fn is_server(&self) -> bool {
         unsafe {
@@ -1529,6 +3315,122 @@ impl SslRef {
     }
 }
 
+// Applies the connection's verification parameters, including the expected host name, to a
+// context used to repeat its verification.
+#[cfg(any(ossl102, ossl110))]
+unsafe fn copy_verify_param(ctx: &X509StoreContextRef, ssl: &SslRef) -> Result<(), ErrorStack> {
+    cvt(ffi::X509_VERIFY_PARAM_set1(ffi::X509_STORE_CTX_get0_param(ctx.as_ptr()),
+                                    ffi::SSL_get0_param(ssl.as_ptr())))
+        .map(|_| ())
+}
+
+#[cfg(not(any(ossl102, ossl110)))]
+unsafe fn copy_verify_param(_: &X509StoreContextRef, _: &SslRef) -> Result<(), ErrorStack> {
+    Ok(())
+}
+
+/// A detailed description of an X509 certificate verification failure.
+///
+/// Returned by `SslRef::verify_error_detail`. The `Display` implementation renders all of the
+/// available information on a single line.
+pub struct VerifyErrorDetail {
+    error: X509VerifyError,
+    depth: Option<u32>,
+    cert: Option<X509>,
+}
+
+impl VerifyErrorDetail {
+    /// Returns the verification error.
+    pub fn error(&self) -> &X509VerifyError {
+        &self.error
+    }
+
+    /// Returns the depth in the chain of the certificate which failed verification.
+    ///
+    /// The peer's own certificate is at depth 0. `None` is returned if the failure could not be
+    /// attributed to a certificate.
+    pub fn depth(&self) -> Option<u32> {
+        self.depth
+    }
+
+    /// Returns the certificate which failed verification.
+    pub fn cert(&self) -> Option<&X509Ref> {
+        self.cert.as_ref().map(|c| &**c)
+    }
+
+    /// Returns a suggestion for resolving the failure, if one is known for the error.
+    pub fn hint(&self) -> Option<&'static str> {
+        let hint = match self.error.as_raw() as c_int {
+            ffi::X509_V_ERR_UNABLE_TO_GET_ISSUER_CERT |
+            ffi::X509_V_ERR_UNABLE_TO_GET_ISSUER_CERT_LOCALLY => {
+                "the issuing CA is not trusted; add it to the certificate store or have the peer \
+                 send its intermediate certificates"
+            }
+            ffi::X509_V_ERR_UNABLE_TO_VERIFY_LEAF_SIGNATURE => {
+                "the peer sent an incomplete chain; have it send its intermediate certificates"
+            }
+            ffi::X509_V_ERR_DEPTH_ZERO_SELF_SIGNED_CERT => {
+                "the peer's certificate is self-signed; add it to the certificate store to trust it"
+            }
+            ffi::X509_V_ERR_SELF_SIGNED_CERT_IN_CHAIN => {
+                "the chain ends in a root CA which is not in the certificate store"
+            }
+            ffi::X509_V_ERR_CERT_HAS_EXPIRED => {
+                "the certificate has expired; renew it or check the system clock"
+            }
+            ffi::X509_V_ERR_CERT_NOT_YET_VALID => {
+                "the certificate is not valid yet; check the system clock"
+            }
+            ffi::X509_V_ERR_CERT_REVOKED => "the certificate has been revoked by its issuer",
+            ffi::X509_V_ERR_UNABLE_TO_GET_CRL => {
+                "no CRL is available for the certificate; add one to the certificate store or \
+                 disable CRL checking"
+            }
+            ffi::X509_V_ERR_INVALID_PURPOSE => {
+                "the certificate may not be used for this purpose; check its extended key usage"
+            }
+            #[cfg(any(ossl102, ossl110))]
+            ffi::X509_V_ERR_HOSTNAME_MISMATCH => {
+                "the certificate does not cover the requested host name; check its subject \
+                 alternative names"
+            }
+            #[cfg(any(ossl102, ossl110))]
+            ffi::X509_V_ERR_IP_ADDRESS_MISMATCH => {
+                "the certificate does not cover the requested IP address"
+            }
+            _ => return None,
+        };
+        Some(hint)
+    }
+}
+
+impl fmt::Debug for VerifyErrorDetail {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("VerifyErrorDetail")
+            .field("error", &self.error)
+            .field("depth", &self.depth)
+            .field("hint", &self.hint())
+            .finish()
+    }
+}
+
+impl fmt::Display for VerifyErrorDetail {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "certificate verification failed"));
+        if let Some(depth) = self.depth {
+            try!(write!(fmt, " at depth {}", depth));
+        }
+        if let Some(ref cert) = self.cert {
+            try!(write!(fmt, " ({})", cert.subject_name()));
+        }
+        try!(write!(fmt, ": {} (error {})", self.error, self.error.as_raw()));
+        if let Some(hint) = self.hint() {
+            try!(write!(fmt, "; hint: {}", hint));
+        }
+        Ok(())
+    }
+}
+
 unsafe impl Sync for Ssl {}
 unsafe impl Send for Ssl {}
 
@@ -1558,11 +3460,13 @@ impl Ssl {
         let mut stream = SslStream::new_base(self, stream);
         let ret = unsafe { ffi::SSL_connect(stream.ssl.as_ptr()) };
         if ret > 0 {
+            stream.check_panic();
             Ok(stream)
         } else {
             match stream.make_error(ret) {
                 e @ Error::WantWrite(_) |
-                e @ Error::WantRead(_) => {
+                e @ Error::WantRead(_) |
+                e @ Error::WantX509Lookup => {
                     Err(HandshakeError::Interrupted(MidHandshakeSslStream {
                         stream: stream,
                         error: e,
@@ -1590,11 +3494,13 @@ impl Ssl {
         let mut stream = SslStream::new_base(self, stream);
         let ret = unsafe { ffi::SSL_accept(stream.ssl.as_ptr()) };
         if ret > 0 {
+            stream.check_panic();
             Ok(stream)
         } else {
             match stream.make_error(ret) {
                 e @ Error::WantWrite(_) |
-                e @ Error::WantRead(_) => {
+                e @ Error::WantRead(_) |
+                e @ Error::WantX509Lookup => {
                     Err(HandshakeError::Interrupted(MidHandshakeSslStream {
                         stream: stream,
                         error: e,
@@ -1609,13 +3515,33 @@ impl Ssl {
             }
         }
     }
-}
 
-/// An SSL stream midway through the handshake process.
-#[derive(Debug)]
-pub struct MidHandshakeSslStream<S> {
-    stream: SslStream<S>,
-    error: Error,
+    /// Starts a client-side handshake over the provided stream, reporting how it progressed.
+    ///
+    /// This is equivalent to `connect`, but distinguishes the reasons a handshake over a
+    /// non-blocking stream can be interrupted, so that the caller's event loop can wait for the
+    /// right condition before calling `MidHandshakeSslStream::handshake_step`.
+    pub fn connect_step<S>(self, stream: S) -> HandshakeProgress<S>
+        where S: Read + Write
+    {
+        HandshakeProgress::from_result(self.connect(stream))
+    }
+
+    /// Starts a server-side handshake over the provided stream, reporting how it progressed.
+    ///
+    /// This is the server-side counterpart of `connect_step`.
+    pub fn accept_step<S>(self, stream: S) -> HandshakeProgress<S>
+        where S: Read + Write
+    {
+        HandshakeProgress::from_result(self.accept(stream))
+    }
+}
+
+/// An SSL stream midway through the handshake process.
+#[derive(Debug)]
+pub struct MidHandshakeSslStream<S> {
+    stream: SslStream<S>,
+    error: Error,
 }
 
 impl<S> MidHandshakeSslStream<S> {
@@ -1648,11 +3574,13 @@ impl<S> MidHandshakeSslStream<S> {
     pub fn handshake(mut self) -> Result<SslStream<S>, HandshakeError<S>> {
         let ret = unsafe { ffi::SSL_do_handshake(self.stream.ssl.as_ptr()) };
         if ret > 0 {
+            self.stream.check_panic();
             Ok(self.stream)
         } else {
             match self.stream.make_error(ret) {
                 e @ Error::WantWrite(_) |
-                e @ Error::WantRead(_) => {
+                e @ Error::WantRead(_) |
+                e @ Error::WantX509Lookup => {
                     self.error = e;
                     Err(HandshakeError::Interrupted(self))
                 }
@@ -1663,6 +3591,53 @@ impl<S> MidHandshakeSslStream<S> {
             }
         }
     }
+
+    /// Continues the handshake process, reporting how it progressed.
+    ///
+    /// This is equivalent to `handshake`, but returns a `HandshakeProgress`.
+    pub fn handshake_step(self) -> HandshakeProgress<S> {
+        HandshakeProgress::from_result(self.handshake())
+    }
+}
+
+/// The state of a handshake after a call to `Ssl::connect_step`, `Ssl::accept_step` or
+/// `MidHandshakeSslStream::handshake_step`.
+#[derive(Debug)]
+pub enum HandshakeProgress<S> {
+    /// The handshake completed.
+    Done(SslStream<S>),
+    /// The handshake can continue once the underlying stream is readable.
+    WantRead(MidHandshakeSslStream<S>),
+    /// The handshake can continue once the underlying stream is writable.
+    WantWrite(MidHandshakeSslStream<S>),
+    /// The handshake can continue once the client certificate callback is ready to provide a
+    /// certificate.
+    WantX509Lookup(MidHandshakeSslStream<S>),
+    /// The handshake failed.
+    ///
+    /// The stream's `error` and `Ssl` describe the failure, for example through
+    /// `SslRef::verify_result` or `SslRef::peer_alert`.
+    Failure(MidHandshakeSslStream<S>),
+    /// The stream could not be set up to perform the handshake.
+    SetupFailure(ErrorStack),
+}
+
+impl<S> HandshakeProgress<S> {
+    fn from_result(result: Result<SslStream<S>, HandshakeError<S>>) -> HandshakeProgress<S> {
+        match result {
+            Ok(stream) => HandshakeProgress::Done(stream),
+            Err(HandshakeError::Interrupted(stream)) => {
+                match stream.error {
+                    Error::WantRead(_) => HandshakeProgress::WantRead(stream),
+                    Error::WantWrite(_) => HandshakeProgress::WantWrite(stream),
+                    Error::WantX509Lookup => HandshakeProgress::WantX509Lookup(stream),
+                    _ => HandshakeProgress::Failure(stream),
+                }
+            }
+            Err(HandshakeError::Failure(stream)) => HandshakeProgress::Failure(stream),
+            Err(HandshakeError::SetupFailure(e)) => HandshakeProgress::SetupFailure(e),
+        }
+    }
 }
 
 /// A stream wrapper which handles SSL encryption for an underlying stream.
@@ -1686,7 +3661,7 @@ impl<S> fmt::Debug for SslStream<S>
 impl<S: Read + Write> SslStream<S> {
     fn new_base(ssl: Ssl, stream: S) -> Self {
         unsafe {
-            let (bio, method) = bio::new(stream).unwrap();
+            let (bio, method) = bio::new(stream, ssl.is_dtls()).unwrap();
             ffi::SSL_set_bio(ssl.as_ptr(), bio, bio);
 
             SslStream {
@@ -1701,6 +3676,11 @@ impl<S: Read + Write> SslStream<S> {
     ///
     /// This is particularly useful with a nonblocking socket, where the error
     /// value will identify if OpenSSL is waiting on read or write readiness.
+    ///
+    /// With DTLS, each call returns the contents of exactly one record, which
+    /// holds the data of a single write by the peer. If `buf` is too small for
+    /// the next record, an error of kind `InvalidInput` is returned and the
+    /// record is left to be read with a larger buffer.
     pub fn ssl_read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
         // The intepretation of the return code here is a little odd with a
         // zero-length write. OpenSSL will likely correctly report back to us
@@ -1711,7 +3691,25 @@ impl<S: Read + Write> SslStream<S> {
             return Ok(0)
         }
 
-        let ret = self.ssl.read(buf);
+        // OpenSSL would return the rest of a large record from the next read
+        let ret = if self.ssl.is_dtls() {
+            let ret = self.ssl.peek(buf);
+            if ret <= 0 {
+                ret
+            } else if self.ssl.pending() > buf.len() {
+                self.check_panic();
+                return Err(Error::Stream(io::Error::new(io::ErrorKind::InvalidInput,
+                                                        "buffer too small for DTLS record")));
+            } else {
+                self.ssl.read(buf)
+            }
+        } else {
+            self.ssl.read(buf)
+        };
+        self.check_panic();
+        if self.ssl.renegotiation_rejected() {
+            return Err(renegotiation_rejected_error());
+        }
         if ret > 0 {
             Ok(ret as usize)
         } else {
@@ -1734,6 +3732,10 @@ impl<S: Read + Write> SslStream<S> {
         }
 
         let ret = self.ssl.write(buf);
+        self.check_panic();
+        if self.ssl.renegotiation_rejected() {
+            return Err(renegotiation_rejected_error());
+        }
         if ret > 0 {
             Ok(ret as usize)
         } else {
@@ -1798,11 +3800,12 @@ impl<S> SslStream<S> {
                     Some(err) => err,
                     None => {
                         io::Error::new(io::ErrorKind::Other,
-                                       "BUG: got an SSL_ERROR_WANT_WRITE with no error in the BIO")
+                                       "BUG: got an SSL_ERROR_WANT_READ with no error in the BIO")
                     }
                 };
                 Error::WantRead(err)
             },
+            ffi::SSL_ERROR_WANT_X509_LOOKUP => Error::WantX509Lookup,
             err => {
                 Error::Stream(io::Error::new(io::ErrorKind::InvalidData,
                                              format!("unexpected error {}", err)))
@@ -1814,6 +3817,9 @@ impl<S> SslStream<S> {
         if let Some(err) = unsafe { bio::take_panic::<S>(self.ssl.get_raw_rbio()) } {
             resume_unwind(err)
         }
+        if let Some(err) = unsafe { take_callback_panic(self.ssl.as_ptr()) } {
+            resume_unwind(err)
+        }
     }
 
     fn get_bio_error(&mut self) -> Option<io::Error> {
@@ -1845,6 +3851,14 @@ impl<S> SslStream<S> {
     pub fn ssl(&self) -> &SslRef {
         &self.ssl
     }
+
+    /// Returns a mutable reference to the OpenSSL `Ssl` object associated with this stream.
+    ///
+    /// This allows operations on an established connection such as
+    /// `SslRef::verify_client_post_handshake`.
+    pub fn ssl_mut(&mut self) -> &mut SslRef {
+        &mut self.ssl
+    }
 }
 
 impl<S: Read + Write> Read for SslStream<S> {
@@ -1852,24 +3866,14 @@ impl<S: Read + Write> Read for SslStream<S> {
         match self.ssl_read(buf) {
             Ok(n) => Ok(n),
             Err(Error::ZeroReturn) => Ok(0),
-            Err(Error::Stream(e)) => Err(e),
-            Err(Error::WantRead(e)) => Err(e),
-            Err(Error::WantWrite(e)) => Err(e),
-            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e)),
+            Err(e) => Err(e.into()),
         }
     }
 }
 
 impl<S: Read + Write> Write for SslStream<S> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.ssl_write(buf).map_err(|e| {
-            match e {
-                Error::Stream(e) => e,
-                Error::WantRead(e) => e,
-                Error::WantWrite(e) => e,
-                e => io::Error::new(io::ErrorKind::Other, e),
-            }
-        })
+        self.ssl_write(buf).map_err(io::Error::from)
     }
 
     fn flush(&mut self) -> io::Result<()> {
@@ -1877,6 +3881,405 @@ impl<S: Read + Write> Write for SslStream<S> {
     }
 }
 
+/// The maximum amount of plaintext carried by a single TLS record.
+const MAX_RECORD_PLAINTEXT: usize = 16 * 1024;
+
+/// An `SslStream` wrapper which buffers decrypted data and implements
+/// `BufRead`.
+///
+/// Unlike wrapping an `SslStream` in a `std::io::BufReader`, data held in the
+/// buffer is accounted for by `pending`, so it is not lost track of when
+/// deciding whether to wait for the underlying stream to become readable.
+///
+/// Writes are passed straight through to the `SslStream` unless the stream is
+/// corked with `set_corked`.
+///
+/// ## Warning
+///
+/// Data written while the stream is corked is **not** sent when the
+/// `BufferedSslStream` is dropped. Call `flush` before dropping the stream, or
+/// the buffered data is silently lost. Unlike `std::io::BufWriter`, the buffer
+/// is not flushed on drop, since a flush may block or fail with `WouldBlock`
+/// and there is nowhere to report the error.
+pub struct BufferedSslStream<S> {
+    stream: SslStream<S>,
+    buf: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+    write_buf: Vec<u8>,
+    corked: bool,
+}
+
+impl<S> fmt::Debug for BufferedSslStream<S>
+    where S: fmt::Debug
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("BufferedSslStream")
+            .field("stream", &self.stream)
+            .field("buffered", &(self.cap - self.pos))
+            .field("corked", &self.corked)
+            .field("buffered_writes", &self.write_buf.len())
+            .finish()
+    }
+}
+
+impl<S> BufferedSslStream<S> {
+    /// Wraps a stream with a buffer large enough to hold a full TLS record.
+    pub fn new(stream: SslStream<S>) -> BufferedSslStream<S> {
+        BufferedSslStream::with_capacity(16 * 1024, stream)
+    }
+
+    /// Wraps a stream with a buffer of the specified capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn with_capacity(capacity: usize, stream: SslStream<S>) -> BufferedSslStream<S> {
+        assert!(capacity > 0, "buffer capacity must be nonzero");
+        BufferedSslStream {
+            stream: stream,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+            write_buf: Vec::new(),
+            corked: false,
+        }
+    }
+
+    /// Corks or uncorks the stream.
+    ///
+    /// While the stream is corked, writes are collected in a buffer rather than each being sent
+    /// in its own TLS record. The buffer is sent as a single record when `flush` is called, or
+    /// when it holds a full record's worth of data. This avoids the overhead of a record per
+    /// write for protocols which produce many small writes.
+    ///
+    /// Data which is buffered when the stream is uncorked is sent by the next `write` or `flush`.
+    /// Buffered data is discarded if the stream is dropped without being flushed.
+    pub fn set_corked(&mut self, corked: bool) {
+        if corked {
+            self.write_buf.reserve(MAX_RECORD_PLAINTEXT);
+        }
+        self.corked = corked;
+    }
+
+    /// Determines if the stream is corked.
+    pub fn is_corked(&self) -> bool {
+        self.corked
+    }
+
+    /// Returns the number of bytes written while corked which have not yet been sent.
+    pub fn buffered_writes(&self) -> usize {
+        self.write_buf.len()
+    }
+
+    /// Returns a shared reference to the wrapped `SslStream`.
+    pub fn get_ref(&self) -> &SslStream<S> {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the wrapped `SslStream`.
+    ///
+    /// ## Warning
+    ///
+    /// Reading directly from the `SslStream` will skip over any buffered data.
+    pub fn get_mut(&mut self) -> &mut SslStream<S> {
+        &mut self.stream
+    }
+
+    /// Returns the OpenSSL `Ssl` object associated with this stream.
+    pub fn ssl(&self) -> &SslRef {
+        self.stream.ssl()
+    }
+
+    /// Returns the data currently held in the buffer.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.cap]
+    }
+
+    /// Returns the number of bytes which can be read without touching the
+    /// underlying stream.
+    ///
+    /// This includes both the contents of the buffer and the remainder of the
+    /// TLS record currently being processed by OpenSSL.
+    pub fn pending(&self) -> usize {
+        (self.cap - self.pos) + self.stream.ssl().pending()
+    }
+
+    /// Consumes the wrapper, returning the `SslStream`.
+    ///
+    /// Any buffered data is discarded, including writes which have not been flushed.
+    pub fn into_inner(self) -> SslStream<S> {
+        self.stream
+    }
+}
+
+impl<S: Read + Write> Read for BufferedSslStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Bypass the buffer entirely for large reads when it's empty
+        if self.pos == self.cap && buf.len() >= self.buf.len() {
+            return self.stream.read(buf);
+        }
+
+        let n = {
+            let mut rem = try!(self.fill_buf());
+            try!(rem.read(buf))
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<S: Read + Write> BufRead for BufferedSslStream<S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.cap {
+            self.cap = try!(self.stream.read(&mut self.buf));
+            self.pos = 0;
+        }
+        Ok(&self.buf[self.pos..self.cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.cap);
+    }
+}
+
+impl<S: Read + Write> BufferedSslStream<S> {
+    fn flush_write_buf(&mut self) -> io::Result<()> {
+        // The buffer is left untouched if a write fails, so that a nonblocking write is retried
+        // with the same data, as OpenSSL requires.
+        while !self.write_buf.is_empty() {
+            let n = try!(self.stream.write(&self.write_buf));
+            self.write_buf.drain(..n);
+        }
+        Ok(())
+    }
+}
+
+impl<S: Read + Write> Write for BufferedSslStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.corked || self.write_buf.len() + buf.len() > MAX_RECORD_PLAINTEXT {
+            try!(self.flush_write_buf());
+        }
+
+        if !self.corked || buf.len() >= MAX_RECORD_PLAINTEXT {
+            self.stream.write(buf)
+        } else {
+            self.write_buf.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        try!(self.flush_write_buf());
+        self.stream.flush()
+    }
+}
+
+/// An SSL session which exchanges ciphertext with the peer through in-memory buffers.
+///
+/// Unlike `SslStream`, no underlying stream is required. Ciphertext received from the peer is
+/// passed to `write_ciphertext`, and ciphertext which must be sent to the peer is retrieved with
+/// `read_ciphertext`. This allows the session to be driven over custom transports, or by an
+/// event loop which cannot provide a blocking `Read + Write` stream.
+///
+/// Operations which need more data from the peer fail with `Error::WantRead`. Any ciphertext
+/// produced by the operation, such as a handshake message, will then be available from
+/// `read_ciphertext`, and the operation should be retried once more ciphertext has been
+/// received.
+pub struct SslMemoryStream {
+    ssl: Ssl,
+}
+
+impl fmt::Debug for SslMemoryStream {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("SslMemoryStream")
+            .field("ssl", &self.ssl())
+            .finish()
+    }
+}
+
+impl SslMemoryStream {
+    /// Creates a new session which will act as the client, attaching a pair of memory buffers to
+    /// `ssl`.
+    ///
+    /// The handshake is started by `handshake`, or by the first call to `read_plaintext` or
+    /// `write_plaintext`.
+    pub fn connect(ssl: Ssl) -> Result<SslMemoryStream, ErrorStack> {
+        let stream = try!(SslMemoryStream::new(ssl));
+        unsafe { ffi::SSL_set_connect_state(stream.ssl.as_ptr()) };
+        Ok(stream)
+    }
+
+    /// Creates a new session which will act as the server, attaching a pair of memory buffers to
+    /// `ssl`.
+    pub fn accept(ssl: Ssl) -> Result<SslMemoryStream, ErrorStack> {
+        let stream = try!(SslMemoryStream::new(ssl));
+        unsafe { ffi::SSL_set_accept_state(stream.ssl.as_ptr()) };
+        Ok(stream)
+    }
+
+    fn new(ssl: Ssl) -> Result<SslMemoryStream, ErrorStack> {
+        unsafe {
+            let rbio = try!(cvt_p(ffi::BIO_new(ffi::BIO_s_mem())));
+            let wbio = match cvt_p(ffi::BIO_new(ffi::BIO_s_mem())) {
+                Ok(wbio) => wbio,
+                Err(e) => {
+                    ffi::BIO_free_all(rbio);
+                    return Err(e);
+                }
+            };
+            ffi::SSL_set_bio(ssl.as_ptr(), rbio, wbio);
+            Ok(SslMemoryStream { ssl: ssl })
+        }
+    }
+
+    /// Performs the handshake, or continues it if it was previously interrupted.
+    pub fn handshake(&mut self) -> Result<(), Error> {
+        let ret = unsafe { ffi::SSL_do_handshake(self.ssl.as_ptr()) };
+        self.check_panic();
+        if ret > 0 {
+            Ok(())
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Passes ciphertext received from the peer to the session.
+    ///
+    /// All of `buf` is buffered until it is consumed by a later operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf.len() > c_int::max_value()`.
+    pub fn write_ciphertext(&mut self, buf: &[u8]) -> Result<(), ErrorStack> {
+        assert!(buf.len() <= c_int::max_value() as usize);
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        unsafe {
+            let bio = ffi::SSL_get_rbio(self.ssl.as_ptr());
+            cvt(ffi::BIO_write(bio, buf.as_ptr() as *const c_void, buf.len() as c_int)).map(|_| ())
+        }
+    }
+
+    /// Returns the number of bytes of ciphertext waiting to be sent to the peer.
+    pub fn pending_ciphertext(&self) -> usize {
+        unsafe { ffi::BIO_ctrl_pending(ffi::SSL_get_wbio(self.ssl.as_ptr())) as usize }
+    }
+
+    /// Reads ciphertext which must be sent to the peer into `buf`, returning the number of bytes
+    /// read.
+    ///
+    /// 0 is returned if there is no ciphertext waiting to be sent.
+    pub fn read_ciphertext(&mut self, buf: &mut [u8]) -> usize {
+        let len = cmp::min(c_int::max_value() as usize, buf.len()) as c_int;
+        if len == 0 {
+            return 0;
+        }
+
+        unsafe {
+            let bio = ffi::SSL_get_wbio(self.ssl.as_ptr());
+            let ret = ffi::BIO_read(bio, buf.as_mut_ptr() as *mut c_void, len);
+            cmp::max(ret, 0) as usize
+        }
+    }
+
+    /// Decrypts application data received from the peer into `buf`, returning the number of
+    /// bytes read.
+    pub fn read_plaintext(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // See SslStream::ssl_read for why we short-circuit on zero-length buffers
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+
+        let ret = self.ssl.read(buf);
+        self.check_panic();
+        if self.ssl.renegotiation_rejected() {
+            return Err(renegotiation_rejected_error());
+        }
+        if ret > 0 {
+            Ok(ret as usize)
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Encrypts application data from `buf`, returning the number of bytes written.
+    ///
+    /// The resulting ciphertext can be retrieved with `read_ciphertext`.
+    pub fn write_plaintext(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+
+        let ret = self.ssl.write(buf);
+        self.check_panic();
+        if self.ssl.renegotiation_rejected() {
+            return Err(renegotiation_rejected_error());
+        }
+        if ret > 0 {
+            Ok(ret as usize)
+        } else {
+            Err(self.make_error(ret))
+        }
+    }
+
+    /// Shuts down the session.
+    ///
+    /// This behaves like `SslStream::shutdown`. The close notify message must be retrieved with
+    /// `read_ciphertext` and sent to the peer.
+    pub fn shutdown(&mut self) -> Result<ShutdownResult, Error> {
+        let ret = unsafe { ffi::SSL_shutdown(self.ssl.as_ptr()) };
+        self.check_panic();
+        match ret {
+            0 => Ok(ShutdownResult::Sent),
+            1 => Ok(ShutdownResult::Received),
+            n => Err(self.make_error(n)),
+        }
+    }
+
+    /// Returns the OpenSSL `Ssl` object associated with this stream.
+    pub fn ssl(&self) -> &SslRef {
+        &self.ssl
+    }
+
+    fn make_error(&mut self, ret: c_int) -> Error {
+        match self.ssl.get_error(ret) {
+            ffi::SSL_ERROR_SSL => Error::Ssl(ErrorStack::get()),
+            ffi::SSL_ERROR_SYSCALL => {
+                let errs = ErrorStack::get();
+                if errs.errors().is_empty() {
+                    Error::Stream(io::Error::new(io::ErrorKind::ConnectionAborted,
+                                                 "unexpected EOF observed"))
+                } else {
+                    Error::Ssl(errs)
+                }
+            }
+            ffi::SSL_ERROR_ZERO_RETURN => Error::ZeroReturn,
+            ffi::SSL_ERROR_WANT_READ => {
+                Error::WantRead(io::Error::new(io::ErrorKind::WouldBlock,
+                                               "more ciphertext is required from the peer"))
+            }
+            ffi::SSL_ERROR_WANT_WRITE => {
+                Error::WantWrite(io::Error::new(io::ErrorKind::WouldBlock,
+                                                "the ciphertext buffer is full"))
+            }
+            ffi::SSL_ERROR_WANT_X509_LOOKUP => Error::WantX509Lookup,
+            err => {
+                Error::Stream(io::Error::new(io::ErrorKind::InvalidData,
+                                             format!("unexpected error {}", err)))
+            }
+        }
+    }
+
+    fn check_panic(&mut self) {
+        if let Some(err) = unsafe { take_callback_panic(self.ssl.as_ptr()) } {
+            resume_unwind(err)
+        }
+    }
+}
+
 /// The result of a shutdown request.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ShutdownResult {
@@ -1895,7 +4298,9 @@ mod compat {
     use libc::c_int;
 
     pub use ffi::{SSL_CTX_get_options, SSL_CTX_set_options, SSL_CTX_clear_options, SSL_CTX_up_ref,
-                  SSL_SESSION_get_master_key, SSL_is_server};
+                  SSL_SESSION_get_master_key, SSL_is_server, SSL_CTX_get_ciphers,
+                  SSL_CTX_set_min_proto_version, SSL_CTX_set_max_proto_version,
+                  SSL_set_min_proto_version, SSL_set_max_proto_version, SSL_is_dtls};
 
     pub unsafe fn get_new_idx(f: ffi::CRYPTO_EX_free) -> c_int {
         ffi::CRYPTO_get_ex_new_index(ffi::CRYPTO_EX_INDEX_SSL_CTX,
@@ -1922,6 +4327,14 @@ mod compat {
     pub fn dtls_method() -> *const ffi::SSL_METHOD {
         unsafe { ffi::DTLS_method() }
     }
+
+    pub fn dtls_client_method() -> *const ffi::SSL_METHOD {
+        unsafe { ffi::DTLS_client_method() }
+    }
+
+    pub fn dtls_server_method() -> *const ffi::SSL_METHOD {
+        unsafe { ffi::DTLS_server_method() }
+    }
 }
 
 #[cfg(ossl10x)]
@@ -1932,6 +4345,18 @@ mod compat {
     use ffi;
     use libc::{self, c_long, c_ulong, c_int, size_t, c_uchar};
 
+    pub unsafe fn SSL_CTX_get_ciphers(ctx: *const ffi::SSL_CTX) -> *mut ffi::stack_st_SSL_CIPHER {
+        (*ctx).cipher_list
+    }
+
+    pub unsafe fn SSL_is_dtls(s: *const ffi::SSL) -> c_int {
+        match ffi::SSL_version(s) {
+            ffi::DTLS1_VERSION | ffi::DTLS1_2_VERSION | ffi::DTLS1_BAD_VER |
+            ffi::DTLS_ANY_VERSION => 1,
+            _ => 0,
+        }
+    }
+
     pub unsafe fn SSL_CTX_get_options(ctx: *const ffi::SSL_CTX) -> c_ulong {
         ffi::SSL_CTX_ctrl(ctx as *mut _, ffi::SSL_CTRL_OPTIONS, 0, ptr::null_mut()) as c_ulong
     }
@@ -1950,6 +4375,91 @@ mod compat {
                           ptr::null_mut()) as c_ulong
     }
 
+    const PROTOCOL_OPTIONS: [(c_int, c_ulong); 4] = [(ffi::SSL3_VERSION, ffi::SSL_OP_NO_SSLv3),
+                                                      (ffi::TLS1_VERSION, ffi::SSL_OP_NO_TLSv1),
+                                                      (ffi::TLS1_1_VERSION, ffi::SSL_OP_NO_TLSv1_1),
+                                                      (ffi::TLS1_2_VERSION, ffi::SSL_OP_NO_TLSv1_2)];
+
+    /// Returns the options which need to be set and cleared to replace one bound of the range of
+    /// enabled protocol versions, or `None` if `version` is unknown.
+    ///
+    /// The other bound of the range is inferred from the protocols disabled in `options`, and a
+    /// version of 0 removes the bound.
+    fn proto_version_options(options: c_ulong,
+                             version: c_int,
+                             is_min: bool)
+                             -> Option<(c_ulong, c_ulong)> {
+        if version != 0 && !PROTOCOL_OPTIONS.iter().any(|&(v, _)| v == version) {
+            return None;
+        }
+
+        let enabled = PROTOCOL_OPTIONS.iter().filter(|&&(_, op)| options & op == 0);
+        let (mut min, mut max) = if is_min {
+            (version, enabled.last().map_or(0, |&(v, _)| v))
+        } else {
+            (enabled.map(|&(v, _)| v).next().unwrap_or(0), version)
+        };
+        if min == PROTOCOL_OPTIONS[0].0 {
+            min = 0;
+        }
+        if max == PROTOCOL_OPTIONS[PROTOCOL_OPTIONS.len() - 1].0 {
+            max = 0;
+        }
+
+        let mut set = 0;
+        let mut clear = 0;
+        for &(v, op) in &PROTOCOL_OPTIONS {
+            if (min != 0 && v < min) || (max != 0 && v > max) {
+                set |= op;
+            } else {
+                clear |= op;
+            }
+        }
+        Some((set, clear))
+    }
+
+    unsafe fn SSL_CTX_set_proto_version(ctx: *mut ffi::SSL_CTX,
+                                        version: c_int,
+                                        is_min: bool)
+                                        -> c_int {
+        match proto_version_options(SSL_CTX_get_options(ctx), version, is_min) {
+            Some((set, clear)) => {
+                SSL_CTX_clear_options(ctx, clear);
+                SSL_CTX_set_options(ctx, set);
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub unsafe fn SSL_CTX_set_min_proto_version(ctx: *mut ffi::SSL_CTX, version: c_int) -> c_int {
+        SSL_CTX_set_proto_version(ctx, version, true)
+    }
+
+    pub unsafe fn SSL_CTX_set_max_proto_version(ctx: *mut ffi::SSL_CTX, version: c_int) -> c_int {
+        SSL_CTX_set_proto_version(ctx, version, false)
+    }
+
+    unsafe fn SSL_set_proto_version(ssl: *mut ffi::SSL, version: c_int, is_min: bool) -> c_int {
+        let options = ffi::SSL_ctrl(ssl, ffi::SSL_CTRL_OPTIONS, 0, ptr::null_mut()) as c_ulong;
+        match proto_version_options(options, version, is_min) {
+            Some((set, clear)) => {
+                ffi::SSL_ctrl(ssl, ffi::SSL_CTRL_CLEAR_OPTIONS, clear as c_long, ptr::null_mut());
+                ffi::SSL_ctrl(ssl, ffi::SSL_CTRL_OPTIONS, set as c_long, ptr::null_mut());
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub unsafe fn SSL_set_min_proto_version(ssl: *mut ffi::SSL, version: c_int) -> c_int {
+        SSL_set_proto_version(ssl, version, true)
+    }
+
+    pub unsafe fn SSL_set_max_proto_version(ssl: *mut ffi::SSL, version: c_int) -> c_int {
+        SSL_set_proto_version(ssl, version, false)
+    }
+
     pub unsafe fn get_new_idx(f: ffi::CRYPTO_EX_free) -> c_int {
         ffi::SSL_CTX_get_ex_new_index(0, ptr::null_mut(), None, None, Some(f))
     }
@@ -1988,6 +4498,14 @@ mod compat {
         unsafe { ffi::DTLSv1_method() }
     }
 
+    pub fn dtls_client_method() -> *const ffi::SSL_METHOD {
+        unsafe { ffi::DTLSv1_client_method() }
+    }
+
+    pub fn dtls_server_method() -> *const ffi::SSL_METHOD {
+        unsafe { ffi::DTLSv1_server_method() }
+    }
+
     pub unsafe fn SSL_is_server(s: *mut ffi::SSL) -> c_int {
         (*s).server
     }
@@ -14,17 +14,21 @@ use std::thread;
 use std::time::Duration;
 use tempdir::TempDir;
 
+use asn1::Asn1Time;
+use bn::BigNum;
 use dh::Dh;
-use hash::MessageDigest;
+use hash::{self, MessageDigest};
 use ocsp::{OcspResponse, RESPONSE_STATUS_UNAUTHORIZED};
 use ssl;
-use ssl::{SslMethod, HandshakeError, SslContext, SslStream, Ssl, ShutdownResult,
-    SslConnectorBuilder, SslAcceptorBuilder, Error, SSL_VERIFY_PEER, SSL_VERIFY_NONE,
-    STATUS_TYPE_OCSP};
-use x509::{X509StoreContext, X509, X509Name, X509_FILETYPE_PEM};
+use ssl::{SslMethod, HandshakeError, SslContext, SslContextBuilder, SslStream, Ssl,
+    ShutdownResult, SslConnectorBuilder, SslAcceptorBuilder, Error, SSL_VERIFY_PEER,
+    SSL_VERIFY_NONE, STATUS_TYPE_OCSP, BufferedSslStream, TLS1_1_VERSION, TLS1_2_VERSION,
+    RevocationPolicy, SslMemoryStream};
+use x509::{X509StoreContext, X509, X509Crl, X509CrlBuilder, X509Name, X509_FILETYPE_PEM};
 #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
 use x509::verify::X509_CHECK_FLAG_NO_PARTIAL_WILDCARDS;
 use pkey::PKey;
+use foreign_types::ForeignTypeRef;
 
 use std::net::UdpSocket;
 
@@ -158,6 +162,26 @@ impl Write for UdpConnected {
     }
 }
 
+/// Returns a context builder for `method` which presents `test/cert.pem`.
+fn cert_ctx(method: SslMethod) -> SslContextBuilder {
+    let mut ctx = SslContext::builder(method).unwrap();
+    ctx.set_certificate_file(&Path::new("test/cert.pem"), X509_FILETYPE_PEM).unwrap();
+    ctx.set_private_key_file(&Path::new("test/key.pem"), X509_FILETYPE_PEM).unwrap();
+    ctx
+}
+
+/// Spawns a thread which runs `server` on the first connection made to a local port, returning
+/// the port and the thread's handle.
+fn spawn_server<F, T>(server: F) -> (u16, thread::JoinHandle<T>)
+    where F: FnOnce(TcpStream) -> T + Send + 'static,
+          T: Send + 'static
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let guard = thread::spawn(move || server(listener.accept().unwrap().0));
+    (port, guard)
+}
+
 macro_rules! run_test(
     ($module:ident, $blk:expr) => (
         #[cfg(test)]
@@ -263,6 +287,349 @@ run_test!(verify_trusted_callback_override_bad, |method, stream| {
     assert!(Ssl::new(&ctx.build()).unwrap().connect(stream).is_err());
 });
 
+#[test]
+fn verify_callback_clear_error() {
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_verify_callback(SSL_VERIFY_PEER, |preverify_ok, x509_ctx| {
+        if !preverify_ok {
+            assert!(x509_ctx.error().is_some());
+            x509_ctx.set_error(None);
+        }
+        true
+    });
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let stream = ssl.connect(stream).unwrap();
+
+    assert!(stream.ssl().verify_result().is_none());
+}
+
+#[test]
+fn verify_callback_chain_owned() {
+    use std::sync::{Arc, Mutex};
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let chain = Arc::new(Mutex::new(Vec::<Vec<u8>>::new()));
+    let chain2 = chain.clone();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_ca_file(&Path::new("test/root-ca.pem")).unwrap();
+    ctx.set_verify_callback(SSL_VERIFY_PEER, move |_, x509_ctx| {
+        if x509_ctx.error_depth() == 0 {
+            let chain = x509_ctx.chain_owned();
+            *chain2.lock().unwrap() = chain.iter().map(|c| c.to_der().unwrap()).collect();
+        }
+        true
+    });
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let stream = ssl.connect(stream).unwrap();
+    drop(stream);
+
+    let chain = chain.lock().unwrap();
+    assert_eq!(chain.len(), 2);
+    let cert = X509::from_pem(include_bytes!("../../../test/cert.pem")).unwrap();
+    let root = X509::from_pem(ROOT_CERT).unwrap();
+    assert_eq!(chain[0], cert.to_der().unwrap());
+    assert_eq!(chain[1], root.to_der().unwrap());
+}
+
+#[test]
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+fn verify_callback_ssl() {
+    use std::sync::{Arc, Mutex};
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let seen = Arc::new(Mutex::new(vec![]));
+    let seen2 = seen.clone();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_ca_file(&Path::new("test/root-ca.pem")).unwrap();
+    ctx.set_verify_callback(SSL_VERIFY_PEER, move |_, x509_ctx| {
+        let ssl = x509_ctx.ssl().unwrap();
+        seen2.lock().unwrap().push((ssl.servername().map(|s| s.to_owned()),
+                                    ssl.verify_hostname().map(|s| s.to_owned()),
+                                    ssl.is_server()));
+        true
+    });
+    let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    assert_eq!(ssl.verify_hostname(), None);
+    ssl.set_hostname("foobar.com").unwrap();
+    ssl.set_verify_hostname("foobar.com").unwrap();
+    ssl.connect(stream).unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert!(!seen.is_empty());
+    for info in &*seen {
+        assert_eq!(*info,
+                   (Some("foobar.com".to_owned()), Some("foobar.com".to_owned()), false));
+    }
+}
+
+#[test]
+fn verify_error_detail_untrusted() {
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let _ = ssl.accept(stream);
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_verify(SSL_VERIFY_PEER);
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let stream = match ssl.connect(stream) {
+        Err(HandshakeError::Failure(s)) => s,
+        _ => panic!("expected a handshake failure"),
+    };
+
+    let detail = stream.ssl().verify_error_detail().unwrap();
+    // X509_V_ERR_UNABLE_TO_GET_ISSUER_CERT_LOCALLY
+    assert_eq!(detail.error().as_raw(), 20);
+    assert_eq!(detail.depth(), Some(0));
+    let cert = X509::from_pem(CERT).unwrap();
+    assert_eq!(detail.cert().unwrap().to_der().unwrap(), cert.to_der().unwrap());
+    assert!(detail.hint().is_some());
+
+    let message = detail.to_string();
+    assert!(message.starts_with("certificate verification failed at depth 0 (CN=foobar.com,O=Internet \
+                                Widgits Pty Ltd,ST=Some-State,C=AU): "),
+            "{}",
+            message);
+    assert!(message.contains("hint: "), "{}", message);
+}
+
+#[test]
+#[cfg(all(feature = "v111", ossl111))]
+fn client_hello_callback_rejects_by_addr() {
+    use ssl::ClientHelloError;
+
+    let (port, guard) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
+        ctx.set_max_cert_list(16 * 1024);
+        ctx.set_client_hello_callback(|ssl| {
+            match ssl.peer_addr() {
+                Some(addr) if addr.ip().is_loopback() => Err(ClientHelloError::handshake_failure()),
+                _ => Ok(()),
+            }
+        });
+        let mut ssl = Ssl::new(&ctx.build()).unwrap();
+        let addr = stream.peer_addr().unwrap();
+        ssl.set_peer_addr(addr);
+        assert_eq!(ssl.peer_addr(), Some(addr));
+        assert!(ssl.accept(stream).is_err());
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    assert!(ssl.connect(stream).is_err());
+
+    guard.join().unwrap();
+}
+
+#[test]
+fn app_data() {
+    use std::sync::Arc;
+
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let ctx_dropped = Arc::new(AtomicBool::new(false));
+    let ssl_dropped = Arc::new(AtomicBool::new(false));
+    let replaced = Arc::new(AtomicBool::new(false));
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_app_data(DropFlag(ctx_dropped.clone()));
+    ctx.set_app_data(String::from("context"));
+    let ctx = ctx.build();
+
+    let mut ssl = Ssl::new(&ctx).unwrap();
+    assert!(ssl.app_data::<u32>().is_none());
+    ssl.set_app_data(DropFlag(replaced.clone()));
+    ssl.set_app_data(DropFlag(ssl_dropped.clone()));
+    assert!(replaced.load(Ordering::SeqCst));
+    ssl.set_app_data(1u32);
+    *ssl.app_data_mut::<u32>().unwrap() += 1;
+    assert_eq!(ssl.app_data::<u32>(), Some(&2));
+    assert_eq!(ssl.ssl_context().app_data::<String>().unwrap(), "context");
+    assert!(ssl.app_data::<String>().is_none());
+
+    drop(ssl);
+    assert!(ssl_dropped.load(Ordering::SeqCst));
+    assert!(!ctx_dropped.load(Ordering::SeqCst));
+    drop(ctx);
+    assert!(ctx_dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn peer_alert_on_untrusted_certificate() {
+    let (port, guard) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let err = ssl.accept(stream).err().unwrap();
+        let alert = err.peer_alert().unwrap();
+        assert!(alert.is_fatal());
+        assert_eq!(alert.description(), ssl::ALERT_UNKNOWN_CA);
+        assert!(err.to_string().contains("received fatal alert: unknown CA"), "{}", err);
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_verify(SSL_VERIFY_PEER);
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let err = ssl.connect(stream).err().unwrap();
+    assert_eq!(err.peer_alert(), None);
+
+    guard.join().unwrap();
+}
+
+#[test]
+fn context_stats() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let guard = thread::spawn(move || {
+        let ctx = cert_ctx(SslMethod::tls()).build();
+        assert_eq!(ctx.stats().accept, 0);
+
+        let stream = listener.accept().unwrap().0;
+        let mut stream = Ssl::new(&ctx).unwrap().accept(stream).unwrap();
+        stream.write_all(b"x").unwrap();
+        ctx.stats()
+    });
+
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut stream = Ssl::new(&ctx).unwrap().connect(stream).unwrap();
+    stream.read_exact(&mut [0]).unwrap();
+
+    let stats = ctx.stats();
+    assert_eq!(stats.connect, 1);
+    assert_eq!(stats.connect_good, 1);
+    assert_eq!(stats.accept, 0);
+
+    let stats = guard.join().unwrap();
+    assert_eq!(stats.accept, 1);
+    assert_eq!(stats.accept_good, 1);
+    assert_eq!(stats.connect, 0);
+    assert_eq!(stats.hits, 0);
+}
+
+#[test]
+fn memory_stream() {
+    fn pump(from: &mut SslMemoryStream, to: &mut SslMemoryStream) -> bool {
+        let mut buf = [0; 4096];
+        let mut moved = false;
+        loop {
+            let n = from.read_ciphertext(&mut buf);
+            if n == 0 {
+                return moved;
+            }
+            to.write_ciphertext(&buf[..n]).unwrap();
+            moved = true;
+        }
+    }
+
+    let ctx = cert_ctx(SslMethod::tls()).build();
+    let mut server = SslMemoryStream::accept(Ssl::new(&ctx).unwrap()).unwrap();
+
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+    let mut client = SslMemoryStream::connect(Ssl::new(&ctx).unwrap()).unwrap();
+
+    assert_eq!(client.pending_ciphertext(), 0);
+    match client.handshake() {
+        Err(Error::WantRead(_)) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+    assert!(client.pending_ciphertext() > 0);
+
+    loop {
+        let client_done = client.handshake().is_ok();
+        let server_done = server.handshake().is_ok();
+        let moved = pump(&mut client, &mut server) | pump(&mut server, &mut client);
+        if client_done && server_done && !moved {
+            break;
+        }
+        assert!(moved, "handshake stalled");
+    }
+
+    assert_eq!(client.write_plaintext(b"hello").unwrap(), 5);
+    pump(&mut client, &mut server);
+    let mut buf = [0; 5];
+    assert_eq!(server.read_plaintext(&mut buf).unwrap(), 5);
+    assert_eq!(&buf, b"hello");
+    match server.read_plaintext(&mut buf) {
+        Err(Error::WantRead(_)) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+
+    assert_eq!(server.shutdown().unwrap(), ShutdownResult::Sent);
+    pump(&mut server, &mut client);
+    match client.read_plaintext(&mut buf) {
+        Err(Error::ZeroReturn) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn error_cause_and_io_round_trip() {
+    use std::error::Error as StdError;
+    use error::ErrorStack;
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let _ = ssl.accept(stream);
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_verify(SSL_VERIFY_PEER);
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let err = match ssl.connect(stream) {
+        Err(HandshakeError::Failure(s)) => s.into_error(),
+        _ => panic!("expected a handshake failure"),
+    };
+
+    let stack = match err {
+        Error::Ssl(ref stack) => stack.clone(),
+        ref e => panic!("unexpected error {:?}", e),
+    };
+    assert_eq!(err.cause().unwrap().to_string(), stack.to_string());
+
+    let io_err = io::Error::from(err);
+    match Error::from_io_error(io_err) {
+        Ok(Error::Ssl(e)) => assert_eq!(e.errors()[0].code(), stack.errors()[0].code()),
+        _ => panic!("expected an SSL error"),
+    }
+
+    let io_err = io::Error::from(stack.clone());
+    match Error::from_io_error(io_err) {
+        Ok(Error::Ssl(e)) => assert_eq!(e.errors()[0].code(), stack.errors()[0].code()),
+        _ => panic!("expected an SSL error"),
+    }
+
+    let io_err = io::Error::new(io::ErrorKind::Other, "foo");
+    assert!(Error::from_io_error(io_err).is_err());
+}
+
 run_test!(verify_callback_load_certs, |method, stream| {
     let mut ctx = SslContext::builder(method).unwrap();
     ctx.set_verify_callback(SSL_VERIFY_PEER, |_, x509_ctx| {
@@ -783,6 +1150,20 @@ fn test_alpn_server_select_none() {
     assert!(Ssl::new(&ctx.build()).unwrap().connect(stream).is_err());
 }
 
+#[test]
+fn select_next_proto() {
+    let server = b"\x02h2\x08http/1.1";
+    let client = b"\x08http/1.1\x06spdy/1\x02h2";
+    assert_eq!(ssl::select_next_proto(server, client), Some(&b"h2"[..]));
+    assert_eq!(ssl::select_next_proto(server, b"\x08http/1.1"), Some(&b"http/1.1"[..]));
+    assert_eq!(ssl::select_next_proto(server, b"\x06spdy/1"), None);
+    assert_eq!(ssl::select_next_proto(server, b""), None);
+    assert_eq!(ssl::select_next_proto(b"", client), None);
+    assert_eq!(ssl::select_next_proto(server, b"\x09http/1.1"), None);
+    assert_eq!(ssl::select_next_proto(server, b"\x02h2\x00"), None);
+    assert_eq!(ssl::select_next_proto(b"\x02h2\x05h", client), None);
+}
+
 #[test]
 #[cfg_attr(any(libressl, windows, target_arch = "arm"), ignore)] // FIXME(#467)
 fn test_read_dtlsv1() {
@@ -861,6 +1242,85 @@ fn test_write_nonblocking() {
     stream.write(" there".as_bytes()).unwrap();
 }
 
+#[test]
+fn nonblocking_handshake_error_code() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    stream.set_nonblocking(true).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+
+    match Ssl::new(&ctx).unwrap().connect(stream) {
+        Err(HandshakeError::Interrupted(s)) => {
+            assert_eq!(s.error().code(), ssl::ERROR_WANT_READ);
+        }
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn handshake_step() {
+    use ssl::HandshakeProgress;
+
+    fn step(progress: HandshakeProgress<TcpStream>) -> HandshakeProgress<TcpStream> {
+        match progress {
+            HandshakeProgress::WantRead(s) => {
+                wait_io(s.get_ref(), true, 100);
+                s.handshake_step()
+            }
+            HandshakeProgress::WantWrite(s) => {
+                wait_io(s.get_ref(), false, 100);
+                s.handshake_step()
+            }
+            HandshakeProgress::Failure(s) => panic!("handshake failed: {:?}", s.error()),
+            HandshakeProgress::SetupFailure(e) => panic!("setup failed: {}", e),
+            p => p,
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let server = listener.accept().unwrap().0;
+    client.set_nonblocking(true).unwrap();
+    server.set_nonblocking(true).unwrap();
+
+    let server_ctx = cert_ctx(SslMethod::tls()).build();
+    let client_ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+
+    let mut client = Ssl::new(&client_ctx).unwrap().connect_step(client);
+    match client {
+        HandshakeProgress::WantRead(_) => {}
+        ref p => panic!("unexpected progress {:?}", p),
+    }
+    let mut server = Ssl::new(&server_ctx).unwrap().accept_step(server);
+
+    // both sides share this thread, so keep stepping them until each has finished
+    for _ in 0..100 {
+        if let (&HandshakeProgress::Done(_), &HandshakeProgress::Done(_)) = (&client, &server) {
+            break;
+        }
+        client = step(client);
+        server = step(server);
+    }
+
+    match (client, server) {
+        (HandshakeProgress::Done(mut client), HandshakeProgress::Done(mut server)) => {
+            client.write_all(b"hello").unwrap();
+            let mut buf = [0; 5];
+            loop {
+                match server.ssl_read(&mut buf) {
+                    Ok(n) => {
+                        assert_eq!(&buf[..n], b"hello");
+                        break;
+                    }
+                    Err(Error::WantRead(..)) => assert!(wait_io(server.get_ref(), true, 1000)),
+                    Err(e) => panic!("read failed: {:?}", e),
+                }
+            }
+        }
+        r => panic!("handshake did not complete: {:?}", r),
+    }
+}
+
 #[test]
 #[cfg_attr(any(libressl, windows, target_arch = "arm"), ignore)] // FIXME(#467)
 fn test_read_nonblocking() {
@@ -1002,68 +1462,507 @@ fn flush_panic() {
 }
 
 #[test]
-fn refcount_ssl_context() {
-    let mut ssl = {
-        let ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ssl::Ssl::new(&ctx.build()).unwrap()
-    };
+#[should_panic(expected = "blammo")]
+fn verify_callback_panic() {
+    let (_s, stream) = Server::new();
 
-    {
-        let new_ctx_a = SslContext::builder(SslMethod::tls()).unwrap().build();
-        let _new_ctx_b = ssl.set_ssl_context(&new_ctx_a);
-    }
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_verify_callback(SSL_VERIFY_PEER, |_, _| panic!("blammo"));
+    let _ = Ssl::new(&ctx.build()).unwrap().connect(stream);
 }
 
 #[test]
-fn default_verify_paths() {
-    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-    ctx.set_default_verify_paths().unwrap();
-    ctx.set_verify(SSL_VERIFY_PEER);
-    let s = TcpStream::connect("google.com:443").unwrap();
-    let mut socket = Ssl::new(&ctx.build()).unwrap().connect(s).unwrap();
-
-    socket.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
-    let mut result = vec![];
-    socket.read_to_end(&mut result).unwrap();
+#[should_panic(expected = "blammo")]
+fn ssl_verify_callback_panic() {
+    let (_s, stream) = Server::new();
 
-    println!("{}", String::from_utf8_lossy(&result));
-    assert!(result.starts_with(b"HTTP/1.0"));
-    assert!(result.ends_with(b"</HTML>\r\n") || result.ends_with(b"</html>"));
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.set_verify_callback(SSL_VERIFY_PEER, |_, _| panic!("blammo"));
+    let _ = ssl.connect(stream);
 }
 
 #[test]
-fn add_extra_chain_cert() {
-    let cert = include_bytes!("../../../test/cert.pem");
-    let cert = X509::from_pem(cert).unwrap();
-    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-    ctx.add_extra_chain_cert(cert).unwrap();
+#[should_panic(expected = "blammo")]
+fn servername_callback_panic() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        let mut ssl = Ssl::new(&ctx.build()).unwrap();
+        ssl.set_hostname("foobar.com").unwrap();
+        let _ = ssl.connect(stream);
+    });
+
+    let stream = listener.accept().unwrap().0;
+    let mut ctx = cert_ctx(SslMethod::tls());
+    ctx.set_servername_callback(|_| panic!("blammo"));
+    let _ = Ssl::new(&ctx.build()).unwrap().accept(stream);
 }
 
 #[test]
-#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
-fn verify_valid_hostname() {
+fn callback_panic_aborts_handshake() {
+    let (port, guard) = spawn_server(move |stream| {
+        Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap().accept(stream).is_ok()
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
     let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-    ctx.set_default_verify_paths().unwrap();
-    ctx.set_verify(SSL_VERIFY_PEER);
+    ctx.set_verify_callback(SSL_VERIFY_PEER, |_, _| panic!("blammo"));
+    let ctx = ctx.build();
+    let res = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+        let _ = Ssl::new(&ctx).unwrap().connect(stream);
+    }));
+    assert!(res.is_err());
+    // The panic is translated into a verification failure, so the server sees the handshake fail.
+    assert!(!guard.join().unwrap());
+}
 
-    let mut ssl = Ssl::new(&ctx.build()).unwrap();
-    ssl.param_mut().set_hostflags(X509_CHECK_FLAG_NO_PARTIAL_WILDCARDS);
-    ssl.param_mut().set_host("google.com").unwrap();
+#[test]
+fn buffered_read_line() {
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        stream.write_all(b"+OK hello\r\n+OK world\r\n").unwrap();
+        stream.shutdown().unwrap();
+    });
 
-    let s = TcpStream::connect("google.com:443").unwrap();
-    let mut socket = ssl.connect(s).unwrap();
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = BufferedSslStream::with_capacity(4, ssl.connect(stream).unwrap());
 
-    socket.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
-    let mut result = vec![];
-    socket.read_to_end(&mut result).unwrap();
+    let mut line = String::new();
+    stream.read_line(&mut line).unwrap();
+    assert_eq!(line, "+OK hello\r\n");
+    assert!(stream.pending() > 0);
 
-    println!("{}", String::from_utf8_lossy(&result));
-    assert!(result.starts_with(b"HTTP/1.0"));
-    assert!(result.ends_with(b"</HTML>\r\n") || result.ends_with(b"</html>"));
+    line.clear();
+    stream.read_line(&mut line).unwrap();
+    assert_eq!(line, "+OK world\r\n");
 }
 
 #[test]
-#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+fn buffered_corked_writes() {
+    let (port, guard) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        // each read returns the contents of at most one record
+        let mut buf = [0; 1024];
+        let first = stream.read(&mut buf).unwrap();
+        let second = stream.read(&mut buf).unwrap();
+        (first, second)
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = BufferedSslStream::new(ssl.connect(stream).unwrap());
+
+    stream.set_corked(true);
+    for _ in 0..100 {
+        stream.write_all(b"x").unwrap();
+    }
+    assert_eq!(stream.buffered_writes(), 100);
+    stream.flush().unwrap();
+    assert_eq!(stream.buffered_writes(), 0);
+
+    stream.set_corked(false);
+    stream.write_all(b"y").unwrap();
+    assert_eq!(stream.buffered_writes(), 0);
+
+    assert_eq!(guard.join().unwrap(), (100, 1));
+}
+
+#[test]
+fn max_send_fragment() {
+    let (port, _) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
+        assert!(ctx.set_max_send_fragment(256).is_err());
+        ctx.set_max_send_fragment(512).unwrap();
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        stream.write_all(&[7; 4096]).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+
+    let mut buf = [0; 4096];
+    let len = stream.read(&mut buf).unwrap();
+    assert!(len <= 512);
+    stream.read_exact(&mut buf[len..]).unwrap();
+    assert!(buf.iter().all(|&b| b == 7));
+}
+
+#[test]
+#[cfg(all(feature = "v111", ossl111))]
+fn max_fragment_length() {
+    use ssl::MAX_FRAGMENT_LENGTH_512;
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        stream.write_all(&[7; 4096]).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_max_fragment_length(MAX_FRAGMENT_LENGTH_512).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+
+    let mut buf = [0; 4096];
+    let len = stream.read(&mut buf).unwrap();
+    assert!(len <= 512);
+    stream.read_exact(&mut buf[len..]).unwrap();
+    assert!(buf.iter().all(|&b| b == 7));
+}
+
+#[test]
+#[cfg(not(osslconf = "OPENSSL_NO_PSK"))]
+fn psk_ciphers() {
+    const CIPHER: &'static str = "PSK-AES128-CBC-SHA";
+    const PSK: &'static [u8] = b"thisisaverysecurekey";
+    const CLIENT_IDENT: &'static [u8] = b"thisisaclient";
+    static SERVER_CALLED: AtomicBool = ATOMIC_BOOL_INIT;
+    static CLIENT_CALLED: AtomicBool = ATOMIC_BOOL_INIT;
+
+    let (port, _) = spawn_server(move |stream| {
+        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ctx.set_cipher_list(CIPHER).unwrap();
+        ctx.set_psk_identity_hint("thisisahint").unwrap();
+        ctx.set_psk_server_callback(move |_, identity, psk| {
+            assert_eq!(identity, Some(CLIENT_IDENT));
+            psk[..PSK.len()].copy_from_slice(PSK);
+            SERVER_CALLED.store(true, Ordering::SeqCst);
+            Ok(PSK.len())
+        });
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_cipher_list(CIPHER).unwrap();
+    ctx.set_psk_client_callback(move |_, _, identity, psk| {
+        identity[..CLIENT_IDENT.len()].copy_from_slice(CLIENT_IDENT);
+        psk[..PSK.len()].copy_from_slice(PSK);
+        CLIENT_CALLED.store(true, Ordering::SeqCst);
+        Ok(PSK.len())
+    });
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.connect(stream).unwrap();
+
+    assert!(CLIENT_CALLED.load(Ordering::SeqCst) && SERVER_CALLED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn negotiated_cipher() {
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_cipher_list("AES128-SHA:AES256-SHA").unwrap();
+    let ctx = ctx.build();
+    let names = ctx.ciphers().unwrap().iter().map(|c| c.name()).collect::<Vec<_>>();
+    assert!(names.ends_with(&["AES128-SHA", "AES256-SHA"]));
+
+    let ssl = Ssl::new(&ctx).unwrap();
+    assert_eq!(ssl.ciphers().unwrap().len(), ctx.ciphers().unwrap().len());
+    let stream = ssl.connect(stream).unwrap();
+
+    let cipher = stream.ssl().current_cipher().unwrap();
+    assert!(names.contains(&cipher.name()));
+    assert!(cipher.bits().secret > 0);
+    assert!(!cipher.description().is_empty());
+}
+
+#[test]
+fn proto_version_bounds() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let mut ctx = cert_ctx(SslMethod::tls());
+        ctx.set_min_proto_version(Some(TLS1_2_VERSION)).unwrap();
+        let ctx = ctx.build();
+
+        for _ in 0..2 {
+            let stream = listener.accept().unwrap().0;
+            let ssl = Ssl::new(&ctx).unwrap();
+            let _ = ssl.accept(stream);
+        }
+    });
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_max_proto_version(Some(TLS1_2_VERSION)).unwrap();
+    let ctx = ctx.build();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ssl = Ssl::new(&ctx).unwrap();
+    let stream = ssl.connect(stream).unwrap();
+    assert_eq!(stream.ssl().version(), "TLSv1.2");
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ssl = Ssl::new(&ctx).unwrap();
+    ssl.set_min_proto_version(None).unwrap();
+    ssl.set_max_proto_version(Some(TLS1_1_VERSION)).unwrap();
+    assert!(ssl.connect(stream).is_err());
+}
+
+#[test]
+fn export_keying_material() {
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        let material = stream.ssl().export_keying_material("EXPERIMENTAL test", None, 32).unwrap();
+        stream.write_all(&material).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+
+    let mut server_material = [0; 32];
+    stream.read_exact(&mut server_material).unwrap();
+    let material = stream.ssl().export_keying_material("EXPERIMENTAL test", None, 32).unwrap();
+    assert_eq!(&material[..], &server_material[..]);
+
+    let with_context = stream.ssl()
+        .export_keying_material("EXPERIMENTAL test", Some(b"context"), 32)
+        .unwrap();
+    assert!(material != with_context);
+}
+
+#[test]
+fn keylog_callback() {
+    use std::sync::{Arc, Mutex};
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let lines2 = lines.clone();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    disable_tls13(&mut ctx);
+    ctx.set_keylog_callback(move |_, line| lines2.lock().unwrap().push(line.to_owned()));
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.connect(stream).unwrap();
+
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 1);
+    let parts = lines[0].split(' ').collect::<Vec<_>>();
+    assert_eq!(parts.len(), 3);
+    if cfg!(ossl110) {
+        assert_eq!(parts[0], "CLIENT_RANDOM");
+        assert_eq!(parts[1].len(), 64);
+        assert_eq!(parts[2].len(), 96);
+    } else {
+        assert_eq!(parts[0], "RSA");
+        assert!(parts[1].starts_with("Session-ID:"));
+        assert_eq!(parts[2].len(), "Master-Key:".len() + 96);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "v111", ossl111))]
+fn keylog_callback_tls13() {
+    use std::sync::{Arc, Mutex};
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        ssl.accept(stream).unwrap();
+    });
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let lines2 = lines.clone();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_keylog_callback(move |_, line| lines2.lock().unwrap().push(line.to_owned()));
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let stream = ssl.connect(stream).unwrap();
+    assert_eq!(stream.ssl().version(), "TLSv1.3");
+
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().any(|l| l.starts_with("CLIENT_HANDSHAKE_TRAFFIC_SECRET ")));
+    assert!(lines.iter().any(|l| l.starts_with("SERVER_TRAFFIC_SECRET_0 ")));
+}
+
+fn disable_tls13(ctx: &mut ::ssl::SslContextBuilder) {
+    #[cfg(ossl111)]
+    ctx.set_options(::ssl::SSL_OP_NO_TLSV1_3);
+}
+
+fn renegotiation_server(disabled: bool) -> (u16, thread::JoinHandle<(u32, bool)>) {
+    let (port, guard) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
+        ctx.set_renegotiation_disabled(disabled);
+        let ctx = ctx.build();
+        assert_eq!(ctx.renegotiation_disabled(), disabled);
+        let ssl = Ssl::new(&ctx).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        let mut buf = [0; 1];
+        stream.read_exact(&mut buf).unwrap();
+        let ok = stream.read_exact(&mut buf).is_ok();
+        (stream.ssl().renegotiations(), ok)
+    });
+
+    (port, guard)
+}
+
+fn renegotiating_client(port: u16) {
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    // TLS 1.3 does not support renegotiation
+    disable_tls13(&mut ctx);
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    stream.write_all(b"a").unwrap();
+    unsafe {
+        ::ffi::SSL_renegotiate(stream.ssl().as_ptr());
+        ::ffi::SSL_do_handshake(stream.ssl().as_ptr());
+    }
+    let _ = stream.write_all(b"b");
+}
+
+#[test]
+fn renegotiation_counted() {
+    let (port, guard) = renegotiation_server(false);
+    renegotiating_client(port);
+    assert_eq!(guard.join().unwrap(), (1, true));
+}
+
+#[test]
+fn renegotiation_disabled() {
+    let (port, guard) = renegotiation_server(true);
+    renegotiating_client(port);
+    let (_, ok) = guard.join().unwrap();
+    assert!(!ok);
+}
+
+#[test]
+fn refcount_ssl_context() {
+    let mut ssl = {
+        let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        ssl::Ssl::new(&ctx.build()).unwrap()
+    };
+
+    {
+        let new_ctx_a = SslContext::builder(SslMethod::tls()).unwrap().build();
+        let _new_ctx_b = ssl.set_ssl_context(&new_ctx_a);
+    }
+}
+
+#[test]
+fn default_verify_paths() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_default_verify_paths().unwrap();
+    ctx.set_verify(SSL_VERIFY_PEER);
+    let s = TcpStream::connect("google.com:443").unwrap();
+    let mut socket = Ssl::new(&ctx.build()).unwrap().connect(s).unwrap();
+
+    socket.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    let mut result = vec![];
+    socket.read_to_end(&mut result).unwrap();
+
+    println!("{}", String::from_utf8_lossy(&result));
+    assert!(result.starts_with(b"HTTP/1.0"));
+    assert!(result.ends_with(b"</HTML>\r\n") || result.ends_with(b"</html>"));
+}
+
+#[test]
+fn add_extra_chain_cert() {
+    let cert = include_bytes!("../../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.add_extra_chain_cert(cert).unwrap();
+}
+
+#[test]
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+fn serverinfo() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&X509::from_pem(CERT).unwrap()).unwrap();
+    ctx.set_serverinfo(&[0x12, 0x34, 0x00, 0x03, 1, 2, 3]).unwrap();
+    assert!(ctx.set_serverinfo(&[0x12, 0x34, 0x00, 0x03, 1]).is_err());
+}
+
+#[test]
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+fn build_cert_chain() {
+    // the fixture certificate may have expired, which is not what is being tested here
+    let flags = ssl::SSL_BUILD_CHAIN_FLAG_UNTRUSTED | ssl::SSL_BUILD_CHAIN_FLAG_IGNORE_ERROR |
+                ssl::SSL_BUILD_CHAIN_FLAG_CLEAR_ERROR;
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&X509::from_pem(CERT).unwrap()).unwrap();
+    ctx.set_ca_file(&Path::new("test/root-ca.pem")).unwrap();
+    ctx.build_cert_chain(flags).unwrap();
+    let ctx = ctx.build();
+    let chain = ctx.extra_chain_certs();
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].subject_name().organization(), Some("Internet Widgits Pty Ltd"));
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&X509::from_pem(CERT).unwrap()).unwrap();
+    ctx.set_ca_file(&Path::new("test/root-ca.pem")).unwrap();
+    ctx.build_cert_chain(flags | ssl::SSL_BUILD_CHAIN_FLAG_NO_ROOT).unwrap();
+    assert_eq!(ctx.build().extra_chain_certs().len(), 0);
+
+    // without a trust anchor no chain can be built
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&X509::from_pem(CERT).unwrap()).unwrap();
+    assert!(ctx.build_cert_chain(ssl::SSL_BUILD_CHAIN_FLAG_UNTRUSTED).is_err());
+}
+
+#[test]
+#[cfg(all(feature = "v111", ossl111))]
+fn serverinfo_v2() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_certificate(&X509::from_pem(CERT).unwrap()).unwrap();
+    ctx.set_serverinfo_v2(&[0x00, 0x00, 0x01, 0xd0, 0x12, 0x34, 0x00, 0x02, 1, 2]).unwrap();
+    assert!(ctx.set_serverinfo_v2(&[0x12, 0x34, 0x00, 0x02, 1, 2]).is_err());
+}
+
+#[test]
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+fn verify_valid_hostname() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_default_verify_paths().unwrap();
+    ctx.set_verify(SSL_VERIFY_PEER);
+
+    let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.param_mut().set_hostflags(X509_CHECK_FLAG_NO_PARTIAL_WILDCARDS);
+    ssl.param_mut().set_host("google.com").unwrap();
+
+    let s = TcpStream::connect("google.com:443").unwrap();
+    let mut socket = ssl.connect(s).unwrap();
+
+    socket.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    let mut result = vec![];
+    socket.read_to_end(&mut result).unwrap();
+
+    println!("{}", String::from_utf8_lossy(&result));
+    assert!(result.starts_with(b"HTTP/1.0"));
+    assert!(result.ends_with(b"</HTML>\r\n") || result.ends_with(b"</html>"));
+}
+
+#[test]
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
 fn verify_invalid_hostname() {
     let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
     ctx.set_default_verify_paths().unwrap();
@@ -1120,92 +2019,300 @@ fn connector_no_hostname_still_verifies() {
         .is_err());
 }
 
-#[test]
-fn connector_no_hostname_can_disable_verify() {
-    let (_s, tcp) = Server::new();
+#[test]
+fn connector_no_hostname_can_disable_verify() {
+    let (_s, tcp) = Server::new();
+
+    let mut connector = SslConnectorBuilder::new(SslMethod::tls()).unwrap();
+    connector.builder_mut().set_verify(SSL_VERIFY_NONE);
+    let connector = connector.build();
+
+    connector.danger_connect_without_providing_domain_for_certificate_verification_and_server_name_indication(tcp).unwrap();
+}
+
+#[test]
+fn connector_client_server_mozilla_intermediate() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let connector =
+            SslAcceptorBuilder::mozilla_intermediate(SslMethod::tls(), &key, &cert, None::<X509>)
+                .unwrap()
+                .build();
+        let stream = listener.accept().unwrap().0;
+        let mut stream = connector.accept(stream).unwrap();
+
+        stream.write_all(b"hello").unwrap();
+    });
+
+    let mut connector = SslConnectorBuilder::new(SslMethod::tls()).unwrap();
+    connector.builder_mut().set_ca_file("test/root-ca.pem").unwrap();
+    let connector = connector.build();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut stream = connector.connect("foobar.com", stream).unwrap();
+
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"hello", &buf);
+
+    t.join().unwrap();
+}
+
+#[test]
+fn acceptor_handshake_summary() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let acceptor =
+            SslAcceptorBuilder::mozilla_intermediate(SslMethod::tls(), &key, &cert, None::<X509>)
+                .unwrap()
+                .build();
+        let stream = listener.accept().unwrap().0;
+        let (mut stream, summary) = acceptor.accept_with_summary(stream).unwrap();
+        stream.write_all(b"hello").unwrap();
+
+        assert_eq!(summary.servername, Some("foobar.com".to_owned()));
+        assert!(summary.alpn_protocol.is_none());
+        assert!(summary.peer_certificate.is_none());
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.set_hostname("foobar.com").unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+
+    t.join().unwrap();
+}
+
+#[test]
+fn connector_client_server_mozilla_modern() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let connector =
+            SslAcceptorBuilder::mozilla_modern(SslMethod::tls(), &key, &cert, None::<X509>)
+                .unwrap()
+                .build();
+        let stream = listener.accept().unwrap().0;
+        let mut stream = connector.accept(stream).unwrap();
+
+        stream.write_all(b"hello").unwrap();
+    });
+
+    let mut connector = SslConnectorBuilder::new(SslMethod::tls()).unwrap();
+    connector.builder_mut().set_ca_file("test/root-ca.pem").unwrap();
+    let connector = connector.build();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut stream = connector.connect("foobar.com", stream).unwrap();
+
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(b"hello", &buf);
+
+    t.join().unwrap();
+}
+
+fn revocation_crl(revoked: bool) -> X509Crl {
+    let ca = X509::from_pem(ROOT_CERT).unwrap();
+    let key = PKey::private_key_from_pem(include_bytes!("../../../test/root-ca.key")).unwrap();
+    let now = Asn1Time::days_from_now(0).unwrap();
+    let next_update = Asn1Time::days_from_now(7).unwrap();
+
+    let mut builder = X509CrlBuilder::new().unwrap();
+    builder.set_issuer_name(ca.subject_name()).unwrap();
+    builder.set_last_update(&now).unwrap();
+    builder.set_next_update(&next_update).unwrap();
+    if revoked {
+        // the serial number of test/cert.pem
+        let serial = BigNum::from_hex_str("8771F7BDEE982FA5").unwrap();
+        builder.add_revoked(&serial, &now).unwrap();
+    }
+    builder.sign(&key, MessageDigest::sha256()).unwrap()
+}
+
+fn revocation_connect(policy: RevocationPolicy, crl: Option<X509Crl>, staple: bool) -> bool {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let t = thread::spawn(move || {
+        let key = PKey::private_key_from_pem(KEY).unwrap();
+        let cert = X509::from_pem(CERT).unwrap();
+        let mut acceptor =
+            SslAcceptorBuilder::mozilla_intermediate(SslMethod::tls(), &key, &cert, None::<X509>)
+                .unwrap();
+        if staple {
+            acceptor.builder_mut()
+                .set_status_callback(|ssl| {
+                    let response = OcspResponse::create(RESPONSE_STATUS_UNAUTHORIZED, None)
+                        .unwrap();
+                    ssl.set_ocsp_status(&response.to_der().unwrap()).unwrap();
+                    Ok(true)
+                })
+                .unwrap();
+        }
+        let acceptor = acceptor.build();
+        let stream = listener.accept().unwrap().0;
+        let _ = acceptor.accept(stream);
+    });
 
     let mut connector = SslConnectorBuilder::new(SslMethod::tls()).unwrap();
-    connector.builder_mut().set_verify(SSL_VERIFY_NONE);
+    connector.builder_mut().set_ca_file("test/root-ca.pem").unwrap();
+    if let Some(crl) = crl {
+        connector.builder_mut().cert_store_mut().add_crl(&crl).unwrap();
+    }
+    connector.set_revocation_check(policy).unwrap();
     let connector = connector.build();
 
-    connector.danger_connect_without_providing_domain_for_certificate_verification_and_server_name_indication(tcp).unwrap();
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ok = connector.connect("foobar.com", stream).is_ok();
+
+    t.join().unwrap();
+    ok
 }
 
 #[test]
-fn connector_client_server_mozilla_intermediate() {
+#[cfg(all(feature = "v110", ossl110))]
+fn crl_set_replace() {
+    use std::sync::{Arc, Mutex};
+    use x509::store::{CrlSet, X509_V_FLAG_CRL_CHECK};
+
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let port = listener.local_addr().unwrap().port();
 
     let t = thread::spawn(move || {
         let key = PKey::private_key_from_pem(KEY).unwrap();
         let cert = X509::from_pem(CERT).unwrap();
-        let connector =
+        let acceptor =
             SslAcceptorBuilder::mozilla_intermediate(SslMethod::tls(), &key, &cert, None::<X509>)
                 .unwrap()
                 .build();
-        let stream = listener.accept().unwrap().0;
-        let mut stream = connector.accept(stream).unwrap();
-
-        stream.write_all(b"hello").unwrap();
+        for _ in 0..3 {
+            let stream = listener.accept().unwrap().0;
+            let _ = acceptor.accept(stream);
+        }
     });
 
-    let mut connector = SslConnectorBuilder::new(SslMethod::tls()).unwrap();
-    connector.builder_mut().set_ca_file("test/root-ca.pem").unwrap();
-    let connector = connector.build();
+    let crls = CrlSet::new(vec![]);
+    let errors = Arc::new(Mutex::new(vec![]));
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_ca_file("test/root-ca.pem").unwrap();
+    ctx.cert_store_mut().set_flags(X509_V_FLAG_CRL_CHECK).unwrap();
+    ctx.cert_store_mut().set_crl_set(&crls).unwrap();
+    let errors2 = errors.clone();
+    ctx.set_verify_callback(SSL_VERIFY_PEER, move |_, x509_ctx| {
+        if let Some(error) = x509_ctx.error() {
+            errors2.lock().unwrap().push(error.as_raw() as i32);
+        }
+        true
+    });
+    let ctx = ctx.build();
 
-    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
-    let mut stream = connector.connect("foobar.com", stream).unwrap();
+    let connect = || {
+        errors.lock().unwrap().clear();
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        Ssl::new(&ctx).unwrap().connect(stream).unwrap();
+        errors.lock().unwrap().clone()
+    };
 
-    let mut buf = [0; 5];
-    stream.read_exact(&mut buf).unwrap();
-    assert_eq!(b"hello", &buf);
+    assert!(connect().contains(&ffi::X509_V_ERR_UNABLE_TO_GET_CRL));
+    crls.replace(vec![revocation_crl(false)]);
+    let errs = connect();
+    assert!(!errs.contains(&ffi::X509_V_ERR_UNABLE_TO_GET_CRL));
+    assert!(!errs.contains(&ffi::X509_V_ERR_CERT_REVOKED));
+    crls.replace(vec![revocation_crl(true)]);
+    assert!(connect().contains(&ffi::X509_V_ERR_CERT_REVOKED));
 
     t.join().unwrap();
 }
 
 #[test]
-fn connector_client_server_mozilla_modern() {
+fn connector_revocation_missing_crl() {
+    assert!(revocation_connect(RevocationPolicy::SoftFail, None, false));
+    assert!(!revocation_connect(RevocationPolicy::HardFail, None, false));
+}
+
+#[test]
+fn connector_revocation_crl() {
+    assert!(revocation_connect(RevocationPolicy::HardFail, Some(revocation_crl(false)), false));
+    assert!(!revocation_connect(RevocationPolicy::HardFail, Some(revocation_crl(true)), false));
+    assert!(!revocation_connect(RevocationPolicy::SoftFail, Some(revocation_crl(true)), false));
+}
+
+#[test]
+fn connector_revocation_unsuccessful_staple() {
+    assert!(revocation_connect(RevocationPolicy::SoftFail, None, true));
+    assert!(!revocation_connect(RevocationPolicy::HardFail, Some(revocation_crl(false)), true));
+}
+
+fn spki_pin(pem: &[u8]) -> [u8; 32] {
+    let cert = X509::from_pem(pem).unwrap();
+    let spki = cert.public_key().unwrap().public_key_to_der().unwrap();
+    let mut pin = [0; 32];
+    pin.copy_from_slice(&hash::hash(MessageDigest::sha256(), &spki).unwrap());
+    pin
+}
+
+// These certificates never expire, so that the pins stay valid.
+static PINNED_ROOT_CERT: &'static [u8] = include_bytes!("../../../test/pinned-root.pem");
+static PINNED_CERT: &'static [u8] = include_bytes!("../../../test/pinned-cert.pem");
+
+fn pinned_connect(pins: &[[u8; 32]], domain: &str) -> bool {
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let port = listener.local_addr().unwrap().port();
 
     let t = thread::spawn(move || {
         let key = PKey::private_key_from_pem(KEY).unwrap();
-        let cert = X509::from_pem(CERT).unwrap();
-        let connector =
-            SslAcceptorBuilder::mozilla_modern(SslMethod::tls(), &key, &cert, None::<X509>)
+        let cert = X509::from_pem(PINNED_CERT).unwrap();
+        let acceptor =
+            SslAcceptorBuilder::mozilla_intermediate(SslMethod::tls(), &key, &cert, None::<X509>)
                 .unwrap()
                 .build();
         let stream = listener.accept().unwrap().0;
-        let mut stream = connector.accept(stream).unwrap();
-
-        stream.write_all(b"hello").unwrap();
+        let _ = acceptor.accept(stream);
     });
 
     let mut connector = SslConnectorBuilder::new(SslMethod::tls()).unwrap();
-    connector.builder_mut().set_ca_file("test/root-ca.pem").unwrap();
+    connector.builder_mut().set_ca_file("test/pinned-root.pem").unwrap();
+    connector.set_spki_pins(pins);
     let connector = connector.build();
 
     let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
-    let mut stream = connector.connect("foobar.com", stream).unwrap();
-
-    let mut buf = [0; 5];
-    stream.read_exact(&mut buf).unwrap();
-    assert_eq!(b"hello", &buf);
+    let ok = connector.connect(domain, stream).is_ok();
 
     t.join().unwrap();
+    ok
 }
 
 #[test]
-fn shutdown() {
-    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
-    let port = listener.local_addr().unwrap().port();
+fn connector_spki_pins() {
+    let leaf = spki_pin(PINNED_CERT);
+    let root = spki_pin(PINNED_ROOT_CERT);
+    assert!(pinned_connect(&[leaf], "foobar.com"));
+    assert!(pinned_connect(&[[0; 32], root], "foobar.com"));
+    assert!(!pinned_connect(&[[0; 32]], "foobar.com"));
+    assert!(!pinned_connect(&[], "foobar.com"));
+    // a matching pin does not bypass hostname verification
+    assert!(!pinned_connect(&[leaf], "bogus.com"));
+}
 
-    thread::spawn(move || {
-        let stream = listener.accept().unwrap().0;
-        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ctx.set_certificate_file(&Path::new("test/cert.pem"), X509_FILETYPE_PEM).unwrap();
-        ctx.set_private_key_file(&Path::new("test/key.pem"), X509_FILETYPE_PEM).unwrap();
-        let ssl = Ssl::new(&ctx.build()).unwrap();
+#[test]
+fn shutdown() {
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
         let mut stream = ssl.accept(stream).unwrap();
 
         stream.write_all(b"hello").unwrap();
@@ -1236,6 +2343,51 @@ fn client_ca_list() {
     ctx.set_client_ca_list(names);
 }
 
+#[test]
+fn client_ca_list_advertised() {
+    use nid;
+
+    let (port, _) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
+        ctx.set_verify_callback(SSL_VERIFY_PEER, |_, _| true);
+        ctx.add_client_ca(&X509::from_pem(ROOT_CERT).unwrap()).unwrap();
+        let ctx = ctx.build();
+        assert_eq!(ctx.client_ca_list().unwrap().len(), 1);
+        let ssl = Ssl::new(&ctx).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        stream.write_all(b"a").unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    stream.read_exact(&mut [0]).unwrap();
+
+    let names = stream.ssl().client_ca_list().unwrap();
+    assert_eq!(names.len(), 1);
+    let org = names.iter()
+        .next()
+        .unwrap()
+        .entries_by_nid(nid::ORGANIZATIONNAME)
+        .next()
+        .unwrap();
+    assert_eq!(&**org.data().as_utf8().unwrap(), "Internet Widgits Pty Ltd");
+}
+
+#[test]
+fn client_ca_file() {
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    ctx.set_client_ca_file("test/certs.pem").unwrap();
+    let ctx = ctx.build();
+    let expected = X509Name::load_client_ca_file("test/certs.pem").unwrap();
+    assert_eq!(ctx.client_ca_list().unwrap().len(), expected.len());
+    assert!(expected.len() > 1);
+
+    let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    assert!(ctx.set_client_ca_file("test/nonexistent.pem").is_err());
+}
+
 #[test]
 fn cert_store() {
     let (_s, tcp) = Server::new();
@@ -1253,14 +2405,8 @@ fn cert_store() {
 fn tmp_dh_callback() {
     static CALLED_BACK: AtomicBool = ATOMIC_BOOL_INIT;
 
-    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
-    let port = listener.local_addr().unwrap().port();
-
-    thread::spawn(move ||{
-        let stream = listener.accept().unwrap().0;
-        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ctx.set_certificate_file(&Path::new("test/cert.pem"), X509_FILETYPE_PEM).unwrap();
-        ctx.set_private_key_file(&Path::new("test/key.pem"), X509_FILETYPE_PEM).unwrap();
+    let (port, _) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
         ctx.set_tmp_dh_callback(|_, _, _| {
             CALLED_BACK.store(true, Ordering::SeqCst);
             let dh = include_bytes!("../../../test/dhparams.pem");
@@ -1287,14 +2433,8 @@ fn tmp_ecdh_callback() {
 
     static CALLED_BACK: AtomicBool = ATOMIC_BOOL_INIT;
 
-    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
-    let port = listener.local_addr().unwrap().port();
-
-    thread::spawn(move ||{
-        let stream = listener.accept().unwrap().0;
-        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ctx.set_certificate_file(&Path::new("test/cert.pem"), X509_FILETYPE_PEM).unwrap();
-        ctx.set_private_key_file(&Path::new("test/key.pem"), X509_FILETYPE_PEM).unwrap();
+    let (port, _) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
         ctx.set_tmp_ecdh_callback(|_, _, _| {
             CALLED_BACK.store(true, Ordering::SeqCst);
             EcKey::new_by_curve_name(nid::X9_62_PRIME256V1)
@@ -1312,19 +2452,40 @@ fn tmp_ecdh_callback() {
     assert!(CALLED_BACK.load(Ordering::SeqCst));
 }
 
+#[test]
+#[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
+fn shared_sigalgs() {
+    use nid;
+    use std::sync::mpsc;
+
+    let (tx, rx) = mpsc::channel();
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let stream = ssl.accept(stream).unwrap();
+        tx.send(stream.ssl().shared_sigalgs()).unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let stream = ssl.connect(stream).unwrap();
+
+    assert!(stream.ssl().peer_signature_nid().is_some());
+    #[cfg(all(feature = "v111", ossl111))]
+    assert!(stream.ssl().peer_signature_type_nid().is_some());
+
+    let sigalgs = rx.recv().unwrap();
+    assert!(!sigalgs.is_empty());
+    assert!(sigalgs.iter().any(|s| s.hash == nid::SHA256));
+}
+
 #[test]
 fn tmp_dh_callback_ssl() {
     static CALLED_BACK: AtomicBool = ATOMIC_BOOL_INIT;
 
-    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
-    let port = listener.local_addr().unwrap().port();
-
-    thread::spawn(move ||{
-        let stream = listener.accept().unwrap().0;
-        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ctx.set_certificate_file(&Path::new("test/cert.pem"), X509_FILETYPE_PEM).unwrap();
-        ctx.set_private_key_file(&Path::new("test/key.pem"), X509_FILETYPE_PEM).unwrap();
-        let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    let (port, _) = spawn_server(move |stream| {
+        let mut ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
         ssl.set_tmp_dh_callback(|_, _, _| {
             CALLED_BACK.store(true, Ordering::SeqCst);
             let dh = include_bytes!("../../../test/dhparams.pem");
@@ -1350,15 +2511,8 @@ fn tmp_ecdh_callback_ssl() {
 
     static CALLED_BACK: AtomicBool = ATOMIC_BOOL_INIT;
 
-    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
-    let port = listener.local_addr().unwrap().port();
-
-    thread::spawn(move ||{
-        let stream = listener.accept().unwrap().0;
-        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ctx.set_certificate_file(&Path::new("test/cert.pem"), X509_FILETYPE_PEM).unwrap();
-        ctx.set_private_key_file(&Path::new("test/key.pem"), X509_FILETYPE_PEM).unwrap();
-        let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    let (port, _) = spawn_server(move |stream| {
+        let mut ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
         ssl.set_tmp_ecdh_callback(|_, _, _| {
             CALLED_BACK.store(true, Ordering::SeqCst);
             EcKey::new_by_curve_name(nid::X9_62_PRIME256V1)
@@ -1398,19 +2552,41 @@ fn active_session() {
     assert_eq!(copied, len);
 }
 
+#[test]
+fn session_to_der() {
+    use ssl::SslSession;
+
+    let (port, _) = spawn_server(move |stream| {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::tls()).build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        stream.write_all(b"a").unwrap();
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap().build();
+    let mut stream = Ssl::new(&ctx).unwrap().connect(stream).unwrap();
+    // TLS 1.3 session tickets arrive after the handshake
+    let mut buf = [0; 1];
+    stream.read_exact(&mut buf).unwrap();
+
+    let session = stream.ssl().session().unwrap();
+    let der = session.to_der().unwrap();
+    let mut buf = vec![0; session.der_len().unwrap()];
+    assert_eq!(session.to_der_into(&mut buf).unwrap(), der.len());
+    assert_eq!(buf, der);
+
+    let decoded = SslSession::from_der(&buf).unwrap();
+    assert_eq!(decoded.id(), session.id());
+    assert_eq!(decoded.master_key_len(), session.master_key_len());
+}
+
 #[test]
 fn status_callbacks() {
     static CALLED_BACK_SERVER: AtomicBool = ATOMIC_BOOL_INIT;
     static CALLED_BACK_CLIENT: AtomicBool = ATOMIC_BOOL_INIT;
 
-    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
-    let port = listener.local_addr().unwrap().port();
-
-    let guard = thread::spawn(move || {
-        let stream = listener.accept().unwrap().0;
-        let mut ctx = SslContext::builder(SslMethod::tls()).unwrap();
-        ctx.set_certificate_file(&Path::new("test/cert.pem"), X509_FILETYPE_PEM).unwrap();
-        ctx.set_private_key_file(&Path::new("test/key.pem"), X509_FILETYPE_PEM).unwrap();
+    let (port, guard) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
         ctx.set_status_callback(|ssl| {
             CALLED_BACK_SERVER.store(true, Ordering::SeqCst);
             let response = OcspResponse::create(RESPONSE_STATUS_UNAUTHORIZED, None).unwrap();
@@ -1440,6 +2616,225 @@ fn status_callbacks() {
     guard.join().unwrap();
 }
 
+#[test]
+#[cfg(all(feature = "v111", ossl111))]
+fn post_handshake_auth() {
+    use ssl::SSL_VERIFY_POST_HANDSHAKE;
+
+    let (port, guard) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
+        ctx.set_verify_callback(SSL_VERIFY_PEER | SSL_VERIFY_POST_HANDSHAKE, |_, _| true);
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        assert!(stream.ssl().peer_certificate().is_none());
+
+        stream.ssl_mut().verify_client_post_handshake().unwrap();
+        stream.write_all(b"a").unwrap();
+        let mut buf = [0; 1];
+        stream.read_exact(&mut buf).unwrap();
+        assert!(stream.ssl().peer_certificate().is_some());
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut ctx = cert_ctx(SslMethod::tls());
+    ctx.set_post_handshake_auth(true);
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(stream).unwrap();
+    let mut buf = [0; 1];
+    stream.read_exact(&mut buf).unwrap();
+    stream.write_all(b"b").unwrap();
+
+    guard.join().unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "v111", ossl111))]
+fn post_handshake_auth_not_offered() {
+    let (port, guard) = spawn_server(move |stream| {
+        let mut ctx = cert_ctx(SslMethod::tls());
+        ctx.set_verify(SSL_VERIFY_PEER);
+        let ssl = Ssl::new(&ctx.build()).unwrap();
+        let mut stream = ssl.accept(stream).unwrap();
+        assert!(stream.ssl_mut().verify_client_post_handshake().is_err());
+    });
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    ssl.connect(stream).unwrap();
+
+    guard.join().unwrap();
+}
+
+#[test]
+#[cfg_attr(any(libressl, windows, target_arch = "arm"), ignore)] // FIXME(#467)
+fn dtls_cookie_exchange() {
+    static GENERATED: AtomicBool = ATOMIC_BOOL_INIT;
+    static VERIFIED: AtomicBool = ATOMIC_BOOL_INIT;
+
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client_socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    server_socket.connect(client_socket.local_addr().unwrap()).unwrap();
+    client_socket.connect(server_socket.local_addr().unwrap()).unwrap();
+
+    let guard = thread::spawn(move || {
+        let mut ctx = cert_ctx(SslMethod::dtls_server());
+        ctx.set_options(ssl::SSL_OP_COOKIE_EXCHANGE);
+        ctx.set_cookie_generate_cb(|_, buf| {
+            GENERATED.store(true, Ordering::SeqCst);
+            buf[..6].copy_from_slice(b"cookie");
+            Ok(6)
+        });
+        ctx.set_cookie_verify_cb(|_, cookie| {
+            VERIFIED.store(true, Ordering::SeqCst);
+            cookie == b"cookie"
+        });
+        let mut ssl = Ssl::new(&ctx.build()).unwrap();
+        ssl.set_mtu(1200).unwrap();
+        let mut stream = ssl.accept(UdpConnected(server_socket)).unwrap();
+        stream.write_all(b"hello").unwrap();
+    });
+
+    let ctx = SslContext::builder(SslMethod::dtls_client()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(UdpConnected(client_socket)).unwrap();
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+
+    guard.join().unwrap();
+
+    assert!(GENERATED.load(Ordering::SeqCst));
+    assert!(VERIFIED.load(Ordering::SeqCst));
+}
+
+#[test]
+#[cfg_attr(any(libressl, windows, target_arch = "arm"), ignore)] // FIXME(#467)
+fn dtls_message_boundaries() {
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client_socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    server_socket.connect(client_socket.local_addr().unwrap()).unwrap();
+    client_socket.connect(server_socket.local_addr().unwrap()).unwrap();
+
+    let guard = thread::spawn(move || {
+        let ssl = Ssl::new(&cert_ctx(SslMethod::dtls_server()).build()).unwrap();
+        let mut stream = ssl.accept(UdpConnected(server_socket)).unwrap();
+        stream.write_all(b"hello").unwrap();
+        stream.write_all(b"world!").unwrap();
+    });
+
+    let ctx = SslContext::builder(SslMethod::dtls_client()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    let mut stream = ssl.connect(UdpConnected(client_socket)).unwrap();
+
+    // each read returns exactly one of the peer's writes
+    let mut buf = [0; 100];
+    let n = stream.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"hello");
+
+    // a record which doesn't fit isn't split across reads
+    match stream.ssl_read(&mut buf[..3]) {
+        Err(Error::Stream(ref e)) if e.kind() == io::ErrorKind::InvalidInput => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+    let n = stream.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"world!");
+
+    guard.join().unwrap();
+}
+
+#[test]
+#[cfg_attr(any(libressl, windows, target_arch = "arm"), ignore)] // FIXME(#467)
+fn dtls_short_write() {
+    #[derive(Debug)]
+    struct ShortWrite(UdpSocket);
+
+    impl Read for ShortWrite {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.recv(buf)
+        }
+    }
+
+    impl Write for ShortWrite {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.send(&buf[..buf.len() / 2])
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client_socket.connect(server_socket.local_addr().unwrap()).unwrap();
+
+    let ctx = SslContext::builder(SslMethod::dtls_client()).unwrap();
+    let ssl = Ssl::new(&ctx.build()).unwrap();
+    match ssl.connect(ShortWrite(client_socket)) {
+        Err(HandshakeError::Failure(s)) => {
+            match *s.error() {
+                Error::Stream(ref e) => assert_eq!(e.kind(), io::ErrorKind::WriteZero),
+                ref e => panic!("unexpected error {:?}", e),
+            }
+        }
+        r => panic!("unexpected result {:?}", r),
+    }
+
+    // OpenSSL must not have sent the rest of the ClientHello as a second datagram
+    let mut buf = [0; 2048];
+    server_socket.set_nonblocking(true).unwrap();
+    server_socket.recv(&mut buf).unwrap();
+    assert_eq!(server_socket.recv(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+}
+
+#[test]
+#[cfg_attr(any(libressl, windows, target_arch = "arm"), ignore)] // FIXME(#467)
+fn dtls_srtp() {
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client_socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    server_socket.connect(client_socket.local_addr().unwrap()).unwrap();
+    client_socket.connect(server_socket.local_addr().unwrap()).unwrap();
+
+    let guard = thread::spawn(move || {
+        let mut ctx = cert_ctx(SslMethod::dtls_server());
+        ctx.set_tlsext_use_srtp("SRTP_AES128_CM_SHA1_32").unwrap();
+        let mut ssl = Ssl::new(&ctx.build()).unwrap();
+        ssl.set_mtu(1200).unwrap();
+        let mut stream = ssl.accept(UdpConnected(server_socket)).unwrap();
+        let profile = stream.ssl().selected_srtp_profile().unwrap();
+        assert_eq!(profile.id(), ssl::SRTP_AES128_CM_SHA1_32);
+        let material = stream.ssl().export_keying_material("EXTRACTOR-dtls_srtp", None, 60).unwrap();
+        stream.write_all(&material).unwrap();
+    });
+
+    let ctx = SslContext::builder(SslMethod::dtls_client()).unwrap();
+    let mut ssl = Ssl::new(&ctx.build()).unwrap();
+    assert!(ssl.selected_srtp_profile().is_none());
+    ssl.set_tlsext_use_srtp("SRTP_AES128_CM_SHA1_80:SRTP_AES128_CM_SHA1_32").unwrap();
+    let mut stream = ssl.connect(UdpConnected(client_socket)).unwrap();
+
+    let profile = stream.ssl().selected_srtp_profile().unwrap();
+    assert_eq!(profile.id(), ssl::SRTP_AES128_CM_SHA1_32);
+    assert_eq!(profile.name(), "SRTP_AES128_CM_SHA1_32");
+
+    let mut buf = [0; 60];
+    stream.read_exact(&mut buf).unwrap();
+    let material = stream.ssl().export_keying_material("EXTRACTOR-dtls_srtp", None, 60).unwrap();
+    assert_eq!(&buf[..], &material[..]);
+
+    guard.join().unwrap();
+}
+
+#[test]
+fn srtp_unknown_profile() {
+    let mut ctx = SslContext::builder(SslMethod::dtls()).unwrap();
+    assert!(ctx.set_tlsext_use_srtp("SRTP_BOGUS").is_err());
+}
+
 fn _check_kinds() {
     fn is_send<T: Send>() {}
     fn is_sync<T: Sync>() {}
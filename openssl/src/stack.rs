@@ -1,19 +1,32 @@
+use ffi;
 use foreign_types::{ForeignTypeRef, ForeignType};
-use libc::c_int;
+use libc::{c_int, c_void};
+use std::any::Any;
 use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp::Ordering;
 use std::convert::AsRef;
 use std::iter;
 use std::marker::PhantomData;
 use std::mem;
-use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::ops::{Deref, DerefMut, Index, IndexMut, Range};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
 
+use {cvt, cvt_p};
+use error::ErrorStack;
 use util::Opaque;
 
 #[cfg(ossl10x)]
 use ffi::{sk_pop as OPENSSL_sk_pop, sk_free as OPENSSL_sk_free, sk_num as OPENSSL_sk_num,
-          sk_value as OPENSSL_sk_value, _STACK as OPENSSL_STACK};
+          sk_value as OPENSSL_sk_value, sk_set as OPENSSL_sk_set,
+          sk_sort as OPENSSL_sk_sort, sk_set_cmp_func as OPENSSL_sk_set_cmp_func,
+          sk_new_null as OPENSSL_sk_new_null, sk_push as OPENSSL_sk_push,
+          _STACK as OPENSSL_STACK};
 #[cfg(ossl110)]
-use ffi::{OPENSSL_sk_pop, OPENSSL_sk_free, OPENSSL_sk_num, OPENSSL_sk_value, OPENSSL_STACK};
+use ffi::{OPENSSL_sk_pop, OPENSSL_sk_free, OPENSSL_sk_num, OPENSSL_sk_value, OPENSSL_sk_set,
+          OPENSSL_sk_sort, OPENSSL_sk_set_cmp_func, OPENSSL_sk_new_null, OPENSSL_sk_push,
+          OPENSSL_STACK};
 
 /// Trait implemented by types which can be placed in a stack.
 ///
@@ -30,10 +43,155 @@ pub trait Stackable: ForeignType {
 pub struct Stack<T: Stackable>(*mut T::StackType);
 
 impl<T: Stackable> Stack<T> {
+    /// Creates a new, empty stack.
+    pub fn new() -> Result<Stack<T>, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let ptr = try!(cvt_p(OPENSSL_sk_new_null()));
+            Ok(Stack(ptr as *mut _))
+        }
+    }
+
     /// Return a new Stack<T>, taking ownership of the handle
     pub unsafe fn from_ptr(stack: *mut T::StackType) -> Stack<T> {
         Stack(stack)
     }
+
+    /// Adds an element to the end of the stack, transferring ownership of it to the stack.
+    pub fn push(&mut self, data: T) -> Result<(), ErrorStack>
+        where T: Deref<Target = <T as ForeignType>::Ref>
+    {
+        unsafe {
+            try!(cvt(OPENSSL_sk_push(self.as_stack(), data.as_ptr() as *mut _)));
+            mem::forget(data);
+            Ok(())
+        }
+    }
+
+    /// Retains only the elements for which `f` returns `true`, freeing the
+    /// others.
+    ///
+    /// The relative order of the retained elements is preserved.
+    pub fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&T::Ref) -> bool
+    {
+        let keep = self.iter().map(|e| f(e)).collect::<Vec<_>>();
+        self.retain_mask(&keep);
+    }
+
+    /// Sorts the stack with OpenSSL's stack sort, using `compare` to order
+    /// its elements.
+    ///
+    /// The sort is not stable. Any comparison function previously installed
+    /// on the stack is restored afterwards.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+        where F: FnMut(&T::Ref, &T::Ref) -> Ordering
+    {
+        unsafe {
+            let mut state = SortState {
+                compare: &mut compare,
+                panic: None,
+            };
+
+            let prev = SORT_STATE.with(|s| {
+                let prev = s.get();
+                s.set(&mut state as *mut SortState<F> as *mut c_void);
+                prev
+            });
+            let old = OPENSSL_sk_set_cmp_func(self.as_stack(), Some(raw_compare::<T, F>));
+            OPENSSL_sk_sort(self.as_stack());
+            OPENSSL_sk_set_cmp_func(self.as_stack(), old);
+            SORT_STATE.with(|s| s.set(prev));
+
+            if let Some(err) = state.panic {
+                panic::resume_unwind(err);
+            }
+        }
+    }
+
+    /// Removes consecutive elements for which `same_bucket` returns `true`,
+    /// freeing them.
+    ///
+    /// `same_bucket` is passed the last retained element followed by the
+    /// element being considered for removal.
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+        where F: FnMut(&T::Ref, &T::Ref) -> bool
+    {
+        let mut keep = vec![true; self.len()];
+        let mut last = 0;
+        for idx in 1..self.len() {
+            if same_bucket(&self[last], &self[idx]) {
+                keep[idx] = false;
+            } else {
+                last = idx;
+            }
+        }
+        self.retain_mask(&keep);
+    }
+
+    /// Removes consecutive equal elements, freeing them.
+    pub fn dedup(&mut self)
+        where T::Ref: PartialEq
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    // Frees the elements whose entry in `keep` is false and compacts the rest in a single pass.
+    // The caller's closures have all run by now, so a panic can't leave the stack half-updated.
+    fn retain_mask(&mut self, keep: &[bool]) {
+        unsafe {
+            let stack = self.as_stack();
+            let mut len = 0;
+            for (idx, &keep) in keep.iter().enumerate() {
+                let ptr = OPENSSL_sk_value(stack, idx as c_int);
+                if keep {
+                    OPENSSL_sk_set(stack, len as c_int, ptr);
+                    len += 1;
+                } else {
+                    T::from_ptr(ptr as *mut _);
+                }
+            }
+            for _ in len..keep.len() {
+                OPENSSL_sk_pop(stack);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static SORT_STATE: Cell<*mut c_void> = Cell::new(ptr::null_mut());
+}
+
+struct SortState<'a, F: 'a> {
+    compare: &'a mut F,
+    panic: Option<Box<Any + Send>>,
+}
+
+unsafe extern "C" fn raw_compare<T, F>(a: *const c_void, b: *const c_void) -> c_int
+    where T: Stackable,
+          F: FnMut(&T::Ref, &T::Ref) -> Ordering
+{
+    let state = SORT_STATE.with(|s| s.get()) as *mut SortState<F>;
+    let state = &mut *state;
+
+    // OpenSSL's sort can't be unwound through, so stash the panic and resume
+    // it once the sort has returned.
+    if state.panic.is_some() {
+        return 0;
+    }
+
+    let a = T::Ref::from_ptr(*(a as *const *mut T::CType));
+    let b = T::Ref::from_ptr(*(b as *const *mut T::CType));
+    let compare = &mut state.compare;
+    match panic::catch_unwind(AssertUnwindSafe(|| compare(a, b))) {
+        Ok(Ordering::Less) => -1,
+        Ok(Ordering::Equal) => 0,
+        Ok(Ordering::Greater) => 1,
+        Err(err) => {
+            state.panic = Some(err);
+            0
+        }
+    }
 }
 
 impl<T: Stackable> Drop for Stack<T> {
@@ -52,7 +210,7 @@ impl<T: Stackable> iter::IntoIterator for Stack<T> {
     fn into_iter(self) -> IntoIter<T> {
         let it = IntoIter {
             stack: self.0,
-            idx: 0,
+            idxs: 0..self.len() as c_int,
         };
         mem::forget(self);
         it
@@ -96,13 +254,7 @@ impl<T: Stackable> DerefMut for Stack<T> {
 
 pub struct IntoIter<T: Stackable> {
     stack: *mut T::StackType,
-    idx: c_int,
-}
-
-impl<T: Stackable> IntoIter<T> {
-    fn stack_len(&self) -> c_int {
-        unsafe { OPENSSL_sk_num(self.stack as *mut _) }
-    }
+    idxs: Range<c_int>,
 }
 
 impl<T: Stackable> Drop for IntoIter<T> {
@@ -119,19 +271,24 @@ impl<T: Stackable> Iterator for IntoIter<T> {
 
     fn next(&mut self) -> Option<T> {
         unsafe {
-            if self.idx == self.stack_len() {
-                None
-            } else {
-                let ptr = OPENSSL_sk_value(self.stack as *mut _, self.idx);
-                self.idx += 1;
-                Some(T::from_ptr(ptr as *mut _))
-            }
+            self.idxs
+                .next()
+                .map(|i| T::from_ptr(OPENSSL_sk_value(self.stack as *mut _, i) as *mut _))
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = (self.stack_len() - self.idx) as usize;
-        (size, Some(size))
+        self.idxs.size_hint()
+    }
+}
+
+impl<T: Stackable> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        unsafe {
+            self.idxs
+                .next_back()
+                .map(|i| T::from_ptr(OPENSSL_sk_value(self.stack as *mut _, i) as *mut _))
+        }
     }
 }
 
@@ -162,14 +319,15 @@ impl<T: Stackable> StackRef<T> {
 
         Iter {
             stack: self,
-            pos: 0,
+            idxs: 0..self.len(),
         }
     }
 
     pub fn iter_mut(&mut self) -> IterMut<T> {
+        let len = self.len();
         IterMut {
             stack: self,
-            pos: 0,
+            idxs: 0..len,
         }
     }
 
@@ -269,26 +427,31 @@ pub struct Iter<'a, T: Stackable>
     where T: 'a
 {
     stack: &'a StackRef<T>,
-    pos: usize,
+    idxs: Range<usize>,
 }
 
 impl<'a, T: Stackable> iter::Iterator for Iter<'a, T> {
     type Item = &'a T::Ref;
 
     fn next(&mut self) -> Option<&'a T::Ref> {
-        let n = self.stack.get(self.pos);
-
-        if n.is_some() {
-            self.pos += 1;
-        }
-
-        n
+        let stack = self.stack;
+        self.idxs.next().map(|i| unsafe { T::Ref::from_ptr(stack._get(i)) })
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let rem = self.stack.len() - self.pos;
+        self.idxs.size_hint()
+    }
 
-        (rem, Some(rem))
+    fn nth(&mut self, n: usize) -> Option<&'a T::Ref> {
+        let stack = self.stack;
+        self.idxs.nth(n).map(|i| unsafe { T::Ref::from_ptr(stack._get(i)) })
+    }
+}
+
+impl<'a, T: Stackable> iter::DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T::Ref> {
+        let stack = self.stack;
+        self.idxs.next_back().map(|i| unsafe { T::Ref::from_ptr(stack._get(i)) })
     }
 }
 
@@ -297,33 +460,34 @@ impl<'a, T: Stackable> iter::ExactSizeIterator for Iter<'a, T> {}
 /// A mutable iterator over the stack's contents.
 pub struct IterMut<'a, T: Stackable + 'a> {
     stack: &'a mut StackRef<T>,
-    pos: usize,
+    idxs: Range<usize>,
 }
 
 impl<'a, T: Stackable> iter::Iterator for IterMut<'a, T> {
     type Item = &'a mut T::Ref;
 
     fn next(&mut self) -> Option<&'a mut T::Ref> {
-        if self.pos >= self.stack.len() {
-            None
-        } else {
-            // Rust won't allow us to get a mutable reference into
-            // `stack` in this situation since it can't statically
-            // guarantee that we won't return several references to
-            // the same object, so we have to use unsafe code for
-            // mutable iterators.
-            let n = unsafe { Some(T::Ref::from_ptr_mut(self.stack._get(self.pos))) };
-
-            self.pos += 1;
-
-            n
-        }
+        // Rust won't allow us to get a mutable reference into `stack` in this situation since it
+        // can't statically guarantee that we won't return several references to the same object,
+        // so we have to use unsafe code for mutable iterators.
+        let stack = &*self.stack;
+        self.idxs.next().map(|i| unsafe { T::Ref::from_ptr_mut(stack._get(i)) })
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let rem = self.stack.len() - self.pos;
+        self.idxs.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<&'a mut T::Ref> {
+        let stack = &*self.stack;
+        self.idxs.nth(n).map(|i| unsafe { T::Ref::from_ptr_mut(stack._get(i)) })
+    }
+}
 
-        (rem, Some(rem))
+impl<'a, T: Stackable> iter::DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T::Ref> {
+        let stack = &*self.stack;
+        self.idxs.next_back().map(|i| unsafe { T::Ref::from_ptr_mut(stack._get(i)) })
     }
 }
 
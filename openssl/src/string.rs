@@ -67,14 +67,6 @@ impl fmt::Debug for OpensslStringRef {
     }
 }
 
-#[cfg(not(ossl110))]
 unsafe fn free(buf: *mut c_char) {
-    ::ffi::CRYPTO_free(buf as *mut c_void);
-}
-
-#[cfg(ossl110)]
-unsafe fn free(buf: *mut c_char) {
-    ::ffi::CRYPTO_free(buf as *mut c_void,
-                       concat!(file!(), "\0").as_ptr() as *const c_char,
-                       line!() as ::libc::c_int);
+    ::util::crypto_free(buf as *mut c_void);
 }
@@ -1,10 +1,15 @@
 use std::cmp;
+use std::error;
+use std::fmt;
 use std::ptr;
-use libc::c_int;
+use libc::{c_int, c_uchar};
 use ffi;
+use foreign_types::ForeignTypeRef;
 
 use {cvt, cvt_p};
 use error::ErrorStack;
+use nid::{self, Nid};
+use x509::AlgorithmIdentifierRef;
 
 #[derive(Copy, Clone)]
 pub enum Mode {
@@ -12,132 +17,240 @@ pub enum Mode {
     Decrypt,
 }
 
-#[derive(Copy, Clone)]
-pub struct Cipher(*const ffi::EVP_CIPHER);
+impl Mode {
+    fn as_raw(&self) -> c_int {
+        match *self {
+            Mode::Encrypt => 1,
+            Mode::Decrypt => 0,
+        }
+    }
+}
+
+/// A symmetric cipher.
+///
+/// The ciphers are also available as constants such as `AES_128_CBC`, which can be used in `match`
+/// patterns. The built-in ciphers are identified by their `Nid`, while a `Cipher` created from
+/// any other implementation, such as one provided by an engine, holds on to that implementation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cipher(Nid, Option<*const ffi::EVP_CIPHER>);
+
+// EVP_CIPHERs are never modified once they have been created
+unsafe impl Sync for Cipher {}
+unsafe impl Send for Cipher {}
+
+pub const AES_128_ECB: Cipher = Cipher(nid::AES_128_ECB, None);
+pub const AES_128_CBC: Cipher = Cipher(nid::AES_128_CBC, None);
+pub const AES_128_XTS: Cipher = Cipher(nid::AES_128_XTS, None);
+pub const AES_128_CTR: Cipher = Cipher(nid::AES_128_CTR, None);
+pub const AES_128_CFB1: Cipher = Cipher(nid::AES_128_CFB1, None);
+pub const AES_128_CFB128: Cipher = Cipher(nid::AES_128_CFB128, None);
+pub const AES_128_CFB8: Cipher = Cipher(nid::AES_128_CFB8, None);
+pub const AES_128_GCM: Cipher = Cipher(nid::AES_128_GCM, None);
+pub const AES_128_CCM: Cipher = Cipher(nid::AES_128_CCM, None);
+pub const AES_256_ECB: Cipher = Cipher(nid::AES_256_ECB, None);
+pub const AES_256_CBC: Cipher = Cipher(nid::AES_256_CBC, None);
+pub const AES_256_XTS: Cipher = Cipher(nid::AES_256_XTS, None);
+pub const AES_256_CTR: Cipher = Cipher(nid::AES_256_CTR, None);
+pub const AES_256_CFB1: Cipher = Cipher(nid::AES_256_CFB1, None);
+pub const AES_256_CFB128: Cipher = Cipher(nid::AES_256_CFB128, None);
+pub const AES_256_CFB8: Cipher = Cipher(nid::AES_256_CFB8, None);
+pub const AES_256_GCM: Cipher = Cipher(nid::AES_256_GCM, None);
+pub const AES_256_CCM: Cipher = Cipher(nid::AES_256_CCM, None);
+pub const BF_CBC: Cipher = Cipher(nid::BF_CBC, None);
+pub const BF_ECB: Cipher = Cipher(nid::BF_ECB, None);
+pub const BF_CFB64: Cipher = Cipher(nid::BF_CFB64, None);
+pub const BF_OFB: Cipher = Cipher(nid::BF_OFB64, None);
+pub const DES_CBC: Cipher = Cipher(nid::DES_CBC, None);
+pub const DES_ECB: Cipher = Cipher(nid::DES_ECB, None);
+pub const RC4: Cipher = Cipher(nid::RC4, None);
+
+/// Requires the `v110` feature and OpenSSL 1.1.0.
+#[cfg(all(ossl110, feature = "v110"))]
+pub const CHACHA20: Cipher = Cipher(nid::CHACHA20, None);
+
+/// Requires the `v110` feature and OpenSSL 1.1.0.
+#[cfg(all(ossl110, feature = "v110"))]
+pub const CHACHA20_POLY1305: Cipher = Cipher(nid::CHACHA20_POLY1305, None);
 
 impl Cipher {
     pub fn aes_128_ecb() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_ecb()) }
+        AES_128_ECB
     }
 
     pub fn aes_128_cbc() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_cbc()) }
+        AES_128_CBC
     }
 
     pub fn aes_128_xts() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_xts()) }
+        AES_128_XTS
     }
 
     pub fn aes_128_ctr() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_ctr()) }
+        AES_128_CTR
     }
 
     pub fn aes_128_cfb1() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_cfb1()) }
+        AES_128_CFB1
     }
 
     pub fn aes_128_cfb128() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_cfb128()) }
+        AES_128_CFB128
     }
 
     pub fn aes_128_cfb8() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_cfb8()) }
+        AES_128_CFB8
     }
 
     pub fn aes_128_gcm() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_128_gcm()) }
+        AES_128_GCM
+    }
+
+    pub fn aes_128_ccm() -> Cipher {
+        AES_128_CCM
     }
 
     pub fn aes_256_ecb() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_ecb()) }
+        AES_256_ECB
     }
 
     pub fn aes_256_cbc() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_cbc()) }
+        AES_256_CBC
     }
 
     pub fn aes_256_xts() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_xts()) }
+        AES_256_XTS
     }
 
     pub fn aes_256_ctr() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_ctr()) }
+        AES_256_CTR
     }
 
     pub fn aes_256_cfb1() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_cfb1()) }
+        AES_256_CFB1
     }
 
     pub fn aes_256_cfb128() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_cfb128()) }
+        AES_256_CFB128
     }
 
     pub fn aes_256_cfb8() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_cfb8()) }
+        AES_256_CFB8
     }
 
     pub fn aes_256_gcm() -> Cipher {
-        unsafe { Cipher(ffi::EVP_aes_256_gcm()) }
+        AES_256_GCM
+    }
+
+    pub fn aes_256_ccm() -> Cipher {
+        AES_256_CCM
     }
 
     pub fn bf_cbc() -> Cipher {
-        unsafe { Cipher(ffi::EVP_bf_cbc()) }
+        BF_CBC
     }
 
     pub fn bf_ecb() -> Cipher {
-        unsafe { Cipher(ffi::EVP_bf_ecb()) }
+        BF_ECB
     }
 
     pub fn bf_cfb64() -> Cipher {
-        unsafe { Cipher(ffi::EVP_bf_cfb64()) }
+        BF_CFB64
     }
 
     pub fn bf_ofb() -> Cipher {
-        unsafe { Cipher(ffi::EVP_bf_ofb()) }
+        BF_OFB
     }
 
     pub fn des_cbc() -> Cipher {
-        unsafe { Cipher(ffi::EVP_des_cbc()) }
+        DES_CBC
     }
 
     pub fn des_ecb() -> Cipher {
-        unsafe { Cipher(ffi::EVP_des_ecb()) }
+        DES_ECB
     }
 
     pub fn rc4() -> Cipher {
-        unsafe { Cipher(ffi::EVP_rc4()) }
+        RC4
     }
 
     /// Requires the `v110` feature and OpenSSL 1.1.0.
     #[cfg(all(ossl110, feature = "v110"))]
     pub fn chacha20() -> Cipher {
-        unsafe { Cipher(ffi::EVP_chacha20()) }
+        CHACHA20
     }
 
     /// Requires the `v110` feature and OpenSSL 1.1.0.
     #[cfg(all(ossl110, feature = "v110"))]
     pub fn chacha20_poly1305() -> Cipher {
-        unsafe { Cipher(ffi::EVP_chacha20_poly1305()) }
+        CHACHA20_POLY1305
+    }
+
+    /// Returns the cipher identified by `nid`, if it is known to OpenSSL.
+    pub fn from_nid(nid: Nid) -> Option<Cipher> {
+        unsafe {
+            let ptr = cipher_by_nid(nid);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(Cipher::from_ptr(ptr))
+            }
+        }
+    }
+
+    /// Returns the cipher identified by `alg` along with the parameters it specifies, if it is a
+    /// cipher known to OpenSSL.
+    ///
+    /// This can be used to resolve the content encryption algorithm of CMS and PKCS#7 structures.
+    pub fn from_algorithm_identifier(alg: &AlgorithmIdentifierRef)
+                                     -> Result<Option<(Cipher, CipherParameters)>, ErrorStack> {
+        let cipher = match alg.cipher() {
+            Some(cipher) => cipher,
+            None => return Ok(None),
+        };
+        unsafe {
+            let ctx = try!(cvt_p(ffi::EVP_CIPHER_CTX_new()));
+            let params = cipher_parameters(ctx, cipher, (*alg.as_ptr()).parameter);
+            ffi::EVP_CIPHER_CTX_free(ctx);
+            params.map(|params| Some((cipher, params)))
+        }
     }
 
+    /// Returns the `Cipher` corresponding to an OpenSSL implementation.
+    ///
+    /// `as_ptr` returns `ptr` itself. If it is one of the built-in ciphers, the result is equal
+    /// to the corresponding constant.
     pub unsafe fn from_ptr(ptr: *const ffi::EVP_CIPHER) -> Cipher {
-        Cipher(ptr)
+        let nid = Nid::from_raw(ffi::EVP_CIPHER_nid(ptr));
+        if builtin_cipher(nid) == ptr {
+            Cipher(nid, None)
+        } else {
+            Cipher(nid, Some(ptr))
+        }
     }
 
-    pub fn as_ptr(&self) -> *const ffi::EVP_CIPHER {
+    /// Returns the identifier of the cipher.
+    pub fn nid(&self) -> Nid {
         self.0
     }
 
+    /// Returns the OpenSSL implementation of the cipher.
+    pub fn as_ptr(&self) -> *const ffi::EVP_CIPHER {
+        match self.1 {
+            Some(ptr) => ptr,
+            None => unsafe { builtin_cipher(self.0) },
+        }
+    }
+
     /// Returns the length of keys used with this cipher.
     pub fn key_len(&self) -> usize {
-        unsafe { EVP_CIPHER_key_length(self.0) as usize }
+        unsafe { EVP_CIPHER_key_length(self.as_ptr()) as usize }
     }
 
     /// Returns the length of the IV used with this cipher, or `None` if the
     /// cipher does not use an IV.
     pub fn iv_len(&self) -> Option<usize> {
         unsafe {
-            let len = EVP_CIPHER_iv_length(self.0) as usize;
+            let len = EVP_CIPHER_iv_length(self.as_ptr()) as usize;
             if len == 0 { None } else { Some(len) }
         }
     }
@@ -148,19 +261,179 @@ impl Cipher {
     ///
     /// Stream ciphers such as RC4 have a block size of 1.
     pub fn block_size(&self) -> usize {
-        unsafe { EVP_CIPHER_block_size(self.0) as usize }
+        unsafe { EVP_CIPHER_block_size(self.as_ptr()) as usize }
+    }
+
+    /// Determines whether the cipher is using CCM mode
+    fn is_ccm(&self) -> bool {
+        // NB: We can't use EVP_CIPHER_mode because that's a macro in OpenSSL 1.0.x.
+        *self == Cipher::aes_128_ccm() || *self == Cipher::aes_256_ccm()
+    }
+
+    /// Determines whether the cipher accepts IVs of lengths other than `iv_len`.
+    fn has_variable_iv_len(&self) -> bool {
+        // OpenSSL 1.0.x doesn't flag CCM as an AEAD cipher.
+        self.is_ccm() || unsafe { EVP_CIPHER_flags(self.as_ptr()) & ffi::EVP_CIPH_FLAG_AEAD_CIPHER != 0 }
+    }
+}
+
+/// The parameters of a cipher specified by an `AlgorithmIdentifier`.
+///
+/// This is returned by `Cipher::from_algorithm_identifier`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CipherParameters {
+    /// The IV, if the cipher uses one and it is present in the parameters.
+    pub iv: Option<Vec<u8>>,
+    /// The length of the key in bytes.
+    ///
+    /// This differs from `Cipher::key_len` for ciphers with a variable key length, such as RC2.
+    pub key_len: usize,
+}
+
+unsafe fn cipher_parameters(ctx: *mut ffi::EVP_CIPHER_CTX,
+                            cipher: Cipher,
+                            parameter: *mut ffi::ASN1_TYPE)
+                            -> Result<CipherParameters, ErrorStack> {
+    try!(cvt(ffi::EVP_CipherInit_ex(ctx,
+                                     cipher.as_ptr(),
+                                     ptr::null_mut(),
+                                     ptr::null_mut(),
+                                     ptr::null_mut(),
+                                     0)));
+
+    let mut iv = None;
+    if !parameter.is_null() {
+        // this checks the parameters and applies any key length they specify to the context
+        try!(cvt(ffi::EVP_CIPHER_asn1_to_param(ctx, parameter)));
+
+        if let Some(len) = cipher.iv_len() {
+            let mut buf = vec![0; len];
+            let mut num = 0;
+            // RC2 stores its IV in a sequence along with the effective key length
+            let r = match ffi::ASN1_TYPE_get(parameter) {
+                ffi::V_ASN1_OCTET_STRING => {
+                    ffi::ASN1_TYPE_get_octetstring(parameter, buf.as_mut_ptr(), len as c_int)
+                }
+                ffi::V_ASN1_SEQUENCE => {
+                    ffi::ASN1_TYPE_get_int_octetstring(parameter,
+                                                       &mut num,
+                                                       buf.as_mut_ptr(),
+                                                       len as c_int)
+                }
+                _ => -1,
+            };
+            if r == len as c_int {
+                iv = Some(buf);
+            }
+        }
     }
+
+    Ok(CipherParameters {
+        iv: iv,
+        key_len: ffi::EVP_CIPHER_CTX_key_length(ctx) as usize,
+    })
+}
+
+// Returns the implementation of one of the ciphers with a constant in this module, or null.
+unsafe fn builtin_cipher(nid: Nid) -> *const ffi::EVP_CIPHER {
+    match nid {
+        nid::AES_128_ECB => ffi::EVP_aes_128_ecb(),
+        nid::AES_128_CBC => ffi::EVP_aes_128_cbc(),
+        nid::AES_128_XTS => ffi::EVP_aes_128_xts(),
+        nid::AES_128_CTR => ffi::EVP_aes_128_ctr(),
+        nid::AES_128_CFB1 => ffi::EVP_aes_128_cfb1(),
+        nid::AES_128_CFB128 => ffi::EVP_aes_128_cfb128(),
+        nid::AES_128_CFB8 => ffi::EVP_aes_128_cfb8(),
+        nid::AES_128_GCM => ffi::EVP_aes_128_gcm(),
+        nid::AES_128_CCM => ffi::EVP_aes_128_ccm(),
+        nid::AES_256_ECB => ffi::EVP_aes_256_ecb(),
+        nid::AES_256_CBC => ffi::EVP_aes_256_cbc(),
+        nid::AES_256_XTS => ffi::EVP_aes_256_xts(),
+        nid::AES_256_CTR => ffi::EVP_aes_256_ctr(),
+        nid::AES_256_CFB1 => ffi::EVP_aes_256_cfb1(),
+        nid::AES_256_CFB128 => ffi::EVP_aes_256_cfb128(),
+        nid::AES_256_CFB8 => ffi::EVP_aes_256_cfb8(),
+        nid::AES_256_GCM => ffi::EVP_aes_256_gcm(),
+        nid::AES_256_CCM => ffi::EVP_aes_256_ccm(),
+        nid::BF_CBC => ffi::EVP_bf_cbc(),
+        nid::BF_ECB => ffi::EVP_bf_ecb(),
+        nid::BF_CFB64 => ffi::EVP_bf_cfb64(),
+        nid::BF_OFB64 => ffi::EVP_bf_ofb(),
+        nid::DES_CBC => ffi::EVP_des_cbc(),
+        nid::DES_ECB => ffi::EVP_des_ecb(),
+        nid::RC4 => ffi::EVP_rc4(),
+        #[cfg(all(ossl110, feature = "v110"))]
+        nid::CHACHA20 => ffi::EVP_chacha20(),
+        #[cfg(all(ossl110, feature = "v110"))]
+        nid::CHACHA20_POLY1305 => ffi::EVP_chacha20_poly1305(),
+        _ => ptr::null(),
+    }
+}
+
+unsafe fn cipher_by_nid(nid: Nid) -> *const ffi::EVP_CIPHER {
+    let ptr = builtin_cipher(nid);
+    if !ptr.is_null() {
+        return ptr;
+    }
+    ffi::init();
+    let name = ffi::OBJ_nid2sn(nid.as_raw());
+    if name.is_null() {
+        ffi::ERR_clear_error();
+        return ptr::null();
+    }
+    ffi::EVP_get_cipherbyname(name)
 }
 
 /// Represents a symmetric cipher context.
+///
+/// Data can be fed to a `Crypter` incrementally with `update`, which makes it suitable for
+/// inputs which are too large to be buffered in memory, unlike the one-shot `encrypt` and
+/// `decrypt` functions. Once the final block has been produced with `finalize`, `reset` prepares
+/// the context for another message with the same key.
+///
+/// # Examples
+///
+/// ```
+/// use openssl::symm::{Cipher, Crypter, Mode};
+///
+/// let cipher = Cipher::aes_128_cbc();
+/// let key = b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F";
+/// let iv = b"\x00\x01\x02\x03\x04\x05\x06\x07\x00\x01\x02\x03\x04\x05\x06\x07";
+/// let chunks: [&[u8]; 3] = [b"Some Crypto", b" Text split across", b" several chunks"];
+///
+/// let mut crypter = Crypter::new(cipher, Mode::Encrypt, key, Some(iv)).unwrap();
+/// let mut ciphertext = vec![];
+/// let mut buf = vec![0; 64 + cipher.block_size()];
+/// for chunk in &chunks {
+///     let count = crypter.update(chunk, &mut buf).unwrap();
+///     ciphertext.extend_from_slice(&buf[..count]);
+/// }
+/// let rest = crypter.finalize(&mut buf).unwrap();
+/// ciphertext.extend_from_slice(&buf[..rest]);
+///
+/// let plaintext = chunks.concat();
+/// assert_eq!(ciphertext.len(), 48);
+/// assert_eq!(ciphertext, openssl::symm::encrypt(cipher, key, Some(iv), &plaintext).unwrap());
+/// ```
 pub struct Crypter {
     ctx: *mut ffi::EVP_CIPHER_CTX,
     block_size: usize,
+    // The IV length the context is currently configured for, which differs from the cipher's
+    // default once a variable length IV has been set.
+    iv_len: Option<usize>,
+    variable_iv_len: bool,
+    decrypt: bool,
+    padding: bool,
+    input_len: u64,
 }
 
 impl Crypter {
     /// Creates a new `Crypter`.
     ///
+    /// An error is returned if the length of `key` is not supported by the cipher, or if the
+    /// length of `iv` differs from `Cipher::iv_len` and the cipher, unlike AEAD ciphers such as
+    /// AES GCM, does not support other lengths.
+    ///
     /// # Panics
     ///
     /// Panics if an IV is required by the cipher but not provided.
@@ -169,6 +442,14 @@ impl Crypter {
                key: &[u8],
                iv: Option<&[u8]>)
                -> Result<Crypter, ErrorStack> {
+        let mut crypter = try!(Crypter::new_uninit(t, mode));
+        try!(crypter.init_key(mode, key, iv));
+        Ok(crypter)
+    }
+
+    // Selects the cipher without keying it, so that parameters which OpenSSL reads when the key is
+    // set can still be adjusted.
+    fn new_uninit(t: Cipher, mode: Mode) -> Result<Crypter, ErrorStack> {
         ffi::init();
 
         unsafe {
@@ -176,11 +457,14 @@ impl Crypter {
             let crypter = Crypter {
                 ctx: ctx,
                 block_size: t.block_size(),
-            };
-
-            let mode = match mode {
-                Mode::Encrypt => 1,
-                Mode::Decrypt => 0,
+                iv_len: t.iv_len(),
+                variable_iv_len: t.has_variable_iv_len(),
+                decrypt: match mode {
+                    Mode::Encrypt => false,
+                    Mode::Decrypt => true,
+                },
+                padding: true,
+                input_len: 0,
             };
 
             try!(cvt(ffi::EVP_CipherInit_ex(crypter.ctx,
@@ -188,34 +472,100 @@ impl Crypter {
                                              ptr::null_mut(),
                                              ptr::null_mut(),
                                              ptr::null_mut(),
-                                             mode)));
+                                             mode.as_raw())));
+
+            Ok(crypter)
+        }
+    }
 
+    fn init_key(&mut self, mode: Mode, key: &[u8], iv: Option<&[u8]>) -> Result<(), ErrorStack> {
+        unsafe {
             assert!(key.len() <= c_int::max_value() as usize);
-            try!(cvt(ffi::EVP_CIPHER_CTX_set_key_length(crypter.ctx, key.len() as c_int)));
+            try!(cvt(ffi::EVP_CIPHER_CTX_set_key_length(self.ctx, key.len() as c_int)));
 
             let key = key.as_ptr() as *mut _;
-            let iv = match (iv, t.iv_len()) {
-                (Some(iv), Some(len)) => {
-                    if iv.len() != len {
-                        assert!(iv.len() <= c_int::max_value() as usize);
-                        try!(cvt(ffi::EVP_CIPHER_CTX_ctrl(crypter.ctx,
+            let iv = try!(self.prepare_iv(iv));
+            cvt(ffi::EVP_CipherInit_ex(self.ctx,
+                                       ptr::null(),
+                                       ptr::null_mut(),
+                                       key,
+                                       iv,
+                                       mode.as_raw()))
+                .map(|_| ())
+        }
+    }
+
+    // Creates a `Crypter` for an AES CCM operation with a tag of `tag_len` bytes. The tag length
+    // is fixed when the key is set, so it can't be changed on a `Crypter` from `new`.
+    fn new_ccm(t: Cipher,
+               mode: Mode,
+               key: &[u8],
+               iv: Option<&[u8]>,
+               tag_len: usize)
+               -> Result<Crypter, ErrorStack> {
+        let mut crypter = try!(Crypter::new_uninit(t, mode));
+        unsafe {
+            assert!(tag_len <= c_int::max_value() as usize);
+            // NB: this constant is actually more general than just GCM.
+            try!(cvt(ffi::EVP_CIPHER_CTX_ctrl(crypter.ctx,
+                                              ffi::EVP_CTRL_GCM_SET_TAG,
+                                              tag_len as c_int,
+                                              ptr::null_mut())));
+        }
+        try!(crypter.init_key(mode, key, iv));
+        Ok(crypter)
+    }
+
+    // Checks the length of `iv`, adjusting the context if the cipher supports a non-default
+    // length, and returns the pointer to pass to `EVP_CipherInit_ex`.
+    fn prepare_iv(&mut self, iv: Option<&[u8]>) -> Result<*mut c_uchar, ErrorStack> {
+        match (iv, self.iv_len) {
+            (Some(iv), Some(len)) => {
+                if iv.len() != len && !self.variable_iv_len {
+                    ::put_error(::R_INVALID_IV_LENGTH,
+                                &format!("an IV of {} bytes is required for this cipher", len));
+                    return Err(ErrorStack::get());
+                } else if iv.len() != len {
+                    assert!(iv.len() <= c_int::max_value() as usize);
+                    unsafe {
+                        try!(cvt(ffi::EVP_CIPHER_CTX_ctrl(self.ctx,
                                                           ffi::EVP_CTRL_GCM_SET_IVLEN,
                                                           iv.len() as c_int,
                                                           ptr::null_mut())));
                     }
-                    iv.as_ptr() as *mut _
+                    self.iv_len = Some(iv.len());
                 }
-                (Some(_), None) | (None, None) => ptr::null_mut(),
-                (None, Some(_)) => panic!("an IV is required for this cipher"),
-            };
-            try!(cvt(ffi::EVP_CipherInit_ex(crypter.ctx,
+                Ok(iv.as_ptr() as *mut _)
+            }
+            (Some(_), None) | (None, None) => Ok(ptr::null_mut()),
+            (None, Some(_)) => panic!("an IV is required for this cipher"),
+        }
+    }
+
+    /// Resets the crypter so that it can process a new message with the same key.
+    ///
+    /// The expanded key schedule is retained, so this is considerably cheaper than creating a new
+    /// `Crypter` when many short messages are encrypted or decrypted with a single key. Only the
+    /// IV is replaced, and the padding setting is preserved.
+    ///
+    /// An error is returned if the length of `iv` is not supported by the cipher. See
+    /// `Crypter::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an IV is required by the cipher but not provided.
+    pub fn reset(&mut self, iv: Option<&[u8]>) -> Result<(), ErrorStack> {
+        unsafe {
+            let iv = try!(self.prepare_iv(iv));
+            // A null key and an `enc` of -1 keep the existing key schedule and direction.
+            try!(cvt(ffi::EVP_CipherInit_ex(self.ctx,
                                             ptr::null(),
                                             ptr::null_mut(),
-                                            key,
+                                            ptr::null_mut(),
                                             iv,
-                                            mode)));
-
-            Ok(crypter)
+                                            -1)));
+            self.input_len = 0;
+            Ok(())
         }
     }
 
@@ -227,11 +577,13 @@ impl Crypter {
         unsafe {
             ffi::EVP_CIPHER_CTX_set_padding(self.ctx, padding as c_int);
         }
+        self.padding = padding;
     }
 
     /// Sets the tag used to authenticate ciphertext in AEAD ciphers such as AES GCM.
     ///
     /// When decrypting cipher text using an AEAD cipher, this must be called before `finalize`.
+    /// With AES CCM, it must instead be called before the first call to `update`.
     pub fn set_tag(&mut self, tag: &[u8]) -> Result<(), ErrorStack> {
         unsafe {
             assert!(tag.len() <= c_int::max_value() as usize);
@@ -244,6 +596,24 @@ impl Crypter {
         }
     }
 
+    /// Feeds the total plaintext or ciphertext length to the cipher.
+    ///
+    /// This is required by AES CCM, and must be called before `aad_update` and `update`. A
+    /// `Crypter` created with `new` always uses 12 byte tags in CCM mode; `encrypt_aead` and
+    /// `decrypt_aead` support other tag lengths.
+    pub fn set_data_len(&mut self, data_len: usize) -> Result<(), ErrorStack> {
+        unsafe {
+            assert!(data_len <= c_int::max_value() as usize);
+            let mut len = 0;
+            cvt(ffi::EVP_CipherUpdate(self.ctx,
+                                      ptr::null_mut(),
+                                      &mut len,
+                                      ptr::null_mut(),
+                                      data_len as c_int))
+                .map(|_| ())
+        }
+    }
+
     /// Feeds Additional Authenticated Data (AAD) through the cipher.
     ///
     /// This can only be used with AEAD ciphers such as AES GCM. Data fed in is not encrypted, but
@@ -285,11 +655,40 @@ impl Crypter {
                                            &mut outl,
                                            input.as_ptr(),
                                            inl)));
+            self.input_len += input.len() as u64;
 
             Ok(outl as usize)
         }
     }
 
+    /// Feeds `data` through the cipher, overwriting it with the encrypted/decrypted bytes.
+    ///
+    /// This avoids allocating a separate output buffer, and is only supported by ciphers which
+    /// produce exactly as many bytes of output as they consume, such as stream ciphers and
+    /// counter based modes like AES CTR, AES GCM or ChaCha20-Poly1305.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cipher's block size is not 1, or if `data.len() > c_int::max_value()`.
+    pub fn update_in_place(&mut self, data: &mut [u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            assert!(self.block_size == 1, "in-place operation requires a block size of 1");
+            assert!(data.len() <= c_int::max_value() as usize);
+            let mut outl = data.len() as c_int;
+            let inl = data.len() as c_int;
+
+            try!(cvt(ffi::EVP_CipherUpdate(self.ctx,
+                                           data.as_mut_ptr(),
+                                           &mut outl,
+                                           data.as_ptr(),
+                                           inl)));
+            debug_assert_eq!(outl as usize, data.len());
+            self.input_len += data.len() as u64;
+
+            Ok(())
+        }
+    }
+
     /// Finishes the encryption/decryption process, writing any remaining data
     /// to `output`.
     ///
@@ -311,6 +710,36 @@ impl Crypter {
         }
     }
 
+    /// Like `finalize`, but reports why the final block could not be produced.
+    ///
+    /// Failures of block cipher modes such as AES CBC are classified by the total length of the
+    /// input: an input which is not a whole number of blocks, or an empty ciphertext when
+    /// padding is enabled, is reported as `FinalizeError::TruncatedInput`, while any other
+    /// failure to decrypt padded data is reported as `FinalizeError::BadPadding`.
+    ///
+    /// Note that revealing a `BadPadding` failure to a remote party, even through timing, can
+    /// allow it to decrypt messages. Unauthenticated ciphertext should generally be rejected
+    /// without distinguishing the two cases.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` is less than the cipher's block size.
+    pub fn finalize_into(&mut self, output: &mut [u8]) -> Result<usize, FinalizeError> {
+        let block_size = self.block_size as u64;
+        self.finalize(output).map_err(|e| {
+            if block_size <= 1 {
+                FinalizeError::Other(e)
+            } else if self.input_len % block_size != 0 ||
+                      (self.decrypt && self.padding && self.input_len == 0) {
+                FinalizeError::TruncatedInput(e)
+            } else if self.decrypt && self.padding {
+                FinalizeError::BadPadding(e)
+            } else {
+                FinalizeError::Other(e)
+            }
+        })
+    }
+
     /// Retrieves the authentication tag used to authenticate ciphertext in AEAD ciphers such
     /// as AES GCM.
     ///
@@ -331,6 +760,67 @@ impl Crypter {
     }
 }
 
+/// An error returned by `Crypter::finalize_into`.
+#[derive(Debug)]
+pub enum FinalizeError {
+    /// The padding of the decrypted data was invalid.
+    ///
+    /// This is typically caused by an incorrect key or IV, or by corrupted ciphertext.
+    BadPadding(ErrorStack),
+    /// The input ended in the middle of a block.
+    TruncatedInput(ErrorStack),
+    /// The final block could not be produced for another reason, such as a tag mismatch in an
+    /// AEAD cipher.
+    Other(ErrorStack),
+}
+
+impl FinalizeError {
+    /// Returns the OpenSSL errors describing the failure.
+    pub fn error_stack(&self) -> &ErrorStack {
+        match *self {
+            FinalizeError::BadPadding(ref e) |
+            FinalizeError::TruncatedInput(ref e) |
+            FinalizeError::Other(ref e) => e,
+        }
+    }
+}
+
+impl fmt::Display for FinalizeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FinalizeError::BadPadding(_) => fmt.write_str("bad padding"),
+            FinalizeError::TruncatedInput(_) => {
+                fmt.write_str("input is not a whole number of blocks")
+            }
+            FinalizeError::Other(ref e) => fmt::Display::fmt(e, fmt),
+        }
+    }
+}
+
+impl error::Error for FinalizeError {
+    fn description(&self) -> &str {
+        match *self {
+            FinalizeError::BadPadding(_) => "bad padding",
+            FinalizeError::TruncatedInput(_) => "truncated input",
+            FinalizeError::Other(ref e) => error::Error::description(e),
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        Some(self.error_stack())
+    }
+}
+
+impl From<FinalizeError> for ErrorStack {
+    fn from(e: FinalizeError) -> ErrorStack {
+        match e {
+            FinalizeError::BadPadding(e) |
+            FinalizeError::TruncatedInput(e) |
+            FinalizeError::Other(e) => e,
+        }
+    }
+}
+
 impl Drop for Crypter {
     fn drop(&mut self) {
         unsafe {
@@ -392,7 +882,13 @@ pub fn encrypt_aead(t: Cipher,
                     data: &[u8],
                     tag: &mut [u8])
                     -> Result<Vec<u8>, ErrorStack> {
-    let mut c = try!(Crypter::new(t, Mode::Encrypt, key, iv));
+    let mut c = if t.is_ccm() {
+        let mut c = try!(Crypter::new_ccm(t, Mode::Encrypt, key, iv, tag.len()));
+        try!(c.set_data_len(data.len()));
+        c
+    } else {
+        try!(Crypter::new(t, Mode::Encrypt, key, iv))
+    };
     let mut out = vec![0; data.len() + t.block_size()];
     try!(c.aad_update(aad));
     let count = try!(c.update(data, &mut out));
@@ -413,23 +909,91 @@ pub fn decrypt_aead(t: Cipher,
                     data: &[u8],
                     tag: &[u8])
                     -> Result<Vec<u8>, ErrorStack> {
-    let mut c = try!(Crypter::new(t, Mode::Decrypt, key, iv));
     let mut out = vec![0; data.len() + t.block_size()];
+
+    let count = if t.is_ccm() {
+        // CCM authenticates the message as part of the single call to update.
+        let mut c = try!(Crypter::new_ccm(t, Mode::Decrypt, key, iv, tag.len()));
+        try!(c.set_tag(tag));
+        try!(c.set_data_len(data.len()));
+        try!(c.aad_update(aad));
+        try!(c.update(data, &mut out))
+    } else {
+        let mut c = try!(Crypter::new(t, Mode::Decrypt, key, iv));
+        try!(c.aad_update(aad));
+        let count = try!(c.update(data, &mut out));
+        try!(c.set_tag(tag));
+        let rest = try!(c.finalize(&mut out[count..]));
+        count + rest
+    };
+    out.truncate(count);
+    Ok(out)
+}
+
+/// Like `encrypt_aead`, but encrypts `data` in place rather than allocating an output buffer.
+///
+/// # Panics
+///
+/// Panics if the block size of the cipher is not 1. See `Crypter::update_in_place`.
+pub fn encrypt_aead_in_place(t: Cipher,
+                             key: &[u8],
+                             iv: Option<&[u8]>,
+                             aad: &[u8],
+                             data: &mut [u8],
+                             tag: &mut [u8])
+                             -> Result<(), ErrorStack> {
+    let mut c = if t.is_ccm() {
+        let mut c = try!(Crypter::new_ccm(t, Mode::Encrypt, key, iv, tag.len()));
+        try!(c.set_data_len(data.len()));
+        c
+    } else {
+        try!(Crypter::new(t, Mode::Encrypt, key, iv))
+    };
     try!(c.aad_update(aad));
-    let count = try!(c.update(data, &mut out));
+    try!(c.update_in_place(data));
+    let mut rest = [0; 1];
+    try!(c.finalize(&mut rest));
+    c.get_tag(tag)
+}
+
+/// Like `decrypt_aead`, but decrypts `data` in place rather than allocating an output buffer.
+///
+/// If authentication fails, `data` will contain the unauthenticated plaintext and must not be
+/// used.
+///
+/// # Panics
+///
+/// Panics if the block size of the cipher is not 1. See `Crypter::update_in_place`.
+pub fn decrypt_aead_in_place(t: Cipher,
+                             key: &[u8],
+                             iv: Option<&[u8]>,
+                             aad: &[u8],
+                             data: &mut [u8],
+                             tag: &[u8])
+                             -> Result<(), ErrorStack> {
+    if t.is_ccm() {
+        let mut c = try!(Crypter::new_ccm(t, Mode::Decrypt, key, iv, tag.len()));
+        try!(c.set_tag(tag));
+        try!(c.set_data_len(data.len()));
+        try!(c.aad_update(aad));
+        return c.update_in_place(data);
+    }
+    let mut c = try!(Crypter::new(t, Mode::Decrypt, key, iv));
+    try!(c.aad_update(aad));
+    try!(c.update_in_place(data));
     try!(c.set_tag(tag));
-    let rest = try!(c.finalize(&mut out[count..]));
-    out.truncate(count + rest);
-    Ok(out)
+    let mut rest = [0; 1];
+    try!(c.finalize(&mut rest));
+    Ok(())
 }
 
 #[cfg(ossl110)]
-use ffi::{EVP_CIPHER_iv_length, EVP_CIPHER_block_size, EVP_CIPHER_key_length};
+use ffi::{EVP_CIPHER_iv_length, EVP_CIPHER_block_size, EVP_CIPHER_key_length, EVP_CIPHER_flags};
 
 #[cfg(ossl10x)]
 #[allow(bad_style)]
 mod compat {
-    use libc::c_int;
+    use libc::{c_int, c_ulong};
     use ffi::EVP_CIPHER;
 
     pub unsafe fn EVP_CIPHER_iv_length(ptr: *const EVP_CIPHER) -> c_int {
@@ -443,6 +1007,10 @@ mod compat {
     pub unsafe fn EVP_CIPHER_key_length(ptr: *const EVP_CIPHER) -> c_int {
         (*ptr).key_len
     }
+
+    pub unsafe fn EVP_CIPHER_flags(ptr: *const EVP_CIPHER) -> c_ulong {
+        (*ptr).flags
+    }
 }
 #[cfg(ossl10x)]
 use self::compat::*;
@@ -780,6 +1348,121 @@ mod tests {
         assert_eq!(pt, out.to_hex());
     }
 
+    #[test]
+    fn test_aes128_ccm() {
+        // Example 1 from NIST SP 800-38C.
+        let key = "404142434445464748494a4b4c4d4e4f";
+        let nonce = "10111213141516";
+        let aad = "0001020304050607";
+        let pt = "20212223";
+        let ct = "7162015b";
+        let tag = "4dac255d";
+
+        let mut actual_tag = [0; 4];
+        let out = encrypt_aead(Cipher::aes_128_ccm(),
+                               &Vec::from_hex(key).unwrap(),
+                               Some(&Vec::from_hex(nonce).unwrap()),
+                               &Vec::from_hex(aad).unwrap(),
+                               &Vec::from_hex(pt).unwrap(),
+                               &mut actual_tag)
+            .unwrap();
+        assert_eq!(ct, out.to_hex());
+        assert_eq!(tag, actual_tag.to_hex());
+
+        let out = decrypt_aead(Cipher::aes_128_ccm(),
+                               &Vec::from_hex(key).unwrap(),
+                               Some(&Vec::from_hex(nonce).unwrap()),
+                               &Vec::from_hex(aad).unwrap(),
+                               &Vec::from_hex(ct).unwrap(),
+                               &Vec::from_hex(tag).unwrap())
+            .unwrap();
+        assert_eq!(pt, out.to_hex());
+    }
+
+    #[test]
+    fn test_aes256_ccm_verify_fail() {
+        let key = [0x42; 32];
+        let nonce = [0x24; 12];
+        let aad = b"header";
+        let pt = b"some secret message";
+
+        let mut tag = [0; 16];
+        let mut ct = encrypt_aead(Cipher::aes_256_ccm(), &key, Some(&nonce), aad, pt, &mut tag)
+            .unwrap();
+        let out = decrypt_aead(Cipher::aes_256_ccm(), &key, Some(&nonce), aad, &ct, &tag)
+            .unwrap();
+        assert_eq!(&out[..], &pt[..]);
+
+        ct[0] ^= 1;
+        assert!(decrypt_aead(Cipher::aes_256_ccm(), &key, Some(&nonce), aad, &ct, &tag).is_err());
+
+        let mut data = pt.to_vec();
+        encrypt_aead_in_place(Cipher::aes_256_ccm(), &key, Some(&nonce), aad, &mut data, &mut tag)
+            .unwrap();
+        decrypt_aead_in_place(Cipher::aes_256_ccm(), &key, Some(&nonce), aad, &mut data, &tag)
+            .unwrap();
+        assert_eq!(&data[..], &pt[..]);
+    }
+
+    #[test]
+    fn test_aes128_gcm_in_place() {
+        let key = Vec::from_hex("feffe9928665731c6d6a8f9467308308").unwrap();
+        let iv = Vec::from_hex("cafebabefacedbaddecaf888").unwrap();
+        let aad = Vec::from_hex("feedfacedeadbeeffeedfacedeadbeefabaddad2").unwrap();
+        let pt = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809\
+                  532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+        let ct = "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c\
+                  7d8f6a5aac84aa051ba30b396a0aac973d58e091";
+        let tag = "5bc94fbc3221a5db94fae95ae7121a47";
+
+        let mut data = Vec::from_hex(pt).unwrap();
+        let mut actual_tag = [0; 16];
+        encrypt_aead_in_place(Cipher::aes_128_gcm(),
+                              &key,
+                              Some(&iv),
+                              &aad,
+                              &mut data,
+                              &mut actual_tag)
+            .unwrap();
+        assert_eq!(ct, data.to_hex());
+        assert_eq!(tag, actual_tag.to_hex());
+
+        decrypt_aead_in_place(Cipher::aes_128_gcm(),
+                              &key,
+                              Some(&iv),
+                              &aad,
+                              &mut data,
+                              &actual_tag)
+            .unwrap();
+        assert_eq!(pt, data.to_hex());
+
+        data[0] ^= 1;
+        assert!(decrypt_aead_in_place(Cipher::aes_128_gcm(),
+                                      &key,
+                                      Some(&iv),
+                                      &aad,
+                                      &mut data,
+                                      &actual_tag)
+            .is_err());
+    }
+
+    #[test]
+    fn test_aes128_ctr_update_in_place() {
+        let key = Vec::from_hex("2B7E151628AED2A6ABF7158809CF4F3C").unwrap();
+        let iv = Vec::from_hex("F0F1F2F3F4F5F6F7F8F9FAFBFCFDFEFF").unwrap();
+        let pt = "6BC1BEE22E409F96E93D7E117393172AAE2D8A571E03AC9C9EB76FAC45AF8E5130C81C46A35CE411\
+                  E5FBC1191A0A52EF";
+        let ct = "874D6191B620E3261BEF6864990DB6CE9806F66B7970FDFF8617187BB9FFFDFF5AE4DF3EDBD5D35E\
+                  5B4F09020DB03EAB";
+
+        let mut data = Vec::from_hex(pt).unwrap();
+        let mut c = Crypter::new(Cipher::aes_128_ctr(), Mode::Encrypt, &key, Some(&iv)).unwrap();
+        let (first, second) = data.split_at_mut(13);
+        c.update_in_place(first).unwrap();
+        c.update_in_place(second).unwrap();
+        assert_eq!(ct, data.to_hex().to_uppercase());
+    }
+
     #[test]
     #[cfg(all(ossl110, feature = "v110"))]
     fn test_chacha20() {
@@ -827,4 +1510,251 @@ mod tests {
             .unwrap();
         assert_eq!(pt, out.to_hex());
     }
+
+    #[test]
+    fn test_reset() {
+        let key = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let ivs = [Vec::from_hex("00000000000000000000000000000000").unwrap(),
+                   Vec::from_hex("0f0e0d0c0b0a09080706050403020100").unwrap()];
+        let messages: [&[u8]; 2] = [b"first message", b"a somewhat longer second message"];
+
+        let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key, Some(&ivs[0]))
+            .unwrap();
+        for (i, (iv, msg)) in ivs.iter().zip(messages.iter()).enumerate() {
+            if i > 0 {
+                crypter.reset(Some(iv)).unwrap();
+            }
+            let mut out = vec![0; msg.len() + 16];
+            let mut len = crypter.update(msg, &mut out).unwrap();
+            len += crypter.finalize(&mut out[len..]).unwrap();
+            out.truncate(len);
+
+            assert_eq!(out, encrypt(Cipher::aes_128_cbc(), &key, Some(iv), msg).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_invalid_key_len() {
+        let key = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let iv = Vec::from_hex("00000000000000000000000000000000").unwrap();
+
+        assert!(Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key[..15], Some(&iv)).is_err());
+        assert!(Crypter::new(Cipher::aes_256_cbc(), Mode::Encrypt, &key, Some(&iv)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_iv_len() {
+        let key = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let iv = Vec::from_hex("0f0e0d0c0b0a09080706050403020100ffff").unwrap();
+
+        assert!(Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key, Some(&iv[..8])).is_err());
+        assert!(Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key, Some(&iv)).is_err());
+
+        let mut c = Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key, Some(&iv[..16]))
+            .unwrap();
+        assert!(c.reset(Some(&iv)).is_err());
+    }
+
+    #[test]
+    fn test_update_chunks() {
+        let key = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let iv = Vec::from_hex("0f0e0d0c0b0a09080706050403020100").unwrap();
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+
+        for &mode in &[Mode::Encrypt, Mode::Decrypt] {
+            let input = match mode {
+                Mode::Encrypt => data.clone(),
+                Mode::Decrypt => encrypt(Cipher::aes_128_cbc(), &key, Some(&iv), &data).unwrap(),
+            };
+
+            let mut crypter = Crypter::new(Cipher::aes_128_cbc(), mode, &key, Some(&iv)).unwrap();
+            let mut out = vec![];
+            let mut buf = [0; 64 + 16];
+            for chunk in input.chunks(63) {
+                let count = crypter.update(chunk, &mut buf).unwrap();
+                out.extend_from_slice(&buf[..count]);
+            }
+            let rest = crypter.finalize(&mut buf).unwrap();
+            out.extend_from_slice(&buf[..rest]);
+
+            let expected = match mode {
+                Mode::Encrypt => encrypt(Cipher::aes_128_cbc(), &key, Some(&iv), &data).unwrap(),
+                Mode::Decrypt => data.clone(),
+            };
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn test_finalize_into() {
+        let key = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let iv = Vec::from_hex("0f0e0d0c0b0a09080706050403020100").unwrap();
+        let ct = encrypt(Cipher::aes_128_cbc(), &key, Some(&iv), b"some plaintext").unwrap();
+        let mut out = vec![0; ct.len() + 16];
+
+        let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Decrypt, &key, Some(&iv))
+            .unwrap();
+        let mut len = crypter.update(&ct, &mut out).unwrap();
+        len += crypter.finalize_into(&mut out[len..]).unwrap();
+        assert_eq!(&out[..len], b"some plaintext");
+
+        crypter.reset(Some(&iv)).unwrap();
+        crypter.update(&ct[..ct.len() - 1], &mut out).unwrap();
+        match crypter.finalize_into(&mut out) {
+            Err(FinalizeError::TruncatedInput(_)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        crypter.reset(Some(&iv)).unwrap();
+        match crypter.finalize_into(&mut out) {
+            Err(FinalizeError::TruncatedInput(_)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        let wrong_key = Vec::from_hex("0f0e0d0c0b0a09080706050403020100").unwrap();
+        let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Decrypt, &wrong_key, Some(&iv))
+            .unwrap();
+        // The last byte of the decryption of a block under an unrelated key is a valid padding
+        // length of 1 with probability 1/256, so look for a ciphertext which fails.
+        let mut modified = ct.clone();
+        loop {
+            crypter.reset(Some(&iv)).unwrap();
+            crypter.update(&modified, &mut out).unwrap();
+            match crypter.finalize_into(&mut out) {
+                Err(FinalizeError::BadPadding(_)) => break,
+                Ok(_) => modified[0] = modified[0].wrapping_add(1),
+                r => panic!("unexpected result {:?}", r),
+            }
+        }
+
+        let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key, Some(&iv))
+            .unwrap();
+        crypter.pad(false);
+        crypter.update(b"not a block", &mut out).unwrap();
+        match crypter.finalize_into(&mut out) {
+            Err(FinalizeError::TruncatedInput(_)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_reset_gcm() {
+        let key = Vec::from_hex("feffe9928665731c6d6a8f9467308308").unwrap();
+        let iv = Vec::from_hex("cafebabefacedbaddecaf888").unwrap();
+        let iv2 = Vec::from_hex("cafebabefacedbaddecaf889").unwrap();
+        let pt = b"some plaintext";
+
+        let mut crypter = Crypter::new(Cipher::aes_128_gcm(), Mode::Encrypt, &key, Some(&iv2))
+            .unwrap();
+        let mut out = vec![0; pt.len() + 16];
+        crypter.update(pt, &mut out).unwrap();
+        crypter.finalize(&mut out).unwrap();
+
+        crypter.reset(Some(&iv)).unwrap();
+        crypter.aad_update(b"aad").unwrap();
+        let mut len = crypter.update(pt, &mut out).unwrap();
+        len += crypter.finalize(&mut out[len..]).unwrap();
+        out.truncate(len);
+        let mut tag = [0; 16];
+        crypter.get_tag(&mut tag).unwrap();
+
+        let mut expected_tag = [0; 16];
+        let expected = encrypt_aead(Cipher::aes_128_gcm(), &key, Some(&iv), b"aad", pt,
+                                    &mut expected_tag)
+            .unwrap();
+        assert_eq!(out, expected);
+        assert_eq!(tag, expected_tag);
+    }
+
+    #[test]
+    fn test_reset_gcm_iv_len() {
+        let key = Vec::from_hex("feffe9928665731c6d6a8f9467308308").unwrap();
+        let long_iv = [0x42; 16];
+        let iv = Vec::from_hex("cafebabefacedbaddecaf888").unwrap();
+        let pt = b"some plaintext";
+
+        let mut crypter = Crypter::new(Cipher::aes_128_gcm(), Mode::Encrypt, &key, Some(&long_iv))
+            .unwrap();
+        let mut out = vec![0; pt.len() + 16];
+        crypter.update(pt, &mut out).unwrap();
+        crypter.finalize(&mut out).unwrap();
+
+        crypter.reset(Some(&iv)).unwrap();
+        let mut len = crypter.update(pt, &mut out).unwrap();
+        len += crypter.finalize(&mut out[len..]).unwrap();
+        out.truncate(len);
+        let mut tag = [0; 16];
+        crypter.get_tag(&mut tag).unwrap();
+
+        let mut expected_tag = [0; 16];
+        let expected = encrypt_aead(Cipher::aes_128_gcm(), &key, Some(&iv), &[], pt,
+                                    &mut expected_tag)
+            .unwrap();
+        assert_eq!(out, expected);
+        assert_eq!(tag, expected_tag);
+    }
+
+    #[test]
+    fn match_cipher() {
+        use nid;
+
+        fn key_bits(cipher: Cipher) -> Option<usize> {
+            match cipher {
+                super::AES_128_GCM => Some(128),
+                super::AES_256_GCM => Some(256),
+                _ => None,
+            }
+        }
+
+        assert_eq!(key_bits(Cipher::aes_256_gcm()), Some(256));
+        assert_eq!(key_bits(Cipher::from_nid(nid::AES_128_GCM).unwrap()), Some(128));
+        assert_eq!(key_bits(Cipher::aes_128_cbc()), None);
+        assert_eq!(Cipher::aes_128_cbc().nid(), nid::AES_128_CBC);
+        unsafe {
+            assert_eq!(Cipher::from_ptr(Cipher::aes_128_cbc().as_ptr()), super::AES_128_CBC);
+        }
+        assert_eq!(Cipher::aes_128_cbc().key_len(), 16);
+        assert!(Cipher::from_nid(nid::SHA256).is_none());
+
+        // ciphers without a constant keep the implementation they were resolved to
+        let cipher = Cipher::from_nid(nid::AES_192_CBC).unwrap();
+        assert_eq!(key_bits(cipher), None);
+        assert_eq!(cipher.key_len(), 24);
+        unsafe {
+            assert_eq!(Cipher::from_ptr(cipher.as_ptr()), cipher);
+        }
+    }
+
+    #[test]
+    fn from_algorithm_identifier() {
+        use x509::AlgorithmIdentifier;
+
+        let iv = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let der = Vec::from_hex("301d060960864801650304010204100001\
+                                 02030405060708090a0b0c0d0e0f")
+            .unwrap();
+        let alg = AlgorithmIdentifier::from_der(&der).unwrap();
+        let (cipher, params) = Cipher::from_algorithm_identifier(&alg).unwrap().unwrap();
+        assert!(cipher == Cipher::aes_128_cbc());
+        assert_eq!(params.iv, Some(iv));
+        assert_eq!(params.key_len, 16);
+
+        // aes-256-ecb has no parameters
+        let der = Vec::from_hex("300b0609608648016503040129").unwrap();
+        let alg = AlgorithmIdentifier::from_der(&der).unwrap();
+        let (cipher, params) = Cipher::from_algorithm_identifier(&alg).unwrap().unwrap();
+        assert!(cipher == Cipher::aes_256_ecb());
+        assert_eq!(params.iv, None);
+        assert_eq!(params.key_len, 32);
+
+        // a truncated IV is rejected
+        let der = Vec::from_hex("3015060960864801650304010204080001020304050607").unwrap();
+        let alg = AlgorithmIdentifier::from_der(&der).unwrap();
+        assert!(Cipher::from_algorithm_identifier(&alg).is_err());
+
+        // sha256 is not a cipher
+        let der = Vec::from_hex("300b0609608648016503040201").unwrap();
+        let alg = AlgorithmIdentifier::from_der(&der).unwrap();
+        assert!(Cipher::from_algorithm_identifier(&alg).unwrap().is_none());
+    }
 }
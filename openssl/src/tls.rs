@@ -0,0 +1,146 @@
+//! TLS clients and servers with secure defaults.
+//!
+//! These functions cover the common cases of connecting to a server with the default certificate
+//! verification and of accepting connections with a certificate and private key. `SslConnector`
+//! and `SslAcceptor` can be used directly when more control is needed.
+
+/// TLS clients.
+pub mod client {
+    use std::io::{Read, Write};
+
+    use ssl::{HandshakeError, SslConnectorBuilder, SslMethod, SslStream};
+
+    /// Connects to the TLS server `domain` over `stream`.
+    ///
+    /// The server's certificate chain is verified against the default trusted certificates, and
+    /// its hostname against `domain`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use openssl::tls;
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("google.com:443").unwrap();
+    /// let mut stream = tls::client::connect("google.com", stream).unwrap();
+    ///
+    /// stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    /// let mut res = vec![];
+    /// stream.read_to_end(&mut res).unwrap();
+    /// ```
+    pub fn connect<S>(domain: &str, stream: S) -> Result<SslStream<S>, HandshakeError<S>>
+        where S: Read + Write
+    {
+        let connector = match SslConnectorBuilder::new(SslMethod::tls()) {
+            Ok(connector) => connector.build(),
+            Err(e) => return Err(HandshakeError::SetupFailure(e)),
+        };
+        connector.connect(domain, stream)
+    }
+}
+
+/// TLS servers.
+pub mod server {
+    use error::ErrorStack;
+    use pkey::PKey;
+    use ssl::{SslAcceptor, SslAcceptorBuilder, SslMethod};
+    use x509::X509;
+
+    /// Creates an acceptor from a PEM encoded certificate chain and private key.
+    ///
+    /// `chain` starts with the server's certificate, which may be followed by intermediate
+    /// certificates. An error with the reason `error::REASON_NO_CERTIFICATES` is returned if it
+    /// contains no certificates. The acceptor uses Mozilla's intermediate configuration (see
+    /// `SslAcceptorBuilder::mozilla_intermediate`), which is compatible with most clients.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use openssl::tls;
+    /// use std::fs::File;
+    /// use std::io::Read;
+    /// use std::net::TcpListener;
+    ///
+    /// let mut chain = vec![];
+    /// File::open("chain.pem").unwrap().read_to_end(&mut chain).unwrap();
+    /// let mut key = vec![];
+    /// File::open("key.pem").unwrap().read_to_end(&mut key).unwrap();
+    /// let acceptor = tls::server::acceptor_from_pem(&chain, &key).unwrap();
+    ///
+    /// let listener = TcpListener::bind("0.0.0.0:8443").unwrap();
+    /// for stream in listener.incoming() {
+    ///     if let Ok(stream) = acceptor.accept(stream.unwrap()) {
+    ///         // ...
+    ///     }
+    /// }
+    /// ```
+    pub fn acceptor_from_pem(chain: &[u8], key: &[u8]) -> Result<SslAcceptor, ErrorStack> {
+        let key = try!(PKey::private_key_from_pem(key));
+        let mut chain = try!(X509::stack_from_pem(chain));
+        if chain.is_empty() {
+            ::put_error(::R_NO_CERTIFICATES, "no certificates were found in the PEM data");
+            return Err(ErrorStack::get());
+        }
+        let cert = chain.remove(0);
+        let acceptor = try!(SslAcceptorBuilder::mozilla_intermediate(SslMethod::tls(),
+                                                                     &key,
+                                                                     &cert,
+                                                                     chain));
+        Ok(acceptor.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use ssl::{Ssl, SslContext, SslMethod};
+    use super::{client, server};
+
+    #[test]
+    fn server_accepts() {
+        let chain = include_bytes!("../test/cert.pem");
+        let key = include_bytes!("../test/key.pem");
+        let acceptor = server::acceptor_from_pem(chain, key).unwrap();
+        assert!(server::acceptor_from_pem(b"", key).is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let guard = thread::spawn(move || {
+            let stream = listener.accept().unwrap().0;
+            let mut stream = acceptor.accept(stream).unwrap();
+            stream.write_all(b"hello").unwrap();
+        });
+
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let ctx = SslContext::builder(SslMethod::tls()).unwrap();
+        let mut stream = Ssl::new(&ctx.build()).unwrap().connect(stream).unwrap();
+        let mut buf = [0; 5];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+        guard.join().unwrap();
+    }
+
+    #[test]
+    fn client_verifies() {
+        let chain = include_bytes!("../test/cert.pem");
+        let key = include_bytes!("../test/key.pem");
+        let acceptor = server::acceptor_from_pem(chain, key).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            let stream = listener.accept().unwrap().0;
+            let _ = acceptor.accept(stream);
+        });
+
+        // the test certificate is not signed by a trusted root
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        assert!(client::connect("foobar.com", stream).is_err());
+    }
+}
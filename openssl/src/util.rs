@@ -3,6 +3,7 @@ use std::any::Any;
 use std::cell::UnsafeCell;
 use std::panic::{self, AssertUnwindSafe};
 use std::slice;
+use ffi;
 
 use error::ErrorStack;
 
@@ -88,7 +89,46 @@ pub unsafe extern fn invoke_passwd_cb<F>(buf: *mut c_char,
     }
 }
 
+/// Key and parameter generation progress callback, passed to `EVP_PKEY_CTX_set_cb`.
+///
+/// The `EVP_PKEY_CTX`'s app data is expected to be a pointer to a `CallbackState`.
+pub unsafe extern fn invoke_pkey_gen_cb<F>(ctx: *mut ffi::EVP_PKEY_CTX) -> c_int
+    where F: FnMut(i32, i32) -> bool
+{
+    let callback = &mut *(ffi::EVP_PKEY_CTX_get_app_data(ctx) as *mut CallbackState<F>);
+    if callback.panic.is_some() {
+        return 0;
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let stage = ffi::EVP_PKEY_CTX_get_keygen_info(ctx, 0);
+        let count = ffi::EVP_PKEY_CTX_get_keygen_info(ctx, 1);
+        callback.cb.as_mut().unwrap()(stage, count)
+    }));
+
+    match result {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(err) => {
+            callback.panic = Some(err);
+            0
+        }
+    }
+}
+
 /// This is intended to be used as the inner type for `FooRef` types converted from raw C pointers.
 /// It has an `UnsafeCell` internally to inform the compiler about aliasability and doesn't
 /// implement `Copy`, so it can't be dereferenced.
 pub struct Opaque(UnsafeCell<()>);
+
+/// Frees memory allocated by OpenSSL with `OPENSSL_malloc`.
+#[cfg(not(ossl110))]
+pub unsafe fn crypto_free(p: *mut c_void) {
+    ffi::CRYPTO_free(p);
+}
+
+/// Frees memory allocated by OpenSSL with `OPENSSL_malloc`.
+#[cfg(ossl110)]
+pub unsafe fn crypto_free(p: *mut c_void) {
+    ffi::CRYPTO_free(p, concat!(file!(), "\0").as_ptr() as *const c_char, line!() as c_int);
+}
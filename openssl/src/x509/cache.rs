@@ -0,0 +1,84 @@
+//! Interning of parsed certificates.
+//!
+//! Workloads which see the same certificates over and over, such as TLS inspection or log
+//! processing, can use an `X509Cache` so that each distinct certificate is held in memory only
+//! once. Every lookup returns a new reference to the shared `X509`.
+//!
+//! The cache is opt-in and has no global instance, but it can be shared between threads, so a
+//! single cache can be stored in a `static` (for example with `lazy_static`) and used by the
+//! whole process.
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use error::ErrorStack;
+use hash::MessageDigest;
+use stack::StackRef;
+use x509::{X509, X509Ref};
+
+/// A cache of certificates keyed by the SHA-256 digest of their DER encoding.
+///
+/// Certificates stay in the cache, and so in memory, until `clear` is called or the cache is
+/// dropped.
+pub struct X509Cache(Mutex<HashMap<Vec<u8>, X509>>);
+
+impl X509Cache {
+    /// Creates an empty cache.
+    pub fn new() -> X509Cache {
+        X509Cache(Mutex::new(HashMap::new()))
+    }
+
+    /// Parses a DER encoded certificate, returning the cached copy if it has been seen before.
+    pub fn from_der(&self, der: &[u8]) -> Result<X509, ErrorStack> {
+        let cert = try!(X509::from_der(der));
+        self.intern(&cert)
+    }
+
+    /// Parses a PEM encoded certificate, returning the cached copy if it has been seen before.
+    pub fn from_pem(&self, pem: &[u8]) -> Result<X509, ErrorStack> {
+        let cert = try!(X509::from_pem(pem));
+        self.intern(&cert)
+    }
+
+    /// Returns the cached copy of `cert`, adding it to the cache if it is not already present.
+    pub fn intern(&self, cert: &X509Ref) -> Result<X509, ErrorStack> {
+        let key = try!(cert.fingerprint(MessageDigest::sha256()));
+        Ok(self.lock().entry(key).or_insert_with(|| cert.to_owned()).clone())
+    }
+
+    /// Interns each certificate of a chain, such as one returned by `SslRef::peer_cert_chain`.
+    pub fn intern_chain(&self, chain: &StackRef<X509>) -> Result<Vec<X509>, ErrorStack> {
+        chain.iter().map(|cert| self.intern(cert)).collect()
+    }
+
+    /// Returns the number of certificates in the cache.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Determines if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all certificates from the cache.
+    ///
+    /// Certificates previously returned by the cache remain valid.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    // The map is left consistent by every operation, so a panic in another thread can't have
+    // broken it.
+    fn lock(&self) -> MutexGuard<HashMap<Vec<u8>, X509>> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+impl Default for X509Cache {
+    fn default() -> X509Cache {
+        X509Cache::new()
+    }
+}
@@ -1,40 +1,49 @@
 use ffi;
 use foreign_types::{ForeignType, ForeignTypeRef};
-use libc::{c_char, c_int, c_long, c_ulong};
+use libc::{c_char, c_int, c_long, c_ulong, c_void};
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::error::Error;
 use std::ffi::{CStr, CString};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::mem;
 use std::path::Path;
 use std::ptr;
 use std::slice;
 use std::str;
 
-use {cvt, cvt_p};
-use asn1::{Asn1StringRef, Asn1Time, Asn1TimeRef};
-use bio::MemBioSlice;
+use {cvt, cvt_n, cvt_p};
+use asn1::{Asn1GeneralizedTime, Asn1Integer, Asn1IntegerRef, Asn1ObjectRef, Asn1String,
+           Asn1StringRef, Asn1Time, Asn1TimeRef, Asn1TypeRef};
+use bn::BigNumRef;
+use bio::{MemBio, MemBioSlice};
 use hash::MessageDigest;
+use symm::Cipher;
 use pkey::{PKey, PKeyRef};
 use rand::rand_bytes;
 use error::ErrorStack;
-use nid::Nid;
+use ssl::SslRef;
+use nid::{self, Nid, SignatureAlgorithms};
 use string::OpensslString;
 use stack::{Stack, StackRef, Stackable};
 
 #[cfg(ossl10x)]
-use ffi::{X509_set_notBefore, X509_set_notAfter, ASN1_STRING_data, X509_STORE_CTX_get_chain};
+use ffi::{X509_set_notBefore, X509_set_notAfter, ASN1_STRING_data, X509_STORE_CTX_get_chain,
+          X509_CRL_set_lastUpdate, X509_CRL_set_nextUpdate};
 #[cfg(ossl110)]
 use ffi::{X509_set1_notBefore as X509_set_notBefore, X509_set1_notAfter as X509_set_notAfter,
           ASN1_STRING_get0_data as ASN1_STRING_data,
-          X509_STORE_CTX_get0_chain as X509_STORE_CTX_get_chain};
+          X509_STORE_CTX_get0_chain as X509_STORE_CTX_get_chain,
+          X509_CRL_set1_lastUpdate as X509_CRL_set_lastUpdate,
+          X509_CRL_set1_nextUpdate as X509_CRL_set_nextUpdate};
 
 #[cfg(any(all(feature = "v102", ossl102), all(feature = "v110", ossl110)))]
 pub mod verify;
 
 use x509::extension::{ExtensionType, Extension};
 
+pub mod cache;
 pub mod extension;
 pub mod store;
 
@@ -66,6 +75,15 @@ impl X509StoreContextRef {
         unsafe { X509VerifyError::from_raw(ffi::X509_STORE_CTX_get_error(self.as_ptr()) as c_long) }
     }
 
+    /// Sets the verification error of the context.
+    ///
+    /// Passing `None` clears the error, which allows a verification callback
+    /// to accept a certificate that OpenSSL would otherwise have rejected.
+    pub fn set_error(&mut self, error: Option<X509VerifyError>) {
+        let error = error.map_or(ffi::X509_V_OK, |e| e.as_raw() as c_int);
+        unsafe { ffi::X509_STORE_CTX_set_error(self.as_ptr(), error) }
+    }
+
     pub fn current_cert(&self) -> Option<&X509Ref> {
         unsafe {
             let ptr = ffi::X509_STORE_CTX_get_current_cert(self.as_ptr());
@@ -77,6 +95,23 @@ impl X509StoreContextRef {
         }
     }
 
+    /// Returns the `Ssl` whose peer certificate is being verified, if this context was created
+    /// during a handshake.
+    ///
+    /// This gives a verify callback access to the connection's configuration, such as its
+    /// `servername`, `verify_hostname` and whether it `is_server`.
+    pub fn ssl(&self) -> Option<&SslRef> {
+        unsafe {
+            let idx = ffi::SSL_get_ex_data_X509_STORE_CTX_idx();
+            let ssl = ffi::X509_STORE_CTX_get_ex_data(self.as_ptr(), idx);
+            if ssl.is_null() {
+                None
+            } else {
+                Some(SslRef::from_ptr(ssl as *mut ffi::SSL))
+            }
+        }
+    }
+
     pub fn error_depth(&self) -> u32 {
         unsafe { ffi::X509_STORE_CTX_get_error_depth(self.as_ptr()) as u32 }
     }
@@ -92,6 +127,17 @@ impl X509StoreContextRef {
             Some(StackRef::from_ptr(chain))
         }
     }
+
+    /// Returns owned copies of the certificates in the chain built during verification.
+    ///
+    /// Unlike `chain`, the returned certificates remain valid after the context is freed. The
+    /// vector is empty if no chain has been built.
+    pub fn chain_owned(&self) -> Vec<X509> {
+        match self.chain() {
+            Some(chain) => chain.iter().map(|cert| cert.to_owned()).collect(),
+            None => vec![],
+        }
+    }
 }
 
 #[allow(non_snake_case)]
@@ -123,6 +169,8 @@ pub struct X509Generator {
     days: u32,
     names: Vec<(String, String)>,
     extensions: Extensions,
+    copied_extensions: Vec<(Nid, Vec<u8>)>,
+    raw_extensions: Vec<Vec<u8>>,
     hash_type: MessageDigest,
 }
 
@@ -139,6 +187,8 @@ impl X509Generator {
             days: 365,
             names: vec![],
             extensions: Extensions::new(),
+            copied_extensions: vec![],
+            raw_extensions: vec![],
             hash_type: MessageDigest::sha1(),
         }
     }
@@ -210,6 +260,40 @@ impl X509Generator {
         self
     }
 
+    /// Copies all extensions of an existing certificate into the generated certificate.
+    ///
+    /// This is useful when reissuing or renewing a certificate. Extensions added with
+    /// `add_extension` or `add_extensions` override copied extensions of the same type,
+    /// regardless of the order the methods are called in. Any previously copied extensions are
+    /// replaced.
+    pub fn copy_extensions_from(mut self, cert: &X509Ref) -> Result<X509Generator, ErrorStack> {
+        self.copied_extensions.clear();
+
+        unsafe {
+            for i in 0..ffi::X509_get_ext_count(cert.as_ptr()) {
+                let ext = try!(cvt_p(ffi::X509_get_ext(cert.as_ptr(), i)));
+                let nid = Nid::from_raw(ffi::OBJ_obj2nid(ffi::X509_EXTENSION_get_object(ext)));
+
+                let len = try!(cvt(ffi::i2d_X509_EXTENSION(ext, ptr::null_mut())));
+                let mut der = vec![0; len as usize];
+                try!(cvt(ffi::i2d_X509_EXTENSION(ext, &mut der.as_mut_ptr())));
+                self.copied_extensions.push((nid, der));
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Adds an extension built with `X509Extension::new` to the certificate.
+    ///
+    /// Unlike `add_extension`, this does not replace an existing extension of the same type.
+    pub fn add_x509_extension(mut self,
+                              ext: &X509ExtensionRef)
+                              -> Result<X509Generator, ErrorStack> {
+        self.raw_extensions.push(try!(ext.to_der()));
+        Ok(self)
+    }
+
     pub fn set_sign_hash(mut self, hash_type: MessageDigest) -> X509Generator {
         self.hash_type = hash_type;
         self
@@ -247,6 +331,31 @@ impl X509Generator {
         }
     }
 
+    fn add_copied_extension_internal(x509: *mut ffi::X509, der: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            assert!(der.len() <= c_long::max_value() as usize);
+            let ext = try!(cvt_p(ffi::d2i_X509_EXTENSION(ptr::null_mut(),
+                                                          &mut der.as_ptr(),
+                                                          der.len() as c_long)));
+            let ret = ffi::X509_add_ext(x509, ext, -1);
+            ffi::X509_EXTENSION_free(ext);
+            cvt(ret).map(|_| ())
+        }
+    }
+
+    fn is_overridden(&self, nid: Nid) -> bool {
+        nid != nid::UNDEF &&
+        self.extensions.iter().any(|(exttype, _)| {
+            match exttype.get_nid() {
+                Some(n) => n == nid,
+                None => {
+                    let name = CString::new(exttype.get_name().unwrap().as_bytes()).unwrap();
+                    unsafe { ffi::OBJ_txt2nid(name.as_ptr()) == nid.as_raw() }
+                }
+            }
+        })
+    }
+
     fn add_name_internal(name: *mut ffi::X509_NAME,
                          key: &str,
                          value: &str)
@@ -322,12 +431,22 @@ impl X509Generator {
             }
             try!(cvt(ffi::X509_set_issuer_name(x509.as_ptr(), name)));
 
+            for &(nid, ref der) in &self.copied_extensions {
+                if !self.is_overridden(nid) {
+                    try!(X509Generator::add_copied_extension_internal(x509.as_ptr(), der));
+                }
+            }
+
             for (exttype, ext) in self.extensions.iter() {
                 try!(X509Generator::add_extension_internal(x509.as_ptr(),
                                                            &exttype,
                                                            &ext.to_string()));
             }
 
+            for der in &self.raw_extensions {
+                try!(X509Generator::add_copied_extension_internal(x509.as_ptr(), der));
+            }
+
             let hash_fn = self.hash_type.as_ptr();
             try!(cvt(ffi::X509_sign(x509.as_ptr(), p_key.as_ptr(), hash_fn)));
             Ok(x509)
@@ -368,6 +487,11 @@ foreign_type! {
     pub struct X509Ref;
 }
 
+// X509Ref only exposes read access to the certificate, and OpenSSL locks the data it caches
+// inside it, so certificates can be used from several threads in the same way libssl uses them.
+unsafe impl Send for X509 {}
+unsafe impl Sync for X509 {}
+
 impl X509Ref {
     pub fn subject_name(&self) -> &X509NameRef {
         unsafe {
@@ -391,6 +515,23 @@ impl X509Ref {
         }
     }
 
+    /// Returns this certificate's extensions, if it has any.
+    pub fn extensions(&self) -> Option<&StackRef<X509Extension>> {
+        unsafe {
+            let exts = compat::X509_get0_extensions(self.as_ptr());
+            if exts.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(exts as *mut _))
+            }
+        }
+    }
+
+    /// Returns the first extension of this certificate identified by `object`, if present.
+    pub fn extension(&self, object: &Asn1ObjectRef) -> Option<&X509ExtensionRef> {
+        self.extensions().and_then(|exts| exts.iter().find(|ext| ext.object() == object))
+    }
+
     pub fn public_key(&self) -> Result<PKey, ErrorStack> {
         unsafe {
             let pkey = try!(cvt_p(ffi::X509_get_pubkey(self.as_ptr())));
@@ -398,6 +539,23 @@ impl X509Ref {
         }
     }
 
+    /// Determines if the certificate's public key matches the public component of `key`.
+    ///
+    /// This can be used to check that a certificate belongs to a private key before using them
+    /// together.
+    pub fn public_key_matches(&self, key: &PKeyRef) -> Result<bool, ErrorStack> {
+        let public_key = try!(self.public_key());
+        Ok(public_key.public_eq(key))
+    }
+
+    /// Checks that the certificate was signed by the private key corresponding to `key`.
+    ///
+    /// This only checks the signature itself. It does not check the validity period or any other
+    /// part of the certificate.
+    pub fn verify(&self, key: &PKeyRef) -> Result<bool, ErrorStack> {
+        unsafe { verify_result(ffi::X509_verify(self.as_ptr(), key.as_ptr())) }
+    }
+
     /// Returns certificate fingerprint calculated using provided hash
     pub fn fingerprint(&self, hash_type: MessageDigest) -> Result<Vec<u8>, ErrorStack> {
         unsafe {
@@ -428,6 +586,35 @@ impl X509Ref {
         }
     }
 
+    /// Returns the serial number of the certificate.
+    pub fn serial_number(&self) -> &Asn1IntegerRef {
+        unsafe {
+            let serial = ffi::X509_get_serialNumber(self.as_ptr());
+            assert!(!serial.is_null());
+            Asn1IntegerRef::from_ptr(serial)
+        }
+    }
+
+    /// Returns the certificate's SubjectPublicKeyInfo.
+    ///
+    /// Unlike `public_key`, this does not decode the key.
+    pub fn public_key_info(&self) -> &X509PubkeyRef {
+        unsafe {
+            let key = compat::X509_get_X509_PUBKEY(self.as_ptr());
+            assert!(!key.is_null());
+            X509PubkeyRef::from_ptr(key)
+        }
+    }
+
+    /// Returns the algorithm used by the issuer to sign the certificate.
+    pub fn signature_algorithm(&self) -> &AlgorithmIdentifierRef {
+        unsafe {
+            let alg = compat::X509_get0_sig_alg(self.as_ptr());
+            assert!(!alg.is_null());
+            AlgorithmIdentifierRef::from_ptr(alg as *mut _)
+        }
+    }
+
     /// Returns the list of OCSP responder URLs specified in the certificate's Authority Information
     /// Access field.
     pub fn ocsp_responders(&self) -> Result<Stack<OpensslString>, ErrorStack> {
@@ -436,6 +623,49 @@ impl X509Ref {
         }
     }
 
+    /// Returns the OCSP responder URLs of the certificate.
+    ///
+    /// This is a convenience wrapper over `ocsp_responders` which returns an empty list if the
+    /// certificate has no Authority Information Access extension.
+    pub fn ocsp_urls(&self) -> Vec<String> {
+        match self.ocsp_responders() {
+            Ok(urls) => urls.iter().map(|url| url.to_string()).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Returns this certificate's CRL distribution points, if they exist.
+    pub fn crl_distribution_points(&self) -> Option<Stack<DistPoint>> {
+        unsafe {
+            let stack = ffi::X509_get_ext_d2i(self.as_ptr(),
+                                              ffi::NID_crl_distribution_points,
+                                              ptr::null_mut(),
+                                              ptr::null_mut());
+            if stack.is_null() {
+                return None;
+            }
+
+            Some(Stack::from_ptr(stack as *mut _))
+        }
+    }
+
+    /// Returns the URLs CRLs for this certificate can be fetched from.
+    ///
+    /// Only distribution points given as a full name are considered.
+    pub fn crl_urls(&self) -> Vec<String> {
+        let points = match self.crl_distribution_points() {
+            Some(points) => points,
+            None => return vec![],
+        };
+
+        points.iter()
+            .filter_map(|point| point.fullname())
+            .flat_map(|names| names.iter())
+            .filter_map(|name| name.uri())
+            .map(|uri| uri.to_owned())
+            .collect()
+    }
+
     /// Checks that this certificate issued `subject`.
     pub fn issued(&self, subject: &X509Ref) -> Result<(), X509VerifyError> {
         unsafe {
@@ -449,6 +679,7 @@ impl X509Ref {
 
     to_pem!(ffi::PEM_write_bio_X509);
     to_der!(ffi::i2d_X509);
+    to_der_into!(ffi::i2d_X509);
 }
 
 impl ToOwned for X509Ref {
@@ -499,7 +730,7 @@ impl X509 {
 
 impl Clone for X509 {
     fn clone(&self) -> X509 {
-        self.to_owned()
+        X509Ref::to_owned(self)
     }
 }
 
@@ -557,6 +788,66 @@ impl X509NameRef {
             loc: -1,
         }
     }
+
+    /// Returns the first common name (CN) entry, if present and stored as text.
+    ///
+    /// `None` is returned if the entry is not a UTF8String or one of the ASCII string types, or if
+    /// its contents are not valid for that type.
+    pub fn common_name(&self) -> Option<&str> {
+        self.first_str(nid::COMMONNAME)
+    }
+
+    /// Returns the first organization (O) entry, if present and stored as text.
+    ///
+    /// See `common_name` for the entries which are accepted.
+    pub fn organization(&self) -> Option<&str> {
+        self.first_str(nid::ORGANIZATIONNAME)
+    }
+
+    /// Returns the first country (C) entry, if present and stored as text.
+    ///
+    /// See `common_name` for the entries which are accepted.
+    pub fn country(&self) -> Option<&str> {
+        self.first_str(nid::COUNTRYNAME)
+    }
+
+    // Only entries whose string type stores ASCII or UTF-8 are returned, since the contents of
+    // other types such as BMPString can happen to be valid UTF-8 without meaning the same text.
+    // Embedded NULs are rejected, since C code handed the name would see it truncated.
+    fn first_str(&self, nid: Nid) -> Option<&str> {
+        let data = match self.entries_by_nid(nid).next() {
+            Some(entry) => entry.data(),
+            None => return None,
+        };
+        let ascii = match unsafe { ffi::ASN1_STRING_type(data.as_ptr()) } {
+            ffi::V_ASN1_UTF8STRING => false,
+            ffi::V_ASN1_NUMERICSTRING |
+            ffi::V_ASN1_PRINTABLESTRING |
+            ffi::V_ASN1_IA5STRING |
+            ffi::V_ASN1_VISIBLESTRING => true,
+            _ => return None,
+        };
+        let bytes = data.as_slice();
+        if bytes.iter().any(|&b| b == 0 || (ascii && b >= 0x80)) {
+            return None;
+        }
+        str::from_utf8(bytes).ok()
+    }
+}
+
+/// Formats the name on a single line as described by RFC 2253, most specific entry first, as in
+/// `CN=foobar.com,O=Internet Widgits Pty Ltd,C=AU`.
+impl fmt::Display for X509NameRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        unsafe {
+            let mem_bio = try!(MemBio::new());
+            try!(cvt_n(ffi::X509_NAME_print_ex(mem_bio.as_ptr(),
+                                               self.as_ptr(),
+                                               0,
+                                               ffi::XN_FLAG_RFC2253)));
+            f.write_str(&String::from_utf8_lossy(mem_bio.get_buf()))
+        }
+    }
 }
 
 pub struct X509NameEntries<'a> {
@@ -629,6 +920,369 @@ impl X509Req {
 impl X509ReqRef {
     to_pem!(ffi::PEM_write_bio_X509_REQ);
     to_der!(ffi::i2d_X509_REQ);
+
+    /// Returns the public key embedded in the request.
+    pub fn public_key(&self) -> Result<PKey, ErrorStack> {
+        unsafe {
+            let pkey = try!(cvt_p(ffi::X509_REQ_get_pubkey(self.as_ptr())));
+            Ok(PKey::from_ptr(pkey))
+        }
+    }
+
+    /// Returns the request's SubjectPublicKeyInfo.
+    ///
+    /// Unlike `public_key`, this does not decode the key.
+    pub fn public_key_info(&self) -> &X509PubkeyRef {
+        unsafe {
+            let key = compat::X509_REQ_get_X509_PUBKEY(self.as_ptr());
+            assert!(!key.is_null());
+            X509PubkeyRef::from_ptr(key)
+        }
+    }
+
+    /// Determines if the request's public key matches the public component of `key`.
+    pub fn public_key_matches(&self, key: &PKeyRef) -> Result<bool, ErrorStack> {
+        let public_key = try!(self.public_key());
+        Ok(public_key.public_eq(key))
+    }
+
+    /// Checks that the request was signed by the private key corresponding to `key`.
+    pub fn verify(&self, key: &PKeyRef) -> Result<bool, ErrorStack> {
+        unsafe { verify_result(ffi::X509_REQ_verify(self.as_ptr(), key.as_ptr())) }
+    }
+
+    /// Checks that the request was signed by the private key corresponding to its own embedded
+    /// public key.
+    ///
+    /// A request which fails this check was not produced by the holder of the key it contains,
+    /// and a certificate should not be issued for it.
+    pub fn verify_self(&self) -> Result<bool, ErrorStack> {
+        let public_key = try!(self.public_key());
+        self.verify(&public_key)
+    }
+}
+
+fn verify_result(r: c_int) -> Result<bool, ErrorStack> {
+    match r {
+        1 => Ok(true),
+        0 => {
+            ErrorStack::get(); // discard error stack
+            Ok(false)
+        }
+        _ => Err(ErrorStack::get()),
+    }
+}
+
+foreign_type! {
+    type CType = ffi::X509_CRL;
+    fn drop = ffi::X509_CRL_free;
+
+    pub struct X509Crl;
+    pub struct X509CrlRef;
+}
+
+impl X509Crl {
+    from_pem!(X509Crl, ffi::PEM_read_bio_X509_CRL);
+    from_der!(X509Crl, ffi::d2i_X509_CRL);
+}
+
+impl X509CrlRef {
+    to_pem!(ffi::PEM_write_bio_X509_CRL);
+    to_der!(ffi::i2d_X509_CRL);
+
+    /// Returns the revoked certificate entries of the CRL, if it has any.
+    pub fn revoked(&self) -> Option<&StackRef<X509Revoked>> {
+        unsafe {
+            let revoked = compat::X509_CRL_get_REVOKED(self.as_ptr());
+            if revoked.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(revoked))
+            }
+        }
+    }
+}
+
+/// The reason a certificate was revoked, as recorded in a CRL entry's reason code extension.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CrlReason(c_int);
+
+impl CrlReason {
+    pub fn from_raw(raw: c_int) -> CrlReason {
+        CrlReason(raw)
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        self.0
+    }
+}
+
+/// `unspecified`
+pub const CRL_REASON_UNSPECIFIED: CrlReason = CrlReason(ffi::CRL_REASON_UNSPECIFIED);
+
+/// `keyCompromise`
+pub const CRL_REASON_KEY_COMPROMISE: CrlReason = CrlReason(ffi::CRL_REASON_KEY_COMPROMISE);
+
+/// `cACompromise`
+pub const CRL_REASON_CA_COMPROMISE: CrlReason = CrlReason(ffi::CRL_REASON_CA_COMPROMISE);
+
+/// `affiliationChanged`
+pub const CRL_REASON_AFFILIATION_CHANGED: CrlReason = CrlReason(ffi::CRL_REASON_AFFILIATION_CHANGED);
+
+/// `superseded`
+pub const CRL_REASON_SUPERSEDED: CrlReason = CrlReason(ffi::CRL_REASON_SUPERSEDED);
+
+/// `cessationOfOperation`
+pub const CRL_REASON_CESSATION_OF_OPERATION: CrlReason = CrlReason(ffi::CRL_REASON_CESSATION_OF_OPERATION);
+
+/// `certificateHold`
+pub const CRL_REASON_CERTIFICATE_HOLD: CrlReason = CrlReason(ffi::CRL_REASON_CERTIFICATE_HOLD);
+
+/// `removeFromCRL`
+pub const CRL_REASON_REMOVE_FROM_CRL: CrlReason = CrlReason(ffi::CRL_REASON_REMOVE_FROM_CRL);
+
+/// `privilegeWithdrawn`
+pub const CRL_REASON_PRIVILEGE_WITHDRAWN: CrlReason = CrlReason(ffi::CRL_REASON_PRIVILEGE_WITHDRAWN);
+
+/// `aACompromise`
+pub const CRL_REASON_AA_COMPROMISE: CrlReason = CrlReason(ffi::CRL_REASON_AA_COMPROMISE);
+
+foreign_type! {
+    type CType = ffi::X509_REVOKED;
+    fn drop = ffi::X509_REVOKED_free;
+
+    /// An entry of a CRL identifying a revoked certificate.
+    pub struct X509Revoked;
+    /// A reference to an `X509Revoked`.
+    pub struct X509RevokedRef;
+}
+
+impl X509Revoked {
+    /// Creates an entry for the certificate with the given serial number, revoked at
+    /// `revocation_date`.
+    ///
+    /// The entry can be added to a CRL with `X509CrlBuilder::add_revoked_entry`.
+    pub fn new(serial: &BigNumRef, revocation_date: &Asn1TimeRef) -> Result<X509Revoked, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let revoked = X509Revoked::from_ptr(try!(cvt_p(ffi::X509_REVOKED_new())));
+            let serial = Asn1Integer::from_ptr(try!(cvt_p(ffi::BN_to_ASN1_INTEGER(serial.as_ptr(),
+                                                                                  ptr::null_mut()))));
+            try!(cvt(ffi::X509_REVOKED_set_serialNumber(revoked.as_ptr(), serial.as_ptr())));
+            try!(cvt(ffi::X509_REVOKED_set_revocationDate(revoked.as_ptr(),
+                                                          revocation_date.as_ptr())));
+            Ok(revoked)
+        }
+    }
+
+    /// Sets the reason code extension of the entry.
+    pub fn set_reason(&mut self, reason: CrlReason) -> Result<(), ErrorStack> {
+        unsafe {
+            let value = try!(cvt_p(ffi::ASN1_ENUMERATED_new()));
+            let r = cvt(ffi::ASN1_ENUMERATED_set(value, reason.0 as c_long)).and_then(|_| {
+                self.add_ext(nid::CRL_REASON, value as *mut _, false)
+            });
+            ffi::ASN1_ENUMERATED_free(value);
+            r
+        }
+    }
+
+    /// Sets the invalidity date extension of the entry, the time at which the certificate is known
+    /// or suspected to have become invalid.
+    pub fn set_invalidity_date(&mut self, date: &Asn1TimeRef) -> Result<(), ErrorStack> {
+        unsafe {
+            let value = try!(cvt_p(ffi::ASN1_TIME_to_generalizedtime(date.as_ptr(),
+                                                                     ptr::null_mut())));
+            let value = Asn1GeneralizedTime::from_ptr(value);
+            self.add_ext(nid::INVALIDITY_DATE, value.as_ptr() as *mut _, false)
+        }
+    }
+
+    /// Sets the certificate issuer extension of the entry, which identifies the issuer of the
+    /// revoked certificate in an indirect CRL.
+    ///
+    /// The extension is marked critical, as required by RFC 5280.
+    pub fn set_certificate_issuer(&mut self, issuer: &X509NameRef) -> Result<(), ErrorStack> {
+        unsafe {
+            let mut names = try!(Stack::<GeneralName>::new());
+            let name = GeneralName::from_ptr(try!(cvt_p(ffi::GENERAL_NAME_new())));
+            let dirname = try!(cvt_p(ffi::X509_NAME_dup(issuer.as_ptr())));
+            ffi::GENERAL_NAME_set0_value(name.as_ptr(), ffi::GEN_DIRNAME, dirname as *mut _);
+            try!(names.push(name));
+            self.add_ext(nid::CERTIFICATE_ISSUER, names.as_ptr() as *mut _, true)
+        }
+    }
+
+    unsafe fn add_ext(&mut self,
+                      nid: Nid,
+                      value: *mut c_void,
+                      critical: bool)
+                      -> Result<(), ErrorStack> {
+        cvt(ffi::X509_REVOKED_add1_ext_i2d(self.as_ptr(),
+                                           nid.as_raw(),
+                                           value,
+                                           critical as c_int,
+                                           ffi::X509V3_ADD_REPLACE))
+            .map(|_| ())
+    }
+}
+
+impl X509RevokedRef {
+    /// Returns the serial number of the revoked certificate.
+    pub fn serial_number(&self) -> &Asn1IntegerRef {
+        unsafe { Asn1IntegerRef::from_ptr(compat::X509_REVOKED_get0_serialNumber(self.as_ptr()) as *mut _) }
+    }
+
+    /// Returns the time at which the certificate was revoked.
+    pub fn revocation_date(&self) -> &Asn1TimeRef {
+        unsafe { Asn1TimeRef::from_ptr(compat::X509_REVOKED_get0_revocationDate(self.as_ptr()) as *mut _) }
+    }
+
+    /// Returns the extensions of the entry, if it has any.
+    pub fn extensions(&self) -> Option<&StackRef<X509Extension>> {
+        unsafe {
+            let exts = compat::X509_REVOKED_get0_extensions(self.as_ptr());
+            if exts.is_null() {
+                None
+            } else {
+                Some(StackRef::from_ptr(exts as *mut _))
+            }
+        }
+    }
+
+    /// Returns the value of the reason code extension, if present.
+    pub fn reason(&self) -> Option<CrlReason> {
+        unsafe {
+            let value = self.ext_d2i(nid::CRL_REASON) as *mut ffi::ASN1_ENUMERATED;
+            if value.is_null() {
+                return None;
+            }
+            let reason = ffi::ASN1_ENUMERATED_get(value);
+            ffi::ASN1_ENUMERATED_free(value);
+            Some(CrlReason(reason as c_int))
+        }
+    }
+
+    /// Returns the value of the invalidity date extension, if present.
+    pub fn invalidity_date(&self) -> Option<Asn1GeneralizedTime> {
+        unsafe {
+            let value = self.ext_d2i(nid::INVALIDITY_DATE) as *mut ffi::ASN1_GENERALIZEDTIME;
+            if value.is_null() {
+                None
+            } else {
+                Some(Asn1GeneralizedTime::from_ptr(value))
+            }
+        }
+    }
+
+    /// Returns the names in the certificate issuer extension, if present.
+    pub fn certificate_issuer(&self) -> Option<Stack<GeneralName>> {
+        unsafe {
+            let value = self.ext_d2i(nid::CERTIFICATE_ISSUER) as *mut ffi::stack_st_GENERAL_NAME;
+            if value.is_null() {
+                None
+            } else {
+                Some(Stack::from_ptr(value))
+            }
+        }
+    }
+
+    unsafe fn ext_d2i(&self, nid: Nid) -> *mut c_void {
+        // a missing extension doesn't touch the error queue
+        ffi::X509_REVOKED_get_ext_d2i(self.as_ptr(), nid.as_raw(), ptr::null_mut(), ptr::null_mut())
+    }
+}
+
+impl Stackable for X509Revoked {
+    type StackType = ffi::stack_st_X509_REVOKED;
+}
+
+/// A builder used to construct an `X509Crl`.
+///
+/// Revoked entries are appended without any ordering requirement and are
+/// sorted by serial number only once, when the CRL is signed, so building a
+/// CRL with a very large number of entries takes `O(n log n)` time.
+pub struct X509CrlBuilder(X509Crl);
+
+impl X509CrlBuilder {
+    /// Creates a new builder for a version 2 CRL.
+    pub fn new() -> Result<X509CrlBuilder, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let crl = X509Crl::from_ptr(try!(cvt_p(ffi::X509_CRL_new())));
+            try!(cvt(ffi::X509_CRL_set_version(crl.as_ptr(), 1)));
+            Ok(X509CrlBuilder(crl))
+        }
+    }
+
+    /// Sets the name of the CRL issuer.
+    pub fn set_issuer_name(&mut self, name: &X509NameRef) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::X509_CRL_set_issuer_name(self.0.as_ptr(), name.as_ptr())).map(|_| ()) }
+    }
+
+    /// Sets the time at which this CRL was issued.
+    pub fn set_last_update(&mut self, time: &Asn1TimeRef) -> Result<(), ErrorStack> {
+        unsafe { cvt(X509_CRL_set_lastUpdate(self.0.as_ptr(), time.as_ptr())).map(|_| ()) }
+    }
+
+    /// Sets the time by which the next CRL will be issued.
+    pub fn set_next_update(&mut self, time: &Asn1TimeRef) -> Result<(), ErrorStack> {
+        unsafe { cvt(X509_CRL_set_nextUpdate(self.0.as_ptr(), time.as_ptr())).map(|_| ()) }
+    }
+
+    /// Adds an entry for the certificate with the given serial number, revoked
+    /// at `revocation_date`.
+    pub fn add_revoked(&mut self,
+                       serial: &BigNumRef,
+                       revocation_date: &Asn1TimeRef)
+                       -> Result<(), ErrorStack> {
+        self.add_revoked_iter(Some((serial, revocation_date)))
+    }
+
+    /// Adds an entry for each `(serial, revocation_date)` pair.
+    ///
+    /// This should be preferred over repeated calls to `add_revoked` when
+    /// adding many entries, as the serial number encoding buffer is reused
+    /// across all of them.
+    pub fn add_revoked_iter<'a, I>(&mut self, entries: I) -> Result<(), ErrorStack>
+        where I: IntoIterator<Item = (&'a BigNumRef, &'a Asn1TimeRef)>
+    {
+        unsafe {
+            let serial = Asn1Integer::from_ptr(try!(cvt_p(ffi::ASN1_INTEGER_new())));
+
+            for (bn, date) in entries {
+                let revoked = try!(cvt_p(ffi::X509_REVOKED_new()));
+                let r = cvt_p(ffi::BN_to_ASN1_INTEGER(bn.as_ptr(), serial.as_ptr()))
+                    .and_then(|_| cvt(ffi::X509_REVOKED_set_serialNumber(revoked, serial.as_ptr())))
+                    .and_then(|_| cvt(ffi::X509_REVOKED_set_revocationDate(revoked, date.as_ptr())))
+                    .and_then(|_| cvt(ffi::X509_CRL_add0_revoked(self.0.as_ptr(), revoked)));
+                if let Err(e) = r {
+                    ffi::X509_REVOKED_free(revoked);
+                    return Err(e);
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Adds a revoked entry, which may carry extensions such as a reason code.
+    pub fn add_revoked_entry(&mut self, entry: X509Revoked) -> Result<(), ErrorStack> {
+        unsafe {
+            try!(cvt(ffi::X509_CRL_add0_revoked(self.0.as_ptr(), entry.as_ptr())));
+            mem::forget(entry);
+            Ok(())
+        }
+    }
+
+    /// Sorts the revoked entries by serial number and signs the CRL.
+    pub fn sign(self, pkey: &PKeyRef, hash: MessageDigest) -> Result<X509Crl, ErrorStack> {
+        unsafe {
+            try!(cvt(ffi::X509_CRL_sort(self.0.as_ptr())));
+            try!(cvt(ffi::X509_CRL_sign(self.0.as_ptr(), pkey.as_ptr(), hash.as_ptr())));
+            Ok(self.0)
+        }
+    }
 }
 
 /// A collection of X.509 extensions.
@@ -765,8 +1419,17 @@ foreign_type! {
 impl GeneralNameRef {
     /// Returns the contents of this `GeneralName` if it is a `dNSName`.
     pub fn dnsname(&self) -> Option<&str> {
+        self.ia5_string(ffi::GEN_DNS)
+    }
+
+    /// Returns the contents of this `GeneralName` if it is a `uniformResourceIdentifier`.
+    pub fn uri(&self) -> Option<&str> {
+        self.ia5_string(ffi::GEN_URI)
+    }
+
+    fn ia5_string(&self, type_: c_int) -> Option<&str> {
         unsafe {
-            if (*self.as_ptr()).type_ != ffi::GEN_DNS {
+            if (*self.as_ptr()).type_ != type_ {
                 return None;
             }
 
@@ -774,13 +1437,24 @@ impl GeneralNameRef {
             let len = ffi::ASN1_STRING_length((*self.as_ptr()).d as *mut _);
 
             let slice = slice::from_raw_parts(ptr as *const u8, len as usize);
-            // dNSNames are stated to be ASCII (specifically IA5). Hopefully
+            // dNSNames and URIs are stated to be ASCII (specifically IA5). Hopefully
             // OpenSSL checks that when loading a certificate but if not we'll
             // use this instead of from_utf8_unchecked just in case.
             str::from_utf8(slice).ok()
         }
     }
 
+    /// Returns the contents of this `GeneralName` if it is a `directoryName`.
+    pub fn directory_name(&self) -> Option<&X509NameRef> {
+        unsafe {
+            if (*self.as_ptr()).type_ != ffi::GEN_DIRNAME {
+                return None;
+            }
+
+            Some(X509NameRef::from_ptr((*self.as_ptr()).d as *mut _))
+        }
+    }
+
     /// Returns the contents of this `GeneralName` if it is an `iPAddress`.
     pub fn ipaddress(&self) -> Option<&[u8]> {
         unsafe {
@@ -800,6 +1474,321 @@ impl Stackable for GeneralName {
     type StackType = ffi::stack_st_GENERAL_NAME;
 }
 
+foreign_type! {
+    type CType = ffi::DIST_POINT;
+    fn drop = ffi::DIST_POINT_free;
+
+    /// An entry of a certificate's CRL distribution points extension.
+    pub struct DistPoint;
+    /// A reference to a `DistPoint`.
+    pub struct DistPointRef;
+}
+
+impl DistPointRef {
+    /// Returns the names of this distribution point, if it is given as a list of
+    /// `GeneralName`s rather than relative to the CRL issuer.
+    pub fn fullname(&self) -> Option<&StackRef<GeneralName>> {
+        unsafe {
+            let name = (*self.as_ptr()).distpoint;
+            if name.is_null() || (*name).type_ != 0 || (*name).name.is_null() {
+                return None;
+            }
+
+            Some(StackRef::from_ptr((*name).name as *mut _))
+        }
+    }
+}
+
+impl Stackable for DistPoint {
+    type StackType = ffi::stack_st_DIST_POINT;
+}
+
+impl ExtensionValue for Stack<GeneralName> {
+    type Error = ErrorStack;
+
+    /// Decodes the value of a subject or issuer alternative name extension.
+    fn from_der(der: &[u8]) -> Result<Stack<GeneralName>, ErrorStack> {
+        unsafe {
+            ffi::init();
+            let len = ::std::cmp::min(der.len(), c_long::max_value() as usize) as c_long;
+            cvt_p(ffi::d2i_GENERAL_NAMES(ptr::null_mut(), &mut der.as_ptr(), len))
+                .map(|p| Stack::from_ptr(p))
+        }
+    }
+}
+
+foreign_type! {
+    type CType = ffi::X509_EXTENSION;
+    fn drop = ffi::X509_EXTENSION_free;
+
+    /// An X509 certificate extension.
+    pub struct X509Extension;
+    /// A reference to an `X509Extension`.
+    pub struct X509ExtensionRef;
+}
+
+impl X509Extension {
+    /// Creates an extension identified by `object` whose value is the DER encoding of `value`.
+    ///
+    /// This can express extensions which `extension::Extension` can't, such as the OCSP no-check
+    /// extension, whose value is a `NULL`.
+    pub fn new(object: &Asn1ObjectRef,
+               critical: bool,
+               value: &Asn1TypeRef)
+               -> Result<X509Extension, ErrorStack> {
+        unsafe {
+            let der = try!(value.to_der());
+            assert!(der.len() <= c_int::max_value() as usize);
+            let data = try!(cvt_p(ffi::ASN1_STRING_type_new(ffi::V_ASN1_OCTET_STRING)));
+            let data = Asn1String::from_ptr(data);
+            try!(cvt(ffi::ASN1_STRING_set(data.as_ptr(),
+                                          der.as_ptr() as *const _,
+                                          der.len() as c_int)));
+            let ext = try!(cvt_p(ffi::X509_EXTENSION_create_by_OBJ(ptr::null_mut(),
+                                                                    object.as_ptr(),
+                                                                    critical as c_int,
+                                                                    data.as_ptr())));
+            Ok(X509Extension::from_ptr(ext))
+        }
+    }
+
+    from_der!(X509Extension, ffi::d2i_X509_EXTENSION);
+}
+
+impl X509ExtensionRef {
+    /// Returns the object identifier of the extension.
+    pub fn object(&self) -> &Asn1ObjectRef {
+        unsafe { Asn1ObjectRef::from_ptr(ffi::X509_EXTENSION_get_object(self.as_ptr())) }
+    }
+
+    /// Returns whether the extension is marked critical.
+    pub fn critical(&self) -> bool {
+        unsafe { ffi::X509_EXTENSION_get_critical(self.as_ptr()) > 0 }
+    }
+
+    /// Returns the DER encoded value of the extension.
+    ///
+    /// This is the contents of the `extnValue` octet string, whose structure depends on the
+    /// extension's type.
+    pub fn value_der(&self) -> &[u8] {
+        unsafe { Asn1StringRef::from_ptr(ffi::X509_EXTENSION_get_data(self.as_ptr())).as_slice() }
+    }
+
+    /// Decodes the value of the extension as a `T`.
+    ///
+    /// The identifier of the extension is not checked, so callers should first confirm that
+    /// `object` identifies an extension holding a `T`.
+    pub fn decode<T: ExtensionValue>(&self) -> Result<T, T::Error> {
+        T::from_der(self.value_der())
+    }
+
+    to_der!(ffi::i2d_X509_EXTENSION);
+}
+
+impl Stackable for X509Extension {
+    type StackType = ffi::stack_st_X509_EXTENSION;
+}
+
+foreign_type! {
+    type CType = ffi::X509_PUBKEY;
+    fn drop = ffi::X509_PUBKEY_free;
+
+    /// An ASN.1 `SubjectPublicKeyInfo`, made up of an algorithm identifier and a public key.
+    ///
+    /// Equality and hashing compare the encoded algorithm and key, so keys can be compared and
+    /// grouped without decoding them to `PKey`s.
+    pub struct X509Pubkey;
+    /// A reference to an `X509Pubkey`.
+    pub struct X509PubkeyRef;
+}
+
+impl X509Pubkey {
+    /// Creates the SubjectPublicKeyInfo for the public component of `key`.
+    pub fn from_pkey(key: &PKeyRef) -> Result<X509Pubkey, ErrorStack> {
+        unsafe {
+            let mut pubkey = ptr::null_mut();
+            try!(cvt(ffi::X509_PUBKEY_set(&mut pubkey, key.as_ptr())));
+            Ok(X509Pubkey(pubkey))
+        }
+    }
+
+    from_der!(X509Pubkey, ffi::d2i_X509_PUBKEY);
+}
+
+impl X509PubkeyRef {
+    /// Decodes the public key.
+    ///
+    /// OpenSSL caches the decoded key, so repeated calls are cheap.
+    pub fn public_key(&self) -> Result<PKey, ErrorStack> {
+        unsafe { cvt_p(ffi::X509_PUBKEY_get(self.as_ptr())).map(|p| PKey::from_ptr(p)) }
+    }
+
+    /// Returns the algorithm of the public key.
+    pub fn algorithm(&self) -> &AlgorithmIdentifierRef {
+        unsafe {
+            let mut alg = ptr::null_mut();
+            ffi::X509_PUBKEY_get0_param(ptr::null_mut(),
+                                        ptr::null_mut(),
+                                        ptr::null_mut(),
+                                        &mut alg,
+                                        self.as_ptr());
+            assert!(!alg.is_null());
+            AlgorithmIdentifierRef::from_ptr(alg)
+        }
+    }
+
+    /// Returns the contents of the `subjectPublicKey` bit string, whose format depends on the
+    /// algorithm.
+    pub fn key_bytes(&self) -> &[u8] {
+        unsafe {
+            let mut pk = ptr::null();
+            let mut len = 0;
+            ffi::X509_PUBKEY_get0_param(ptr::null_mut(),
+                                        &mut pk,
+                                        &mut len,
+                                        ptr::null_mut(),
+                                        self.as_ptr());
+            if pk.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(pk, len as usize)
+            }
+        }
+    }
+
+    to_der!(ffi::i2d_X509_PUBKEY);
+}
+
+impl PartialEq for X509PubkeyRef {
+    fn eq(&self, other: &X509PubkeyRef) -> bool {
+        self.key_bytes() == other.key_bytes() &&
+        self.algorithm().to_der().ok() == other.algorithm().to_der().ok()
+    }
+}
+
+impl Eq for X509PubkeyRef {}
+
+impl Hash for X509PubkeyRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key_bytes().hash(state)
+    }
+}
+
+impl PartialEq for X509Pubkey {
+    fn eq(&self, other: &X509Pubkey) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for X509Pubkey {}
+
+impl Hash for X509Pubkey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl Borrow<X509PubkeyRef> for X509Pubkey {
+    fn borrow(&self) -> &X509PubkeyRef {
+        &*self
+    }
+}
+
+impl fmt::Debug for X509PubkeyRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("X509Pubkey")
+            .field("algorithm", &self.algorithm().object())
+            .field("key_len", &self.key_bytes().len())
+            .finish()
+    }
+}
+
+impl fmt::Debug for X509Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+foreign_type! {
+    type CType = ffi::X509_ALGOR;
+    fn drop = ffi::X509_ALGOR_free;
+
+    /// An ASN.1 `AlgorithmIdentifier`, made up of an object identifier and optional parameters.
+    pub struct AlgorithmIdentifier;
+    /// A reference to an `AlgorithmIdentifier`.
+    pub struct AlgorithmIdentifierRef;
+}
+
+impl AlgorithmIdentifier {
+    from_der!(AlgorithmIdentifier, ffi::d2i_X509_ALGOR);
+}
+
+impl AlgorithmIdentifierRef {
+    /// Returns the object identifier of the algorithm.
+    pub fn object(&self) -> &Asn1ObjectRef {
+        unsafe { Asn1ObjectRef::from_ptr((*self.as_ptr()).algorithm) }
+    }
+
+    /// Returns the DER encoded parameters of the algorithm, or `None` if they are absent.
+    ///
+    /// Note that many algorithms have an explicit ASN.1 `NULL` parameter, which is encoded as
+    /// `05 00`, rather than omitting it.
+    pub fn parameters_der(&self) -> Result<Option<Vec<u8>>, ErrorStack> {
+        unsafe {
+            let parameter = (*self.as_ptr()).parameter;
+            if parameter.is_null() {
+                return Ok(None);
+            }
+            let len = try!(cvt(ffi::i2d_ASN1_TYPE(parameter, ptr::null_mut())));
+            let mut buf = vec![0; len as usize];
+            try!(cvt(ffi::i2d_ASN1_TYPE(parameter, &mut buf.as_mut_ptr())));
+            Ok(Some(buf))
+        }
+    }
+
+    /// Returns the digest identified by the algorithm, if it is a digest algorithm known to
+    /// OpenSSL.
+    ///
+    /// Signature algorithms are not resolved by this method, unlike `MessageDigest::from_nid`.
+    /// Use `signature_algorithms` to find the digest they use instead.
+    pub fn digest(&self) -> Option<MessageDigest> {
+        let nid = self.object().nid();
+        match MessageDigest::from_nid(nid) {
+            // OpenSSL also knows signature algorithms as aliases of their digests
+            Some(md) if md.nid() == nid => Some(md),
+            _ => None,
+        }
+    }
+
+    /// Returns the cipher identified by the algorithm, if it is a cipher known to OpenSSL.
+    ///
+    /// The cipher's parameters, such as its IV, are not interpreted. Use
+    /// `Cipher::from_algorithm_identifier` to decode them as well.
+    pub fn cipher(&self) -> Option<Cipher> {
+        Cipher::from_nid(self.object().nid())
+    }
+
+    /// Returns the digest and public key algorithms used by the algorithm, if it is a signature
+    /// algorithm such as `sha256WithRSAEncryption`.
+    pub fn signature_algorithms(&self) -> Option<SignatureAlgorithms> {
+        self.object().nid().signature_algorithms()
+    }
+
+    to_der!(ffi::i2d_X509_ALGOR);
+}
+
+/// A type which can be decoded from the value of an X509 extension.
+///
+/// This is implemented for the extension values modeled by this crate, and may be implemented by
+/// other crates to decode extensions which it does not support.
+pub trait ExtensionValue: Sized {
+    /// The error returned when the value cannot be decoded.
+    type Error;
+
+    /// Decodes a value from the DER encoded contents of an extension.
+    fn from_der(der: &[u8]) -> Result<Self, Self::Error>;
+}
+
 #[test]
 fn test_negative_serial() {
     // I guess that's enough to get a random negative number
@@ -815,6 +1804,19 @@ mod compat {
     pub use ffi::X509_getm_notBefore as X509_get_notBefore;
     pub use ffi::X509_up_ref;
     pub use ffi::X509_get0_extensions;
+    pub use ffi::{X509_get_X509_PUBKEY, X509_REQ_get_X509_PUBKEY};
+    pub use ffi::{X509_CRL_get_REVOKED, X509_REVOKED_get0_serialNumber,
+                  X509_REVOKED_get0_revocationDate, X509_REVOKED_get0_extensions};
+
+    use ffi;
+    use std::ptr;
+
+    #[allow(bad_style)]
+    pub unsafe fn X509_get0_sig_alg(x: *mut ffi::X509) -> *const ffi::X509_ALGOR {
+        let mut alg = ptr::null();
+        ffi::X509_get0_signature(ptr::null_mut(), &mut alg, x);
+        alg
+    }
 }
 
 #[cfg(ossl10x)]
@@ -827,6 +1829,18 @@ mod compat {
         (*(*(*x).cert_info).validity).notAfter
     }
 
+    pub unsafe fn X509_get0_sig_alg(x: *mut ffi::X509) -> *const ffi::X509_ALGOR {
+        (*x).sig_alg
+    }
+
+    pub unsafe fn X509_get_X509_PUBKEY(x: *mut ffi::X509) -> *mut ffi::X509_PUBKEY {
+        (*(*x).cert_info).key
+    }
+
+    pub unsafe fn X509_REQ_get_X509_PUBKEY(req: *mut ffi::X509_REQ) -> *mut ffi::X509_PUBKEY {
+        (*(*req).req_info).pubkey
+    }
+
     pub unsafe fn X509_get_notBefore(x: *mut ffi::X509) -> *mut ffi::ASN1_TIME {
         (*(*(*x).cert_info).validity).notBefore
     }
@@ -848,4 +1862,29 @@ mod compat {
             (*info).extensions
         }
     }
+
+    pub unsafe fn X509_CRL_get_REVOKED(crl: *mut ffi::X509_CRL) -> *mut ffi::stack_st_X509_REVOKED {
+        // the CRL's X509_CRL_INFO is the first field of X509_CRL
+        let info = *(crl as *mut *mut ffi::X509_CRL_INFO);
+        if info.is_null() {
+            0 as *mut _
+        } else {
+            (*info).revoked
+        }
+    }
+
+    pub unsafe fn X509_REVOKED_get0_serialNumber(x: *mut ffi::X509_REVOKED)
+                                                 -> *const ffi::ASN1_INTEGER {
+        (*x).serialNumber
+    }
+
+    pub unsafe fn X509_REVOKED_get0_revocationDate(x: *mut ffi::X509_REVOKED)
+                                                   -> *const ffi::ASN1_TIME {
+        (*x).revocationDate
+    }
+
+    pub unsafe fn X509_REVOKED_get0_extensions(x: *mut ffi::X509_REVOKED)
+                                               -> *const ffi::stack_st_X509_EXTENSION {
+        (*x).extensions
+    }
 }
@@ -1,10 +1,43 @@
 use ffi;
 use foreign_types::ForeignTypeRef;
+use libc::c_ulong;
 use std::mem;
+#[cfg(all(feature = "v110", ossl110))]
+use libc::{c_int, c_long, c_void};
+#[cfg(all(feature = "v110", ossl110))]
+use std::ptr;
+#[cfg(all(feature = "v110", ossl110))]
+use std::sync::{Arc, RwLock};
 
 use {cvt, cvt_p};
 use error::ErrorStack;
-use x509::X509;
+use x509::{X509, X509CrlRef};
+#[cfg(all(feature = "v110", ossl110))]
+use x509::X509Crl;
+
+bitflags! {
+    /// Flags controlling certificate chain verification.
+    pub flags X509VerifyFlags: c_ulong {
+        const X509_V_FLAG_USE_CHECK_TIME = ffi::X509_V_FLAG_USE_CHECK_TIME,
+        /// Checks the leaf certificate against a CRL in the store.
+        const X509_V_FLAG_CRL_CHECK = ffi::X509_V_FLAG_CRL_CHECK,
+        /// Checks every certificate in the chain against a CRL in the store.
+        ///
+        /// Only has an effect in combination with `X509_V_FLAG_CRL_CHECK`.
+        const X509_V_FLAG_CRL_CHECK_ALL = ffi::X509_V_FLAG_CRL_CHECK_ALL,
+        const X509_V_FLAG_IGNORE_CRITICAL = ffi::X509_V_FLAG_IGNORE_CRITICAL,
+        const X509_V_FLAG_X509_STRICT = ffi::X509_V_FLAG_X509_STRICT,
+        const X509_V_FLAG_ALLOW_PROXY_CERTS = ffi::X509_V_FLAG_ALLOW_PROXY_CERTS,
+        const X509_V_FLAG_POLICY_CHECK = ffi::X509_V_FLAG_POLICY_CHECK,
+        const X509_V_FLAG_EXPLICIT_POLICY = ffi::X509_V_FLAG_EXPLICIT_POLICY,
+        const X509_V_FLAG_INHIBIT_ANY = ffi::X509_V_FLAG_INHIBIT_ANY,
+        const X509_V_FLAG_INHIBIT_MAP = ffi::X509_V_FLAG_INHIBIT_MAP,
+        const X509_V_FLAG_NOTIFY_POLICY = ffi::X509_V_FLAG_NOTIFY_POLICY,
+        const X509_V_FLAG_EXTENDED_CRL_SUPPORT = ffi::X509_V_FLAG_EXTENDED_CRL_SUPPORT,
+        const X509_V_FLAG_USE_DELTAS = ffi::X509_V_FLAG_USE_DELTAS,
+        const X509_V_FLAG_CHECK_SS_SIGNATURE = ffi::X509_V_FLAG_CHECK_SS_SIGNATURE,
+    }
+}
 
 foreign_type! {
     type CType = ffi::X509_STORE;
@@ -44,6 +77,39 @@ impl X509StoreBuilderRef {
         }
     }
 
+    /// Adds a certificate revocation list to the certificate store.
+    ///
+    /// CRLs are only consulted during verification if `X509_V_FLAG_CRL_CHECK` is set.
+    pub fn add_crl(&mut self, crl: &X509CrlRef) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::X509_STORE_add_crl(self.as_ptr(), crl.as_ptr())).map(|_| ()) }
+    }
+
+    /// Configures the store to consult `crls` whenever a CRL is needed during verification, in
+    /// addition to the CRLs added with `add_crl`.
+    ///
+    /// Requires the `v110` feature and OpenSSL 1.1.0.
+    #[cfg(all(feature = "v110", ossl110))]
+    pub fn set_crl_set(&mut self, crls: &CrlSet) -> Result<(), ErrorStack> {
+        unsafe {
+            let old = ffi::X509_STORE_get_ex_data(self.as_ptr(), *CRL_SET_IDX);
+            let new = Box::into_raw(Box::new(crls.clone()));
+            if ffi::X509_STORE_set_ex_data(self.as_ptr(), *CRL_SET_IDX, new as *mut c_void) <= 0 {
+                drop(Box::from_raw(new));
+                return Err(ErrorStack::get());
+            }
+            if !old.is_null() {
+                drop(Box::from_raw(old as *mut CrlSet));
+            }
+            ffi::X509_STORE_set_lookup_crls(self.as_ptr(), Some(lookup_crls));
+            Ok(())
+        }
+    }
+
+    /// Adds flags to those used when verifying certificates against the store.
+    pub fn set_flags(&mut self, flags: X509VerifyFlags) -> Result<(), ErrorStack> {
+        unsafe { cvt(ffi::X509_STORE_set_flags(self.as_ptr(), flags.bits)).map(|_| ()) }
+    }
+
     /// Load certificates from their default locations.
     ///
     /// These locations are read from the `SSL_CERT_FILE` and `SSL_CERT_DIR`
@@ -63,3 +129,92 @@ foreign_type! {
     pub struct X509Store;
     pub struct X509StoreRef;
 }
+
+/// A shared set of certificate revocation lists which can be replaced at any time.
+///
+/// Stores configured with `X509StoreBuilderRef::set_crl_set` read the set each time they look up
+/// a CRL, so `replace` updates the revocation data used by every context sharing the store
+/// without rebuilding it. Each lookup sees either the old or the new CRLs in their entirety.
+///
+/// Requires the `v110` feature and OpenSSL 1.1.0.
+#[cfg(all(feature = "v110", ossl110))]
+#[derive(Clone)]
+pub struct CrlSet(Arc<RwLock<Vec<X509Crl>>>);
+
+// X509_CRL is reference counted and not modified once parsed
+#[cfg(all(feature = "v110", ossl110))]
+unsafe impl Sync for CrlSet {}
+#[cfg(all(feature = "v110", ossl110))]
+unsafe impl Send for CrlSet {}
+
+#[cfg(all(feature = "v110", ossl110))]
+impl CrlSet {
+    /// Creates a new `CrlSet` containing `crls`.
+    pub fn new(crls: Vec<X509Crl>) -> CrlSet {
+        CrlSet(Arc::new(RwLock::new(crls)))
+    }
+
+    /// Replaces the CRLs in the set.
+    pub fn replace(&self, crls: Vec<X509Crl>) {
+        let mut guard = match self.0.write() {
+            Ok(guard) => guard,
+            Err(err) => err.into_inner(),
+        };
+        *guard = crls;
+    }
+}
+
+#[cfg(all(feature = "v110", ossl110))]
+lazy_static! {
+    static ref CRL_SET_IDX: c_int = unsafe {
+        let idx = ffi::CRYPTO_get_ex_new_index(ffi::CRYPTO_EX_INDEX_X509_STORE,
+                                               0,
+                                               ptr::null_mut(),
+                                               None,
+                                               None,
+                                               Some(free_crl_set));
+        assert!(idx >= 0);
+        idx
+    };
+}
+
+#[cfg(all(feature = "v110", ossl110))]
+unsafe extern "C" fn free_crl_set(_parent: *mut c_void,
+                                  ptr: *mut c_void,
+                                  _ad: *mut ffi::CRYPTO_EX_DATA,
+                                  _idx: c_int,
+                                  _argl: c_long,
+                                  _argp: *mut c_void) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(ptr as *mut CrlSet));
+    }
+}
+
+#[cfg(all(feature = "v110", ossl110))]
+unsafe extern "C" fn lookup_crls(ctx: *mut ffi::X509_STORE_CTX,
+                                 name: *mut ffi::X509_NAME)
+                                 -> *mut ffi::stack_st_X509_CRL {
+    // OpenSSL skips CRLs from other issuers, so the whole set is returned along with the
+    // store's own matches
+    let mut crls = ffi::X509_STORE_CTX_get1_crls(ctx, name);
+    if crls.is_null() {
+        crls = ffi::OPENSSL_sk_new_null() as *mut _;
+        if crls.is_null() {
+            return ptr::null_mut();
+        }
+    }
+
+    let store = ffi::X509_STORE_CTX_get0_store(ctx);
+    let set = &*(ffi::X509_STORE_get_ex_data(store, *CRL_SET_IDX) as *const CrlSet);
+    let guard = match set.0.read() {
+        Ok(guard) => guard,
+        Err(err) => err.into_inner(),
+    };
+    for crl in guard.iter() {
+        if ffi::OPENSSL_sk_push(crls as *mut _, crl.as_ptr() as *const c_void) <= 0 {
+            break;
+        }
+        ffi::X509_CRL_up_ref(crl.as_ptr());
+    }
+    crls
+}
@@ -1,13 +1,25 @@
+use std::sync::Arc;
+use std::thread;
+
+use ffi;
+use foreign_types::ForeignTypeRef;
 use hex::{FromHex, ToHex};
 
+use asn1::{Asn1Object, Asn1Time, Asn1Type};
+use bn::BigNum;
+use error::ErrorStack;
 use ec::{NAMED_CURVE, EcGroup, EcKey};
 use hash::MessageDigest;
 use nid::X9_62_PRIME256V1;
 use pkey::PKey;
 use rsa::Rsa;
 use ssl::{SslMethod, SslContextBuilder};
-use x509::{X509, X509Generator};
+use stack::Stack;
+use symm::Cipher;
+use x509::{self, X509, X509Crl, X509CrlBuilder, X509Generator, X509Req, X509Revoked,
+           AlgorithmIdentifier, ExtensionValue, GeneralName, X509Extension, X509Pubkey};
 use x509::extension::Extension::{KeyUsage, ExtKeyUsage, SubjectAltName, OtherNid, OtherStr};
+use x509::cache::X509Cache;
 use x509::extension::AltNameOption as SAN;
 use x509::extension::KeyUsageOption::{DigitalSignature, KeyEncipherment};
 use x509::extension::ExtKeyUsageOption::{self, ClientAuth, ServerAuth};
@@ -70,6 +82,40 @@ fn test_cert_gen_extension_bad_ordering() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_cert_gen_copy_extensions() {
+    let pkey = pkey();
+    let old = X509::from_pem(include_bytes!("../../test/alt_name_cert.pem")).unwrap();
+
+    let cert = X509Generator::new().copy_extensions_from(&old).unwrap().sign(&pkey).unwrap();
+    let names = cert.subject_alt_names().unwrap();
+    assert_eq!(names.len(), 3);
+    assert_eq!(names[0].dnsname(), Some("foobar.com"));
+
+    let cert = X509Generator::new()
+        .add_extension(SubjectAltName(vec![(SAN::DNS, "example.com".to_owned())]))
+        .copy_extensions_from(&old)
+        .unwrap()
+        .sign(&pkey)
+        .unwrap();
+    let names = cert.subject_alt_names().unwrap();
+    assert_eq!(names.len(), 1);
+    assert_eq!(names[0].dnsname(), Some("example.com"));
+}
+
+#[test]
+fn test_cert_gen_x509_extension() {
+    let nocheck = "1.3.6.1.5.5.7.48.1.5".parse::<Asn1Object>().unwrap();
+    let value = Asn1Type::null().unwrap();
+    let ext = X509Extension::new(&nocheck, false, &value).unwrap();
+    assert_eq!(ext.value_der(), [0x05, 0x00]);
+
+    let cert = X509Generator::new().add_x509_extension(&ext).unwrap().sign(&pkey()).unwrap();
+    let ext = cert.extension(&nocheck).unwrap();
+    assert!(!ext.critical());
+    assert_eq!(ext.value_der(), [0x05, 0x00]);
+}
+
 #[test]
 fn test_req_gen() {
     let pkey = pkey();
@@ -122,6 +168,52 @@ fn test_subject_read_cn() {
     assert_eq!(cn.data().as_slice(), b"foobar.com")
 }
 
+#[test]
+fn test_subject_display_getters() {
+    let cert = include_bytes!("../../test/cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let subject = cert.subject_name();
+    assert_eq!(subject.to_string(),
+               "CN=foobar.com,O=Internet Widgits Pty Ltd,ST=Some-State,C=AU");
+    assert_eq!(subject.common_name(), Some("foobar.com"));
+    assert_eq!(subject.organization(), Some("Internet Widgits Pty Ltd"));
+    assert_eq!(subject.country(), Some("AU"));
+
+    let cert = include_bytes!("../../test/nid_test_cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let subject = cert.subject_name();
+    assert_eq!(subject.to_string(),
+               "friendlyName=Example,CN=example.com,emailAddress=test@example.com");
+    assert_eq!(subject.organization(), None);
+}
+
+#[test]
+fn test_subject_getters_reject_non_text() {
+    let cert = X509Generator::new().sign(&pkey()).unwrap();
+    unsafe {
+        let name = ffi::X509_get_subject_name(cert.as_ptr());
+        // "AB" as a BMPString, whose contents happen to be valid UTF-8
+        ffi::X509_NAME_add_entry_by_txt(name,
+                                        b"O\0".as_ptr() as *const _,
+                                        ffi::V_ASN1_BMPSTRING,
+                                        b"\0A\0B".as_ptr(),
+                                        4,
+                                        -1,
+                                        0);
+        ffi::X509_NAME_add_entry_by_txt(name,
+                                        b"C\0".as_ptr() as *const _,
+                                        ffi::V_ASN1_UTF8STRING,
+                                        b"A\0".as_ptr(),
+                                        2,
+                                        -1,
+                                        0);
+    }
+    let subject = cert.subject_name();
+    assert_eq!(subject.common_name(), Some("rust-openssl"));
+    assert_eq!(subject.organization(), None);
+    assert_eq!(subject.country(), None);
+}
+
 #[test]
 fn test_nid_values() {
     let cert = include_bytes!("../../test/nid_test_cert.pem");
@@ -162,6 +254,61 @@ fn test_subject_alt_name() {
                Some(&b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01"[..]));
 }
 
+#[test]
+fn test_ocsp_and_crl_urls() {
+    let pkey = pkey();
+    let cert = get_generator()
+        .add_extension(OtherNid(nid::INFO_ACCESS,
+                                "OCSP;URI:http://ocsp.example.com/".to_owned()))
+        .add_extension(OtherNid(nid::CRL_DISTRIBUTION_POINTS,
+                                "URI:http://crl.example.com/ca.crl".to_owned()))
+        .sign(&pkey)
+        .unwrap();
+
+    assert_eq!(cert.ocsp_urls(), vec!["http://ocsp.example.com/".to_owned()]);
+    assert_eq!(cert.crl_urls(), vec!["http://crl.example.com/ca.crl".to_owned()]);
+
+    let cert = get_generator().sign(&pkey).unwrap();
+    assert!(cert.ocsp_urls().is_empty());
+    assert!(cert.crl_urls().is_empty());
+}
+
+#[test]
+fn test_extension_decode() {
+    // A decoder for an extension this crate doesn't model, as another crate might write.
+    struct KeyIdentifier(Vec<u8>);
+
+    impl ExtensionValue for KeyIdentifier {
+        type Error = ();
+
+        fn from_der(der: &[u8]) -> Result<KeyIdentifier, ()> {
+            if der.len() >= 2 && der[0] == 0x04 && der[1] as usize == der.len() - 2 {
+                Ok(KeyIdentifier(der[2..].to_vec()))
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    let cert = include_bytes!("../../test/alt_name_cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    assert_eq!(cert.extensions().unwrap().len(), 6);
+
+    let san = cert.extension(&"subjectAltName".parse::<Asn1Object>().unwrap()).unwrap();
+    assert!(!san.critical());
+    let names = san.decode::<Stack<GeneralName>>().unwrap();
+    assert_eq!(3, names.len());
+    assert_eq!(Some("foobar.com"), names[0].dnsname());
+    assert_eq!(names[1].ipaddress(), Some(&[127, 0, 0, 1][..]));
+
+    let ski = cert.extension(&"2.5.29.14".parse::<Asn1Object>().unwrap()).unwrap();
+    let id = ski.decode::<KeyIdentifier>().unwrap();
+    assert_eq!(id.0.to_hex(), "021c1e14cf52a55d2bce6ee3bbe8f33d2f88918f");
+    assert!(san.decode::<KeyIdentifier>().is_err());
+
+    assert!(cert.extension(&"1.3.6.1.4.1.311.21.7".parse::<Asn1Object>().unwrap()).is_none());
+}
+
 #[test]
 fn test_subject_alt_name_iter() {
     let cert = include_bytes!("../../test/alt_name_cert.pem");
@@ -220,3 +367,309 @@ fn ecdsa_cert() {
     ctx.set_private_key(&key).unwrap();
     ctx.check_private_key().unwrap();
 }
+
+#[test]
+fn crl_entries_sorted() {
+    let ca = include_bytes!("../../test/root-ca.pem");
+    let ca = X509::from_pem(ca).unwrap();
+    let key = include_bytes!("../../test/root-ca.key");
+    let key = PKey::private_key_from_pem(key).unwrap();
+    let last_update = Asn1Time::days_from_now(0).unwrap();
+    let next_update = Asn1Time::days_from_now(7).unwrap();
+    let serials = (1..10001).map(|i| BigNum::from_u32(i).unwrap()).collect::<Vec<_>>();
+
+    let build = |serials: &mut Iterator<Item = &BigNum>| {
+        let mut builder = X509CrlBuilder::new().unwrap();
+        builder.set_issuer_name(ca.subject_name()).unwrap();
+        builder.set_last_update(&last_update).unwrap();
+        builder.set_next_update(&next_update).unwrap();
+        builder.add_revoked_iter(serials.map(|s| (&**s, &*last_update))).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap().to_der().unwrap()
+    };
+
+    let ascending = build(&mut serials.iter());
+    let descending = build(&mut serials.iter().rev());
+    assert_eq!(ascending, descending);
+
+    let crl = X509Crl::from_der(&ascending).unwrap();
+    let pem = crl.to_pem().unwrap();
+    let crl = X509Crl::from_pem(&pem).unwrap();
+    assert_eq!(crl.to_der().unwrap(), ascending);
+}
+
+#[test]
+fn serial_number() {
+    let cert = X509::from_pem(include_bytes!("../../test/cert.pem")).unwrap();
+    let serial = cert.serial_number();
+    assert_eq!(serial.to_string(), "9759854262612406181");
+    assert_eq!(format!("{:?}", serial), "9759854262612406181");
+    assert_eq!(&**serial.to_hex_str().unwrap(), "8771F7BDEE982FA5");
+}
+
+#[test]
+fn algorithm_identifier() {
+    let cert = X509::from_pem(include_bytes!("../../test/cert.pem")).unwrap();
+    let alg = cert.signature_algorithm();
+    assert_eq!(alg.object().nid(), nid::SHA256WITHRSAENCRYPTION);
+    assert_eq!(alg.parameters_der().unwrap(), Some(vec![0x05, 0x00]));
+    let algs = alg.signature_algorithms().unwrap();
+    assert_eq!(algs.digest, nid::SHA256);
+    assert_eq!(algs.pkey, nid::RSAENCRYPTION);
+    assert!(alg.digest().is_none());
+    assert!(alg.cipher().is_none());
+
+    let der = alg.to_der().unwrap();
+    let alg = AlgorithmIdentifier::from_der(&der).unwrap();
+    assert_eq!(alg.to_der().unwrap(), der);
+
+    // AlgorithmIdentifier { id-sha256, NULL }
+    let der = Vec::from_hex("300d06096086480165030402010500").unwrap();
+    let alg = AlgorithmIdentifier::from_der(&der).unwrap();
+    assert_eq!(alg.digest().unwrap().as_ptr(), MessageDigest::sha256().as_ptr());
+    assert!(alg.signature_algorithms().is_none());
+
+    // AlgorithmIdentifier { aes-128-cbc } with the IV omitted
+    let der = Vec::from_hex("300b0609608648016503040102").unwrap();
+    let alg = AlgorithmIdentifier::from_der(&der).unwrap();
+    assert_eq!(alg.parameters_der().unwrap(), None);
+    assert_eq!(alg.cipher().unwrap().as_ptr(), Cipher::aes_128_cbc().as_ptr());
+    assert!(alg.digest().is_none());
+}
+
+#[test]
+fn public_key_info() {
+    use std::collections::HashSet;
+
+    let cert = X509::from_pem(include_bytes!("../../test/cert.pem")).unwrap();
+    let key = PKey::private_key_from_pem(include_bytes!("../../test/key.pem")).unwrap();
+    let info = cert.public_key_info();
+    assert_eq!(info.algorithm().object().nid(), nid::RSAENCRYPTION);
+    assert!(info.public_key().unwrap().public_eq(&key));
+
+    let from_key = X509Pubkey::from_pkey(&key).unwrap();
+    assert_eq!(info, &*from_key);
+    assert_eq!(info.key_bytes(), from_key.key_bytes());
+    let der = info.to_der().unwrap();
+    assert_eq!(X509Pubkey::from_der(&der).unwrap().to_der().unwrap(), der);
+
+    let other = X509Pubkey::from_pkey(&pkey()).unwrap();
+    assert!(*info != *other);
+
+    let mut set = HashSet::new();
+    set.insert(from_key);
+    set.insert(other);
+    assert!(set.contains(info));
+    assert_eq!(set.len(), 2);
+
+    let req = get_generator().request(&key).unwrap();
+    assert_eq!(req.public_key_info(), info);
+}
+
+#[test]
+fn crl_entry_extensions() {
+    let ca = X509::from_pem(include_bytes!("../../test/root-ca.pem")).unwrap();
+    let key = PKey::private_key_from_pem(include_bytes!("../../test/root-ca.key")).unwrap();
+    let now = Asn1Time::from_str_asn1("20250101000000Z").unwrap();
+    let compromised = Asn1Time::from_str_asn1("20241201000000Z").unwrap();
+
+    let mut builder = X509CrlBuilder::new().unwrap();
+    builder.set_issuer_name(ca.subject_name()).unwrap();
+    builder.set_last_update(&now).unwrap();
+    builder.add_revoked(&BigNum::from_u32(1).unwrap(), &now).unwrap();
+    let mut entry = X509Revoked::new(&BigNum::from_u32(2).unwrap(), &now).unwrap();
+    entry.set_reason(x509::CRL_REASON_SUPERSEDED).unwrap();
+    entry.set_reason(x509::CRL_REASON_KEY_COMPROMISE).unwrap();
+    entry.set_invalidity_date(&compromised).unwrap();
+    entry.set_certificate_issuer(ca.subject_name()).unwrap();
+    builder.add_revoked_entry(entry).unwrap();
+    let der = builder.sign(&key, MessageDigest::sha256()).unwrap().to_der().unwrap();
+
+    let crl = X509Crl::from_der(&der).unwrap();
+    let revoked = crl.revoked().unwrap();
+    assert_eq!(revoked.len(), 2);
+
+    let plain = &revoked[0];
+    assert_eq!(plain.serial_number().to_bn().unwrap(), BigNum::from_u32(1).unwrap());
+    assert_eq!(plain.revocation_date().to_string(), "Jan  1 00:00:00 2025 GMT");
+    assert!(plain.extensions().is_none());
+    assert_eq!(plain.reason(), None);
+    assert!(plain.invalidity_date().is_none());
+    assert!(plain.certificate_issuer().is_none());
+    // missing extensions aren't reported as errors
+    assert!(ErrorStack::get().errors().is_empty());
+
+    let entry = &revoked[1];
+    assert_eq!(entry.serial_number().to_bn().unwrap(), BigNum::from_u32(2).unwrap());
+    assert_eq!(entry.extensions().unwrap().len(), 3);
+    assert_eq!(entry.reason(), Some(x509::CRL_REASON_KEY_COMPROMISE));
+    assert_eq!(entry.invalidity_date().unwrap().to_string(), "Dec  1 00:00:00 2024 GMT");
+    let issuer = entry.certificate_issuer().unwrap();
+    assert_eq!(issuer.len(), 1);
+    let name = issuer[0].directory_name().unwrap();
+    assert_eq!(name.common_name(), ca.subject_name().common_name());
+}
+
+#[test]
+fn stack_sort_retain_dedup() {
+    let cert = include_bytes!("../../test/alt_name_cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+
+    let mut names = cert.subject_alt_names().unwrap();
+    names.sort_by(|a, b| b.ipaddress().cmp(&a.ipaddress()));
+    assert_eq!(names[0].ipaddress(), Some(&[127, 0, 0, 1][..]));
+    assert_eq!(names[1].ipaddress(),
+               Some(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1][..]));
+    assert_eq!(names[2].dnsname(), Some("foobar.com"));
+
+    names.dedup_by(|a, b| a.ipaddress().is_some() && b.ipaddress().is_some());
+    assert_eq!(names.len(), 2);
+    assert_eq!(names[1].dnsname(), Some("foobar.com"));
+
+    names.retain(|n| n.dnsname().is_some());
+    assert_eq!(names.len(), 1);
+    assert_eq!(names[0].dnsname(), Some("foobar.com"));
+}
+
+#[test]
+fn stack_new_push() {
+    let mut certs = Stack::new().unwrap();
+    assert_eq!(certs.len(), 0);
+    certs.push(X509::from_pem(include_bytes!("../../test/cert.pem")).unwrap()).unwrap();
+    certs.push(X509::from_pem(include_bytes!("../../test/root-ca.pem")).unwrap()).unwrap();
+    assert_eq!(certs.len(), 2);
+    assert_eq!(certs[0].subject_name().common_name(), Some("foobar.com"));
+
+    let last = certs.pop().unwrap();
+    assert_eq!(certs.len(), 1);
+    certs.push(last).unwrap();
+
+    let certs = certs.into_iter().collect::<Vec<X509>>();
+    assert_eq!(certs.len(), 2);
+
+    let mut objects = Stack::new().unwrap();
+    objects.push("1.3.6.1.5.5.7.3.1".parse::<Asn1Object>().unwrap()).unwrap();
+    assert_eq!(objects[0].nid(), nid::SERVER_AUTH);
+}
+
+#[test]
+fn stack_iter_double_ended() {
+    let cert = include_bytes!("../../test/alt_name_cert.pem");
+    let cert = X509::from_pem(cert).unwrap();
+    let mut names = cert.subject_alt_names().unwrap();
+
+    let dnsnames = names.iter().rev().map(|n| n.dnsname()).collect::<Vec<_>>();
+    assert_eq!(dnsnames, [None, None, Some("foobar.com")]);
+
+    let mut iter = names.iter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next_back().unwrap().ipaddress(),
+               Some(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1][..]));
+    assert_eq!(iter.len(), 2);
+    assert_eq!(iter.nth(1).unwrap().ipaddress(), Some(&[127, 0, 0, 1][..]));
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+
+    assert_eq!(names.iter_mut().rev().len(), 3);
+
+    let mut iter = names.into_iter();
+    assert_eq!(iter.next_back().unwrap().dnsname(), None);
+    assert_eq!(iter.next().unwrap().dnsname(), Some("foobar.com"));
+    assert_eq!(iter.len(), 1);
+}
+
+#[test]
+fn req_verify_self() {
+    let key = pkey();
+    let other = pkey();
+
+    let req = get_generator().request(&key).unwrap();
+    let req = X509Req::from_der(&req.to_der().unwrap()).unwrap();
+    assert!(req.verify_self().unwrap());
+    assert!(req.verify(&key).unwrap());
+    assert!(!req.verify(&other).unwrap());
+    assert!(req.public_key_matches(&key).unwrap());
+    assert!(!req.public_key_matches(&other).unwrap());
+
+    let group = EcGroup::from_curve_name(X9_62_PRIME256V1).unwrap();
+    let ec_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    assert!(!req.public_key_matches(&ec_key).unwrap());
+
+    let ec_req = get_generator().request(&ec_key).unwrap();
+    assert!(ec_req.verify_self().unwrap());
+    assert!(ec_req.public_key_matches(&ec_key).unwrap());
+}
+
+#[test]
+fn cert_public_key_matches() {
+    let cert = X509::from_pem(include_bytes!("../../test/cert.pem")).unwrap();
+    let key = PKey::private_key_from_pem(include_bytes!("../../test/key.pem")).unwrap();
+    let root = X509::from_pem(include_bytes!("../../test/root-ca.pem")).unwrap();
+
+    assert!(cert.public_key_matches(&key).unwrap());
+    assert!(!root.public_key_matches(&key).unwrap());
+    assert!(cert.verify(&root.public_key().unwrap()).unwrap());
+    assert!(!cert.verify(&key).unwrap());
+}
+
+#[test]
+fn cache_interns_certificates() {
+    let cache = X509Cache::new();
+    assert!(cache.is_empty());
+
+    let pem = include_bytes!("../../test/cert.pem");
+    let der = X509::from_pem(pem).unwrap().to_der().unwrap();
+
+    let a = cache.from_der(&der).unwrap();
+    let b = cache.from_der(&der).unwrap();
+    let c = cache.from_pem(pem).unwrap();
+    assert_eq!(a.as_ptr(), b.as_ptr());
+    assert_eq!(a.as_ptr(), c.as_ptr());
+    assert_eq!(cache.len(), 1);
+
+    let root = X509::from_pem(include_bytes!("../../test/root-ca.pem")).unwrap();
+    let mut chain = Stack::new().unwrap();
+    chain.push(root.clone()).unwrap();
+    chain.push(X509::from_der(&der).unwrap()).unwrap();
+    let interned = cache.intern_chain(&chain).unwrap();
+    assert_eq!(interned[0].as_ptr(), root.as_ptr());
+    assert_eq!(interned[1].as_ptr(), a.as_ptr());
+    assert_eq!(cache.len(), 2);
+
+    let mut trailing = der.clone();
+    trailing.extend_from_slice(b"trailing data");
+    let d = cache.from_der(&trailing).unwrap();
+    assert_eq!(d.as_ptr(), a.as_ptr());
+    assert_eq!(cache.len(), 2);
+
+    assert!(cache.from_der(b"not a certificate").is_err());
+    assert_eq!(cache.len(), 2);
+
+    let shared = Arc::new(cache);
+    let other = shared.clone();
+    let e = thread::spawn(move || other.from_der(&trailing).unwrap()).join().unwrap();
+    assert_eq!(e.as_ptr(), a.as_ptr());
+    let cache = Arc::try_unwrap(shared).ok().unwrap();
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(a.to_der().unwrap(), der);
+}
+
+#[test]
+fn cert_to_der_into() {
+    let cert = X509::from_pem(include_bytes!("../../test/cert.pem")).unwrap();
+    let der = cert.to_der().unwrap();
+    assert_eq!(cert.der_len().unwrap(), der.len());
+
+    let mut buf = vec![0xff; der.len() + 10];
+    let len = cert.to_der_into(&mut buf).unwrap();
+    assert_eq!(&buf[..len], &der[..]);
+    assert!(buf[len..].iter().all(|&b| b == 0xff));
+}
+
+#[test]
+fn cert_to_der_into_short() {
+    let cert = X509::from_pem(include_bytes!("../../test/cert.pem")).unwrap();
+    let len = cert.der_len().unwrap();
+    assert!(cert.to_der_into(&mut vec![0; len - 1]).is_err());
+}
@@ -8,6 +8,9 @@ case "$BUILD_OPENSSL_VERSION" in
     1.1.0*)
         FEATURES="v110"
         ;;
+    1.1.1*)
+        FEATURES="v110 v111"
+        ;;
 esac
 
 echo Using features: $FEATURES
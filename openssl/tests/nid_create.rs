@@ -0,0 +1,15 @@
+//! `Nid::create` must not run concurrently with other uses of the object table, so this test
+//! runs in a binary of its own rather than alongside the rest of the test suite.
+extern crate openssl;
+
+use openssl::nid::Nid;
+
+#[test]
+fn create() {
+    let nid = unsafe {
+        Nid::create("1.3.6.1.4.1.54321.1", "rustTestObject", "Rust Test Object").unwrap()
+    };
+    assert_eq!(nid.short_name().unwrap(), "rustTestObject");
+    assert_eq!(nid.long_name().unwrap(), "Rust Test Object");
+    assert_eq!(Nid::from_short_name("rustTestObject"), Some(nid));
+}
@@ -0,0 +1,32 @@
+//! The generator installed by `set_rand_callback` is used by the whole process, so this test runs
+//! in a binary of its own rather than alongside the rest of the test suite.
+extern crate openssl;
+
+#[cfg(not(libressl))]
+#[test]
+fn rand_callback() {
+    use openssl::rand::{clear_rand_callback, rand_bytes, set_rand_callback};
+    use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+    static NEXT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    set_rand_callback(|buf| {
+        for b in buf.iter_mut() {
+            *b = NEXT.fetch_add(1, Ordering::SeqCst) as u8;
+        }
+        true
+    }).unwrap();
+    let mut a = [0; 4];
+    let mut b = [0; 4];
+    rand_bytes(&mut a).unwrap();
+    rand_bytes(&mut b).unwrap();
+
+    set_rand_callback(|_| false).unwrap();
+    let failed = rand_bytes(&mut [0; 4]).is_err();
+    clear_rand_callback();
+
+    assert_eq!(a, [0, 1, 2, 3]);
+    assert_eq!(b, [4, 5, 6, 7]);
+    assert!(failed);
+    rand_bytes(&mut a).unwrap();
+}
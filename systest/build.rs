@@ -88,6 +88,7 @@ fn main() {
     });
     cfg.skip_field_type(|s, field| {
         (s == "EVP_PKEY" && field == "pkey") ||      // union
+            (s == "ASN1_TYPE" && field == "value") ||    // union
             (s == "GENERAL_NAME" && field == "d")    // union
     });
     cfg.skip_signededness(|s| {